@@ -0,0 +1,421 @@
+use std::{fmt::Write, ops::Range};
+
+use clap::ValueEnum;
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use serde::Serialize;
+
+use crate::sequence_modifier::ancestry::{Ancestry, CharacterOrigin};
+
+/// The sequence against which the true alignments are written.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlignmentReference {
+    /// Align reference and query each against the ancestor.
+    Ancestor,
+    /// Align the query against the reference, composed through the ancestor.
+    Reference,
+}
+
+/// An operation of an alignment of a query against a reference.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AlignmentOperation {
+    Match,
+    Mismatch,
+    /// A character that exists only in the query.
+    Insertion,
+    /// A character that exists only in the reference.
+    Deletion,
+}
+
+/// The true alignment of a query against a reference.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alignment {
+    /// Run-length encoded alignment operations.
+    operations: Vec<(AlignmentOperation, usize)>,
+    reference_range: Range<usize>,
+    query_range: Range<usize>,
+}
+
+impl Alignment {
+    /// Composes the alignment of query and reference from their ancestries.
+    ///
+    /// Characters that descend from the same ancestral character are aligned to each other,
+    /// and all other characters are aligned to gaps.
+    /// Template switches hence show up as a deletion and an insertion.
+    /// Leading and trailing gaps are not part of the alignment.
+    pub fn compose<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SequenceType,
+        reference_ancestry: &Ancestry,
+        query: &SequenceType,
+        query_ancestry: &Ancestry,
+    ) -> Self {
+        let reference_origins = reference_ancestry.origins();
+        let query_origins = query_ancestry.origins();
+        debug_assert_eq!(reference.len(), reference_origins.len());
+        debug_assert_eq!(query.len(), query_origins.len());
+
+        let mut operations = Vec::new();
+        let mut reference_index = 0;
+        let mut query_index = 0;
+
+        while reference_index < reference_origins.len() || query_index < query_origins.len() {
+            let operation = match (
+                reference_origins.get(reference_index),
+                query_origins.get(query_index),
+            ) {
+                (Some(CharacterOrigin::Inserted | CharacterOrigin::TemplateSwitch), _)
+                | (Some(_), None) => AlignmentOperation::Deletion,
+                (_, Some(CharacterOrigin::Inserted | CharacterOrigin::TemplateSwitch))
+                | (None, Some(_)) => AlignmentOperation::Insertion,
+                (
+                    Some(&CharacterOrigin::Ancestral {
+                        ancestor_position: reference_position,
+                    }),
+                    Some(&CharacterOrigin::Ancestral {
+                        ancestor_position: query_position,
+                    }),
+                ) => {
+                    if reference_position < query_position {
+                        AlignmentOperation::Deletion
+                    } else if reference_position > query_position {
+                        AlignmentOperation::Insertion
+                    } else if reference[reference_index] == query[query_index] {
+                        AlignmentOperation::Match
+                    } else {
+                        AlignmentOperation::Mismatch
+                    }
+                }
+                (None, None) => unreachable!(),
+            };
+
+            match operation {
+                AlignmentOperation::Match | AlignmentOperation::Mismatch => {
+                    reference_index += 1;
+                    query_index += 1;
+                }
+                AlignmentOperation::Insertion => query_index += 1,
+                AlignmentOperation::Deletion => reference_index += 1,
+            }
+
+            if let Some((last_operation, count)) = operations.last_mut() {
+                if *last_operation == operation {
+                    *count += 1;
+                    continue;
+                }
+            }
+            operations.push((operation, 1));
+        }
+
+        let mut reference_range = 0..reference_origins.len();
+        let mut query_range = 0..query_origins.len();
+        while let Some(&(operation, count)) = operations.first() {
+            match operation {
+                AlignmentOperation::Insertion => query_range.start += count,
+                AlignmentOperation::Deletion => reference_range.start += count,
+                AlignmentOperation::Match | AlignmentOperation::Mismatch => break,
+            }
+            operations.remove(0);
+        }
+        while let Some(&(operation, count)) = operations.last() {
+            match operation {
+                AlignmentOperation::Insertion => query_range.end -= count,
+                AlignmentOperation::Deletion => reference_range.end -= count,
+                AlignmentOperation::Match | AlignmentOperation::Mismatch => break,
+            }
+            operations.pop();
+        }
+
+        if operations.is_empty() {
+            reference_range = 0..0;
+            query_range = 0..0;
+        }
+
+        Self {
+            operations,
+            reference_range,
+            query_range,
+        }
+    }
+
+    /// The aligned range of the reference.
+    pub fn reference_range(&self) -> Range<usize> {
+        self.reference_range.clone()
+    }
+
+    /// The aligned range of the query.
+    pub fn query_range(&self) -> Range<usize> {
+        self.query_range.clone()
+    }
+
+    /// The number of columns of the alignment.
+    pub fn column_amount(&self) -> usize {
+        self.operations.iter().map(|(_, count)| count).sum()
+    }
+
+    /// The number of matching columns of the alignment.
+    pub fn match_amount(&self) -> usize {
+        self.operations
+            .iter()
+            .filter(|(operation, _)| *operation == AlignmentOperation::Match)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// The number of columns that are not matches, i.e. the edit distance of the aligned ranges.
+    pub fn edit_distance(&self) -> usize {
+        self.column_amount() - self.match_amount()
+    }
+
+    /// The alignment as CIGAR string using the operations `=`, `X`, `I` and `D`.
+    pub fn cigar(&self) -> String {
+        let mut cigar = String::new();
+        for (operation, count) in &self.operations {
+            let operation = match operation {
+                AlignmentOperation::Match => '=',
+                AlignmentOperation::Mismatch => 'X',
+                AlignmentOperation::Insertion => 'I',
+                AlignmentOperation::Deletion => 'D',
+            };
+            write!(cigar, "{count}{operation}").unwrap();
+        }
+        cigar
+    }
+}
+
+/// The true alignment of a derived sequence against its ancestor as CIGAR string.
+///
+/// The CIGAR is folded from the ancestry of the derived sequence,
+/// which already accounts for the shifts that later modifications apply to the coordinates of earlier ones.
+/// It uses the operations `=`, `X`, `I` and `D` like [`Alignment::cigar`],
+/// and additionally `T` for characters copied by a template switch, which like `I` consume only the derived sequence.
+/// The ancestral characters replaced by a template switch are written as `D` after its `T` operation.
+/// Unlike [`Alignment::cigar`], leading and trailing gaps are part of the CIGAR.
+pub fn ancestor_cigar<
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    ancestor: &SequenceType,
+    derived: &SequenceType,
+    derived_ancestry: &Ancestry,
+) -> String {
+    let origins = derived_ancestry.origins();
+    debug_assert_eq!(derived.len(), origins.len());
+
+    let mut operations: Vec<(char, usize)> = Vec::new();
+    let mut push = |operation, count| {
+        if count == 0 {
+            return;
+        }
+        if let Some((last_operation, last_count)) = operations.last_mut() {
+            if *last_operation == operation {
+                *last_count += count;
+                return;
+            }
+        }
+        operations.push((operation, count));
+    };
+
+    let mut ancestor_index = 0;
+    for (derived_index, origin) in origins.iter().enumerate() {
+        match *origin {
+            CharacterOrigin::Ancestral { ancestor_position } => {
+                push('D', ancestor_position - ancestor_index);
+                ancestor_index = ancestor_position + 1;
+                if ancestor[ancestor_position] == derived[derived_index] {
+                    push('=', 1);
+                } else {
+                    push('X', 1);
+                }
+            }
+            CharacterOrigin::Inserted => push('I', 1),
+            CharacterOrigin::TemplateSwitch => push('T', 1),
+        }
+    }
+    push('D', ancestor.len() - ancestor_index);
+
+    let mut cigar = String::new();
+    for (operation, count) in operations {
+        write!(cigar, "{count}{operation}").unwrap();
+    }
+    cigar
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::OwnedGenomeSequence,
+    };
+    use traitsequence::interface::Sequence;
+
+    use crate::sequence_modifier::{ancestry::Ancestry, SequenceModification};
+
+    use super::{ancestor_cigar, Alignment};
+
+    fn compose(
+        ancestor: &[u8],
+        reference_modifications: &[SequenceModification],
+        query_modifications: &[SequenceModification],
+    ) -> Alignment {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(ancestor).unwrap();
+        let mut reference = ancestor.clone();
+        let mut reference_ancestry = Ancestry::new(ancestor.len());
+        for modification in reference_modifications {
+            modification.apply(&mut reference, &ancestor).unwrap();
+            reference_ancestry.apply_modification(modification);
+        }
+        let mut query = ancestor.clone();
+        let mut query_ancestry = Ancestry::new(ancestor.len());
+        for modification in query_modifications {
+            modification.apply(&mut query, &ancestor).unwrap();
+            query_ancestry.apply_modification(modification);
+        }
+
+        Alignment::compose(&reference, &reference_ancestry, &query, &query_ancestry)
+    }
+
+    fn derive_cigar(ancestor: &[u8], modifications: &[SequenceModification]) -> String {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(ancestor).unwrap();
+        let mut derived = ancestor.clone();
+        let mut ancestry = Ancestry::new(ancestor.len());
+        for modification in modifications {
+            modification.apply(&mut derived, &ancestor).unwrap();
+            ancestry.apply_modification(modification);
+        }
+
+        ancestor_cigar(&ancestor, &derived, &ancestry)
+    }
+
+    #[test]
+    fn substitutions() {
+        let alignment = compose(
+            b"ACGTACGTAC",
+            &[SequenceModification::Substitution {
+                position: 2,
+                character_increment: 1,
+            }],
+            &[SequenceModification::Substitution {
+                position: 7,
+                character_increment: 3,
+            }],
+        );
+        assert_eq!(alignment.cigar(), "2=1X4=1X2=");
+        assert_eq!(alignment.reference_range(), 0..10);
+        assert_eq!(alignment.query_range(), 0..10);
+        assert_eq!(alignment.match_amount(), 8);
+        assert_eq!(alignment.edit_distance(), 2);
+    }
+
+    #[test]
+    fn gaps() {
+        let alignment = compose(
+            b"ACGTACGTAC",
+            &[
+                SequenceModification::Deletion {
+                    position: 3,
+                    length: 2,
+                },
+                SequenceModification::Insertion {
+                    position: 6,
+                    source: 0,
+                    length: 3,
+                },
+            ],
+            &[
+                SequenceModification::Deletion {
+                    position: 0,
+                    length: 1,
+                },
+                SequenceModification::Insertion {
+                    position: 1,
+                    source: 5,
+                    length: 1,
+                },
+            ],
+        );
+        assert_eq!(alignment.cigar(), "1=1I1=2I3=3D2=");
+        assert_eq!(alignment.reference_range(), 1..11);
+        assert_eq!(alignment.query_range(), 0..10);
+        assert_eq!(alignment.column_amount(), 13);
+    }
+
+    #[test]
+    fn template_switch() {
+        let alignment = compose(
+            b"AAAAACCCCCGGGGGTTTTT",
+            &[SequenceModification::TemplateSwitch {
+                position: 10,
+                length: 4,
+                offset: -2,
+                length_difference: 1,
+                inter_sequence: false,
+            }],
+            &[],
+        );
+        assert_eq!(alignment.cigar(), "10=4D3I7=");
+        assert_eq!(alignment.reference_range(), 0..21);
+        assert_eq!(alignment.query_range(), 0..20);
+        assert_eq!(alignment.edit_distance(), 7);
+    }
+
+    #[test]
+    fn ancestor_cigars() {
+        assert_eq!(derive_cigar(b"AACCGGTTAC", &[]), "10=");
+        assert_eq!(
+            derive_cigar(
+                b"AACCGGTTAC",
+                &[
+                    SequenceModification::Insertion {
+                        position: 2,
+                        source: 0,
+                        length: 2,
+                    },
+                    SequenceModification::Deletion {
+                        position: 10,
+                        length: 2,
+                    },
+                ],
+            ),
+            "2=2I6=2D"
+        );
+        // The template switch applies to positions shifted by the deletion before it.
+        assert_eq!(
+            derive_cigar(
+                b"AACCGGTTAC",
+                &[
+                    SequenceModification::Substitution {
+                        position: 0,
+                        character_increment: 1,
+                    },
+                    SequenceModification::Deletion {
+                        position: 4,
+                        length: 2,
+                    },
+                    SequenceModification::TemplateSwitch {
+                        position: 5,
+                        length: 2,
+                        offset: -2,
+                        length_difference: 0,
+                        inter_sequence: false,
+                    },
+                ],
+            ),
+            "1X3=2D1=2T2D1="
+        );
+        assert_eq!(
+            derive_cigar(
+                b"AACCGGTTAC",
+                &[SequenceModification::Deletion {
+                    position: 0,
+                    length: 3,
+                }],
+            ),
+            "3D7="
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Serialize, Serializer};
+
+use crate::error::Error;
+
+/// The distribution from which the length of each generated ancestor is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AncestorLengthDistribution {
+    /// Every ancestor has the same length.
+    Fixed(usize),
+    /// The length is drawn uniformly from the inclusive range.
+    Uniform { min: usize, max: usize },
+    /// The length is drawn from a normal distribution and rounded, negative draws become zero.
+    Normal { mean: f64, standard_deviation: f64 },
+    /// The length is drawn uniformly from the given lengths.
+    Empirical(Vec<usize>),
+    /// The pair with index `i` gets the `i`-th length, cycling through the list if it is shorter than the number of pairs.
+    List(Vec<usize>),
+}
+
+impl AncestorLengthDistribution {
+    /// Draws the ancestor length of the pair with the given index.
+    ///
+    /// A fixed length or a length from a list is returned without consuming randomness.
+    /// The normal distribution is sampled with the Box-Muller transform using the software functions of `libm`,
+    /// such that the result only depends on the random number generator.
+    pub fn sample(&self, pair_index: usize, rng: &mut impl Rng) -> usize {
+        match self {
+            AncestorLengthDistribution::Fixed(length) => *length,
+            AncestorLengthDistribution::Uniform { min, max } => rng.gen_range(*min..=*max),
+            AncestorLengthDistribution::Normal {
+                mean,
+                standard_deviation,
+            } => {
+                // Uniform samples from (0, 1] and [0, 1) with 53 random bits, which are exact in an f64.
+                let uniform1 = ((rng.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+                let uniform2 = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                let standard_normal =
+                    (-2.0 * libm::log(uniform1)).sqrt() * libm::cos(2.0 * PI * uniform2);
+                let length = mean + standard_deviation * standard_normal;
+                // `round` rounds half-way cases away from zero, which does not depend on the platform.
+                if length < 0.0 {
+                    0
+                } else {
+                    length.round() as usize
+                }
+            }
+            AncestorLengthDistribution::Empirical(lengths) => *lengths.choose(rng).unwrap(),
+            AncestorLengthDistribution::List(lengths) => lengths[pair_index % lengths.len()],
+        }
+    }
+
+    /// The smallest length that can be drawn, if it is bounded.
+    pub fn min(&self) -> Option<usize> {
+        match self {
+            AncestorLengthDistribution::Fixed(length) => Some(*length),
+            AncestorLengthDistribution::Uniform { min, .. } => Some(*min),
+            AncestorLengthDistribution::Normal { .. } => None,
+            AncestorLengthDistribution::Empirical(lengths)
+            | AncestorLengthDistribution::List(lengths) => lengths.iter().min().copied(),
+        }
+    }
+}
+
+impl std::fmt::Display for AncestorLengthDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AncestorLengthDistribution::Fixed(length) => write!(f, "{length}"),
+            AncestorLengthDistribution::Uniform { min, max } => write!(f, "uniform:{min}-{max}"),
+            AncestorLengthDistribution::Normal {
+                mean,
+                standard_deviation,
+            } => write!(f, "normal:{mean},{standard_deviation}"),
+            AncestorLengthDistribution::Empirical(lengths) => write!(
+                f,
+                "empirical:{}",
+                lengths
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            AncestorLengthDistribution::List(lengths) => write!(
+                f,
+                "{}",
+                lengths
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+impl Serialize for AncestorLengthDistribution {
+    /// Serialises the distribution in the same format as it is parsed from the command line.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for AncestorLengthDistribution {
+    type Err = Error;
+
+    /// Parses `uniform:MIN-MAX`, `normal:MEAN,STANDARD_DEVIATION` or `empirical:LENGTH,LENGTH,...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || Error::InvalidAncestorLengthDistribution(s.to_string());
+        let (kind, parameters) = s.split_once(':').ok_or_else(error)?;
+
+        match kind {
+            "uniform" => {
+                let (min, max) = parameters.split_once('-').ok_or_else(error)?;
+                let min: usize = min.trim().parse().map_err(|_| error())?;
+                let max: usize = max.trim().parse().map_err(|_| error())?;
+                if min > max {
+                    return Err(error());
+                }
+                Ok(Self::Uniform { min, max })
+            }
+            "normal" => {
+                let (mean, standard_deviation) = parameters.split_once(',').ok_or_else(error)?;
+                let mean: f64 = mean.trim().parse().map_err(|_| error())?;
+                let standard_deviation: f64 =
+                    standard_deviation.trim().parse().map_err(|_| error())?;
+                if !mean.is_finite() || !standard_deviation.is_finite() || standard_deviation < 0.0
+                {
+                    return Err(error());
+                }
+                Ok(Self::Normal {
+                    mean,
+                    standard_deviation,
+                })
+            }
+            "empirical" => {
+                let lengths = parameters
+                    .split(',')
+                    .map(|length| length.trim().parse())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|_| error())?;
+                if lengths.is_empty() {
+                    return Err(error());
+                }
+                Ok(Self::Empirical(lengths))
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Reads a file with one ancestor length per line.
+///
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_ancestor_lengths_file(path: impl AsRef<Path>) -> crate::error::Result<Vec<usize>> {
+    let mut lengths = Vec::new();
+
+    for (line_index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let Ok(length) = trimmed.parse() else {
+            return Err(Error::InvalidAncestorLengthLine {
+                line_number: line_index + 1,
+                line,
+            });
+        };
+        lengths.push(length);
+    }
+
+    Ok(lengths)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::AncestorLengthDistribution;
+
+    #[test]
+    fn parse_and_sample() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let uniform: AncestorLengthDistribution = "uniform:100-200".parse().unwrap();
+        assert_eq!(
+            uniform,
+            AncestorLengthDistribution::Uniform { min: 100, max: 200 }
+        );
+        assert!((0..100).all(|_| (100..=200).contains(&uniform.sample(0, &mut rng))));
+
+        let normal: AncestorLengthDistribution = "normal:1000,10".parse().unwrap();
+        let mean = (0..1000).map(|_| normal.sample(0, &mut rng)).sum::<usize>() as f64 / 1000.0;
+        assert!((mean - 1000.0).abs() < 2.0, "{mean}");
+
+        let empirical: AncestorLengthDistribution = "empirical:5,7".parse().unwrap();
+        assert!((0..100).all(|_| [5, 7].contains(&empirical.sample(0, &mut rng))));
+        assert_eq!(empirical.min(), Some(5));
+
+        // A list is cycled through by pair index.
+        let list = AncestorLengthDistribution::List(vec![5, 9, 7]);
+        assert_eq!(
+            (0..5)
+                .map(|pair_index| list.sample(pair_index, &mut rng))
+                .collect::<Vec<_>>(),
+            [5, 9, 7, 5, 9]
+        );
+        assert_eq!(list.min(), Some(5));
+
+        for invalid in [
+            "uniform:200-100",
+            "normal:10,-1",
+            "empirical:",
+            "gamma:1,2",
+            "100",
+        ] {
+            assert!(invalid.parse::<AncestorLengthDistribution>().is_err());
+        }
+    }
+}
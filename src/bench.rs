@@ -0,0 +1,211 @@
+use std::{hint::black_box, time::Instant};
+
+use compact_genome::{
+    implementation::{
+        bit_array_kmer::{BitStore, BitView, BitViewSized},
+        DefaultGenome,
+    },
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+use log::info;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    choose_alphabet_and_n::ChooseAlphabetAndN,
+    cli::{BenchCommand, IntoCliAlphabet},
+    error::{Error, Result},
+    n_gram_model::NGramModel,
+    sequence_modifier::SequenceModification,
+};
+
+/// The throughput of one repetition of the benchmark.
+#[derive(Debug, Clone, Copy)]
+pub struct BenchResult {
+    /// The windows of the training sequence counted per second by [`NGramModel::from_sequences`].
+    pub training_windows_per_second: f64,
+    /// The characters generated per second by [`NGramModel::generate_sequence`].
+    pub generated_characters_per_second: f64,
+    /// The template switches applied per second by [`SequenceModification::apply`].
+    pub template_switches_per_second: f64,
+    /// The template switches applied per second by [`SequenceModification::apply_with_buffer`] with a reused buffer.
+    pub buffered_template_switches_per_second: f64,
+}
+
+/// The length of the template switches applied by the benchmark.
+const TEMPLATE_SWITCH_LENGTH: usize = 100;
+/// The offset of the template switches applied by the benchmark, such that their source lies upstream of the replaced interval.
+const TEMPLATE_SWITCH_OFFSET: isize = -2 * TEMPLATE_SWITCH_LENGTH as isize;
+
+/// Measures the throughput of training and generating with an n-gram model.
+///
+/// The model is trained on a uniformly random sequence, so it contains nearly all n-grams of the alphabet.
+/// Afterwards, length-preserving template switches are applied to the generated sequence.
+pub struct Bench;
+
+impl ChooseAlphabetAndN for Bench {
+    type Arguments = BenchCommand;
+
+    type Return = Vec<BenchResult>;
+
+    fn call<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        BitArrayType: BitViewSized
+            + BitStore
+            + BitView<Store = BitArrayType>
+            + Serialize
+            + for<'de> Deserialize<'de>
+            + Send
+            + Sync,
+        AlphabetType: 'static + Alphabet + IntoCliAlphabet + Send + Sync,
+    >(
+        bench_command: Self::Arguments,
+    ) -> Result<Self::Return>
+    where
+        [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
+        AlphabetType::CharacterType: Send + Sync,
+    {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(bench_command.random_seed);
+        let training_sequence: DefaultGenome<AlphabetType> = (0..bench_command.training_length)
+            .map(|_| {
+                AlphabetType::CharacterType::from_index(rng.gen_range(0..AlphabetType::SIZE))
+                    .unwrap()
+            })
+            .collect();
+        let window_amount =
+            NGramModel::<N, ALPHABET_SIZE, AlphabetType, BitArrayType>::window_amount(
+                bench_command.training_length,
+                false,
+            );
+
+        // The source ends at `position + offset + 1`, and the replaced interval ends at `position + length`.
+        let min_position = (TEMPLATE_SWITCH_LENGTH as isize - TEMPLATE_SWITCH_OFFSET - 1) as usize;
+        let template_switch_positions = min_position
+            ..=bench_command
+                .generated_length
+                .saturating_sub(TEMPLATE_SWITCH_LENGTH);
+        if bench_command.template_switch_amount > 0 && template_switch_positions.is_empty() {
+            return Err(Error::SequenceTooShortForTemplateSwitch {
+                sequence_length: bench_command.generated_length,
+                template_switch_required_sequence_length: min_position + TEMPLATE_SWITCH_LENGTH,
+            });
+        }
+        let template_switches: Vec<_> = (0..bench_command.template_switch_amount)
+            .map(|_| SequenceModification::TemplateSwitch {
+                position: rng.gen_range(template_switch_positions.clone()),
+                length: TEMPLATE_SWITCH_LENGTH,
+                offset: TEMPLATE_SWITCH_OFFSET,
+                length_difference: 0,
+                inter_sequence: false,
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for repetition in 0..bench_command.repetitions {
+            let training_sequence = training_sequence.clone();
+            let start = Instant::now();
+            let model = NGramModel::<N, ALPHABET_SIZE, AlphabetType, BitArrayType>::from_sequences(
+                [training_sequence],
+                false,
+            );
+            let training_seconds = start.elapsed().as_secs_f64();
+
+            let start = Instant::now();
+            let generated_sequence: DefaultGenome<AlphabetType> = model.generate_sequence(
+                bench_command.generated_length,
+                None,
+                false,
+                None,
+                &mut rng,
+                |_| {},
+            )?;
+            let generation_seconds = start.elapsed().as_secs_f64();
+
+            let mut sequence = generated_sequence.clone();
+            let start = Instant::now();
+            for template_switch in &template_switches {
+                template_switch.apply(&mut sequence, &generated_sequence)?;
+            }
+            let template_switch_seconds = start.elapsed().as_secs_f64();
+            black_box(sequence);
+
+            let mut sequence = generated_sequence.clone();
+            let mut buffer = Vec::with_capacity(TEMPLATE_SWITCH_LENGTH);
+            let start = Instant::now();
+            for template_switch in &template_switches {
+                template_switch.apply_with_buffer(
+                    &mut sequence,
+                    &generated_sequence,
+                    &mut buffer,
+                )?;
+            }
+            let buffered_template_switch_seconds = start.elapsed().as_secs_f64();
+            black_box(sequence);
+
+            let result = BenchResult {
+                training_windows_per_second: window_amount as f64 / training_seconds,
+                generated_characters_per_second: bench_command.generated_length as f64
+                    / generation_seconds,
+                template_switches_per_second: template_switches.len() as f64
+                    / template_switch_seconds,
+                buffered_template_switches_per_second: template_switches.len() as f64
+                    / buffered_template_switch_seconds,
+            };
+            info!(
+                "Repetition {}: trained {:.0} windows per second, generated {:.0} characters per second, applied {:.0} template switches per second, or {:.0} with a reused buffer",
+                repetition + 1,
+                result.training_windows_per_second,
+                result.generated_characters_per_second,
+                result.template_switches_per_second,
+                result.buffered_template_switches_per_second,
+            );
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use compact_genome::implementation::alphabets::dna_alphabet::DnaAlphabet;
+
+    use crate::{
+        choose_alphabet_and_n::ChooseAlphabetAndN,
+        cli::{Cli, CliCommands},
+    };
+
+    use super::Bench;
+
+    #[test]
+    fn runs() {
+        let CliCommands::Bench(command) = Cli::parse_from([
+            "tsgen",
+            "bench",
+            "--training-length",
+            "1000",
+            "--generated-length",
+            "1000",
+            "--template-switch-amount",
+            "100",
+            "--repetitions",
+            "2",
+        ])
+        .command
+        else {
+            panic!("wrong subcommand");
+        };
+
+        let results = Bench::call::<3, 4, u8, DnaAlphabet>(command).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| {
+            result.training_windows_per_second > 0.0
+                && result.generated_characters_per_second > 0.0
+                && result.template_switches_per_second > 0.0
+                && result.buffered_template_switches_per_second > 0.0
+        }));
+    }
+}
@@ -0,0 +1,300 @@
+use std::io::Write;
+
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+use rand::Rng;
+
+use crate::{
+    cli::{CheckCommand, ModificationAmountResolver, SequenceModificationRate},
+    error::{Error, Result},
+    generate::{generate_pair, AncestorSource, PairParameters, PairRngs},
+    random_stream::{RandomStream, RngAlgorithm},
+    sequence_modifier::{
+        position_bias::PositionBias, smallest_template_switch, SequenceModification,
+    },
+};
+
+/// Whether the requested template switches fit into the ancestor.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Verdict {
+    /// The geometry allows the template switches, and all trial rolls applied them.
+    Feasible,
+    /// The geometry allows the template switches, but some trial rolls failed to apply them.
+    Unlikely,
+    /// The geometry does not allow the template switches.
+    Infeasible,
+}
+
+/// The result of checking a parameter set against an ancestor length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckReport {
+    pub ancestor_length: usize,
+    /// The amount of template switches of the sequence that receives the larger share.
+    pub template_switch_amount: usize,
+    /// The template switch margin for the ancestor length.
+    pub template_switch_margin: usize,
+    /// The shortest sequence that fits a single template switch with its margin.
+    pub single_template_switch_length: usize,
+    /// The characters covered by `template_switch_amount` non-overlapping template switches including their margins,
+    /// or `None` if template switches may overlap.
+    pub packed_template_switch_length: Option<usize>,
+    pub trials: usize,
+    /// The trial rolls that applied all requested template switches.
+    pub successful_trials: usize,
+    pub verdict: Verdict,
+    /// The constraint that caused a verdict other than [`Verdict::Feasible`].
+    pub limiting_constraint: Option<String>,
+}
+
+/// Checks whether the template switches of the parameter set fit into an ancestor of the given length.
+///
+/// First, the geometry of the template switches is analysed like in [`SequenceModifier::next`](crate::sequence_modifier::SequenceModifier::next) and the [`TemplateSwitchOverlapDetector`](crate::sequence_modifier::template_switch_overlap_detector::TemplateSwitchOverlapDetector),
+/// using the template switches that require the fewest characters.
+/// If it allows the template switches, then the modifications are applied to a few uniformly random ancestors.
+/// Position biases, hotspots, allowed regions and rate multipliers are not considered.
+pub fn check(check_command: &CheckCommand) -> Result<CheckReport> {
+    let parameters = &check_command.sequence_modification_parameters;
+    let ancestor_length = check_command.ancestor_length;
+    let amount = check_command
+        .sequence_modification_amount
+        .template_switch_amount;
+    let larger_fraction = check_command
+        .reference_ancestry_fraction
+        .max(1.0 - check_command.reference_ancestry_fraction);
+    let template_switch_amount = (amount as f64 * larger_fraction).ceil() as usize;
+    let template_switch_margin = parameters.resolve_template_switch_margin(ancestor_length);
+
+    let (single_template_switch_length, packed_width) =
+        smallest_template_switch(parameters, template_switch_margin)?;
+    let packed_template_switch_length = (!parameters.template_switch_overlap)
+        .then(|| template_switch_amount.saturating_mul(packed_width));
+
+    let mut report = CheckReport {
+        ancestor_length,
+        template_switch_amount,
+        template_switch_margin,
+        single_template_switch_length,
+        packed_template_switch_length,
+        trials: check_command.trials,
+        successful_trials: 0,
+        verdict: Verdict::Feasible,
+        limiting_constraint: None,
+    };
+
+    if template_switch_amount > 0 && single_template_switch_length > ancestor_length {
+        report.verdict = Verdict::Infeasible;
+        report.limiting_constraint = Some(format!("a single template switch with the given lengths, offsets and margin requires a sequence length of {single_template_switch_length}, but the ancestor length is {ancestor_length}"));
+        return Ok(report);
+    }
+    if let Some(packed_template_switch_length) =
+        packed_template_switch_length.filter(|length| *length > ancestor_length)
+    {
+        report.verdict = Verdict::Infeasible;
+        report.limiting_constraint = Some(format!("{template_switch_amount} non-overlapping template switches including their margins cover at least {packed_template_switch_length} characters, but the ancestor length is {ancestor_length}"));
+        return Ok(report);
+    }
+
+    for trial in 0..check_command.trials {
+        match trial_roll(check_command, trial) {
+            Ok(()) => report.successful_trials += 1,
+            Err(error) => {
+                report.verdict = Verdict::Unlikely;
+                report.limiting_constraint.get_or_insert(error.to_string());
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Generates a pair from a uniformly random ancestor with [`generate_pair`] like `generate-pair`,
+/// and returns an error if not all template switches were applied.
+fn trial_roll(check_command: &CheckCommand, trial: usize) -> Result<()> {
+    let rngs = || {
+        let [ancestor, split, reference, query] = [
+            RandomStream::Ancestor,
+            RandomStream::Split,
+            RandomStream::Reference,
+            RandomStream::Query,
+        ]
+        .map(|stream| stream.rng(check_command.random_seed, trial, RngAlgorithm::Xoshiro256));
+        PairRngs {
+            ancestor,
+            split,
+            reference,
+            query,
+            modification_types: Default::default(),
+        }
+    };
+
+    // The geometry does not depend on the alphabet.
+    let mut ancestor_rng = rngs().ancestor;
+    let ancestor: DefaultGenome<DnaAlphabet> = (0..check_command.ancestor_length)
+        .map(|_| {
+            <DnaAlphabet as Alphabet>::CharacterType::from_index(
+                ancestor_rng.gen_range(0..DnaAlphabet::SIZE),
+            )
+            .unwrap()
+        })
+        .collect();
+    let ancestor_source =
+        AncestorSource::<1, { DnaAlphabet::SIZE }, _, u8>::Sequence(ancestor, None);
+
+    let parameters = PairParameters {
+        reference_ancestry_fraction: check_command.reference_ancestry_fraction,
+        modification_amount_resolver: ModificationAmountResolver {
+            amount: check_command.sequence_modification_amount,
+            rate: SequenceModificationRate {
+                template_switch_rate: None,
+                gap_rate: None,
+                substitution_rate: None,
+            },
+            draws_types: false,
+        },
+        modification_type_probabilities: None,
+        sequence_modification_parameters: check_command.sequence_modification_parameters,
+        position_bias: PositionBias::Uniform,
+        allowed_intervals: None,
+        rate_multiplier_intervals: None,
+        max_edit_distance: None,
+        template_switch_edit_cost: 1.0,
+        realistic_starts: false,
+        restart_limit: None,
+        target_gc: None,
+        target_gc_tries: 1,
+        max_homopolymer: None,
+        max_homopolymer_tries: 1,
+        require_template_switch: None,
+        require_template_switch_tries: 1,
+        retain_ancestor: false,
+        track_ancestry: false,
+        track_substitutions: false,
+    };
+    let pair = generate_pair(&ancestor_source, &parameters, trial, |_| rngs(), |_, _| {})?;
+
+    let requested = check_command
+        .sequence_modification_amount
+        .template_switch_amount;
+    let realized = pair
+        .reference_modifications
+        .iter()
+        .chain(&pair.query_modifications)
+        .filter(|modification| matches!(modification, SequenceModification::TemplateSwitch { .. }))
+        .count();
+    if realized < requested {
+        return Err(Error::ModificationAmountNotReached {
+            modification_type: "template switches",
+            requested,
+            realized,
+        });
+    }
+
+    Ok(())
+}
+
+impl CheckReport {
+    /// Writes the report as tab-separated key-value pairs.
+    pub fn write(&self, mut writer: impl Write) -> Result<()> {
+        writeln!(writer, "ancestor_length\t{}", self.ancestor_length)?;
+        writeln!(
+            writer,
+            "template_switch_amount\t{}",
+            self.template_switch_amount
+        )?;
+        writeln!(
+            writer,
+            "template_switch_margin\t{}",
+            self.template_switch_margin
+        )?;
+        writeln!(
+            writer,
+            "single_template_switch_length\t{}",
+            self.single_template_switch_length
+        )?;
+        if let Some(packed_template_switch_length) = self.packed_template_switch_length {
+            writeln!(
+                writer,
+                "packed_template_switch_length\t{packed_template_switch_length}"
+            )?;
+        }
+        writeln!(
+            writer,
+            "successful_trials\t{}/{}",
+            self.successful_trials, self.trials
+        )?;
+        writeln!(
+            writer,
+            "verdict\t{}",
+            match self.verdict {
+                Verdict::Feasible => "feasible",
+                Verdict::Unlikely => "unlikely",
+                Verdict::Infeasible => "infeasible",
+            }
+        )?;
+        if let Some(limiting_constraint) = &self.limiting_constraint {
+            writeln!(writer, "limiting_constraint\t{limiting_constraint}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::cli::{Cli, CliCommands};
+
+    use super::{check, Verdict};
+
+    #[test]
+    fn verdicts() {
+        let report = |arguments: &[&str]| {
+            let CliCommands::Check(mut command) =
+                Cli::parse_from(["tsgen", "check", "--trials", "3"].iter().chain(arguments))
+                    .command
+            else {
+                panic!("wrong subcommand");
+            };
+            command
+                .sequence_modification_parameters
+                .set_alphabet_defaults(command.alphabet);
+            command.verify().unwrap();
+            check(&command).unwrap()
+        };
+
+        let feasible = report(&["--ancestor-length", "1000"]);
+        assert_eq!(feasible.template_switch_amount, 1);
+        assert_eq!(feasible.successful_trials, 3);
+        assert_eq!(feasible.verdict, Verdict::Feasible);
+
+        // The shortest template switches have length 10 and length difference 10,
+        // so with a non-negative offset up to 10 they touch 11 characters besides their margins.
+        let single = report(&["--ancestor-length", "100", "--template-switch-margin", "50"]);
+        assert_eq!(single.single_template_switch_length, 111);
+        assert_eq!(single.verdict, Verdict::Infeasible);
+
+        // The detector reserves 10 characters besides the margins for each of them.
+        let packed = report(&["--ancestor-length", "100", "--template-switch-amount", "8"]);
+        assert_eq!(packed.template_switch_amount, 4);
+        assert_eq!(packed.packed_template_switch_length, Some(120));
+        assert_eq!(packed.verdict, Verdict::Infeasible);
+        assert_eq!(packed.successful_trials, 0);
+
+        let unlikely = report(&[
+            "--ancestor-length",
+            "200",
+            "--template-switch-amount",
+            "20",
+            "--template-switch-margin",
+            "0",
+            "--template-switch-maximum-overlap-tries",
+            "10",
+        ]);
+        assert_eq!(unlikely.packed_template_switch_length, Some(100));
+        assert_eq!(unlikely.verdict, Verdict::Unlikely);
+        assert!(unlikely.successful_trials < 3);
+        assert!(unlikely.limiting_constraint.is_some());
+    }
+}
@@ -10,8 +10,12 @@ use serde::{Deserialize, Serialize};
 use crate::{
     cli::{CliAlphabet, IntoCliAlphabet},
     error::{Error, Result},
+    reduced_alphabet::PurinePyrimidineAlphabet,
 };
 
+/// The largest n for which a model can be created or used.
+pub const MAX_N: usize = 9;
+
 pub fn call<Function: ChooseAlphabetAndN>(
     alphabet: CliAlphabet,
     n: usize,
@@ -21,12 +25,17 @@ pub fn call<Function: ChooseAlphabetAndN>(
         CliAlphabet::Dna => {
             with_alphabet::<{ DnaAlphabet::SIZE }, DnaAlphabet, Function>(n, arguments)
         }
+        CliAlphabet::PurinePyrimidine => {
+            with_alphabet::<{ PurinePyrimidineAlphabet::SIZE }, PurinePyrimidineAlphabet, Function>(
+                n, arguments,
+            )
+        }
     }
 }
 
 fn with_alphabet<
     const ALPHABET_SIZE: usize,
-    AlphabetType: 'static + Alphabet + IntoCliAlphabet,
+    AlphabetType: 'static + Alphabet + IntoCliAlphabet + Send + Sync,
     Function: ChooseAlphabetAndN,
 >(
     n: usize,
@@ -34,6 +43,7 @@ fn with_alphabet<
 ) -> Result<Function::Return>
 where
     [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
+    AlphabetType::CharacterType: Send + Sync,
 {
     match n {
         0 => with_alphabet_and_n::<0, ALPHABET_SIZE, AlphabetType, Function>(arguments),
@@ -45,7 +55,7 @@ where
         6 => with_alphabet_and_n::<6, ALPHABET_SIZE, AlphabetType, Function>(arguments),
         7 => with_alphabet_and_n::<7, ALPHABET_SIZE, AlphabetType, Function>(arguments),
         8 => with_alphabet_and_n::<8, ALPHABET_SIZE, AlphabetType, Function>(arguments),
-        9 => with_alphabet_and_n::<9, ALPHABET_SIZE, AlphabetType, Function>(arguments),
+        MAX_N => with_alphabet_and_n::<MAX_N, ALPHABET_SIZE, AlphabetType, Function>(arguments),
         n => Err(Error::UnsupportedN(n)),
     }
 }
@@ -53,13 +63,14 @@ where
 fn with_alphabet_and_n<
     const N: usize,
     const ALPHABET_SIZE: usize,
-    AlphabetType: 'static + Alphabet + IntoCliAlphabet,
+    AlphabetType: 'static + Alphabet + IntoCliAlphabet + Send + Sync,
     Function: ChooseAlphabetAndN,
 >(
     arguments: Function::Arguments,
 ) -> Result<Function::Return>
 where
     [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
+    AlphabetType::CharacterType: Send + Sync,
 {
     let n_gram_bit_width = (ALPHABET_SIZE + 1).ilog2() as usize;
     let bit_width = n_gram_bit_width * N;
@@ -92,13 +103,16 @@ pub trait ChooseAlphabetAndN {
             + BitStore
             + BitView<Store = BitArrayType>
             + Serialize
-            + for<'de> Deserialize<'de>,
-        AlphabetType: 'static + Alphabet + IntoCliAlphabet,
+            + for<'de> Deserialize<'de>
+            + Send
+            + Sync,
+        AlphabetType: 'static + Alphabet + IntoCliAlphabet + Send + Sync,
     >(
         arguments: Self::Arguments,
     ) -> Result<Self::Return>
     where
-        [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>;
+        [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
+        AlphabetType::CharacterType: Send + Sync;
 }
 
 #[cfg(test)]
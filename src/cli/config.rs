@@ -0,0 +1,212 @@
+use std::{ffi::OsString, fs, path::PathBuf};
+
+use clap::{parser::ValueSource, Arg, CommandFactory};
+use toml::{Table, Value};
+
+use crate::error::{Error, Result};
+
+use super::{Cli, GeneratePairCommand};
+
+/// The name of the subcommand that accepts a `--config` file.
+const CONFIG_SUBCOMMAND: &str = "generate-pair";
+
+/// Inserts the values of the `--config` file of the generate-pair subcommand into the command line arguments.
+///
+/// The values are inserted as arguments directly after the subcommand,
+/// skipping all arguments that are also given on the command line.
+/// Hence the merged arguments are parsed and verified as if they were all given on the command line.
+/// If no config file is given, then the arguments are returned unchanged.
+pub fn arguments_with_config(
+    arguments: impl IntoIterator<Item = impl Into<OsString>>,
+) -> Result<Vec<OsString>> {
+    let mut arguments: Vec<OsString> = arguments.into_iter().map(Into::into).collect();
+
+    // Missing required arguments may be given by the config file,
+    // and all other errors are reported when parsing the merged arguments.
+    let matches = Cli::command()
+        .ignore_errors(true)
+        .get_matches_from(arguments.clone());
+    let Some(subcommand_matches) = matches.subcommand_matches(CONFIG_SUBCOMMAND) else {
+        return Ok(arguments);
+    };
+    let Some(config) = subcommand_matches.get_one::<PathBuf>("config") else {
+        return Ok(arguments);
+    };
+    let config: Table = toml::from_str(&fs::read_to_string(config)?)?;
+
+    let command = Cli::command();
+    let subcommand = command.find_subcommand(CONFIG_SUBCOMMAND).unwrap();
+    let mut config_arguments = Vec::new();
+
+    for (key, value) in config {
+        let Some(argument) = subcommand
+            .get_arguments()
+            .find(|argument| argument.get_long() == Some(&key) && argument.get_id() != "config")
+        else {
+            return Err(Error::UnknownConfigKey(key));
+        };
+        if subcommand_matches.value_source(argument.get_id().as_str())
+            == Some(ValueSource::CommandLine)
+        {
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Boolean(true) => {
+                    config_arguments.push(format!("--{key}").into());
+                    continue;
+                }
+                Value::Boolean(false) => continue,
+                Value::String(value) => value,
+                Value::Integer(value) => value.to_string(),
+                Value::Float(value) => value.to_string(),
+                value => {
+                    return Err(Error::InvalidConfigValue {
+                        key,
+                        value: value.to_string(),
+                    })
+                }
+            };
+            // The `=` keeps negative numbers from being interpreted as arguments.
+            config_arguments.push(format!("--{key}={value}").into());
+        }
+    }
+
+    // The program name and the subcommand come first.
+    arguments.splice(2..2, config_arguments);
+    Ok(arguments)
+}
+
+/// Returns the fully resolved arguments of the generate-pair subcommand in the format of a `--config` file.
+///
+/// Default values that conflict with a set argument, such as `--template-switch-margin` if `--template-switch-margin-fraction` is set,
+/// are left out, such that the result can be used as config file again.
+pub fn resolved_config(generate_pair_command: &GeneratePairCommand) -> Result<String> {
+    let mut config = Table::try_from(generate_pair_command)?;
+
+    let mut command = Cli::command();
+    command.build();
+    let subcommand = command.find_subcommand(CONFIG_SUBCOMMAND).unwrap();
+    let is_set_without_default = |argument: &Arg| {
+        argument.get_default_values().is_empty()
+            && argument
+                .get_long()
+                .is_some_and(|key| config.contains_key(key))
+    };
+    let conflicting_keys: Vec<_> = subcommand
+        .get_arguments()
+        .filter(|argument| {
+            subcommand
+                .get_arg_conflicts_with(argument)
+                .into_iter()
+                .any(is_set_without_default)
+        })
+        .filter_map(|argument| argument.get_long())
+        .collect();
+    for key in conflicting_keys {
+        config.remove(key);
+    }
+
+    Ok(toml::to_string(&config)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+
+    use crate::cli::{Cli, CliCommands, CliPositionBias};
+
+    use super::{arguments_with_config, resolved_config};
+
+    #[test]
+    fn config_file() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let config = output_directory.path().join("config.toml");
+        fs::write(
+            &config,
+            "output = \"pair.fa\"\n\
+             ancestor-length = 500\n\
+             gap-amount = 20\n\
+             template-switch-min-offset = -5\n\
+             template-switch-overlap = true\n\
+             position-bias = \"hotspots\"\n\
+             hotspots = [100, 200]\n\
+             hotspot-spread = 10.0\n\
+             template-switch-margin-fraction = 0.1\n",
+        )
+        .unwrap();
+
+        let arguments = arguments_with_config([
+            "tsgen".as_ref(),
+            "generate-pair".as_ref(),
+            "--config".as_ref(),
+            config.as_os_str(),
+            "--model".as_ref(),
+            "model".as_ref(),
+            "--gap-amount".as_ref(),
+            "30".as_ref(),
+            "--hotspots".as_ref(),
+            "300".as_ref(),
+        ])
+        .unwrap();
+        let CliCommands::GeneratePair(command) = Cli::parse_from(arguments).command else {
+            panic!("wrong subcommand");
+        };
+        command.verify().unwrap();
+
+        assert_eq!(
+            command.output.as_deref().and_then(|output| output.to_str()),
+            Some("pair.fa")
+        );
+        assert_eq!(command.ancestor_length, Some(500));
+        assert_eq!(command.sequence_modification_amount.gap_amount, 30);
+        assert_eq!(command.sequence_modification_amount.substitution_amount, 10);
+        assert_eq!(
+            command
+                .sequence_modification_parameters
+                .template_switch_min_offset,
+            -5
+        );
+        assert!(
+            command
+                .sequence_modification_parameters
+                .template_switch_overlap
+        );
+        assert!(
+            command.sequence_modification_parameters.position_bias == CliPositionBias::Hotspots
+        );
+        assert_eq!(command.hotspots, [300]);
+
+        // The resolved configuration can be used as config file again.
+        let resolved = resolved_config(&command).unwrap();
+        assert!(!resolved.contains("template-switch-margin ="));
+        fs::write(&config, &resolved).unwrap();
+        let arguments = arguments_with_config([
+            "tsgen".as_ref(),
+            "generate-pair".as_ref(),
+            "--config".as_ref(),
+            config.as_os_str(),
+        ])
+        .unwrap();
+        let CliCommands::GeneratePair(resolved_command) = Cli::parse_from(arguments).command else {
+            panic!("wrong subcommand");
+        };
+        assert_eq!(resolved_config(&resolved_command).unwrap(), resolved);
+
+        fs::write(&config, "gap-count = 1\n").unwrap();
+        assert!(arguments_with_config([
+            "tsgen".as_ref(),
+            "generate-pair".as_ref(),
+            "--config".as_ref(),
+            config.as_os_str(),
+        ])
+        .is_err());
+    }
+}
@@ -1,9 +1,35 @@
-use std::path::PathBuf;
+use std::{
+    ffi::OsString,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use compact_genome::implementation::alphabets::dna_alphabet::DnaAlphabet;
+use log::warn;
+use serde::Serialize;
 
-use crate::error::{Error, Result};
+use crate::{
+    alignment::AlignmentReference,
+    ancestor_length::{read_ancestor_lengths_file, AncestorLengthDistribution},
+    error::{Error, Result},
+    generate::{PairParameters, PairRngs},
+    n_gram_model::{Composition, ModelFormat, RestartLimit},
+    pair_output_path,
+    random_stream::{retry_seed, RandomStream, RngAlgorithm},
+    reduced_alphabet::AlphabetReduction,
+    sequence_modifier::{
+        allowed_regions::AllowedIntervals, ancestry::ModificationFrame,
+        length_difference::LengthDifferenceDistribution, position_bias::PositionBias,
+        rate_multipliers::RateMultipliers, smallest_template_switch, template_switch_self_overlaps,
+        ModificationTypeRngs,
+    },
+    sweep::SweepParameter,
+};
+
+pub use config::{arguments_with_config, resolved_config};
+
+mod config;
 
 #[derive(Parser)]
 #[command(version)]
@@ -14,91 +40,189 @@ pub struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum CliCommands {
     CreateNGramModel(CreateModelCommand),
     GeneratePair(GeneratePairCommand),
+    /// Generate sequences from a model without modifying them.
+    Generate(GenerateCommand),
+    Validate(ValidateCommand),
+    /// Summarize the stats and template switch outputs of a set of generated pairs.
+    Summarize(SummarizeCommand),
+    /// Check whether the template switches of a parameter set fit into an ancestor of the given length.
+    Check(CheckCommand),
+    /// Generate pairs for all combinations of the given values of generate-pair parameters.
+    Sweep(SweepCommand),
+    /// Measure the throughput of training and generating with an n-gram model, to track performance regressions.
+    #[command(hide = true)]
+    Bench(BenchCommand),
 }
 
 #[derive(Args)]
-pub struct CreateModelCommand {
-    /// The input fasta file that contains the sequences used to create the model.
+pub struct GenerateCommand {
+    /// The path to an n-gram model file that was generated by this tool.
+    /// Gzip-compressed models are decompressed automatically.
     #[arg(short, long)]
-    pub input_fasta: PathBuf,
-
-    /// The alphabet expected in the input file.
-    #[arg(short, long, default_value = "dna")]
-    pub alphabet: CliAlphabet,
+    pub model: PathBuf,
 
-    /// The output file in which the model is stored.
-    /// It will be overwritten if it already exists.
+    /// The output fasta file of the generated sequences.
+    /// If the path ends in `.gz`, then the output is compressed with gzip.
     #[arg(short, long)]
     pub output: PathBuf,
 
-    /// Compute n-grams after removing all unknown characters.
+    /// The length of each generated sequence.
     ///
-    /// If this is not set, then unknown characters will result in an error.
+    /// With `--continue-from`, this is the length of the generated continuation, without the continued sequence.
     #[arg(short, long)]
-    pub skip_unknown_characters: bool,
+    pub length: usize,
 
-    /// Compute n-grams after capitalising all unknown characters.
+    /// Continue the first record of this fasta file instead of starting each sequence at a random n-gram.
     ///
-    /// Capitalisation happens before skipping unknown characters.
-    #[arg(short, long)]
-    pub capitalise_characters: bool,
+    /// The last n characters of the record are the first n-gram, which must have a successor in the model.
+    /// Gzip-compressed files are decompressed automatically, and lower case characters are capitalised.
+    #[arg(long)]
+    pub continue_from: Option<PathBuf>,
 
-    /// The number of predecessor characters that determine the probability of the next character.
+    /// Write the continued sequence followed by the generated continuation, instead of only the continuation.
+    #[arg(long, requires = "continue_from")]
+    pub concatenate: bool,
+
+    /// The number of sequences to generate.
+    #[arg(long, default_value = "1")]
+    pub num_sequences: usize,
+
+    /// The record id of each sequence.
     ///
-    /// Setting this to zero means that all characters are generated independently,
-    /// setting it to one means that characters are generated with a probability depending on the previous one character, and so on.
-    #[arg(short, long)]
-    pub n_gram_context_length: usize,
+    /// The placeholder `{index}` is replaced by the zero-based index of the sequence,
+    /// and is required if more than one sequence is generated.
+    #[arg(long, default_value = "sequence{index}")]
+    pub id: String,
+
+    /// The seed of the random generators.
+    ///
+    /// Each sequence draws from its own random generator, which is seeded from this seed and the index of the sequence.
+    /// Hence each sequence is the same as the ancestor of the pair with the same index generated by `generate-pair`
+    /// with the same model, seed, `--ancestor-length` and generation arguments, unless `--circular` is set.
+    #[arg(long, default_value = "0")]
+    pub random_seed: u64,
+
+    /// The algorithm of the random generators.
+    #[arg(long, value_enum, default_value = "xoshiro256")]
+    pub rng: RngAlgorithm,
+
+    /// Draw the first n-gram of each sequence from the first n-grams of the training sequences of the model.
+    ///
+    /// A continued sequence starts at the last n-gram of `--continue-from`, so this cannot be combined with it.
+    #[arg(long, conflicts_with = "continue_from")]
+    pub realistic_starts: bool,
+
+    /// Generate circular sequences, e.g. bacterial genomes or plasmids.
+    ///
+    /// The last n characters of each sequence are drawn again, such that the n-grams that wrap around
+    /// from the end to the start of the sequence have the following characters as successors in the model.
+    /// The replacement is drawn proportionally to its probability under the model of order n, without `--interpolation-weights`.
+    /// The length must be at least twice n.
+    #[arg(long, conflicts_with = "continue_from")]
+    pub circular: bool,
+
+    #[command(flatten)]
+    pub n_gram_generation_parameters: NGramGenerationParameters,
+
+    /// The maximum number of characters per sequence line in the fasta output.
+    /// If zero, each sequence is written on a single line.
+    #[arg(long, default_value = "0")]
+    pub line_width: usize,
+
+    /// Show a progress bar of the generated sequences.
+    ///
+    /// The progress bar is only shown if standard error is a terminal.
+    #[arg(long)]
+    pub progress: bool,
 }
 
 #[derive(Args)]
-pub struct GeneratePairCommand {
-    /// The path to an n-gram model file that was generated by this tool.
-    #[arg(short = 'm', long)]
+pub struct ValidateCommand {
+    /// The model file to validate.
+    ///
+    /// Only the header and the structure of the model are checked, the n-grams are not interpreted.
+    #[arg(short, long)]
     pub model: PathBuf,
+}
 
-    /// The path to the fasta output file to generate.
-    /// It will be overwritten if it already exists.
-    #[arg(short = 'o', long)]
-    pub output: PathBuf,
+#[derive(Args)]
+pub struct SummarizeCommand {
+    /// The directory containing the outputs of the pairs.
+    ///
+    /// Files whose names start with `--stats-prefix` and end with `.tsv` are read as `--stats-output` of `generate-pair`,
+    /// and files whose names start with `--template-switch-prefix` and end with `.json` as `--template-switch-json`.
+    /// Other TSV and JSON files, like spectra and manifests, are skipped with a warning, and all other files are ignored.
+    /// The summary is printed to standard output.
+    #[arg(short, long)]
+    pub input_directory: PathBuf,
 
-    /// If set, the common ancestor will be stored in this fasta file.
-    /// It will be overwritten if it already exists.
-    #[arg(long)]
-    pub ancestor_output: Option<PathBuf>,
+    /// The prefix of the names of the `--stats-output` files.
+    #[arg(long, default_value = "stats")]
+    pub stats_prefix: String,
 
-    /// If set, the edit operations on the reference and query will be stored in this text file.
-    /// It will be overwritten if it already exists.
-    #[arg(long)]
-    pub debug_output: Option<PathBuf>,
+    /// The prefix of the names of the `--template-switch-json` files.
+    #[arg(long, default_value = "template-switches")]
+    pub template_switch_prefix: String,
+}
 
-    /// The length of the ancestor sequence to generate.
-    #[arg(short = 'l', long)]
-    pub ancestor_length: usize,
+#[derive(Args)]
+pub struct SweepCommand {
+    /// The directory of the outputs.
+    ///
+    /// Each combination writes `pair.fa` and its other outputs into the subdirectory `combination<index>`,
+    /// and `sweep.json` maps the outputs of each combination to its parameters.
+    #[arg(short, long)]
+    pub output_directory: PathBuf,
+
+    /// A generate-pair parameter and its values, as `KEY=VALUE,VALUE,...`, e.g. `substitution-amount=10,50,100`.
+    ///
+    /// The key is the long name of the argument without the leading dashes.
+    /// The amounts of the modification types, `reference-ancestry-fraction`, `gap-length-mean`, `template-switch-margin`,
+    /// `inter-sequence-template-switch-probability` and the bounds of the template switch lengths, offsets and length differences can be swept.
+    /// This can be given multiple times, and pairs are generated for the cartesian product of all values.
+    #[arg(short, long = "parameter", required = true)]
+    pub parameters: Vec<SweepParameter>,
 
-    /// The seed to use for the random generator.
+    /// The seed from which the seed of each combination is derived, together with the index of the combination.
     #[arg(long, default_value = "0")]
     pub random_seed: u64,
 
-    /// The closeness of the reference to the common ancestor, between 0.0 and 1.0.
+    /// The arguments of generate-pair shared by all combinations, e.g. `-- --model model.bin --ancestor-length 1000`.
     ///
-    /// A pair is generated by first generating a common ancestor, and then modifying it into both a reference and a query sequence.
-    /// This value decides what fraction of all modifications are performed on the reference, and what on the query.
-    /// The higher this value, the closer the reference is to the common ancestor, and hence the more modifications are performed on the query.
-    ///
-    /// If it is not possible to distribute the modifications between reference and query adhering this ratio,
-    /// then a modification is randomly placed on either the reference or the query.
-    ///
-    /// **Examples:**
-    /// If this value is 1.0, then all modifications are performed on the query.
-    /// If this value is 0.0, then all modifications are performed on the reference.
-    /// For the default value of 0.5, half the modifications are performed on the reference, and the other half are performed on the query.
+    /// A `--config` file may be given as well. The output and the random seed are set by the sweep.
+    /// Other output files, e.g. `--stats-output`, are written into the directory of each combination, keeping their file names.
+    #[arg(last = true)]
+    pub arguments: Vec<OsString>,
+}
+
+#[derive(Args)]
+pub struct CheckCommand {
+    /// The length of the ancestor.
+    #[arg(short = 'l', long)]
+    pub ancestor_length: usize,
+
+    /// The alphabet of the ancestor, which determines the defaults of the template switch lengths and the gap length mean.
+    #[arg(short, long, default_value = "dna")]
+    pub alphabet: CliAlphabet,
+
+    /// The closeness of the reference to the common ancestor, between 0.0 and 1.0, see `generate-pair`.
     #[arg(long, default_value = "0.5")]
     pub reference_ancestry_fraction: f64,
 
+    /// The number of trial rolls that apply the modifications to a uniformly random ancestor.
+    ///
+    /// If zero, then only the geometry of the template switches is analysed.
+    #[arg(long, default_value = "10")]
+    pub trials: usize,
+
+    /// The seed of the random generators of the trial rolls.
+    #[arg(long, default_value = "0")]
+    pub random_seed: u64,
+
     #[command(flatten)]
     pub sequence_modification_amount: SequenceModificationAmount,
 
@@ -106,159 +230,2584 @@ pub struct GeneratePairCommand {
     pub sequence_modification_parameters: SequenceModificationParameters,
 }
 
-#[derive(Args, Clone, Copy)]
-pub struct SequenceModificationAmount {
-    /// The number of template switches to generate.
-    #[arg(long, default_value = "1")]
+#[derive(Args)]
+pub struct BenchCommand {
+    /// The alphabet of the synthetic sequences.
+    #[arg(short, long, default_value = "dna")]
+    pub alphabet: CliAlphabet,
+
+    /// The number of predecessor characters that determine the probability of the next character.
+    #[arg(short, long, default_value = "4")]
+    pub n_gram_context_length: usize,
+
+    /// The length of the uniformly random sequence on which the model is trained.
+    #[arg(long, default_value = "1000000")]
+    pub training_length: usize,
+
+    /// The length of the sequence generated from the model.
+    #[arg(long, default_value = "1000000")]
+    pub generated_length: usize,
+
+    /// The number of template switches applied to the generated sequence, once with a new copy of each source and once with a reused buffer.
+    ///
+    /// The template switches have length 100, so the generated length must be at least 400 if this is positive.
+    #[arg(long, default_value = "10000")]
     pub template_switch_amount: usize,
 
-    /// The number of gaps to generate.
-    #[arg(long, default_value = "10")]
-    pub gap_amount: usize,
+    /// The number of times that training and generating are measured.
+    #[arg(long, default_value = "3")]
+    pub repetitions: usize,
 
-    /// The number of substitutions to generate.
-    #[arg(long, default_value = "10")]
-    pub substitution_amount: usize,
+    /// The seed of the random generator of the synthetic sequences.
+    #[arg(long, default_value = "0")]
+    pub random_seed: u64,
 }
 
-#[derive(Args, Clone, Copy)]
-pub struct SequenceModificationParameters {
-    /// If set, template switches are allowed to overlap.
+#[derive(Args)]
+pub struct CreateModelCommand {
+    /// The input fasta file that contains the sequences used to create the model.
+    ///
+    /// This can be given multiple times to create the model from the sequences of all files.
+    #[arg(short, long, required = true)]
+    pub input_fasta: Vec<PathBuf>,
+
+    /// The weight of each `--input-fasta` file, given once per file in the same order.
+    ///
+    /// The n-grams of each sequence are counted as often as the weight of its file,
+    /// i.e. a file with weight 2 contributes as if it was given twice.
+    /// If not given, then all files have weight 1.
     #[arg(long)]
-    pub template_switch_overlap: bool,
+    pub input_weight: Vec<u32>,
 
-    /// The maximum number of tries to create a non-overlapping template switch.
-    /// If the maximum number of tries is reached and `--template-switch-overlap` is not set,
-    /// then the generation aborts with an error.
-    #[arg(long, default_value = "10000")]
-    pub template_switch_maximum_overlap_tries: usize,
+    /// The alphabet expected in the input file.
+    #[arg(short, long, default_value = "dna")]
+    pub alphabet: CliAlphabet,
 
-    /// The minimum length of a template switch.
-    #[arg(long, default_value = "10")]
-    pub template_switch_min_length: usize,
+    /// The output file in which the model is stored.
+    /// It will be overwritten if it already exists.
+    ///
+    /// If the file name ends in `.gz`, then the model is gzip-compressed, see `--compress`.
+    #[arg(short, long)]
+    pub output: PathBuf,
 
-    /// The maximum length of a template switch.
-    #[arg(long, default_value = "30")]
-    pub template_switch_max_length: usize,
+    /// Gzip-compress the model, including its header with `n` and the alphabet.
+    ///
+    /// Compressed models are detected and decompressed automatically when they are read.
+    #[arg(long)]
+    pub compress: bool,
 
-    /// The minimum offset of a template switch.
-    #[arg(long, allow_negative_numbers = true, default_value = "-30")]
-    pub template_switch_min_offset: isize,
+    /// The format of the model file.
+    ///
+    /// `cbor` is compact, while `json` writes a single self-describing object with `n`, the alphabet,
+    /// the successor abundances of each n-gram and the abundances of the start n-grams, which is larger but readable by other tools.
+    /// The format is detected automatically when the model is read.
+    #[arg(long, value_enum, default_value = "cbor")]
+    pub model_format: ModelFormat,
 
-    /// The maximum offset of a template switch.
-    #[arg(long, allow_negative_numbers = true, default_value = "10")]
-    pub template_switch_max_offset: isize,
+    /// Compute n-grams after removing all unknown characters.
+    ///
+    /// If this is not set, then unknown characters will result in an error.
+    /// The characters around removed characters are joined, so n-grams may span them.
+    #[arg(short, long)]
+    pub skip_unknown_characters: bool,
 
-    /// The minimum length difference of a template switch.
-    #[arg(long, allow_negative_numbers = true, default_value = "-10")]
-    pub template_switch_min_length_difference: isize,
+    /// Compute n-grams on the segments between unknown characters.
+    ///
+    /// Each record is split at its unknown characters, e.g. at a run of `N` in DNA,
+    /// and the segments are treated as separate sequences.
+    /// Unlike with `--skip-unknown-characters`, no n-gram spans the unknown characters.
+    #[arg(long, conflicts_with = "skip_unknown_characters")]
+    pub split_on_unknown: bool,
 
-    /// The maximum length difference of a template switch.
-    #[arg(long, allow_negative_numbers = true, default_value = "10")]
-    pub template_switch_max_length_difference: isize,
+    /// Warn prominently about records that lose more than this fraction of their characters
+    /// to `--skip-unknown-characters` or `--split-on-unknown`, between 0.0 and 1.0.
+    ///
+    /// The amount of removed characters of each record and in total is always reported.
+    #[arg(long, default_value = "0.1")]
+    pub unknown_character_warning_fraction: f64,
 
-    /// The amount of characters at the beginning and end of a sequence that are not allowed to be part of a template switch.
-    #[arg(long, default_value = "10")]
-    pub template_switch_margin: usize,
+    /// Compute n-grams after capitalising all unknown characters.
+    ///
+    /// Capitalisation happens before skipping or splitting on unknown characters.
+    #[arg(short, long)]
+    pub capitalise_characters: bool,
 
-    /// The parameter mean of the exponential distribution determining the gap length.
-    /// Generated gaps always have a minimum length of one.
-    #[arg(long, allow_negative_numbers = true, default_value = "3.0")]
-    pub gap_length_mean: f64,
-}
+    /// Collapse the input characters into a smaller alphabet before computing n-grams.
+    ///
+    /// The input is read in the alphabet given by `--alphabet`, which must be the input alphabet of the reduction.
+    /// Reduction happens after capitalisation and before skipping or splitting on unknown characters.
+    /// The model stores the reduced alphabet, so pairs generated from it are written in the reduced alphabet.
+    #[arg(long)]
+    pub reduce_alphabet: Option<AlphabetReduction>,
 
-#[derive(ValueEnum, Clone, Copy)]
-pub enum CliAlphabet {
-    Dna,
-}
+    /// The number of predecessor characters that determine the probability of the next character.
+    ///
+    /// Setting this to zero means that all characters are generated independently,
+    /// setting it to one means that characters are generated with a probability depending on the previous one character, and so on.
+    #[arg(short, long)]
+    pub n_gram_context_length: usize,
 
-pub trait IntoCliAlphabet {
-    fn into_cli_alphabet() -> CliAlphabet;
+    /// Treat the input sequences as circular, e.g. for bacterial genomes or plasmids.
+    ///
+    /// If set, the n-grams that wrap around from the end to the start of each sequence are counted as well.
+    /// Sequences generated from the model are linear, unless they are generated with `generate --circular`.
+    /// The modifications of `generate-pair` never wrap around the origin of the ancestor.
+    /// Since every n-gram of a circular sequence has a successor, generation from such a model never restarts.
+    #[arg(long)]
+    pub circular: bool,
+
+    /// Show a progress bar of the processed records.
+    ///
+    /// The progress bar is only shown if standard error is a terminal.
+    #[arg(long)]
+    pub progress: bool,
 }
 
-impl IntoCliAlphabet for DnaAlphabet {
-    fn into_cli_alphabet() -> CliAlphabet {
-        CliAlphabet::Dna
+impl CreateModelCommand {
+    /// Returns true if the model is written gzip-compressed.
+    pub fn compresses_output(&self) -> bool {
+        self.compress
+            || self
+                .output
+                .extension()
+                .is_some_and(|extension| extension == "gz")
     }
-}
 
-impl GeneratePairCommand {
-    pub fn verify(&self) -> Result<()> {
-        if self.reference_ancestry_fraction.is_nan() {
-            return Err(Error::ReferenceAncestryFractionIsNaN);
-        }
-        if self.reference_ancestry_fraction < 0.0 || self.reference_ancestry_fraction > 1.0 {
-            return Err(Error::ReferenceAncestryFractionOutOfRange(
-                self.reference_ancestry_fraction,
-            ));
-        }
+    /// Returns each input file with its weight.
+    pub fn weighted_inputs(&self) -> impl Iterator<Item = (&PathBuf, u32)> {
+        self.input_fasta
+            .iter()
+            .enumerate()
+            .map(|(index, input)| (input, self.input_weight.get(index).copied().unwrap_or(1)))
+    }
 
-        if self
-            .sequence_modification_parameters
-            .gap_length_mean
-            .is_nan()
-        {
-            return Err(Error::GapLengthMeanIsNaN);
-        }
-        if self.sequence_modification_parameters.gap_length_mean < 1.0
-            || self.sequence_modification_parameters.gap_length_mean > self.ancestor_length as f64
-        {
-            return Err(Error::GapLengthMeanOutOfRange {
-                actual: self.sequence_modification_parameters.gap_length_mean,
-                minimum: 1.0,
-                maximum: self.ancestor_length as f64,
-            });
-        }
+    /// Returns the alphabet of the created model, which is the reduced alphabet if the input is reduced.
+    pub fn model_alphabet(&self) -> CliAlphabet {
+        self.reduce_alphabet
+            .map_or(self.alphabet, |reduction| reduction.reduced_alphabet())
+    }
 
-        if self
-            .sequence_modification_parameters
-            .template_switch_min_offset
-            > self
-                .sequence_modification_parameters
-                .template_switch_max_offset
-        {
-            return Err(Error::TemplateSwitchOffsetEmpty {
-                min: self
-                    .sequence_modification_parameters
-                    .template_switch_min_offset,
-                max: self
-                    .sequence_modification_parameters
-                    .template_switch_max_offset,
+    pub fn verify(&self) -> Result<()> {
+        if !self.input_weight.is_empty() && self.input_weight.len() != self.input_fasta.len() {
+            return Err(Error::InputWeightAmountMismatch {
+                weight_amount: self.input_weight.len(),
+                input_amount: self.input_fasta.len(),
             });
         }
-        if self
-            .sequence_modification_parameters
-            .template_switch_min_length
-            > self
-                .sequence_modification_parameters
-                .template_switch_max_length
-        {
-            return Err(Error::TemplateSwitchLengthEmpty {
-                min: self
-                    .sequence_modification_parameters
-                    .template_switch_min_length,
-                max: self
-                    .sequence_modification_parameters
-                    .template_switch_max_length,
-            });
+        if self.input_weight.contains(&0) {
+            return Err(Error::ZeroInputWeight);
         }
-        if self
-            .sequence_modification_parameters
-            .template_switch_min_length_difference
-            > self
-                .sequence_modification_parameters
-                .template_switch_max_length_difference
-        {
-            return Err(Error::TemplateSwitchLengthDifferenceEmpty {
-                min: self
-                    .sequence_modification_parameters
-                    .template_switch_min_length_difference,
-                max: self
-                    .sequence_modification_parameters
-                    .template_switch_max_length_difference,
-            });
+        if !(0.0..=1.0).contains(&self.unknown_character_warning_fraction) {
+            return Err(Error::UnknownCharacterWarningFractionOutOfRange(
+                self.unknown_character_warning_fraction,
+            ));
+        }
+
+        if let Some(reduction) = self.reduce_alphabet {
+            if reduction.input_alphabet() != self.alphabet {
+                return Err(Error::AlphabetReductionMismatch {
+                    reduction: reduction
+                        .to_possible_value()
+                        .unwrap()
+                        .get_name()
+                        .to_string(),
+                    required: reduction.input_alphabet().name(),
+                    alphabet: self.alphabet.name(),
+                });
+            }
         }
 
         Ok(())
     }
 }
+
+#[derive(Args, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct GeneratePairCommand {
+    /// A TOML file with default values for the other arguments of this command.
+    ///
+    /// The keys are the long names of the arguments without the leading dashes, e.g. `gap-amount = 20`.
+    /// Flags are set with boolean values, and arguments that take multiple values accept arrays.
+    /// Arguments given on the command line override the values from the file.
+    /// Relative paths are resolved against the working directory, not against the directory of the file.
+    #[arg(long)]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
+    /// The path to an n-gram model file that was generated by this tool.
+    /// Gzip-compressed models are decompressed automatically.
+    ///
+    /// Either this, `--composition` or `--ancestor-input` must be given.
+    #[arg(
+        short = 'm',
+        long,
+        required_unless_present_any = ["ancestor_input", "composition"],
+        conflicts_with_all = ["ancestor_input", "composition"],
+        requires = "ancestor_length_source"
+    )]
+    pub model: Option<PathBuf>,
+
+    /// The frequencies of the characters, e.g. `A=0.3,C=0.2,G=0.2,T=0.3`, from which a model is built instead of reading `--model`.
+    ///
+    /// The model has order zero, i.e. it draws each character independently.
+    /// The frequencies must sum to one, and the characters must be exactly those of `--ancestor-alphabet`.
+    #[arg(
+        long,
+        conflicts_with = "ancestor_input",
+        requires = "ancestor_length_source"
+    )]
+    pub composition: Option<Composition>,
+
+    /// The path to a fasta file containing the ancestor sequence.
+    ///
+    /// If set, then the ancestor is not generated from a model, but loaded from this file.
+    /// The file must not contain characters outside of the alphabet.
+    #[arg(
+        long,
+        conflicts_with_all = ["interpolation_weights", "max_restarts", "global_fallback"]
+    )]
+    pub ancestor_input: Option<PathBuf>,
+
+    /// The id of the fasta record in `--ancestor-input` to use as ancestor.
+    /// If not set, then the first record is used.
+    #[arg(long, requires = "ancestor_input")]
+    pub ancestor_id: Option<String>,
+
+    /// The alphabet of the sequences in `--ancestor-input`, or of the characters in `--composition`.
+    /// When generating the ancestor from a model, the alphabet of the model is used instead.
+    #[arg(long, default_value = "dna")]
+    pub ancestor_alphabet: CliAlphabet,
+
+    /// Preserve the soft-masking (lower case characters) of `--ancestor-input` in the output sequences.
+    ///
+    /// Copied characters keep the case of their source, and template switches copy the case in reverse.
+    /// Without this flag, the ancestor input must not contain lower case characters.
+    #[arg(long, requires = "ancestor_input")]
+    pub preserve_case: bool,
+
+    /// The path to the fasta output file to generate.
+    /// It will be overwritten if it already exists.
+    ///
+    /// Either this or `--combined-output` is required, unless `--dry-run` is set.
+    #[arg(
+        short = 'o',
+        long,
+        required_unless_present_any = ["dry_run", "combined_output"]
+    )]
+    pub output: Option<PathBuf>,
+
+    /// If set, the ancestor, the reference and the query will be stored in this fasta file, in this order.
+    /// It will be overwritten if it already exists.
+    ///
+    /// This can be given instead of or in addition to `--output` and `--ancestor-output`.
+    /// The ids of the three records must be distinct.
+    #[arg(long)]
+    pub combined_output: Option<PathBuf>,
+
+    /// Print the modifications of each pair instead of writing any output files.
+    ///
+    /// The pairs are still generated in memory, since the positions of modifications
+    /// depend on the modifications applied before and on the detection of overlapping template switches.
+    /// Hence the printed modifications are exactly those of a run without this flag.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// The id of the reference record in the outputs.
+    ///
+    /// The placeholder `{index}` is replaced by the index of the pair, which is zero if only one pair is generated.
+    #[arg(long, default_value = "reference")]
+    pub reference_id: String,
+
+    /// The id of the query record in the outputs.
+    ///
+    /// The placeholder `{index}` is replaced by the index of the pair, which is zero if only one pair is generated.
+    #[arg(long, default_value = "query")]
+    pub query_id: String,
+
+    /// The id of the ancestor record in `--ancestor-output`.
+    ///
+    /// The placeholder `{index}` is replaced by the index of the pair, which is zero if only one pair is generated.
+    /// This is not to be confused with `--ancestor-id`, which selects the record of `--ancestor-input`.
+    #[arg(long, default_value = "ancestor")]
+    pub ancestor_output_id: String,
+
+    /// If set, the common ancestor will be stored in this fasta file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The ancestor is kept in memory until the end only if this, `--combined-output`, `--msa-output` or an ancestor CIGAR output is set.
+    /// Otherwise, the query is derived from the ancestor itself, such that only two instead of three sequences are kept in memory.
+    #[arg(long)]
+    pub ancestor_output: Option<PathBuf>,
+
+    /// If set, the edit operations on the reference and query will be stored in this text file.
+    /// It will be overwritten if it already exists.
+    #[arg(long)]
+    pub debug_output: Option<PathBuf>,
+
+    /// List the modifications in `--debug-output` and `--dry-run` sorted by the position of the ancestor at which they start,
+    /// instead of in the order in which they were applied.
+    ///
+    /// The listed positions are given in the frame of `--modification-frame`.
+    /// Modifications with the same start are ordered by type, i.e. template switches, insertions, deletions and then substitutions.
+    #[arg(long)]
+    pub sort_modifications: bool,
+
+    /// The coordinate frame of the positions of the modifications listed in `--debug-output` and `--dry-run`.
+    ///
+    /// `applied` lists the position in the derived sequence at the time the modification was applied.
+    /// `ancestor` maps it back to the position of the first character at or after it that descends from the ancestor.
+    /// `final` maps it forward to the position in the final derived sequence of the first character at or after it that is neither deleted nor replaced by a later template switch,
+    /// before `--query-reverse-complement` is applied.
+    /// Lengths and offsets are not mapped.
+    #[arg(long, value_enum, default_value = "applied")]
+    pub modification_frame: ModificationFrame,
+
+    /// If set, the true alignment of the query against the reference will be stored in this PAF file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The alignment is composed from the modifications applied to the common ancestor,
+    /// and stored as CIGAR string with `=` and `X` operations in the `cg:Z:` tag.
+    /// Template switches are represented as a deletion followed by an insertion.
+    #[arg(long)]
+    pub paf_output: Option<PathBuf>,
+
+    /// The target of the alignments in `--paf-output`.
+    ///
+    /// With `reference`, the file contains a single record of the query against the reference.
+    /// With `ancestor`, it contains a record of the reference and a record of the query, each against the ancestor.
+    #[arg(long, value_enum, default_value = "reference")]
+    pub alignment_reference: AlignmentReference,
+
+    /// If set, the true alignment of the reference against the ancestor will be stored as CIGAR string in this text file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The CIGAR uses the operations `=`, `X`, `I` and `D`, and `T` for characters copied by a template switch.
+    #[arg(long)]
+    pub ancestor_reference_cigar: Option<PathBuf>,
+
+    /// If set, the true alignment of the query against the ancestor will be stored as CIGAR string in this text file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The CIGAR is like that of `--ancestor-reference-cigar`,
+    /// and refers to the query before it is reverse complemented by `--query-reverse-complement`.
+    #[arg(long)]
+    pub ancestor_query_cigar: Option<PathBuf>,
+
+    /// If set, the applied template switches of reference and query will be stored as JSON array in this file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// Each template switch is recorded with the interval of the ancestor it replaces,
+    /// its position in the derived sequence at the time it was applied, its length, offset, length difference and strand.
+    #[arg(long)]
+    pub template_switch_json: Option<PathBuf>,
+
+    /// If set, the applied modifications of reference and query will be stored as GFF3 features in this file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// Each modification is a feature of type `substitution`, `insertion`, `deletion` or `template_switch`
+    /// that covers the characters it wrote into the final reference or query, in one-based inclusive coordinates.
+    /// Deletions are zero-length features whose start and end are the character before the deleted interval.
+    /// The coordinates of the query refer to its reverse complement if `--query-reverse-complement` is set.
+    #[arg(long)]
+    pub gff3_output: Option<PathBuf>,
+
+    /// If set, the origin of each character of reference and query will be stored in this file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The file has the same records as `--output` with one code per character, separated by spaces and wrapped like `--output`:
+    /// `.` for ancestral, `S` for substituted and `I` for inserted characters,
+    /// and e.g. `T3` for characters of the template switch with ID `3` in `--gff3-output`.
+    /// The codes of the query refer to its reverse complement if `--query-reverse-complement` is set.
+    /// If `--fragment-length` is set, then there is a record for each fragment with the same id as in `--output`.
+    #[arg(long)]
+    pub provenance_output: Option<PathBuf>,
+
+    /// If set, the true multiple sequence alignment of ancestor, reference and query will be stored in this aligned fasta file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// Characters that descend from the same ancestral character are aligned to each other,
+    /// and gaps are written as `-`.
+    /// Characters copied by template switches are written in lower case.
+    #[arg(long)]
+    pub msa_output: Option<PathBuf>,
+
+    /// If set, the realized divergence between reference and query will be stored in this TSV file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The divergence is the edit distance and identity of the generated sequences, which is also logged.
+    /// Its computation takes time proportional to the sequence length times the edit distance.
+    /// The file also contains the amounts of applied template switches, gaps and substitutions.
+    /// The stats of many pairs can be aggregated with the `summarize` subcommand.
+    #[arg(long)]
+    pub stats_output: Option<PathBuf>,
+
+    /// If set, the realized mutation spectrum of reference and query will be stored in this file.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The spectrum counts the substitutions by original and substituted character,
+    /// the insertions and deletions by length, and the template switches by offset and by length.
+    /// It is written as TSV if the path ends with `.tsv`, and as JSON otherwise.
+    #[arg(long)]
+    pub spectrum_output: Option<PathBuf>,
+
+    /// If set, a manifest of the run will be stored in this JSON file after all other outputs are written.
+    /// It will be overwritten if it already exists.
+    ///
+    /// The manifest contains the version of this tool, the command line and the resolved parameters including seed and random generator,
+    /// as well as the SHA-256 hashes of the model or ancestor input and of all written output files.
+    /// It contains no timestamps, so repeating a run reproduces the manifest exactly.
+    #[arg(long)]
+    pub manifest_output: Option<PathBuf>,
+
+    /// If set, the query is written as the reverse complement of the modified sequence.
+    ///
+    /// The PAF output then reports the query on the reverse strand, with its coordinates on the written query.
+    /// The multiple sequence alignment and the divergence refer to the query before reverse complementing,
+    /// i.e. to the strand of the ancestor.
+    #[arg(long)]
+    pub query_reverse_complement: bool,
+
+    /// The length of the ancestor sequence to generate.
+    /// Either this, `--ancestor-length-distribution`, `--ancestor-lengths` or `--ancestor-lengths-file` is required if the ancestor is generated from a model.
+    #[arg(
+        short = 'l',
+        long,
+        conflicts_with = "ancestor_input",
+        group = "ancestor_length_source"
+    )]
+    pub ancestor_length: Option<usize>,
+
+    /// The distribution from which the length of each generated ancestor is drawn.
+    ///
+    /// One of `uniform:MIN-MAX` (inclusive), `normal:MEAN,STANDARD_DEVIATION` (rounded)
+    /// or `empirical:LENGTH,LENGTH,...` (drawn uniformly from the list).
+    /// The length is drawn before generating the ancestor of each pair.
+    /// If a drawn length is lower than n, then the generation aborts with an error.
+    #[arg(
+        long,
+        conflicts_with = "ancestor_input",
+        group = "ancestor_length_source"
+    )]
+    pub ancestor_length_distribution: Option<AncestorLengthDistribution>,
+
+    /// The lengths of the generated ancestors, separated by commas, e.g. `1000,2500,10000`.
+    ///
+    /// The pair with index `i` gets the `i`-th length, which allows to reproduce the lengths of an existing dataset.
+    /// The list must contain at least `--num-pairs` lengths, unless `--cycle-ancestor-lengths` is set.
+    /// If a length is lower than n, then the generation aborts with an error.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with = "ancestor_input",
+        groups = ["ancestor_length_source", "ancestor_length_list"]
+    )]
+    pub ancestor_lengths: Vec<usize>,
+
+    /// The path to a file with the lengths of the generated ancestors, one per line.
+    ///
+    /// The lengths are used like those of `--ancestor-lengths`.
+    /// Empty lines and lines starting with `#` are skipped.
+    #[arg(
+        long,
+        conflicts_with = "ancestor_input",
+        groups = ["ancestor_length_source", "ancestor_length_list"]
+    )]
+    pub ancestor_lengths_file: Option<PathBuf>,
+
+    /// If set, then the ancestor lengths of `--ancestor-lengths` or `--ancestor-lengths-file` are repeated if they are fewer than the pairs.
+    #[arg(long, requires = "ancestor_length_list")]
+    pub cycle_ancestor_lengths: bool,
+
+    /// The minimum GC content of generated ancestors, between 0.0 and 1.0.
+    ///
+    /// Ancestors outside of the GC range are rejected and generated again, up to `--target-gc-tries` times.
+    /// This requires the DNA alphabet.
+    #[arg(long, conflicts_with = "ancestor_input")]
+    pub target_gc_min: Option<f64>,
+
+    /// The maximum GC content of generated ancestors, between 0.0 and 1.0.
+    ///
+    /// See `--target-gc-min`.
+    #[arg(long, conflicts_with = "ancestor_input")]
+    pub target_gc_max: Option<f64>,
+
+    /// The maximum number of ancestors generated to reach the target GC content.
+    ///
+    /// Only ancestors rejected for their GC content count, not those rejected by `--max-homopolymer`.
+    #[arg(long, default_value = "1000")]
+    pub target_gc_tries: usize,
+
+    /// The maximum length of a run of identical characters in generated ancestors.
+    ///
+    /// Ancestors with a longer run are rejected and generated again, up to `--max-homopolymer-tries` times.
+    #[arg(long, conflicts_with = "ancestor_input")]
+    pub max_homopolymer: Option<usize>,
+
+    /// The maximum number of ancestors generated to stay within the maximum homopolymer length.
+    ///
+    /// Only ancestors rejected for their homopolymers count, not those rejected by the target GC content.
+    #[arg(long, default_value = "1000")]
+    pub max_homopolymer_tries: usize,
+
+    /// Require at least this many template switches in the reference and at least this many in the query,
+    /// or one in each if no number is given.
+    ///
+    /// The template switches are split between reference and query according to `--reference-ancestry-fraction`,
+    /// so this cannot be reached if the split leaves one of them fewer template switches.
+    ///
+    /// Template switches can fail to apply, e.g. if no overlap-free template switch fits into a short sequence.
+    /// Pairs with fewer template switches are generated again from derived seeds, up to `--require-template-switch-tries` times,
+    /// and the number of retries is logged.
+    /// Without this, such pairs are written like any other.
+    #[arg(long, num_args = 0..=1, default_missing_value = "1")]
+    pub require_template_switch: Option<usize>,
+
+    /// The maximum number of times a pair is generated to reach `--require-template-switch`.
+    #[arg(long, default_value = "100")]
+    pub require_template_switch_tries: usize,
+
+    /// Draw the first n-gram of each ancestor from the first n-grams of the training sequences of the model.
+    ///
+    /// Otherwise, the first n-gram is drawn proportionally to the abundance of each n-gram in the training sequences.
+    /// This requires a model created by a version that stores the first n-grams.
+    #[arg(long, conflicts_with = "ancestor_input")]
+    pub realistic_starts: bool,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub n_gram_generation_parameters: NGramGenerationParameters,
+
+    /// The seed to use for the random generators.
+    ///
+    /// The ancestor, the split of the modifications between reference and query,
+    /// the reference modifications and the query modifications each draw from their own random generator.
+    /// These are seeded from this seed, the index of the pair and the labels `ancestor`, `split`, `reference` and `query`.
+    /// Hence e.g. additional random draws for the split do not shift the random draws of the modifications.
+    #[arg(long, default_value = "0")]
+    pub random_seed: u64,
+
+    /// The algorithm of the random generators.
+    ///
+    /// The generated pairs depend on the algorithm, so the same seed gives different pairs with different algorithms.
+    #[arg(long, value_enum, default_value = "xoshiro256")]
+    pub rng: RngAlgorithm,
+
+    /// If set, the template switches of reference and query draw from their own random generators seeded from this seed.
+    ///
+    /// The type of each modification and the modifications of types without own seed still draw from the generators
+    /// seeded from `--random-seed`. Hence e.g. varying only `--substitution-seed` keeps the types and, mostly,
+    /// the template switches and gaps fixed. However, since modifications are drawn again if they overlap a template switch,
+    /// and positions refer to the sequence modified by all previous modifications,
+    /// changing the seed of one type may still change where modifications of other types land.
+    #[arg(long)]
+    pub template_switch_seed: Option<u64>,
+
+    /// If set, the gaps of reference and query draw from their own random generators seeded from this seed.
+    ///
+    /// See `--template-switch-seed`.
+    #[arg(long)]
+    pub gap_seed: Option<u64>,
+
+    /// If set, the substitutions of reference and query draw from their own random generators seeded from this seed.
+    ///
+    /// See `--template-switch-seed`.
+    #[arg(long)]
+    pub substitution_seed: Option<u64>,
+
+    /// The number of pairs to generate.
+    ///
+    /// If greater than one, then the index of each pair is inserted before the extension of each output file,
+    /// e.g. `pair.fa` becomes `pair.0.fa`, `pair.1.fa`, and so on.
+    /// When generating the ancestor from a model, each pair gets its own ancestor.
+    /// The output does not depend on the number of threads.
+    #[arg(long, default_value = "1")]
+    pub num_pairs: usize,
+
+    /// The number of threads used to generate multiple pairs.
+    /// If zero, then one thread per CPU core is used.
+    #[arg(long, default_value = "0")]
+    pub threads: usize,
+
+    /// The closeness of the reference to the common ancestor, between 0.0 and 1.0.
+    ///
+    /// A pair is generated by first generating a common ancestor, and then modifying it into both a reference and a query sequence.
+    /// This value decides what fraction of all modifications are performed on the reference, and what on the query.
+    /// The higher this value, the closer the reference is to the common ancestor, and hence the more modifications are performed on the query.
+    ///
+    /// If it is not possible to distribute the modifications between reference and query adhering this ratio,
+    /// then a modification is randomly placed on either the reference or the query.
+    ///
+    /// **Examples:**
+    /// If this value is 1.0, then all modifications are performed on the query.
+    /// If this value is 0.0, then all modifications are performed on the reference.
+    /// For the default value of 0.5, half the modifications are performed on the reference, and the other half are performed on the query.
+    #[arg(long, default_value = "0.5")]
+    pub reference_ancestry_fraction: f64,
+
+    /// The maximum number of characters per sequence line in the fasta outputs.
+    /// If zero, each sequence is written on a single line.
+    #[arg(long, default_value = "0")]
+    pub line_width: usize,
+
+    /// If set, `--output` contains fragments of this length of the reference and the query instead of the full sequences,
+    /// like reads of a sequencer.
+    ///
+    /// The starts of the fragments are uniformly distributed, and fragments may overlap.
+    /// The id of each fragment is the id of its sequence followed by its one-based inclusive coordinates, e.g. `reference:11-60`.
+    /// The coordinates of query fragments refer to the query after `--query-reverse-complement`.
+    /// The other outputs are not affected.
+    #[arg(long, requires_all = ["fragment_count", "output"])]
+    pub fragment_length: Option<usize>,
+
+    /// The number of fragments of each of reference and query, see `--fragment-length`.
+    #[arg(long, requires = "fragment_length")]
+    pub fragment_count: Option<usize>,
+
+    /// If set, the resolved parameters are printed to standard output in the format of a `--config` file
+    /// instead of being logged.
+    ///
+    /// The parameters are printed after they are verified, and include the values of all defaults.
+    /// The `n` and alphabet of the model are added as comments.
+    #[arg(long)]
+    pub print_parameters: bool,
+
+    /// Show a progress bar of the characters of the generated ancestor, or of the generated pairs if `--num-pairs` is greater than one.
+    ///
+    /// The progress bar is only shown if standard error is a terminal.
+    #[arg(long)]
+    pub progress: bool,
+
+    /// If set, the generation parameters are not written into the comments of the fasta records.
+    #[arg(long)]
+    pub no_comment: bool,
+
+    /// If set, modifications are only applied within the intervals of this BED file.
+    ///
+    /// The intervals refer to the coordinates of the ancestor, and the chromosome column is ignored.
+    #[arg(long)]
+    pub modifiable_regions: Option<PathBuf>,
+
+    /// If set, no modifications are applied within the intervals of this BED file.
+    ///
+    /// The intervals refer to the coordinates of the ancestor, and the chromosome column is ignored.
+    /// Masked regions take precedence over modifiable regions.
+    #[arg(long)]
+    pub masked_regions: Option<PathBuf>,
+
+    /// If set, the density of modifications along the sequence is scaled by the multipliers in this TSV file.
+    ///
+    /// Each line contains the start, end and multiplier of an interval, where the intervals are zero-based and half-open,
+    /// refer to the coordinates of the ancestor and must partition the ancestor.
+    /// This scales the expected local density of modifications, but not their total amount.
+    #[arg(long)]
+    pub rate_multipliers: Option<PathBuf>,
+
+    /// The positions around which modifications cluster if `--position-bias hotspots` is set, separated by commas.
+    ///
+    /// The positions refer to the coordinates of the ancestor and are not adjusted for previously applied gaps.
+    #[arg(long, value_delimiter = ',')]
+    pub hotspots: Vec<usize>,
+
+    /// The maximum total edit cost of the modifications of reference and query, split like the amounts.
+    ///
+    /// Substitutions cost one, gaps cost their length and template switches cost their length
+    /// times `--template-switch-edit-cost`.
+    /// The budget caps the amounts: modifications that would exceed it are discarded,
+    /// while cheaper modifications are still applied as long as they fit.
+    /// Discarded modifications do not count as shortfall for `--strict`.
+    #[arg(long)]
+    pub max_edit_distance: Option<usize>,
+
+    /// The edit cost of each character of a template switch for `--max-edit-distance`.
+    ///
+    /// A template switch is charged for the longer of its copied and its replaced interval.
+    /// With a cost of at least one, the edit distance of each sequence to the ancestor stays within the budget.
+    #[arg(long, default_value = "1.0")]
+    pub template_switch_edit_cost: f64,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sequence_modification_amount: SequenceModificationAmount,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sequence_modification_rate: SequenceModificationRate,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sequence_modification_probabilities: SequenceModificationProbabilities,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sequence_modification_parameters: SequenceModificationParameters,
+}
+
+#[derive(Args, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct NGramGenerationParameters {
+    /// Draw each successor from a mixture of the orders of the model, with these weights separated by commas.
+    ///
+    /// The weights are given from order n downwards, i.e. `W_N,W_N-1,...,W_0`, and missing weights of low orders are zero.
+    /// The successor abundances of the lower orders are derived from the n-grams of the model.
+    /// Orders whose context does not occur in the model are left out, and the weights of the others are normalised.
+    /// Hence the generation does not restart at n-grams without successor if a lower order has a positive weight.
+    #[arg(long, value_delimiter = ',')]
+    pub interpolation_weights: Vec<f64>,
+
+    /// The maximum number of times the generation of a sequence restarts at an n-gram without successor.
+    ///
+    /// Each restart draws a new n-gram, which leaves a seam in the sequence.
+    /// If more restarts would be needed, then the generation aborts with an error, unless `--unigram-fallback` is set.
+    /// Without this, the generation restarts as often as needed.
+    #[arg(long)]
+    pub max_restarts: Option<usize>,
+
+    /// After `--max-restarts` restarts, follow each n-gram without successor by a character
+    /// drawn from the total successor abundances of all n-grams, instead of aborting with an error.
+    #[arg(long, requires = "max_restarts")]
+    pub unigram_fallback: bool,
+
+    /// Never restart the generation of a sequence, but follow each n-gram without successor by a character
+    /// drawn from the total successor abundances of all n-grams.
+    ///
+    /// This keeps the sequence continuous, but the n-gram after the drawn character may itself be unseen.
+    /// It is the same as `--max-restarts 0 --unigram-fallback`.
+    #[arg(long, conflicts_with = "max_restarts")]
+    pub global_fallback: bool,
+}
+
+#[derive(Args, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SequenceModificationAmount {
+    /// The number of template switches to generate.
+    #[arg(long, default_value = "1")]
+    pub template_switch_amount: usize,
+
+    /// The number of gaps to generate.
+    #[arg(long, default_value = "10")]
+    pub gap_amount: usize,
+
+    /// The number of substitutions to generate.
+    #[arg(long, default_value = "10")]
+    pub substitution_amount: usize,
+}
+
+/// The amounts of modifications relative to the length of the ancestor.
+#[derive(Args, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub struct SequenceModificationRate {
+    /// The number of template switches per character of the ancestor.
+    ///
+    /// If set, this replaces `--template-switch-amount` with the rate times the ancestor length, rounded to the nearest integer.
+    #[arg(
+        long,
+        allow_negative_numbers = true,
+        conflicts_with = "template_switch_amount"
+    )]
+    pub template_switch_rate: Option<f64>,
+
+    /// The number of gaps per character of the ancestor.
+    ///
+    /// If set, this replaces `--gap-amount` with the rate times the ancestor length, rounded to the nearest integer.
+    #[arg(long, allow_negative_numbers = true, conflicts_with = "gap_amount")]
+    pub gap_rate: Option<f64>,
+
+    /// The number of substitutions per character of the ancestor.
+    ///
+    /// If set, this replaces `--substitution-amount` with the rate times the ancestor length, rounded to the nearest integer.
+    #[arg(
+        long,
+        allow_negative_numbers = true,
+        conflicts_with = "substitution_amount"
+    )]
+    pub substitution_rate: Option<f64>,
+}
+
+/// A total amount of modifications whose types are drawn with fixed probabilities.
+#[derive(Args, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub struct SequenceModificationProbabilities {
+    /// The total number of modifications to generate.
+    ///
+    /// If set, then the type of each modification is drawn independently with the probabilities given by
+    /// `--template-switch-probability`, `--gap-probability` and `--substitution-probability`,
+    /// such that the expected mixture of types stays the same throughout the generation.
+    /// This replaces the amounts and rates of the individual types.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "template_switch_amount",
+            "gap_amount",
+            "substitution_amount",
+            "template_switch_rate",
+            "gap_rate",
+            "substitution_rate",
+        ]
+    )]
+    pub total_modifications: Option<usize>,
+
+    /// The probability of a modification being a template switch if `--total-modifications` is set.
+    ///
+    /// The probabilities of all types are normalised to sum up to one, and missing probabilities are zero.
+    #[arg(long, requires = "total_modifications", allow_negative_numbers = true)]
+    pub template_switch_probability: Option<f64>,
+
+    /// The probability of a modification being a gap if `--total-modifications` is set.
+    ///
+    /// See `--template-switch-probability`.
+    #[arg(long, requires = "total_modifications", allow_negative_numbers = true)]
+    pub gap_probability: Option<f64>,
+
+    /// The probability of a modification being a substitution if `--total-modifications` is set.
+    ///
+    /// See `--template-switch-probability`.
+    #[arg(long, requires = "total_modifications", allow_negative_numbers = true)]
+    pub substitution_probability: Option<f64>,
+}
+
+impl SequenceModificationRate {
+    /// Returns the given amounts with those that have a rate replaced by the rate times the ancestor length,
+    /// rounded to the nearest integer.
+    pub fn resolve(
+        &self,
+        amount: &SequenceModificationAmount,
+        ancestor_length: usize,
+    ) -> SequenceModificationAmount {
+        let resolve = |amount: usize, rate: Option<f64>| {
+            rate.map_or(amount, |rate| {
+                (rate * ancestor_length as f64).round() as usize
+            })
+        };
+
+        SequenceModificationAmount {
+            template_switch_amount: resolve(
+                amount.template_switch_amount,
+                self.template_switch_rate,
+            ),
+            gap_amount: resolve(amount.gap_amount, self.gap_rate),
+            substitution_amount: resolve(amount.substitution_amount, self.substitution_rate),
+        }
+    }
+}
+
+/// The amounts of modifications of a command, which are resolved for the length of each ancestor.
+#[derive(Clone, Copy)]
+pub struct ModificationAmountResolver {
+    pub amount: SequenceModificationAmount,
+    pub rate: SequenceModificationRate,
+    /// True if the types of modifications are drawn with fixed probabilities instead.
+    pub draws_types: bool,
+}
+
+impl ModificationAmountResolver {
+    /// Returns the amounts of modifications for an ancestor of the given length.
+    ///
+    /// Rates are multiplied by the ancestor length and rounded to the nearest integer.
+    /// If the types of modifications are drawn with fixed probabilities, then all amounts are zero.
+    pub fn resolve(&self, ancestor_length: usize) -> SequenceModificationAmount {
+        if self.draws_types {
+            return SequenceModificationAmount {
+                template_switch_amount: 0,
+                gap_amount: 0,
+                substitution_amount: 0,
+            };
+        }
+
+        self.rate.resolve(&self.amount, ancestor_length)
+    }
+}
+
+impl SequenceModificationProbabilities {
+    /// Returns the total amount of modifications and the probabilities of template switches, gaps and substitutions,
+    /// if the types of modifications are drawn with fixed probabilities.
+    pub fn resolve(&self) -> Option<(usize, [f64; 3])> {
+        self.total_modifications.map(|total_modifications| {
+            (
+                total_modifications,
+                [
+                    self.template_switch_probability.unwrap_or(0.0),
+                    self.gap_probability.unwrap_or(0.0),
+                    self.substitution_probability.unwrap_or(0.0),
+                ],
+            )
+        })
+    }
+}
+
+#[derive(Args, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub struct SequenceModificationParameters {
+    /// If set, template switches are allowed to overlap.
+    ///
+    /// Otherwise, the template switches of each sequence including their margins do not overlap each other.
+    /// Template switches of reference and query are independent of each other.
+    #[arg(long)]
+    pub template_switch_overlap: bool,
+
+    /// If set, template switches never copy from within the interval they replace.
+    ///
+    /// Without this flag, the source of a template switch may overlap the interval it replaces,
+    /// in which case the source is copied as it was before the template switch was applied.
+    /// Whether a template switch overlaps itself depends only on its length, offset and length difference,
+    /// and self-overlapping template switches are redrawn within the maximum number of tries.
+    /// Template switches that copy from the sibling sequence never overlap themselves.
+    #[arg(long)]
+    pub template_switch_forbid_self_overlap: bool,
+
+    /// The maximum number of tries to create a non-overlapping template switch.
+    /// If the maximum number of tries is reached and `--template-switch-overlap` is not set,
+    /// then the template switch is skipped and a warning is printed.
+    /// With `--strict`, the generation instead aborts with an error if fewer template switches than requested were applied.
+    ///
+    /// Template switches that do not fit into the allowed regions of `--modifiable-regions` and `--masked-regions`
+    /// are drawn again as well, and count towards this maximum.
+    #[arg(long, default_value = "10000")]
+    pub template_switch_maximum_overlap_tries: usize,
+
+    /// The minimum length of a template switch.
+    /// Defaults to 10 for DNA and 20 for the purine/pyrimidine alphabet.
+    #[arg(long)]
+    pub template_switch_min_length: Option<usize>,
+
+    /// The maximum length of a template switch.
+    /// Defaults to 30 for DNA and 60 for the purine/pyrimidine alphabet.
+    #[arg(long)]
+    pub template_switch_max_length: Option<usize>,
+
+    /// The minimum offset of a template switch.
+    #[arg(long, allow_negative_numbers = true, default_value = "-30")]
+    pub template_switch_min_offset: isize,
+
+    /// The maximum offset of a template switch.
+    #[arg(long, allow_negative_numbers = true, default_value = "10")]
+    pub template_switch_max_offset: isize,
+
+    /// The probability that a template switch copies from upstream, i.e. has a negative offset, between 0.0 and 1.0.
+    ///
+    /// If set, the direction of the source is drawn first,
+    /// and then the offset is drawn from the negative or non-negative offsets of the offset range.
+    /// Otherwise, the offset is drawn from the whole offset range.
+    /// The offset range must contain offsets of each direction that has a positive probability.
+    #[arg(long)]
+    pub template_switch_upstream_fraction: Option<f64>,
+
+    /// If set, the offset of a template switch is drawn with a probability proportional to `exp(-|offset| / DECAY)`,
+    /// so sources near the template switch are more likely than distant ones.
+    ///
+    /// The offset stays within the offset range, and within the direction drawn with `--template-switch-upstream-fraction`.
+    /// Without this, the offset is drawn uniformly.
+    #[arg(long)]
+    pub template_switch_offset_decay: Option<f64>,
+
+    /// The minimum length difference of a template switch.
+    #[arg(long, allow_negative_numbers = true, default_value = "-10")]
+    pub template_switch_min_length_difference: isize,
+
+    /// The maximum length difference of a template switch.
+    #[arg(long, allow_negative_numbers = true, default_value = "10")]
+    pub template_switch_max_length_difference: isize,
+
+    /// The distribution of the length difference of a template switch.
+    ///
+    /// Either `uniform`, or `normal:STANDARD_DEVIATION` or `laplace:SCALE`, which are centered at zero.
+    /// The distribution is truncated to the range between the minimum and the maximum length difference,
+    /// where the maximum is additionally limited by the length of the template switch.
+    #[arg(long, default_value = "uniform")]
+    pub template_switch_length_difference_distribution: LengthDifferenceDistribution,
+
+    /// If set, the length difference of each template switch is a multiple of three,
+    /// such that the reading frame is preserved after the template switch.
+    ///
+    /// The length difference is drawn from the multiples of three between the minimum and the maximum length difference.
+    /// This requires a `--reading-frame`.
+    #[arg(long, requires = "reading_frame")]
+    pub template_switch_preserve_frame: bool,
+
+    /// The amount of characters at the beginning and end of a sequence that are not allowed to be part of a template switch.
+    #[arg(
+        long,
+        default_value = "10",
+        conflicts_with = "template_switch_margin_fraction"
+    )]
+    pub template_switch_margin: usize,
+
+    /// The template switch margin as a fraction of the current sequence length, between 0.0 and 1.0.
+    ///
+    /// If set, this replaces `--template-switch-margin`.
+    /// The fraction is resolved to an absolute margin (rounded down) each time a template switch is generated,
+    /// using the length of the sequence at that time.
+    #[arg(long)]
+    pub template_switch_margin_fraction: Option<f64>,
+
+    /// The probability that a template switch copies its replacement from the sibling sequence, between 0.0 and 1.0.
+    ///
+    /// This models recombination between reference and query.
+    /// The source of an inter-sequence template switch is taken from the same coordinates in the sibling sequence
+    /// as it would be taken from the sequence itself.
+    /// Since the reference is modified before the query, the sibling of the reference is the unmodified ancestor,
+    /// while the sibling of the query is the modified reference.
+    /// Unless `--template-switch-overlap` is set, template switches of the query do not overlap
+    /// the sources of the inter-sequence template switches of the reference.
+    #[arg(long, default_value = "0.0")]
+    pub inter_sequence_template_switch_probability: f64,
+
+    /// If set, substitutions are drawn within the codons of this reading frame, which must be 0, 1 or 2.
+    ///
+    /// Codons start at this position of the current sequence and every third position after it.
+    /// Characters outside of complete codons are not substituted.
+    /// Whether substitutions change the encoded amino acid of the standard genetic code
+    /// is controlled by `--synonymous-only` or `--synonymous-fraction`.
+    /// This requires the DNA alphabet.
+    #[arg(long)]
+    pub reading_frame: Option<usize>,
+
+    /// If set, substitutions within the `--reading-frame` never change the encoded amino acid.
+    #[arg(
+        long,
+        requires = "reading_frame",
+        conflicts_with = "synonymous_fraction"
+    )]
+    pub synonymous_only: bool,
+
+    /// The fraction of substitutions within the `--reading-frame` that keep the encoded amino acid, between 0.0 and 1.0.
+    ///
+    /// All other substitutions change the encoded amino acid, where stop is treated as an amino acid.
+    #[arg(long, requires = "reading_frame")]
+    pub synonymous_fraction: Option<f64>,
+
+    /// The factor by which CpG sites are more likely to be substituted than other positions.
+    ///
+    /// This models the hypermutability of methylated CpG dinucleotides.
+    /// A substituted CpG site undergoes the transition C to T (or G to A on the other strand)
+    /// with probability `1 - 1 / FACTOR`, and a uniformly random substitution otherwise.
+    /// Factors below one make CpG sites less likely to be substituted.
+    /// This requires the DNA alphabet.
+    #[arg(long, default_value = "1.0", conflicts_with = "reading_frame")]
+    pub cpg_bias: f64,
+
+    /// If set, then a substitution may keep its character, i.e. be an identity substitution.
+    ///
+    /// By default, every substitution changes its character,
+    /// since aligners treat an event that changed nothing differently from a mismatch.
+    /// If set, uniformly random substitutions and the substitutions of CpG-biased sites that are no transition
+    /// draw the new character from the whole alphabet including the original character.
+    /// Codon-preserving substitutions always change their character.
+    #[arg(long)]
+    pub allow_identity_substitution: bool,
+
+    /// The bias of the positions of modifications along the sequence.
+    ///
+    /// This applies to substitutions, gaps and the replaced interval of template switches,
+    /// but not to the source of inserted characters.
+    #[arg(long, default_value = "uniform")]
+    pub position_bias: CliPositionBias,
+
+    /// The standard deviation of the Gaussian kernel around each hotspot if `--position-bias hotspots` is set.
+    #[arg(long, default_value = "100.0")]
+    pub hotspot_spread: f64,
+
+    /// The parameter mean of the exponential distribution determining the gap length.
+    /// Generated gaps always have a minimum length of one.
+    /// Defaults to 3.0 for all alphabets.
+    #[arg(long, allow_negative_numbers = true)]
+    pub gap_length_mean: Option<f64>,
+
+    /// The minimum length of the reference and query while they are modified.
+    ///
+    /// Deletions that would shorten a sequence below this length are rejected and drawn again,
+    /// possibly as insertions, up to `--min-sequence-length-tries` times per gap.
+    /// Template switches and substitutions are not affected.
+    #[arg(long)]
+    pub min_sequence_length: Option<usize>,
+
+    /// The maximum number of tries to draw a gap that keeps the sequence at least `--min-sequence-length` characters long.
+    /// If it is reached, then the generation aborts with an error.
+    #[arg(long, default_value = "1000")]
+    pub min_sequence_length_tries: usize,
+
+    /// If set, gaps are placed into homopolymer runs, modelling the indel errors of sequencing.
+    ///
+    /// A run is chosen with probability proportional to its length,
+    /// and the gap length is drawn uniformly between one and half the run length (rounded up).
+    /// Insertions repeat the character of the run.
+    /// In this mode, `--gap-length-mean` and `--position-bias` do not apply to gaps.
+    /// If there is no run of at least `--homopolymer-min-length` characters within the allowed regions,
+    /// then the gap is placed as without this flag.
+    #[arg(long)]
+    pub homopolymer_gaps: bool,
+
+    /// The minimum length of a homopolymer run in which `--homopolymer-gaps` places gaps.
+    #[arg(long, default_value = "3")]
+    pub homopolymer_min_length: usize,
+
+    /// If set, the generation aborts with an error if fewer modifications of some type than requested could be applied.
+    ///
+    /// Modifications can fail to apply e.g. if the sequence is too short for a gap,
+    /// or if no overlap-free template switch was found within the maximum number of tries.
+    /// Without this flag, failed modifications are skipped and a warning is printed.
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(ValueEnum, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliPositionBias {
+    /// All positions are equally likely.
+    Uniform,
+    /// Modifications cluster towards both ends of the sequence.
+    Ends,
+    /// Modifications cluster towards the center of the sequence.
+    Center,
+    /// Modifications cluster around the positions given by `--hotspots`.
+    Hotspots,
+}
+
+#[derive(ValueEnum, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliAlphabet {
+    Dna,
+    /// The reduced DNA alphabet of purines `R` and pyrimidines `Y`, see `--reduce-alphabet`.
+    PurinePyrimidine,
+}
+
+pub trait IntoCliAlphabet {
+    fn into_cli_alphabet() -> CliAlphabet;
+}
+
+impl CliAlphabet {
+    pub fn is_dna(&self) -> bool {
+        match self {
+            CliAlphabet::Dna => true,
+            CliAlphabet::PurinePyrimidine => false,
+        }
+    }
+
+    /// The name of the alphabet as given on the command line.
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+
+    /// The default minimum and maximum length of a template switch in this alphabet.
+    ///
+    /// A purine/pyrimidine character carries half the information of a DNA character,
+    /// so template switches are twice as long to stand out as much from random similarity.
+    pub fn default_template_switch_length(&self) -> (usize, usize) {
+        match self {
+            CliAlphabet::Dna => (10, 30),
+            CliAlphabet::PurinePyrimidine => (20, 60),
+        }
+    }
+
+    /// The default mean of the gap length in this alphabet.
+    pub fn default_gap_length_mean(&self) -> f64 {
+        match self {
+            CliAlphabet::Dna | CliAlphabet::PurinePyrimidine => 3.0,
+        }
+    }
+}
+
+impl IntoCliAlphabet for DnaAlphabet {
+    fn into_cli_alphabet() -> CliAlphabet {
+        CliAlphabet::Dna
+    }
+}
+
+impl GenerateCommand {
+    /// Returns the record id of the sequence with the given index.
+    pub fn record_id(&self, index: usize) -> String {
+        self.id.replace("{index}", &index.to_string())
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if self.num_sequences == 0 {
+            return Err(Error::NoSequences);
+        }
+        if self.num_sequences > 1 && !self.id.contains("{index}") {
+            return Err(Error::DuplicateRecordId(self.id.clone()));
+        }
+        self.n_gram_generation_parameters.verify()
+    }
+}
+
+impl NGramGenerationParameters {
+    /// Returns the bound on the restarts of the generation of a sequence, if it is given.
+    pub fn restart_limit(&self) -> Option<RestartLimit> {
+        if self.global_fallback {
+            return Some(RestartLimit {
+                max_restarts: 0,
+                unigram_fallback: true,
+            });
+        }
+
+        self.max_restarts.map(|max_restarts| RestartLimit {
+            max_restarts,
+            unigram_fallback: self.unigram_fallback,
+        })
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if !self.interpolation_weights.is_empty()
+            && (self
+                .interpolation_weights
+                .iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+                || self.interpolation_weights.iter().sum::<f64>() <= 0.0)
+        {
+            return Err(Error::InvalidInterpolationWeights(
+                self.interpolation_weights.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verify the parameters that depend on the `n` of the model.
+    pub fn verify_n(&self, n: usize) -> Result<()> {
+        if self.interpolation_weights.len() > n + 1 {
+            return Err(Error::TooManyInterpolationWeights {
+                amount: self.interpolation_weights.len(),
+                orders: n + 1,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl GeneratePairCommand {
+    /// Returns the paths of the output files written by this command, in the order of the pairs.
+    ///
+    /// The manifest output is not included, and no paths are returned if `--dry-run` is set.
+    pub fn output_paths(&self) -> Vec<PathBuf> {
+        if self.dry_run {
+            return Vec::new();
+        }
+
+        let pair_indices: Vec<_> = if self.num_pairs == 1 {
+            vec![None]
+        } else {
+            (0..self.num_pairs).map(Some).collect()
+        };
+        pair_indices
+            .into_iter()
+            .flat_map(|pair_index| {
+                [
+                    &self.output,
+                    &self.combined_output,
+                    &self.ancestor_output,
+                    &self.debug_output,
+                    &self.paf_output,
+                    &self.ancestor_reference_cigar,
+                    &self.ancestor_query_cigar,
+                    &self.template_switch_json,
+                    &self.gff3_output,
+                    &self.provenance_output,
+                    &self.msa_output,
+                    &self.stats_output,
+                    &self.spectrum_output,
+                ]
+                .into_iter()
+                .flatten()
+                .map(move |path| pair_output_path(path, pair_index))
+            })
+            .collect()
+    }
+
+    /// Moves all output files including the manifest into the given directory, keeping their file names.
+    pub fn move_outputs_into(&mut self, directory: &Path) {
+        for path in [
+            &mut self.output,
+            &mut self.combined_output,
+            &mut self.ancestor_output,
+            &mut self.debug_output,
+            &mut self.paf_output,
+            &mut self.ancestor_reference_cigar,
+            &mut self.ancestor_query_cigar,
+            &mut self.template_switch_json,
+            &mut self.gff3_output,
+            &mut self.provenance_output,
+            &mut self.msa_output,
+            &mut self.stats_output,
+            &mut self.spectrum_output,
+            &mut self.manifest_output,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(file_name) = path.file_name() {
+                *path = directory.join(file_name);
+            }
+        }
+    }
+
+    /// Returns the reference, query and ancestor record ids of the pair with the given index.
+    pub fn record_ids(&self, pair_index: Option<usize>) -> [String; 3] {
+        let pair_index = pair_index.unwrap_or(0).to_string();
+        [&self.reference_id, &self.query_id, &self.ancestor_output_id]
+            .map(|id| id.replace("{index}", &pair_index))
+    }
+
+    /// Returns the resolver of the amounts of modifications for each ancestor length.
+    pub fn modification_amount_resolver(&self) -> ModificationAmountResolver {
+        ModificationAmountResolver {
+            amount: self.sequence_modification_amount,
+            rate: self.sequence_modification_rate,
+            draws_types: self
+                .sequence_modification_probabilities
+                .total_modifications
+                .is_some(),
+        }
+    }
+
+    /// Returns the amounts of modifications for an ancestor of the given length, see [`ModificationAmountResolver::resolve`].
+    pub fn resolve_sequence_modification_amount(
+        &self,
+        ancestor_length: usize,
+    ) -> SequenceModificationAmount {
+        self.modification_amount_resolver().resolve(ancestor_length)
+    }
+
+    /// Returns the distribution of the ancestor length, if the ancestor is generated from a model.
+    ///
+    /// A list of ancestor lengths is read from its file if necessary,
+    /// and must not be empty or shorter than the number of pairs unless it is cycled through.
+    pub fn ancestor_length_distribution(&self) -> Result<Option<AncestorLengthDistribution>> {
+        let lengths = if let Some(ancestor_lengths_file) = &self.ancestor_lengths_file {
+            read_ancestor_lengths_file(ancestor_lengths_file)?
+        } else if !self.ancestor_lengths.is_empty() {
+            self.ancestor_lengths.clone()
+        } else {
+            return Ok(self
+                .ancestor_length
+                .map(AncestorLengthDistribution::Fixed)
+                .or_else(|| self.ancestor_length_distribution.clone()));
+        };
+
+        if lengths.is_empty() {
+            return Err(Error::EmptyAncestorLengths);
+        }
+        if lengths.len() < self.num_pairs && !self.cycle_ancestor_lengths {
+            return Err(Error::TooFewAncestorLengths {
+                amount: lengths.len(),
+                num_pairs: self.num_pairs,
+            });
+        }
+        Ok(Some(AncestorLengthDistribution::List(lengths)))
+    }
+
+    /// Returns true if an output requires the ancestries of reference and query.
+    pub fn requires_ancestry(&self) -> bool {
+        self.paf_output.is_some()
+            || self.msa_output.is_some()
+            || self.ancestor_reference_cigar.is_some()
+            || self.ancestor_query_cigar.is_some()
+    }
+
+    /// Returns true if an output requires the ancestor after reference and query were derived from it.
+    pub fn requires_ancestor(&self) -> bool {
+        self.ancestor_output.is_some()
+            || self.combined_output.is_some()
+            || (self.paf_output.is_some()
+                && self.alignment_reference == AlignmentReference::Ancestor)
+            || self.msa_output.is_some()
+            || self.ancestor_reference_cigar.is_some()
+            || self.ancestor_query_cigar.is_some()
+    }
+
+    /// Returns the range of the GC content of generated ancestors, if it is constrained.
+    pub fn target_gc(&self) -> Option<RangeInclusive<f64>> {
+        if self.target_gc_min.is_none() && self.target_gc_max.is_none() {
+            None
+        } else {
+            Some(self.target_gc_min.unwrap_or(0.0)..=self.target_gc_max.unwrap_or(1.0))
+        }
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if self.num_pairs == 0 {
+            return Err(Error::NoPairs);
+        }
+
+        // The ids contain the same placeholders, so they are distinct for all pairs if they are distinct as given.
+        if self.reference_id == self.query_id {
+            return Err(Error::DuplicateRecordId(self.reference_id.clone()));
+        }
+        if self.combined_output.is_some()
+            && [&self.reference_id, &self.query_id].contains(&&self.ancestor_output_id)
+        {
+            return Err(Error::DuplicateRecordId(self.ancestor_output_id.clone()));
+        }
+
+        if let Some((_, probabilities)) = self.sequence_modification_probabilities.resolve() {
+            if probabilities
+                .iter()
+                .any(|probability| !probability.is_finite() || *probability < 0.0)
+                || probabilities.iter().sum::<f64>() <= 0.0
+            {
+                return Err(Error::InvalidModificationProbabilities(probabilities));
+            }
+        }
+
+        for (modification_type, rate) in [
+            (
+                "template switch",
+                self.sequence_modification_rate.template_switch_rate,
+            ),
+            ("gap", self.sequence_modification_rate.gap_rate),
+            (
+                "substitution",
+                self.sequence_modification_rate.substitution_rate,
+            ),
+        ] {
+            if let Some(rate) = rate {
+                if !rate.is_finite() || rate < 0.0 {
+                    return Err(Error::ModificationRateOutOfRange {
+                        modification_type,
+                        rate,
+                    });
+                }
+            }
+        }
+
+        for target_gc in [self.target_gc_min, self.target_gc_max]
+            .into_iter()
+            .flatten()
+        {
+            if !(0.0..=1.0).contains(&target_gc) {
+                return Err(Error::TargetGcOutOfRange(target_gc));
+            }
+        }
+        if let Some(target_gc) = self.target_gc() {
+            if target_gc.is_empty() {
+                return Err(Error::TargetGcEmpty {
+                    min: *target_gc.start(),
+                    max: *target_gc.end(),
+                });
+            }
+        }
+
+        self.n_gram_generation_parameters.verify()?;
+
+        if !self.template_switch_edit_cost.is_finite() || self.template_switch_edit_cost < 0.0 {
+            return Err(Error::TemplateSwitchEditCostOutOfRange(
+                self.template_switch_edit_cost,
+            ));
+        }
+
+        if self.fragment_length == Some(0) {
+            return Err(Error::ZeroFragmentLength);
+        }
+
+        if self.reference_ancestry_fraction.is_nan() {
+            return Err(Error::ReferenceAncestryFractionIsNaN);
+        }
+        if self.reference_ancestry_fraction < 0.0 || self.reference_ancestry_fraction > 1.0 {
+            return Err(Error::ReferenceAncestryFractionOutOfRange(
+                self.reference_ancestry_fraction,
+            ));
+        }
+
+        self.sequence_modification_parameters.verify()?;
+        if let Some(ancestor_length) = self.ancestor_length {
+            self.verify_ancestor_length(ancestor_length)?;
+            if self.has_template_switches(ancestor_length) {
+                self.sequence_modification_parameters
+                    .verify_template_switch_geometry(ancestor_length)?;
+            }
+        }
+
+        if self.sequence_modification_parameters.position_bias == CliPositionBias::Hotspots {
+            if self.hotspots.is_empty() {
+                return Err(Error::MissingHotspots);
+            }
+
+            let hotspot_spread = self.sequence_modification_parameters.hotspot_spread;
+            if hotspot_spread.is_nan() {
+                return Err(Error::HotspotSpreadIsNaN);
+            }
+            if hotspot_spread <= 0.0 {
+                return Err(Error::HotspotSpreadNotPositive(hotspot_spread));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify the parameters that depend on the length of the ancestor.
+    pub fn verify_ancestor_length(&self, ancestor_length: usize) -> Result<()> {
+        self.sequence_modification_parameters
+            .verify_ancestor_length(ancestor_length)
+    }
+
+    /// Returns true if template switches may be drawn for an ancestor of the given length.
+    fn has_template_switches(&self, ancestor_length: usize) -> bool {
+        if let Some((total_amount, [template_switch_probability, ..])) =
+            self.sequence_modification_probabilities.resolve()
+        {
+            total_amount > 0 && template_switch_probability > 0.0
+        } else {
+            self.resolve_sequence_modification_amount(ancestor_length)
+                .template_switch_amount
+                > 0
+        }
+    }
+}
+
+impl GeneratePairCommand {
+    /// Returns a compact `key=value` description of the generation parameters.
+    ///
+    /// The keys are always written in the same order, such that the description can be parsed and compared.
+    ///
+    /// If the ancestor was loaded from a file, then `n` is `None`.
+    pub fn fasta_comment(
+        &self,
+        n: Option<usize>,
+        alphabet: CliAlphabet,
+        ancestor_length: usize,
+    ) -> String {
+        let amount = &self.resolve_sequence_modification_amount(ancestor_length);
+        let parameters = &self.sequence_modification_parameters;
+        let template_switch_margin = if let Some(template_switch_margin_fraction) =
+            parameters.template_switch_margin_fraction
+        {
+            format!("template_switch_margin_fraction={template_switch_margin_fraction}")
+        } else {
+            format!(
+                "template_switch_margin={}",
+                parameters.template_switch_margin
+            )
+        };
+        let position_bias = if parameters.position_bias == CliPositionBias::Hotspots {
+            format!(
+                "position_bias=hotspots hotspots={} hotspot_spread={}",
+                self.hotspots
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                parameters.hotspot_spread
+            )
+        } else {
+            format!(
+                "position_bias={}",
+                parameters
+                    .position_bias
+                    .to_possible_value()
+                    .unwrap()
+                    .get_name()
+            )
+        };
+
+        [
+            format!("seed={}", self.random_seed),
+            format!("rng={}", self.rng.to_possible_value().unwrap().get_name()),
+            format!(
+                "template_switch_seed={}",
+                self.template_switch_seed
+                    .map_or_else(|| "none".to_string(), |seed| seed.to_string())
+            ),
+            format!(
+                "gap_seed={}",
+                self.gap_seed
+                    .map_or_else(|| "none".to_string(), |seed| seed.to_string())
+            ),
+            format!(
+                "substitution_seed={}",
+                self.substitution_seed
+                    .map_or_else(|| "none".to_string(), |seed| seed.to_string())
+            ),
+            if let Some(n) = n {
+                format!("n={n}")
+            } else {
+                "ancestor_input=true".to_string()
+            },
+            format!("alphabet={}", alphabet.name()),
+            format!("ancestor_length={ancestor_length}"),
+            if let Some(target_gc) = self.target_gc() {
+                format!("target_gc={}-{}", target_gc.start(), target_gc.end())
+            } else {
+                "target_gc=none".to_string()
+            },
+            format!(
+                "reference_ancestry_fraction={}",
+                self.reference_ancestry_fraction
+            ),
+            format!("template_switch_amount={}", amount.template_switch_amount),
+            format!("gap_amount={}", amount.gap_amount),
+            format!("substitution_amount={}", amount.substitution_amount),
+            if let Some((total_modifications, probabilities)) =
+                self.sequence_modification_probabilities.resolve()
+            {
+                format!(
+                    "total_modifications={total_modifications} modification_probabilities={}",
+                    probabilities
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            } else {
+                "total_modifications=none".to_string()
+            },
+            format!(
+                "template_switch_overlap={}",
+                parameters.template_switch_overlap
+            ),
+            format!(
+                "template_switch_forbid_self_overlap={}",
+                parameters.template_switch_forbid_self_overlap
+            ),
+            format!(
+                "template_switch_min_length={}",
+                parameters.template_switch_min_length()
+            ),
+            format!(
+                "template_switch_max_length={}",
+                parameters.template_switch_max_length()
+            ),
+            format!(
+                "template_switch_min_offset={}",
+                parameters.template_switch_min_offset
+            ),
+            format!(
+                "template_switch_max_offset={}",
+                parameters.template_switch_max_offset
+            ),
+            format!(
+                "template_switch_upstream_fraction={}",
+                parameters
+                    .template_switch_upstream_fraction
+                    .map_or_else(|| "none".to_string(), |fraction| fraction.to_string())
+            ),
+            format!(
+                "template_switch_offset_decay={}",
+                parameters
+                    .template_switch_offset_decay
+                    .map_or_else(|| "none".to_string(), |decay| decay.to_string())
+            ),
+            format!(
+                "template_switch_min_length_difference={}",
+                parameters.template_switch_min_length_difference
+            ),
+            format!(
+                "template_switch_max_length_difference={}",
+                parameters.template_switch_max_length_difference
+            ),
+            format!(
+                "template_switch_length_difference_distribution={}",
+                parameters.template_switch_length_difference_distribution
+            ),
+            format!(
+                "template_switch_preserve_frame={}",
+                parameters.template_switch_preserve_frame
+            ),
+            template_switch_margin,
+            format!(
+                "inter_sequence_template_switch_probability={}",
+                parameters.inter_sequence_template_switch_probability
+            ),
+            position_bias,
+            format!("gap_length_mean={}", parameters.gap_length_mean()),
+            format!(
+                "min_sequence_length={}",
+                parameters
+                    .min_sequence_length
+                    .map_or_else(|| "none".to_string(), |length| length.to_string())
+            ),
+            if let (Some(reading_frame), Some(synonymous_fraction)) =
+                (parameters.reading_frame, parameters.synonymous_fraction())
+            {
+                format!("reading_frame={reading_frame} synonymous_fraction={synonymous_fraction}")
+            } else {
+                "reading_frame=none".to_string()
+            },
+            format!("cpg_bias={}", parameters.cpg_bias),
+            format!(
+                "allow_identity_substitution={}",
+                parameters.allow_identity_substitution
+            ),
+            if parameters.homopolymer_gaps {
+                format!(
+                    "homopolymer_gaps=true homopolymer_min_length={}",
+                    parameters.homopolymer_min_length
+                )
+            } else {
+                "homopolymer_gaps=false".to_string()
+            },
+            format!(
+                "query_strand={}",
+                if self.query_reverse_complement {
+                    '-'
+                } else {
+                    '+'
+                }
+            ),
+        ]
+        .join(" ")
+    }
+}
+
+impl GeneratePairCommand {
+    pub fn position_bias(&self) -> PositionBias {
+        match self.sequence_modification_parameters.position_bias {
+            CliPositionBias::Uniform => PositionBias::Uniform,
+            CliPositionBias::Ends => PositionBias::Ends,
+            CliPositionBias::Center => PositionBias::Center,
+            CliPositionBias::Hotspots => PositionBias::Hotspots {
+                positions: self.hotspots.clone(),
+                spread: self.sequence_modification_parameters.hotspot_spread,
+            },
+        }
+    }
+
+    /// Returns the parameters of generating a pair, reading the files of allowed regions and rate multipliers.
+    pub fn pair_parameters(&self) -> Result<PairParameters> {
+        Ok(PairParameters {
+            reference_ancestry_fraction: self.reference_ancestry_fraction,
+            modification_amount_resolver: self.modification_amount_resolver(),
+            modification_type_probabilities: self.sequence_modification_probabilities.resolve(),
+            sequence_modification_parameters: self.sequence_modification_parameters,
+            position_bias: self.position_bias(),
+            allowed_intervals: AllowedIntervals::from_bed_files(
+                self.modifiable_regions.as_deref(),
+                self.masked_regions.as_deref(),
+            )?,
+            rate_multiplier_intervals: self
+                .rate_multipliers
+                .as_deref()
+                .map(RateMultipliers::read_tsv_file)
+                .transpose()?,
+            max_edit_distance: self.max_edit_distance,
+            template_switch_edit_cost: self.template_switch_edit_cost,
+            realistic_starts: self.realistic_starts,
+            restart_limit: self.n_gram_generation_parameters.restart_limit(),
+            target_gc: self.target_gc(),
+            target_gc_tries: self.target_gc_tries,
+            max_homopolymer: self.max_homopolymer,
+            max_homopolymer_tries: self.max_homopolymer_tries,
+            require_template_switch: self.require_template_switch,
+            require_template_switch_tries: self.require_template_switch_tries,
+            retain_ancestor: self.requires_ancestor(),
+            track_ancestry: self.requires_ancestry(),
+            track_substitutions: self.spectrum_output.is_some(),
+        })
+    }
+
+    /// Returns the random generators of the given attempt of generating the pair with the given index.
+    ///
+    /// Each generator draws from its own stream, see [`RandomStream`].
+    /// Later attempts replace all seeds by seeds derived from them, see [`retry_seed`].
+    pub fn pair_rngs(&self, pair_index: usize, attempt: usize) -> PairRngs {
+        let [ancestor, split, reference, query] = [
+            RandomStream::Ancestor,
+            RandomStream::Split,
+            RandomStream::Reference,
+            RandomStream::Query,
+        ]
+        .map(|stream| stream.rng(retry_seed(self.random_seed, attempt), pair_index, self.rng));
+        let modification_types = [RandomStream::Reference, RandomStream::Query].map(|stream| {
+            let type_rng = |modification_type, seed: Option<u64>| {
+                seed.map(|seed| {
+                    stream.modification_type_rng(
+                        modification_type,
+                        retry_seed(seed, attempt),
+                        pair_index,
+                        self.rng,
+                    )
+                })
+            };
+            ModificationTypeRngs {
+                template_switch: type_rng("template-switch", self.template_switch_seed),
+                gap: type_rng("gap", self.gap_seed),
+                substitution: type_rng("substitution", self.substitution_seed),
+            }
+        });
+
+        PairRngs {
+            ancestor,
+            split,
+            reference,
+            query,
+            modification_types,
+        }
+    }
+}
+
+impl CheckCommand {
+    pub fn verify(&self) -> Result<()> {
+        if self.reference_ancestry_fraction.is_nan() {
+            return Err(Error::ReferenceAncestryFractionIsNaN);
+        }
+        if self.reference_ancestry_fraction < 0.0 || self.reference_ancestry_fraction > 1.0 {
+            return Err(Error::ReferenceAncestryFractionOutOfRange(
+                self.reference_ancestry_fraction,
+            ));
+        }
+
+        self.sequence_modification_parameters.verify()?;
+        self.sequence_modification_parameters
+            .verify_ancestor_length(self.ancestor_length)
+    }
+}
+
+impl SequenceModificationParameters {
+    /// Verify the parameters that do not depend on the length of the ancestor.
+    pub fn verify(&self) -> Result<()> {
+        if self.gap_length_mean().is_nan() {
+            return Err(Error::GapLengthMeanIsNaN);
+        }
+
+        if let Some(template_switch_margin_fraction) = self.template_switch_margin_fraction {
+            if template_switch_margin_fraction.is_nan() {
+                return Err(Error::TemplateSwitchMarginFractionIsNaN);
+            }
+            if !(0.0..=1.0).contains(&template_switch_margin_fraction) {
+                return Err(Error::TemplateSwitchMarginFractionOutOfRange(
+                    template_switch_margin_fraction,
+                ));
+            }
+        }
+
+        let inter_sequence_template_switch_probability =
+            self.inter_sequence_template_switch_probability;
+        if inter_sequence_template_switch_probability.is_nan() {
+            return Err(Error::InterSequenceTemplateSwitchProbabilityIsNaN);
+        }
+        if !(0.0..=1.0).contains(&inter_sequence_template_switch_probability) {
+            return Err(Error::InterSequenceTemplateSwitchProbabilityOutOfRange(
+                inter_sequence_template_switch_probability,
+            ));
+        }
+
+        if let Some(reading_frame) = self.reading_frame {
+            if reading_frame > 2 {
+                return Err(Error::InvalidReadingFrame(reading_frame));
+            }
+
+            let synonymous_fraction = self
+                .synonymous_fraction()
+                .ok_or(Error::MissingSynonymousFraction)?;
+            if synonymous_fraction.is_nan() {
+                return Err(Error::SynonymousFractionIsNaN);
+            }
+            if !(0.0..=1.0).contains(&synonymous_fraction) {
+                return Err(Error::SynonymousFractionOutOfRange(synonymous_fraction));
+            }
+        }
+
+        let cpg_bias = self.cpg_bias;
+        if cpg_bias.is_nan() {
+            return Err(Error::CpgBiasIsNaN);
+        }
+        if cpg_bias < 0.0 || cpg_bias.is_infinite() {
+            return Err(Error::CpgBiasOutOfRange(cpg_bias));
+        }
+
+        if self.template_switch_min_offset > self.template_switch_max_offset {
+            return Err(Error::TemplateSwitchOffsetEmpty {
+                min: self.template_switch_min_offset,
+                max: self.template_switch_max_offset,
+            });
+        }
+        if let Some(decay) = self.template_switch_offset_decay {
+            if !(decay > 0.0 && decay.is_finite()) {
+                return Err(Error::TemplateSwitchOffsetDecayNotPositive(decay));
+            }
+        }
+        if let Some(upstream_fraction) = self.template_switch_upstream_fraction {
+            if upstream_fraction.is_nan() {
+                return Err(Error::TemplateSwitchUpstreamFractionIsNaN);
+            }
+            if !(0.0..=1.0).contains(&upstream_fraction) {
+                return Err(Error::TemplateSwitchUpstreamFractionOutOfRange(
+                    upstream_fraction,
+                ));
+            }
+            let min_offset = self.template_switch_min_offset;
+            let max_offset = self.template_switch_max_offset;
+            if (upstream_fraction > 0.0 && min_offset >= 0)
+                || (upstream_fraction < 1.0 && max_offset < 0)
+            {
+                return Err(Error::TemplateSwitchOffsetDirectionMissing {
+                    upstream_fraction,
+                    min: min_offset,
+                    max: max_offset,
+                });
+            }
+        }
+        if self.template_switch_min_length() > self.template_switch_max_length() {
+            return Err(Error::TemplateSwitchLengthEmpty {
+                min: self.template_switch_min_length(),
+                max: self.template_switch_max_length(),
+            });
+        }
+        if self.template_switch_min_length_difference > self.template_switch_max_length_difference {
+            return Err(Error::TemplateSwitchLengthDifferenceEmpty {
+                min: self.template_switch_min_length_difference,
+                max: self.template_switch_max_length_difference,
+            });
+        }
+        let min_length_difference = self.template_switch_min_valid_length_difference();
+        if min_length_difference > self.template_switch_max_length_difference {
+            return Err(Error::TemplateSwitchLengthDifferenceNoMultipleOfThree {
+                min: self.template_switch_min_length_difference,
+                max: self.template_switch_max_length_difference,
+            });
+        }
+        // Otherwise, there would be no valid length difference for the shortest template switches.
+        if min_length_difference > self.template_switch_min_length() as isize {
+            return Err(Error::TemplateSwitchLengthDifferenceExceedsLength {
+                length: self.template_switch_min_length(),
+                length_difference: min_length_difference,
+            });
+        }
+        if self.template_switch_forbid_self_overlap && self.template_switch_always_self_overlaps() {
+            return Err(Error::TemplateSwitchAlwaysSelfOverlapping);
+        }
+
+        Ok(())
+    }
+
+    /// Verify the parameters that depend on the length of the ancestor.
+    pub fn verify_ancestor_length(&self, ancestor_length: usize) -> Result<()> {
+        if self.gap_length_mean() < 1.0 || self.gap_length_mean() > ancestor_length as f64 {
+            return Err(Error::GapLengthMeanOutOfRange {
+                actual: self.gap_length_mean(),
+                minimum: 1.0,
+                maximum: ancestor_length as f64,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if no template switch fits into an ancestor of the given length, naming the parameter that prevents it.
+    ///
+    /// Otherwise, this warns if the template switches with an extreme offset or the maximum length never fit,
+    /// since such template switches are skipped whenever they are drawn.
+    pub fn verify_template_switch_geometry(&self, ancestor_length: usize) -> Result<()> {
+        let margin = self.resolve_template_switch_margin(ancestor_length);
+        let required_length = |parameters: &Self, margin| {
+            smallest_template_switch(parameters, margin).map(|(required_length, _)| required_length)
+        };
+
+        let minimum_length = required_length(self, margin)?;
+        if minimum_length == usize::MAX || minimum_length <= ancestor_length {
+            for (parameter, offset) in [
+                (
+                    "template-switch-min-offset",
+                    self.template_switch_min_offset,
+                ),
+                (
+                    "template-switch-max-offset",
+                    self.template_switch_max_offset,
+                ),
+            ] {
+                // Offsets in the direction that is never drawn do not count.
+                match self.template_switch_upstream_fraction {
+                    Some(1.0) if offset >= 0 => continue,
+                    Some(0.0) if offset < 0 => continue,
+                    _ => {}
+                }
+                let parameters = Self {
+                    template_switch_min_offset: offset,
+                    template_switch_max_offset: offset,
+                    ..*self
+                };
+                let minimum_length = required_length(&parameters, margin)?;
+                if minimum_length != usize::MAX && minimum_length > ancestor_length {
+                    warn!("Template switches with offset {offset} require a sequence length of {minimum_length}, but the ancestor length is {ancestor_length}, so they are skipped when drawn; consider changing --{parameter}");
+                }
+            }
+
+            let max_length = self.template_switch_max_length();
+            let parameters = Self {
+                template_switch_min_length: Some(max_length),
+                ..*self
+            };
+            let minimum_length = required_length(&parameters, margin)?;
+            if minimum_length != usize::MAX && minimum_length > ancestor_length {
+                warn!("Template switches of length {max_length} require a sequence length of {minimum_length}, but the ancestor length is {ancestor_length}, so they are skipped when drawn; consider reducing --template-switch-max-length");
+            }
+            return Ok(());
+        }
+
+        // Find the first parameter whose relaxation makes a template switch fit.
+        let relaxed_offsets = Self {
+            template_switch_min_offset: self.template_switch_min_offset.min(-1),
+            template_switch_max_offset: self
+                .template_switch_max_offset
+                .max(self.template_switch_max_length() as isize),
+            ..*self
+        };
+        let parameter = if required_length(&relaxed_offsets, margin)? <= ancestor_length {
+            if self.template_switch_min_offset > 0 {
+                "template-switch-min-offset"
+            } else {
+                "template-switch-max-offset"
+            }
+        } else if required_length(&relaxed_offsets, 0)? <= ancestor_length {
+            if self.template_switch_margin_fraction.is_some() {
+                "template-switch-margin-fraction"
+            } else {
+                "template-switch-margin"
+            }
+        } else {
+            "template-switch-min-length"
+        };
+
+        Err(Error::TemplateSwitchInfeasible {
+            parameter,
+            required_length: minimum_length,
+            ancestor_length,
+        })
+    }
+
+    /// Sets the parameters that were not given to the defaults of the alphabet.
+    pub fn set_alphabet_defaults(&mut self, alphabet: CliAlphabet) {
+        let (min_length, max_length) = alphabet.default_template_switch_length();
+        self.template_switch_min_length.get_or_insert(min_length);
+        self.template_switch_max_length.get_or_insert(max_length);
+        self.gap_length_mean
+            .get_or_insert(alphabet.default_gap_length_mean());
+    }
+
+    /// The minimum length of a template switch.
+    ///
+    /// If it was neither given nor set by [`Self::set_alphabet_defaults`], then this is the default for DNA.
+    pub fn template_switch_min_length(&self) -> usize {
+        self.template_switch_min_length
+            .unwrap_or_else(|| CliAlphabet::Dna.default_template_switch_length().0)
+    }
+
+    /// The maximum length of a template switch, see [`Self::template_switch_min_length`].
+    pub fn template_switch_max_length(&self) -> usize {
+        self.template_switch_max_length
+            .unwrap_or_else(|| CliAlphabet::Dna.default_template_switch_length().1)
+    }
+
+    /// The mean of the gap length, see [`Self::template_switch_min_length`].
+    pub fn gap_length_mean(&self) -> f64 {
+        self.gap_length_mean
+            .unwrap_or_else(|| CliAlphabet::Dna.default_gap_length_mean())
+    }
+
+    /// Returns true if every template switch allowed by the parameters overlaps itself,
+    /// see [`crate::sequence_modifier::SequenceModification::is_self_overlapping`].
+    pub fn template_switch_always_self_overlaps(&self) -> bool {
+        // Self-overlapping offsets form an interval, and it is shortest for the shortest replaced interval,
+        // which belongs to the shortest template switches with the largest length difference.
+        let length = self.template_switch_min_length();
+        let replaced_length = length as isize
+            - self
+                .template_switch_max_length_difference
+                .min(length as isize);
+        self.inter_sequence_template_switch_probability < 1.0
+            && [
+                self.template_switch_min_offset,
+                self.template_switch_max_offset,
+            ]
+            .into_iter()
+            .all(|offset| template_switch_self_overlaps(length, offset, replaced_length))
+    }
+
+    /// Returns the number of which each template switch length difference is a multiple.
+    pub fn template_switch_length_difference_multiple(&self) -> isize {
+        if self.template_switch_preserve_frame {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Returns the smallest length difference that is at least the minimum and a multiple of
+    /// [`Self::template_switch_length_difference_multiple`].
+    fn template_switch_min_valid_length_difference(&self) -> isize {
+        let multiple = self.template_switch_length_difference_multiple();
+        -(-self.template_switch_min_length_difference).div_euclid(multiple) * multiple
+    }
+
+    /// Returns the fraction of synonymous substitutions, if it was given.
+    pub fn synonymous_fraction(&self) -> Option<f64> {
+        if self.synonymous_only {
+            Some(1.0)
+        } else {
+            self.synonymous_fraction
+        }
+    }
+
+    /// Returns the absolute template switch margin for a sequence of the given length.
+    pub fn resolve_template_switch_margin(&self, sequence_length: usize) -> usize {
+        if let Some(template_switch_margin_fraction) = self.template_switch_margin_fraction {
+            (template_switch_margin_fraction * sequence_length as f64).floor() as usize
+        } else {
+            self.template_switch_margin
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+
+    use crate::{ancestor_length::AncestorLengthDistribution, error::Error};
+
+    use super::{Cli, CliAlphabet, CliCommands, GeneratePairCommand};
+
+    fn parse(arguments: &[&str]) -> Result<GeneratePairCommand, clap::Error> {
+        let arguments = ["tsgen", "generate-pair", "--output", "pair.fa"]
+            .iter()
+            .chain(arguments);
+        match Cli::try_parse_from(arguments)?.command {
+            CliCommands::GeneratePair(command) => Ok(command),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn generate_conflicts() {
+        let generate = |arguments: &[&str]| {
+            Cli::try_parse_from(
+                [
+                    "tsgen", "generate", "--model", "model", "--output", "out.fa", "--length", "10",
+                ]
+                .iter()
+                .chain(arguments),
+            )
+        };
+
+        assert!(generate(&["--realistic-starts"]).is_ok());
+        assert!(generate(&["--continue-from", "prefix.fa"]).is_ok());
+        assert!(generate(&["--continue-from", "prefix.fa", "--realistic-starts"]).is_err());
+
+        // The interpolation weights are validated like those of generate-pair.
+        for (weights, valid) in [("0.5,0.5", true), ("-1,2", false), ("0,0", false)] {
+            // The `=` keeps negative weights from being interpreted as arguments.
+            let argument = format!("--interpolation-weights={weights}");
+            let CliCommands::Generate(command) = generate(&[&argument]).unwrap().command else {
+                unreachable!()
+            };
+            assert_eq!(command.verify().is_ok(), valid);
+            let command =
+                parse(&["--model", "model", "--ancestor-length", "100", &argument]).unwrap();
+            assert_eq!(command.n_gram_generation_parameters.verify().is_ok(), valid);
+        }
+
+        // Ancestors read from a file are not generated.
+        assert!(parse(&["--ancestor-input", "ancestor.fa"]).is_ok());
+        for argument in [
+            "--interpolation-weights=1",
+            "--max-restarts=1",
+            "--global-fallback",
+        ] {
+            assert!(parse(&["--ancestor-input", "ancestor.fa", argument]).is_err());
+        }
+    }
+
+    #[test]
+    fn create_model_verify() {
+        let verify = |arguments: &[&str]| {
+            let arguments = [
+                "tsgen",
+                "create-n-gram-model",
+                "--output",
+                "model.cbor",
+                "-n",
+                "2",
+            ]
+            .iter()
+            .chain(arguments);
+            match Cli::try_parse_from(arguments).unwrap().command {
+                CliCommands::CreateNGramModel(command) => command.verify(),
+                _ => unreachable!(),
+            }
+        };
+
+        assert!(verify(&[
+            "-i",
+            "a.fa",
+            "-i",
+            "b.fa",
+            "--input-weight",
+            "1",
+            "--input-weight",
+            "2"
+        ])
+        .is_ok());
+        assert!(matches!(
+            verify(&["-i", "a.fa", "-i", "b.fa", "--input-weight", "1"]),
+            Err(Error::InputWeightAmountMismatch { .. })
+        ));
+        assert!(matches!(
+            verify(&["-i", "a.fa", "--input-weight", "0"]),
+            Err(Error::ZeroInputWeight)
+        ));
+        assert!(matches!(
+            verify(&["-i", "a.fa", "--unknown-character-warning-fraction", "1.5"]),
+            Err(Error::UnknownCharacterWarningFractionOutOfRange(_))
+        ));
+        assert!(verify(&["-i", "a.fa", "--reduce-alphabet", "purine-pyrimidine"]).is_ok());
+        assert!(matches!(
+            verify(&[
+                "-i",
+                "a.fa",
+                "--alphabet",
+                "purine-pyrimidine",
+                "--reduce-alphabet",
+                "purine-pyrimidine"
+            ]),
+            Err(Error::AlphabetReductionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn fasta_comment_seeds() {
+        let command = parse(&[
+            "--model",
+            "model",
+            "--ancestor-length",
+            "100",
+            "--gap-seed",
+            "5",
+        ])
+        .unwrap();
+        let comment = command.fasta_comment(Some(2), CliAlphabet::Dna, 100);
+        assert!(comment.contains(
+            "seed=0 rng=xoshiro256 template_switch_seed=none gap_seed=5 substitution_seed=none "
+        ));
+    }
+
+    #[test]
+    fn modification_rates() {
+        let command = parse(&[
+            "--ancestor-input",
+            "ancestor.fa",
+            "--gap-rate",
+            "0.0125",
+            "--substitution-rate",
+            "0.1",
+        ])
+        .unwrap();
+        command.verify().unwrap();
+        let amount = command.resolve_sequence_modification_amount(1000);
+        assert_eq!(amount.template_switch_amount, 1);
+        assert_eq!(amount.gap_amount, 13);
+        assert_eq!(amount.substitution_amount, 100);
+
+        assert!(parse(&[
+            "--ancestor-input",
+            "ancestor.fa",
+            "--gap-rate",
+            "0.1",
+            "--gap-amount",
+            "10"
+        ])
+        .is_err());
+
+        for rate in ["-0.1", "inf", "NaN"] {
+            let command = parse(&[
+                "--ancestor-input",
+                "ancestor.fa",
+                "--template-switch-rate",
+                rate,
+            ])
+            .unwrap();
+            assert!(matches!(
+                command.verify(),
+                Err(Error::ModificationRateOutOfRange {
+                    modification_type: "template switch",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn record_ids() {
+        let command =
+            parse(&["--ancestor-input", "ancestor.fa", "--query-id", "reference"]).unwrap();
+        assert!(matches!(
+            command.verify(),
+            Err(Error::DuplicateRecordId(id)) if id == "reference"
+        ));
+
+        let arguments = [
+            "--ancestor-input",
+            "ancestor.fa",
+            "--ancestor-output-id",
+            "query",
+        ];
+        parse(&arguments).unwrap().verify().unwrap();
+        let command = parse(&[&arguments[..], &["--combined-output", "all.fa"]].concat()).unwrap();
+        assert!(matches!(
+            command.verify(),
+            Err(Error::DuplicateRecordId(id)) if id == "query"
+        ));
+    }
+
+    #[test]
+    fn template_switch_self_overlap() {
+        let verify = |min_offset: &str, max_offset: &str, max_length_difference: &str| {
+            parse(&[
+                "--ancestor-input",
+                "ancestor.fa",
+                "--template-switch-forbid-self-overlap",
+                &format!("--template-switch-min-offset={min_offset}"),
+                &format!("--template-switch-max-offset={max_offset}"),
+                &format!("--template-switch-max-length-difference={max_length_difference}"),
+            ])
+            .unwrap()
+            .verify()
+        };
+
+        // The shortest template switches of length 10 overlap themselves for offsets 0 to 18.
+        assert!(matches!(
+            verify("0", "18", "0"),
+            Err(Error::TemplateSwitchAlwaysSelfOverlapping)
+        ));
+        verify("-1", "18", "0").unwrap();
+        verify("0", "19", "0").unwrap();
+        verify("0", "18", "10").unwrap();
+    }
+
+    #[test]
+    fn template_switch_preserve_frame() {
+        let verify = |min_length_difference: &str, max_length_difference: &str| {
+            parse(&[
+                "--ancestor-input",
+                "ancestor.fa",
+                "--reading-frame",
+                "0",
+                "--synonymous-only",
+                "--template-switch-preserve-frame",
+                "--template-switch-min-length=2",
+                &format!("--template-switch-min-length-difference={min_length_difference}"),
+                &format!("--template-switch-max-length-difference={max_length_difference}"),
+            ])
+            .unwrap()
+            .verify()
+        };
+
+        verify("-2", "2").unwrap();
+        verify("-5", "-3").unwrap();
+        assert!(matches!(
+            verify("1", "2"),
+            Err(Error::TemplateSwitchLengthDifferenceNoMultipleOfThree { min: 1, max: 2 })
+        ));
+        assert!(matches!(
+            verify("-5", "-4"),
+            Err(Error::TemplateSwitchLengthDifferenceNoMultipleOfThree { .. })
+        ));
+        // The smallest multiple of three exceeds the shortest template switch.
+        assert!(matches!(
+            verify("1", "3"),
+            Err(Error::TemplateSwitchLengthDifferenceExceedsLength {
+                length: 2,
+                length_difference: 3
+            })
+        ));
+        assert!(parse(&[
+            "--ancestor-input",
+            "ancestor.fa",
+            "--template-switch-preserve-frame"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn template_switch_upstream_fraction() {
+        let verify = |upstream_fraction: &str, min_offset: &str, max_offset: &str| {
+            parse(&[
+                "--ancestor-input",
+                "ancestor.fa",
+                &format!("--template-switch-upstream-fraction={upstream_fraction}"),
+                &format!("--template-switch-min-offset={min_offset}"),
+                &format!("--template-switch-max-offset={max_offset}"),
+            ])
+            .unwrap()
+            .verify()
+        };
+
+        verify("0.8", "-30", "10").unwrap();
+        verify("1.0", "-30", "-1").unwrap();
+        verify("0.0", "0", "10").unwrap();
+        assert!(matches!(
+            verify("1.5", "-30", "10"),
+            Err(Error::TemplateSwitchUpstreamFractionOutOfRange(_))
+        ));
+        assert!(matches!(
+            verify("0.8", "-30", "-1"),
+            Err(Error::TemplateSwitchOffsetDirectionMissing { .. })
+        ));
+        assert!(matches!(
+            verify("0.8", "0", "10"),
+            Err(Error::TemplateSwitchOffsetDirectionMissing { .. })
+        ));
+    }
+
+    #[test]
+    fn template_switch_geometry() {
+        let verify = |arguments: &[&str]| {
+            let mut arguments = arguments.to_vec();
+            arguments.extend(["--model", "model", "--ancestor-length", "1000"]);
+            parse(&arguments).unwrap().verify()
+        };
+        let infeasible_parameter = |arguments: &[&str]| match verify(arguments) {
+            Err(Error::TemplateSwitchInfeasible { parameter, .. }) => parameter,
+            result => panic!("unexpected result {result:?}"),
+        };
+
+        verify(&[]).unwrap();
+        // Some offsets do not fit, which only warns.
+        verify(&["--template-switch-max-offset", "2000"]).unwrap();
+        assert_eq!(
+            infeasible_parameter(&[
+                "--template-switch-min-offset",
+                "2000",
+                "--template-switch-max-offset",
+                "3000"
+            ]),
+            "template-switch-min-offset"
+        );
+        assert_eq!(
+            infeasible_parameter(&[
+                "--template-switch-min-offset",
+                "-3000",
+                "--template-switch-max-offset",
+                "-2000"
+            ]),
+            "template-switch-max-offset"
+        );
+        assert_eq!(
+            infeasible_parameter(&["--template-switch-margin", "600"]),
+            "template-switch-margin"
+        );
+        assert_eq!(
+            infeasible_parameter(&[
+                "--template-switch-min-length",
+                "1500",
+                "--template-switch-max-length",
+                "2000"
+            ]),
+            "template-switch-min-length"
+        );
+        // Without template switches, their geometry is irrelevant.
+        verify(&[
+            "--template-switch-margin",
+            "600",
+            "--template-switch-amount",
+            "0",
+        ])
+        .unwrap();
+    }
+
+    #[test]
+    fn alphabet_defaults() {
+        let parameters = |arguments: &[&str], alphabet| {
+            let mut command = parse(arguments).unwrap();
+            command
+                .sequence_modification_parameters
+                .set_alphabet_defaults(alphabet);
+            command.verify().unwrap();
+            let parameters = command.sequence_modification_parameters;
+            (
+                parameters.template_switch_min_length(),
+                parameters.template_switch_max_length(),
+                parameters.gap_length_mean(),
+            )
+        };
+        let ancestor_input = ["--ancestor-input", "ancestor.fa"];
+
+        assert_eq!(parameters(&ancestor_input, CliAlphabet::Dna), (10, 30, 3.0));
+        assert_eq!(
+            parameters(&ancestor_input, CliAlphabet::PurinePyrimidine),
+            (20, 60, 3.0)
+        );
+        // Given parameters override the defaults of the alphabet.
+        assert_eq!(
+            parameters(
+                &[
+                    "--ancestor-input",
+                    "ancestor.fa",
+                    "--template-switch-max-length",
+                    "40",
+                    "--gap-length-mean",
+                    "2.0"
+                ],
+                CliAlphabet::PurinePyrimidine
+            ),
+            (20, 40, 2.0)
+        );
+    }
+
+    #[test]
+    fn ancestor_lengths() {
+        let distribution = |arguments: &[&str]| {
+            let arguments: Vec<_> = ["--model", "model.cbor"]
+                .iter()
+                .chain(arguments)
+                .copied()
+                .collect();
+            parse(&arguments).unwrap().ancestor_length_distribution()
+        };
+
+        assert_eq!(
+            distribution(&["--ancestor-lengths", "100,250", "--num-pairs", "2"]).unwrap(),
+            Some(AncestorLengthDistribution::List(vec![100, 250]))
+        );
+        assert!(matches!(
+            distribution(&["--ancestor-lengths", "100,250", "--num-pairs", "3"]),
+            Err(Error::TooFewAncestorLengths {
+                amount: 2,
+                num_pairs: 3
+            })
+        ));
+        assert!(distribution(&[
+            "--ancestor-lengths",
+            "100,250",
+            "--num-pairs",
+            "3",
+            "--cycle-ancestor-lengths"
+        ])
+        .is_ok());
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("ancestor-lengths.txt");
+        fs::write(&path, "# lengths\n100\n\n250\n").unwrap();
+        assert_eq!(
+            distribution(&["--ancestor-lengths-file", path.to_str().unwrap()]).unwrap(),
+            Some(AncestorLengthDistribution::List(vec![100, 250]))
+        );
+        fs::write(&path, "# no lengths\n").unwrap();
+        assert!(matches!(
+            distribution(&["--ancestor-lengths-file", path.to_str().unwrap()]),
+            Err(Error::EmptyAncestorLengths)
+        ));
+        fs::write(&path, "100\nlong\n").unwrap();
+        assert!(matches!(
+            distribution(&["--ancestor-lengths-file", path.to_str().unwrap()]),
+            Err(Error::InvalidAncestorLengthLine { line_number: 2, .. })
+        ));
+
+        // Cycling requires a list of lengths.
+        assert!(parse(&[
+            "--model",
+            "model.cbor",
+            "--ancestor-length",
+            "100",
+            "--cycle-ancestor-lengths"
+        ])
+        .is_err());
+
+        // Only one source of the ancestor length may be given.
+        assert!(parse(&[
+            "--model",
+            "model.cbor",
+            "--ancestor-length",
+            "100",
+            "--ancestor-lengths",
+            "100,250"
+        ])
+        .is_err());
+    }
+}
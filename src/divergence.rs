@@ -0,0 +1,143 @@
+/// The realized divergence between reference and query.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Divergence {
+    pub reference_length: usize,
+    pub query_length: usize,
+    /// The unit cost edit distance between reference and query.
+    pub edit_distance: usize,
+}
+
+impl Divergence {
+    /// Computes the divergence between the given sequences.
+    pub fn compute<Character: Eq>(reference: &[Character], query: &[Character]) -> Self {
+        Self {
+            reference_length: reference.len(),
+            query_length: query.len(),
+            edit_distance: edit_distance(reference, query),
+        }
+    }
+
+    /// The fraction of the longer sequence that is not affected by edits, between 0.0 and 1.0.
+    pub fn identity(&self) -> f64 {
+        let length = self.reference_length.max(self.query_length);
+        if length == 0 {
+            1.0
+        } else {
+            1.0 - self.edit_distance as f64 / length as f64
+        }
+    }
+}
+
+/// Computes the unit cost edit distance between `a` and `b`.
+///
+/// The dynamic programming table is restricted to a band around the main diagonal,
+/// and the band is doubled until it contains the distance.
+/// Hence the runtime is linear in the length of the sequences times the distance.
+pub fn edit_distance<Character: Eq>(a: &[Character], b: &[Character]) -> usize {
+    let mut band = a.len().abs_diff(b.len()).max(16);
+    loop {
+        if let Some(distance) = banded_edit_distance(a, b, band) {
+            return distance;
+        }
+        band *= 2;
+    }
+}
+
+/// Computes the edit distance between `a` and `b` if it is at most `band`.
+fn banded_edit_distance<Character: Eq>(
+    a: &[Character],
+    b: &[Character],
+    band: usize,
+) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > band {
+        return None;
+    }
+
+    // Entry `k` of a row `i` stores the distance between `a[..i]` and `b[..i + k - band]`.
+    let width = 2 * band + 1;
+    let unreachable = usize::MAX / 2;
+    let mut previous_row = vec![unreachable; width];
+    let mut row = vec![unreachable; width];
+    for j in 0..=b.len().min(band) {
+        previous_row[j + band] = j;
+    }
+
+    for i in 1..=a.len() {
+        row.fill(unreachable);
+        let first_j = i.saturating_sub(band);
+        let last_j = (i + band).min(b.len());
+        for j in first_j..=last_j {
+            let k = j + band - i;
+            let mut distance = unreachable;
+            if k + 1 < width {
+                distance = distance.min(previous_row[k + 1] + 1);
+            }
+            if j > 0 {
+                let substitution_cost = usize::from(a[i - 1] != b[j - 1]);
+                distance = distance.min(previous_row[k] + substitution_cost);
+                if k > 0 {
+                    distance = distance.min(row[k - 1] + 1);
+                }
+            }
+            row[k] = distance;
+        }
+        std::mem::swap(&mut previous_row, &mut row);
+    }
+
+    let distance = previous_row[b.len() + band - a.len()];
+    (distance <= band).then_some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::{banded_edit_distance, edit_distance, Divergence};
+
+    fn full_edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut previous_row: Vec<_> = (0..=b.len()).collect();
+        for i in 1..=a.len() {
+            let mut row = vec![i; b.len() + 1];
+            for j in 1..=b.len() {
+                row[j] = (previous_row[j] + 1)
+                    .min(row[j - 1] + 1)
+                    .min(previous_row[j - 1] + usize::from(a[i - 1] != b[j - 1]));
+            }
+            previous_row = row;
+        }
+        previous_row[b.len()]
+    }
+
+    #[test]
+    fn edit_distance_matches_full_table() {
+        assert_eq!(edit_distance(b"", b""), 0);
+        assert_eq!(edit_distance(b"ACGT", b""), 4);
+        assert_eq!(edit_distance(b"kitten", b"sitting"), 3);
+        assert_eq!(banded_edit_distance(b"AAAATTTT", b"TTTTAAAA", 4), None);
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        for _ in 0..100 {
+            let a: Vec<u8> = (0..rng.gen_range(0..200))
+                .map(|_| b"ACGT"[rng.gen_range(0..4)])
+                .collect();
+            let mut b = a.clone();
+            for _ in 0..rng.gen_range(0..50) {
+                let position = rng.gen_range(0..=b.len());
+                match rng.gen_range(0..3) {
+                    0 => b.insert(position, b"ACGT"[rng.gen_range(0..4)]),
+                    1 if position < b.len() => {
+                        b.remove(position);
+                    }
+                    _ if position < b.len() => b[position] = b"ACGT"[rng.gen_range(0..4)],
+                    _ => {}
+                }
+            }
+            assert_eq!(edit_distance(&a, &b), full_edit_distance(&a, &b));
+        }
+
+        let divergence = Divergence::compute(b"ACGTACGTAC", b"ACGTTCGTA");
+        assert_eq!(divergence.edit_distance, 2);
+        assert_eq!(divergence.identity(), 0.8);
+    }
+}
@@ -22,21 +22,115 @@ pub enum Error {
     #[error("model deserialisation error: {0}")]
     ModelDeserialisation(#[from] ciborium::de::Error<std::io::Error>),
 
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("config file error: {0}")]
+    Config(#[from] toml::de::Error),
+
+    #[error("config serialisation error: {0}")]
+    ConfigSerialisation(#[from] toml::ser::Error),
+
+    #[error("the config file contains the unknown key {0:?}")]
+    UnknownConfigKey(String),
+
+    #[error("the config file contains the invalid value {value} for key {key:?}")]
+    InvalidConfigValue { key: String, value: String },
+
     #[error("the given reference ancestry fraction is not a number")]
     ReferenceAncestryFractionIsNaN,
 
     #[error("the given reference ancestry fraction {0} is out of range [0.0, 1.0]")]
     ReferenceAncestryFractionOutOfRange(f64),
 
+    #[error("the given template switch margin fraction is not a number")]
+    TemplateSwitchMarginFractionIsNaN,
+
+    #[error("the given template switch margin fraction {0} is out of range [0.0, 1.0]")]
+    TemplateSwitchMarginFractionOutOfRange(f64),
+
+    #[error("the given inter-sequence template switch probability is not a number")]
+    InterSequenceTemplateSwitchProbabilityIsNaN,
+
+    #[error("the given inter-sequence template switch probability {0} is out of range [0.0, 1.0]")]
+    InterSequenceTemplateSwitchProbabilityOutOfRange(f64),
+
+    #[error("the given reading frame {0} is not one of 0, 1 or 2")]
+    InvalidReadingFrame(usize),
+
+    #[error("a reading frame requires either --synonymous-only or --synonymous-fraction")]
+    MissingSynonymousFraction,
+
+    #[error("the given synonymous fraction is not a number")]
+    SynonymousFractionIsNaN,
+
+    #[error("the given synonymous fraction {0} is out of range [0.0, 1.0]")]
+    SynonymousFractionOutOfRange(f64),
+
+    #[error("codon-preserving substitutions require the DNA alphabet")]
+    ReadingFrameRequiresDna,
+
+    #[error("the given CpG bias is not a number")]
+    CpgBiasIsNaN,
+
+    #[error("the given CpG bias {0} is out of range [0.0, inf)")]
+    CpgBiasOutOfRange(f64),
+
+    #[error("CpG-biased substitutions require the DNA alphabet")]
+    CpgBiasRequiresDna,
+
+    #[error("the position bias is hotspots, but no hotspots were given")]
+    MissingHotspots,
+
+    #[error("the given hotspot spread is not a number")]
+    HotspotSpreadIsNaN,
+
+    #[error("the given hotspot spread {0} is not positive")]
+    HotspotSpreadNotPositive(f64),
+
     #[error("the given minimum template switch offset {min} is greater than the maximum {max}")]
     TemplateSwitchOffsetEmpty { min: isize, max: isize },
 
+    #[error("the given template switch offset decay {0} is not positive and finite")]
+    TemplateSwitchOffsetDecayNotPositive(f64),
+
+    #[error("the given template switch upstream fraction is not a number")]
+    TemplateSwitchUpstreamFractionIsNaN,
+
+    #[error("the given template switch upstream fraction {0} is out of range [0.0, 1.0]")]
+    TemplateSwitchUpstreamFractionOutOfRange(f64),
+
+    #[error("the given template switch upstream fraction {upstream_fraction} requires offsets in a direction that is missing from the offset range [{min}, {max}]")]
+    TemplateSwitchOffsetDirectionMissing {
+        upstream_fraction: f64,
+        min: isize,
+        max: isize,
+    },
+
     #[error("the given minimum template switch length {min} is greater than the maximum {max}")]
     TemplateSwitchLengthEmpty { min: usize, max: usize },
 
     #[error("the given minimum template switch length difference {min} is greater than the maximum {max}")]
     TemplateSwitchLengthDifferenceEmpty { min: isize, max: isize },
 
+    #[error("the template switch length differences are restricted to multiples of three, but the given range [{min}, {max}] contains none")]
+    TemplateSwitchLengthDifferenceNoMultipleOfThree { min: isize, max: isize },
+
+    #[error("invalid template switch length difference distribution {0:?}, expected uniform, normal:STANDARD_DEVIATION or laplace:SCALE with a positive parameter")]
+    InvalidLengthDifferenceDistribution(String),
+
+    #[error("the template switch length difference {length_difference} exceeds the template switch length {length}")]
+    TemplateSwitchLengthDifferenceExceedsLength {
+        length: usize,
+        length_difference: isize,
+    },
+
+    #[error("self-overlapping template switches are forbidden, but the given offsets, lengths and length differences only allow self-overlapping template switches")]
+    TemplateSwitchAlwaysSelfOverlapping,
+
+    #[error("the template switch parameters exceed the range of representable positions")]
+    TemplateSwitchPositionOverflow,
+
     #[error("the given gap length mean is not a number")]
     GapLengthMeanIsNaN,
 
@@ -47,25 +141,212 @@ pub enum Error {
         maximum: f64,
     },
 
-    #[error("the lambda {lambda} produced by dividing 1.0 / mean, where mean = {mean} is unsuitable for the exponential distribution")]
-    GapLengthMeanLambda { lambda: f64, mean: f64 },
+    #[error("the given {modification_type} rate {rate} is not finite and non-negative")]
+    ModificationRateOutOfRange {
+        modification_type: &'static str,
+        rate: f64,
+    },
+
+    #[error("the given modification probabilities {0:?} of template switches, gaps and substitutions are not finite and non-negative with a positive sum")]
+    InvalidModificationProbabilities([f64; 3]),
+
+    #[error("the given template switch edit cost {0} is not finite and non-negative")]
+    TemplateSwitchEditCostOutOfRange(f64),
+
+    #[error("the record id {0:?} is given to more than one record of the same output")]
+    DuplicateRecordId(String),
+
+    #[error("the number of pairs to generate must be at least one")]
+    NoPairs,
+
+    #[error("the number of sequences to generate must be at least one")]
+    NoSequences,
+
+    #[error("invalid sweep parameter {0:?}, expected KEY=VALUE,VALUE,...")]
+    InvalidSweepParameter(String),
+
+    #[error("the parameter {0:?} cannot be swept")]
+    UnsupportedSweepParameter(String),
+
+    #[error("the swept parameter {key} conflicts with --{argument}")]
+    SweepParameterConflict { key: String, argument: String },
+
+    #[error("the generate-pair arguments of the sweep are invalid: {0}")]
+    InvalidSweepArguments(String),
+
+    #[error("the fragment length must be at least one")]
+    ZeroFragmentLength,
+
+    #[error(
+        "the fragment length {fragment_length} exceeds the length {sequence_length} of {id:?}"
+    )]
+    FragmentLongerThanSequence {
+        id: String,
+        sequence_length: usize,
+        fragment_length: usize,
+    },
+
+    #[error("thread pool error: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+
+    #[error("the given target GC content {0} is out of range [0.0, 1.0]")]
+    TargetGcOutOfRange(f64),
+
+    #[error("the given minimum target GC content {min} is greater than the maximum {max}")]
+    TargetGcEmpty { min: f64, max: f64 },
+
+    #[error("a target GC content requires the DNA alphabet")]
+    TargetGcRequiresDna,
+
+    #[error("no ancestor with a GC content in [{min}, {max}] was generated within {tries} tries")]
+    TargetGcNotReached { min: f64, max: f64, tries: usize },
+
+    #[error(
+        "no ancestor without a homopolymer longer than {max} was generated within {tries} tries"
+    )]
+    MaxHomopolymerNotReached { max: usize, tries: usize },
+
+    #[error("no pair with at least {min} template switches in both reference and query was generated within {tries} tries")]
+    RequiredTemplateSwitchesNotReached { min: usize, tries: usize },
+
+    #[error(
+        "the given interpolation weights {0:?} are not finite and non-negative with a positive sum"
+    )]
+    InvalidInterpolationWeights(Vec<f64>),
+
+    #[error("{amount} interpolation weights were given, but the model has only {orders} orders")]
+    TooManyInterpolationWeights { amount: usize, orders: usize },
+
+    #[error("{weight_amount} input weights were given for {input_amount} input files")]
+    InputWeightAmountMismatch {
+        weight_amount: usize,
+        input_amount: usize,
+    },
+
+    #[error("the input weights must be positive")]
+    ZeroInputWeight,
+
+    #[error(
+        "the weighted count of an n-gram exceeds the maximum count {}",
+        u32::MAX
+    )]
+    NGramCountOverflow,
 
     #[error("the given ancestor length {length} is lower than n {n}")]
     LengthLowerThanN { length: usize, n: usize },
 
-    #[error("the model is empty")]
+    #[error("the length {length} of a circular sequence is lower than twice n {n}")]
+    CircularLengthLowerThanTwiceN { length: usize, n: usize },
+
+    #[error("no characters at the end of the sequence make it circular under the model")]
+    NoCircularClosure,
+
+    #[error("the sequence to continue from contains no records")]
+    EmptyContinuationInput,
+
+    #[error("the sequence to continue from has length {length}, which is lower than n {n}")]
+    ContinuationShorterThanN { length: usize, n: usize },
+
+    #[error(
+        "the last n-gram {0:?} of the sequence to continue from has no successor in the model"
+    )]
+    ContinuationNGramNotInModel(String),
+
+    #[error("neither a model, a composition nor an ancestor input file was given")]
+    MissingAncestorSource,
+
+    #[error("invalid composition {0:?}, expected CHARACTER=FREQUENCY,... with distinct characters and non-negative frequencies")]
+    InvalidComposition(String),
+
+    #[error("the frequencies of the composition sum to {0} instead of one")]
+    CompositionSumNotOne(f64),
+
+    #[error("the composition {0:?} does not give a frequency for exactly the characters of the alphabet")]
+    CompositionAlphabetMismatch(String),
+
+    #[error("the ancestor length or its distribution is required when generating the ancestor from a model")]
+    MissingAncestorLength,
+
+    #[error("invalid ancestor length distribution {0:?}, expected uniform:MIN-MAX, normal:MEAN,STANDARD_DEVIATION or empirical:LENGTH,...")]
+    InvalidAncestorLengthDistribution(String),
+
+    #[error("the list of ancestor lengths is empty")]
+    EmptyAncestorLengths,
+
+    #[error("{amount} ancestor lengths were given for {num_pairs} pairs, consider --cycle-ancestor-lengths")]
+    TooFewAncestorLengths { amount: usize, num_pairs: usize },
+
+    #[error("line {line_number} of the ancestor lengths file is invalid: {line:?}")]
+    InvalidAncestorLengthLine { line_number: usize, line: String },
+
+    #[error("the ancestor input file contains no records")]
+    EmptyAncestorInput,
+
+    #[error("the ancestor input file contains no record with id {0:?}")]
+    AncestorIdNotFound(String),
+
+    #[error("the input contains the character {0:?}, which is not part of the alphabet")]
+    UnknownCharacter(char),
+
+    #[error("the given unknown character warning fraction {0} is out of range [0.0, 1.0]")]
+    UnknownCharacterWarningFractionOutOfRange(f64),
+
+    #[error("the alphabet reduction {reduction} requires the input alphabet {required}, but {alphabet} was given")]
+    AlphabetReductionMismatch {
+        reduction: String,
+        required: String,
+        alphabet: String,
+    },
+
+    #[error("the n-gram {0:?} of the JSON model does not consist of n characters of the alphabet")]
+    InvalidJsonNGram(String),
+
+    #[error("the n-gram {n_gram:?} of the JSON model has {amount} successor abundances, but the alphabet has {alphabet_size} characters")]
+    InvalidJsonAbundances {
+        n_gram: String,
+        amount: usize,
+        alphabet_size: usize,
+    },
+
+    #[error("the model contains no n-grams")]
     EmptyModel,
 
+    #[error("the model contains {0} n-grams, but all their successor abundances are zero")]
+    ZeroWeightModel(usize),
+
+    #[error("the model contains {0} start n-grams, but all their abundances are zero")]
+    ZeroWeightStartNGrams(usize),
+
+    #[error("the generation restarted {0} times at n-grams without successor and would need to restart again, consider --unigram-fallback")]
+    MaxRestartsExceeded(usize),
+
+    #[error("the model contains no start n-grams, it may have been created by an older version")]
+    MissingStartNGrams,
+
+    #[error("the input contains no n-grams to train the model: {contributing_record_amount} of {record_amount} records are long enough for n = {n}")]
+    UntrainableCorpus {
+        record_amount: usize,
+        contributing_record_amount: usize,
+        n: usize,
+    },
+
     #[error("during modifying a sequence, it became empty")]
     SequenceBecameEmpty,
 
+    #[error("no template switch fits into the ancestor of length {ancestor_length}, since the smallest requires a sequence length of {required_length}; consider changing --{parameter}")]
+    TemplateSwitchInfeasible {
+        parameter: &'static str,
+        required_length: usize,
+        ancestor_length: usize,
+    },
+
     #[error("tried to generate a template switch that requires a sequence length of {template_switch_required_sequence_length} which is longer than the sequence length {sequence_length}")]
     SequenceTooShortForTemplateSwitch {
         sequence_length: usize,
         template_switch_required_sequence_length: usize,
     },
 
-    #[error("tried to generate a gap of length {gap_length} which is longer than the sequence of length {sequence_length}")]
+    #[error("tried to generate a gap of length {gap_length}, but the sequence of length {sequence_length} is not longer than the gap")]
     SequenceTooShortForGap {
         sequence_length: usize,
         gap_length: usize,
@@ -75,4 +356,48 @@ pub enum Error {
         "the maximum number of tries for generating an overlap-free template switch was reached"
     )]
     TemplateSwitchOverlap,
+
+    #[error("{modification} with edit cost {cost} exceeds the remaining edit cost budget of {remaining_cost}")]
+    EditCostBudgetExceeded {
+        modification: String,
+        cost: f64,
+        remaining_cost: f64,
+    },
+
+    #[error("no gap that keeps the sequence at least {min_sequence_length} characters long was generated within {tries} tries")]
+    MinSequenceLengthNotKept {
+        min_sequence_length: usize,
+        tries: usize,
+    },
+
+    #[error("no {} substitution was found within the codons of the reading frame", if *synonymous { "synonymous" } else { "non-synonymous" })]
+    NoCodonSubstitution { synonymous: bool },
+
+    #[error("the stats file {0:?} is invalid")]
+    InvalidStatsFile(std::path::PathBuf),
+
+    #[error("the directory {0:?} contains no stats or template switch files")]
+    EmptySummaryInput(std::path::PathBuf),
+
+    #[error("line {line_number} of the BED file is invalid: {line:?}")]
+    InvalidBedLine { line_number: usize, line: String },
+
+    #[error("line {line_number} of the rate multiplier file is invalid: {line:?}")]
+    InvalidRateMultiplierLine { line_number: usize, line: String },
+
+    #[error("the rate multiplier intervals do not partition the sequence, the interval starting at {position} is missing or overlaps")]
+    RateMultipliersNotPartitioning { position: usize },
+
+    #[error("the rate multiplier {0} is not finite and non-negative, or all multipliers are zero")]
+    InvalidRateMultiplier(f64),
+
+    #[error("the allowed regions contain no interval that fits a modification of length {modification_length}")]
+    AllowedRegionsTooShort { modification_length: usize },
+
+    #[error("only {realized} of {requested} requested {modification_type} could be applied")]
+    ModificationAmountNotReached {
+        modification_type: &'static str,
+        requested: usize,
+        realized: usize,
+    },
 }
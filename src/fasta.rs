@@ -0,0 +1,630 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Split, Write},
+    path::Path,
+};
+
+use compact_genome::{
+    implementation::DefaultGenome,
+    interface::{alphabet::Alphabet, sequence::GenomeSequence},
+    io::fasta::FastaRecord,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::{
+    error::{Error, Result},
+    reduced_alphabet::AlphabetReduction,
+    sequence_modifier::soft_mask::SoftMask,
+};
+
+/// Open a fasta file for reading, gzip-decompressing it if the file name ends in `.gz`.
+fn open_fasta_file(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Read the soft-mask of a record of a fasta file, i.e. which of its characters are lower case.
+///
+/// If `id` is not set, then the first record is used.
+/// If the file name ends in `.gz`, then the input is gzip-decompressed.
+pub fn read_soft_mask(path: impl AsRef<Path>, id: Option<&str>) -> Result<SoftMask> {
+    read_soft_mask_from(open_fasta_file(path)?, id)
+}
+
+fn read_soft_mask_from(reader: impl BufRead, id: Option<&str>) -> Result<SoftMask> {
+    let mut lowercase = Vec::new();
+    let mut is_selected_record = false;
+    let mut has_found_record = false;
+
+    for line in reader.split(b'\n') {
+        let line = line?;
+        if let Some(header) = line.strip_prefix(b">") {
+            if has_found_record {
+                break;
+            }
+            let record_id = header
+                .split(u8::is_ascii_whitespace)
+                .next()
+                .unwrap_or_default();
+            is_selected_record = match id {
+                Some(id) => record_id == id.as_bytes(),
+                None => true,
+            };
+            has_found_record = is_selected_record;
+        } else if is_selected_record {
+            lowercase.extend(
+                line.iter()
+                    .filter(|character| !character.is_ascii_whitespace())
+                    .map(u8::is_ascii_lowercase),
+            );
+        }
+    }
+
+    if has_found_record {
+        Ok(SoftMask::new(lowercase))
+    } else if let Some(id) = id {
+        Err(Error::AncestorIdNotFound(id.to_string()))
+    } else {
+        Err(Error::EmptyAncestorInput)
+    }
+}
+
+/// How characters that are not part of the alphabet are handled when reading sequences.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UnknownCharacters {
+    /// Reading fails with an error.
+    Error,
+    /// The characters are removed, and the characters around them are joined.
+    Skip,
+    /// Each record is split into segments at the characters.
+    Split,
+}
+
+/// The amount of characters of a fasta record that are not part of the alphabet.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownCharacterCount {
+    pub record_id: String,
+    /// The amount of characters that are not part of the alphabet.
+    pub unknown_amount: usize,
+    /// The amount of all characters of the record, excluding whitespace.
+    pub character_amount: usize,
+}
+
+impl UnknownCharacterCount {
+    /// The fraction of the characters of the record that are not part of the alphabet.
+    pub fn unknown_fraction(&self) -> f64 {
+        if self.character_amount == 0 {
+            0.0
+        } else {
+            self.unknown_amount as f64 / self.character_amount as f64
+        }
+    }
+}
+
+/// Reads the sequences of the records of a fasta file one at a time.
+///
+/// If `capitalise_characters` is set, then characters are capitalised before they are checked against the alphabet.
+/// If `reduction` is set, then characters are reduced after capitalising.
+/// With [`UnknownCharacters::Split`], each segment is a maximal run of alphabet characters within a record,
+/// and empty segments are dropped.
+/// Otherwise, each record results in one sequence.
+///
+/// Only the sequence that is currently read is held in memory, so reading a large file does not require memory proportional to its size.
+/// The amount of unknown characters of each record is collected while reading, see [`Self::unknown_character_counts`].
+/// After an error, the reader returns no further sequences.
+pub struct FastaSequenceReader<AlphabetType: Alphabet, Reader> {
+    lines: Split<Reader>,
+    capitalise_characters: bool,
+    reduction: Option<AlphabetReduction>,
+    unknown_characters: UnknownCharacters,
+    /// The characters of the sequence that is currently read.
+    sequence: Vec<AlphabetType::CharacterType>,
+    /// The sequences that were completed by the last line, but not yet returned.
+    finished_sequences: VecDeque<DefaultGenome<AlphabetType>>,
+    unknown_character_counts: Vec<UnknownCharacterCount>,
+    has_record: bool,
+    is_finished: bool,
+}
+
+impl<AlphabetType: Alphabet> FastaSequenceReader<AlphabetType, Box<dyn BufRead>> {
+    /// Opens a fasta file for reading its sequences.
+    ///
+    /// If the file name ends in `.gz`, then the input is gzip-decompressed.
+    pub fn open(
+        path: impl AsRef<Path>,
+        capitalise_characters: bool,
+        reduction: Option<AlphabetReduction>,
+        unknown_characters: UnknownCharacters,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            open_fasta_file(path)?,
+            capitalise_characters,
+            reduction,
+            unknown_characters,
+        ))
+    }
+}
+
+impl<AlphabetType: Alphabet, Reader: BufRead> FastaSequenceReader<AlphabetType, Reader> {
+    pub fn new(
+        reader: Reader,
+        capitalise_characters: bool,
+        reduction: Option<AlphabetReduction>,
+        unknown_characters: UnknownCharacters,
+    ) -> Self {
+        Self {
+            lines: reader.split(b'\n'),
+            capitalise_characters,
+            reduction,
+            unknown_characters,
+            sequence: Vec::new(),
+            finished_sequences: VecDeque::new(),
+            unknown_character_counts: Vec::new(),
+            has_record: false,
+            is_finished: false,
+        }
+    }
+
+    /// The amount of unknown characters of each record read so far.
+    pub fn unknown_character_counts(&self) -> &[UnknownCharacterCount] {
+        &self.unknown_character_counts
+    }
+
+    fn finish_sequence(&mut self) {
+        if !self.sequence.is_empty() || self.unknown_characters != UnknownCharacters::Split {
+            self.finished_sequences
+                .push_back(DefaultGenome::from_iter(self.sequence.drain(..)));
+        }
+    }
+
+    fn read_line(&mut self, line: &[u8]) -> Result<()> {
+        if let Some(header) = line.strip_prefix(b">") {
+            if self.has_record {
+                self.finish_sequence();
+            }
+            self.has_record = true;
+            let record_id = header
+                .split(u8::is_ascii_whitespace)
+                .next()
+                .unwrap_or_default();
+            self.unknown_character_counts.push(UnknownCharacterCount {
+                record_id: String::from_utf8_lossy(record_id).into_owned(),
+                unknown_amount: 0,
+                character_amount: 0,
+            });
+            return Ok(());
+        }
+        // Characters before the first header are not part of any record.
+        if !self.has_record {
+            return Ok(());
+        }
+        let record_index = self.unknown_character_counts.len() - 1;
+
+        for &character in line
+            .iter()
+            .filter(|character| !character.is_ascii_whitespace())
+        {
+            let mut character = if self.capitalise_characters {
+                character.to_ascii_uppercase()
+            } else {
+                character
+            };
+            if let Some(reduction) = self.reduction {
+                character = reduction.reduce_ascii(character);
+            }
+            self.unknown_character_counts[record_index].character_amount += 1;
+
+            if let Ok(character) = AlphabetType::ascii_to_character(character) {
+                self.sequence.push(character);
+            } else {
+                self.unknown_character_counts[record_index].unknown_amount += 1;
+                match self.unknown_characters {
+                    UnknownCharacters::Error => {
+                        return Err(Error::UnknownCharacter(char::from(character)))
+                    }
+                    UnknownCharacters::Skip => {}
+                    UnknownCharacters::Split => self.finish_sequence(),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<AlphabetType: Alphabet, Reader: BufRead> Iterator
+    for FastaSequenceReader<AlphabetType, Reader>
+{
+    type Item = Result<DefaultGenome<AlphabetType>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(sequence) = self.finished_sequences.pop_front() {
+                return Some(Ok(sequence));
+            }
+            if self.is_finished {
+                return None;
+            }
+
+            let result = match self.lines.next() {
+                Some(line) => line
+                    .map_err(Error::from)
+                    .and_then(|line| self.read_line(&line)),
+                None => {
+                    self.is_finished = true;
+                    if self.has_record {
+                        self.finish_sequence();
+                    }
+                    Ok(())
+                }
+            };
+            if let Err(error) = result {
+                self.is_finished = true;
+                self.finished_sequences.clear();
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Write a fasta file from the given records, wrapping sequence lines after `line_width` characters.
+///
+/// If `soft_masks` are given, then the masked characters of each record are written in lower case.
+/// If `line_width` is zero, then each sequence is written on a single line.
+/// If the file name ends in `.gz`, then the output is gzip-compressed.
+pub fn write_fasta_file<
+    'records,
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + 'records,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    path: impl AsRef<Path>,
+    records: impl IntoIterator<Item = &'records FastaRecord<SequenceType>>,
+    soft_masks: Option<&[SoftMask]>,
+    line_width: usize,
+) -> Result<()> {
+    let path = path.as_ref();
+    let file = File::create(path)?;
+
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let mut writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+        write_fasta(&mut writer, records, soft_masks, line_width)?;
+        writer.finish()?.flush()?;
+    } else {
+        let mut writer = BufWriter::new(file);
+        write_fasta(&mut writer, records, soft_masks, line_width)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Write fasta records, wrapping sequence lines after `line_width` characters.
+///
+/// If `soft_masks` are given, then the masked characters of each record are written in lower case.
+/// If `line_width` is zero, then each sequence is written on a single line.
+/// The writer should be buffered for performance.
+pub fn write_fasta<
+    'records,
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + 'records,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    mut writer: impl Write,
+    records: impl IntoIterator<Item = &'records FastaRecord<SequenceType>>,
+    soft_masks: Option<&[SoftMask]>,
+    line_width: usize,
+) -> Result<()> {
+    let mut line = Vec::new();
+
+    for (record_index, record) in records.into_iter().enumerate() {
+        let soft_mask = soft_masks.map(|soft_masks| &soft_masks[record_index]);
+        writeln!(
+            writer,
+            ">{id}{space}{comment}",
+            id = record.id,
+            space = if record.comment.is_empty() { "" } else { " " },
+            comment = record.comment
+        )?;
+
+        let mut has_written_line = false;
+        for (position, character) in record.sequence_handle.iter().enumerate() {
+            let character = AlphabetType::character_to_ascii(character.clone());
+            if soft_mask.is_some_and(|soft_mask| soft_mask.is_lowercase(position)) {
+                line.push(character.to_ascii_lowercase());
+            } else {
+                line.push(character);
+            }
+
+            if line.len() == line_width {
+                writer.write_all(&line)?;
+                writeln!(writer)?;
+                line.clear();
+                has_written_line = true;
+            }
+        }
+
+        if !line.is_empty() || !has_written_line {
+            writer.write_all(&line)?;
+            writeln!(writer)?;
+            line.clear();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::BufRead};
+
+    use compact_genome::{
+        implementation::{
+            alphabets::dna_alphabet::DnaAlphabet, handle_sequence_store::HandleSequenceStore,
+            DefaultGenome, DefaultSubGenome,
+        },
+        interface::{
+            alphabet::Alphabet,
+            sequence::{GenomeSequence, OwnedGenomeSequence},
+        },
+        io::fasta::{read_fasta_file, FastaRecord},
+    };
+
+    use crate::{
+        error::{Error, Result},
+        n_gram_model::NGramModel,
+        reduced_alphabet::{AlphabetReduction, PurinePyrimidineAlphabet},
+        sequence_modifier::soft_mask::SoftMask,
+    };
+
+    use super::{
+        read_soft_mask_from, write_fasta, FastaSequenceReader, UnknownCharacterCount,
+        UnknownCharacters,
+    };
+
+    /// Reads all sequences and the amounts of unknown characters at once.
+    fn read_fasta_sequences_from<AlphabetType: Alphabet>(
+        reader: impl BufRead,
+        capitalise_characters: bool,
+        reduction: Option<AlphabetReduction>,
+        unknown_characters: UnknownCharacters,
+    ) -> Result<(Vec<DefaultGenome<AlphabetType>>, Vec<UnknownCharacterCount>)> {
+        let mut reader =
+            FastaSequenceReader::new(reader, capitalise_characters, reduction, unknown_characters);
+        let sequences = reader.by_ref().collect::<Result<_>>()?;
+        Ok((sequences, reader.unknown_character_counts().to_vec()))
+    }
+
+    #[test]
+    fn line_width() {
+        let records = [
+            FastaRecord {
+                id: "a".to_string(),
+                comment: "x=1".to_string(),
+                sequence_handle: DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTACGTAC")
+                    .unwrap(),
+            },
+            FastaRecord {
+                id: "b".to_string(),
+                comment: String::new(),
+                sequence_handle: DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGT").unwrap(),
+            },
+            FastaRecord {
+                id: "c".to_string(),
+                comment: String::new(),
+                sequence_handle: DefaultGenome::<DnaAlphabet>::from_slice_u8(b"").unwrap(),
+            },
+        ];
+
+        for (line_width, expected) in [
+            (0, ">a x=1\nACGTACGTAC\n>b\nACGT\n>c\n\n"),
+            (4, ">a x=1\nACGT\nACGT\nAC\n>b\nACGT\n>c\n\n"),
+            (3, ">a x=1\nACG\nTAC\nGTA\nC\n>b\nACG\nT\n>c\n\n"),
+            (20, ">a x=1\nACGTACGTAC\n>b\nACGT\n>c\n\n"),
+        ] {
+            let mut output = Vec::new();
+            write_fasta(&mut output, &records, None, line_width).unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), expected, "{line_width}");
+        }
+    }
+
+    #[test]
+    fn soft_mask() {
+        let input = b">a\nACgt\n>b x=1\naCG\nTa\n>c\nAC\n";
+        let soft_mask = read_soft_mask_from(&input[..], Some("b")).unwrap();
+        assert_eq!(
+            soft_mask,
+            SoftMask::new(vec![true, false, false, false, true])
+        );
+        assert!(read_soft_mask_from(&input[..], Some("d")).is_err());
+
+        let record = FastaRecord {
+            id: "b".to_string(),
+            comment: String::new(),
+            sequence_handle: DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTA").unwrap(),
+        };
+        let mut output = Vec::new();
+        write_fasta(&mut output, [&record], Some(&[soft_mask]), 3).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), ">b\naCG\nTa\n");
+    }
+
+    #[test]
+    fn segments() {
+        let input = b">a\nACGNNT\nAC\n>b\nnGGn\n>c\nNN\n";
+        let segments: Vec<_> = read_fasta_sequences_from::<DnaAlphabet>(
+            &input[..],
+            false,
+            None,
+            UnknownCharacters::Split,
+        )
+        .unwrap()
+        .0
+        .iter()
+        .map(|segment| segment.as_string())
+        .collect();
+        assert_eq!(segments, ["ACG", "TAC", "GG"]);
+
+        let segments: Vec<_> = read_fasta_sequences_from::<DnaAlphabet>(
+            &b">a\nacgNt\n"[..],
+            true,
+            None,
+            UnknownCharacters::Split,
+        )
+        .unwrap()
+        .0
+        .iter()
+        .map(|segment| segment.as_string())
+        .collect();
+        assert_eq!(segments, ["ACG", "T"]);
+    }
+
+    #[test]
+    fn characters_before_first_header() {
+        for unknown_characters in [
+            UnknownCharacters::Error,
+            UnknownCharacters::Skip,
+            UnknownCharacters::Split,
+        ] {
+            let (sequences, unknown_character_counts) = read_fasta_sequences_from::<DnaAlphabet>(
+                &b"ACNGT\nTT\n>a\nGG\n>b\nCA\n"[..],
+                false,
+                None,
+                unknown_characters,
+            )
+            .unwrap();
+            let sequences: Vec<_> = sequences
+                .iter()
+                .map(|sequence| sequence.as_string())
+                .collect();
+            assert_eq!(sequences, ["GG", "CA"]);
+            assert_eq!(unknown_character_counts[0].character_amount, 2);
+        }
+    }
+
+    #[test]
+    fn reduction() {
+        let input = b">a\nACGT\n>b\nggaa\n>c\n";
+        let read = |reduction| {
+            read_fasta_sequences_from::<PurinePyrimidineAlphabet>(
+                &input[..],
+                true,
+                reduction,
+                UnknownCharacters::Error,
+            )
+        };
+        let sequences: Vec<_> = read(Some(AlphabetReduction::PurinePyrimidine))
+            .unwrap()
+            .0
+            .iter()
+            .map(|sequence| sequence.as_string())
+            .collect();
+        assert_eq!(sequences, ["RYRY", "RRRR", ""]);
+        assert!(matches!(read(None), Err(Error::UnknownCharacter('A'))));
+
+        // The reduction halves the symbols of DNA.
+        let symbols = |sequences: Vec<String>| {
+            sequences
+                .concat()
+                .chars()
+                .collect::<std::collections::BTreeSet<_>>()
+                .len()
+        };
+        let dna_sequences = read_fasta_sequences_from::<DnaAlphabet>(
+            &input[..],
+            true,
+            None,
+            UnknownCharacters::Error,
+        )
+        .unwrap()
+        .0
+        .iter()
+        .map(|sequence| sequence.as_string())
+        .collect();
+        assert_eq!(symbols(dna_sequences), 4);
+        assert_eq!(symbols(sequences), 2);
+    }
+
+    #[test]
+    fn unknown_character_counts() {
+        let input = b">a x=1\nACNNG\nT\n>b\nNNNN\n>c\n\n>d\nACGT\n";
+        for unknown_characters in [UnknownCharacters::Skip, UnknownCharacters::Split] {
+            let (sequences, counts) = read_fasta_sequences_from::<DnaAlphabet>(
+                &input[..],
+                false,
+                None,
+                unknown_characters,
+            )
+            .unwrap();
+            let sequences: Vec<_> = sequences
+                .iter()
+                .map(|sequence| sequence.as_string())
+                .collect();
+            if unknown_characters == UnknownCharacters::Skip {
+                assert_eq!(sequences, ["ACGT", "", "", "ACGT"]);
+            } else {
+                assert_eq!(sequences, ["AC", "GT", "ACGT"]);
+            }
+
+            let count = |record_id: &str, unknown_amount, character_amount| UnknownCharacterCount {
+                record_id: record_id.to_string(),
+                unknown_amount,
+                character_amount,
+            };
+            assert_eq!(
+                counts,
+                [
+                    count("a", 2, 6),
+                    count("b", 4, 4),
+                    count("c", 0, 0),
+                    count("d", 0, 4)
+                ]
+            );
+            assert_eq!(counts[0].unknown_fraction(), 2.0 / 6.0);
+            assert_eq!(counts[2].unknown_fraction(), 0.0);
+        }
+    }
+
+    #[test]
+    fn streamed_model() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("streamed-model.fa");
+        fs::write(
+            &path,
+            ">a x=1\nACGTTGCAac\nGTAC\n>b\nGGATCCAT\n>c\nAC\n>d\n\n>e\nacctggatACGGT\n",
+        )
+        .unwrap();
+
+        let mut sequence_store =
+            HandleSequenceStore::<DnaAlphabet, DefaultGenome<_>, DefaultSubGenome<_>>::new();
+        let batch_sequences = read_fasta_file(&path, &mut sequence_store, false, true)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.sequence_handle);
+        let batch = NGramModel::<3, 4, DnaAlphabet, u8>::from_sequences(batch_sequences, false);
+
+        let streamed_sequences = FastaSequenceReader::<DnaAlphabet, _>::open(
+            &path,
+            true,
+            None,
+            UnknownCharacters::Error,
+        )
+        .unwrap()
+        .map(Result::unwrap);
+        let streamed =
+            NGramModel::<3, 4, DnaAlphabet, u8>::from_sequences(streamed_sequences, false);
+
+        assert!(batch.n_gram_amount() > 0);
+        assert_eq!(
+            streamed.iter().collect::<Vec<_>>(),
+            batch.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(streamed.start_abundances(), batch.start_abundances());
+    }
+}
@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+use rand::{seq::IteratorRandom, Rng};
+
+use crate::error::{Error, Result};
+
+/// Draws `count` fragments of the given length with uniformly distributed starts from a sequence of the given length.
+///
+/// The fragments may overlap each other, and are returned in ascending order of their starts.
+pub fn sample_fragments(
+    id: &str,
+    sequence_length: usize,
+    fragment_length: usize,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<Range<usize>>> {
+    if fragment_length > sequence_length {
+        return Err(Error::FragmentLongerThanSequence {
+            id: id.to_string(),
+            sequence_length,
+            fragment_length,
+        });
+    }
+
+    let mut fragments: Vec<_> = (0..count)
+        .map(|_| {
+            let start = (0..=sequence_length - fragment_length).choose(rng).unwrap();
+            start..start + fragment_length
+        })
+        .collect();
+    fragments.sort_by_key(|fragment| fragment.start);
+    Ok(fragments)
+}
+
+/// The record id of a fragment of the record with the given id.
+///
+/// The coordinates are one-based and inclusive, like the regions of samtools, e.g. `reference:11-60`.
+pub fn fragment_id(id: &str, fragment: &Range<usize>) -> String {
+    format!("{id}:{}-{}", fragment.start + 1, fragment.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use crate::error::Error;
+
+    use super::{fragment_id, sample_fragments};
+
+    #[test]
+    fn fragments() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let fragments = sample_fragments("reference", 20, 5, 100, &mut rng).unwrap();
+        assert_eq!(fragments.len(), 100);
+        assert!(fragments
+            .iter()
+            .all(|fragment| fragment.len() == 5 && fragment.end <= 20));
+        assert!(fragments
+            .windows(2)
+            .all(|window| window[0].start <= window[1].start));
+        // All starts are drawn.
+        assert_eq!(fragments.first().unwrap().start, 0);
+        assert_eq!(fragments.last().unwrap().end, 20);
+
+        assert_eq!(
+            sample_fragments("reference", 5, 5, 3, &mut rng).unwrap(),
+            [0..5, 0..5, 0..5]
+        );
+        assert!(matches!(
+            sample_fragments("query", 4, 5, 1, &mut rng),
+            Err(Error::FragmentLongerThanSequence {
+                sequence_length: 4,
+                fragment_length: 5,
+                ..
+            })
+        ));
+
+        assert_eq!(fragment_id("reference", &(10..60)), "reference:11-60");
+    }
+}
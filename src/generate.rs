@@ -0,0 +1,879 @@
+use std::ops::{Range, RangeInclusive};
+
+use compact_genome::{
+    implementation::{
+        bit_array_kmer::{BitStore, BitView, BitViewSized},
+        DefaultGenome,
+    },
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+use log::{debug, info};
+use traitsequence::interface::Sequence;
+
+use crate::{
+    ancestor_length::AncestorLengthDistribution,
+    cli::{ModificationAmountResolver, SequenceModificationParameters},
+    error::{Error, Result},
+    n_gram_model::{Interpolation, NGramModel, RestartLimit},
+    random_stream::StreamRng,
+    sequence_modifier::{
+        allowed_regions::AllowedIntervals, ancestry::Ancestry, position_bias::PositionBias,
+        rate_multipliers::RateMultipliers, soft_mask::SoftMask,
+        template_switch_overlap_detector::TemplateSwitchOverlapDetector, ModificationTypeRngs,
+        SequenceModification, SequenceModifier, SequenceModifierPair,
+    },
+};
+
+/// The source of the ancestors of the generated pairs.
+pub enum AncestorSource<
+    const N: usize,
+    const ALPHABET_SIZE: usize,
+    AlphabetType: Alphabet,
+    BitArrayType: BitViewSized + BitStore,
+> {
+    /// Each ancestor is generated from the model with a length drawn from the distribution.
+    Model {
+        model: Box<NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>>,
+        ancestor_length_distribution: AncestorLengthDistribution,
+        /// If set, the successors are drawn from a mixture of the orders of the model.
+        interpolation: Option<Interpolation<ALPHABET_SIZE>>,
+    },
+    /// All pairs share the same ancestor loaded from a fasta file, with its soft-mask if it is preserved.
+    Sequence(DefaultGenome<AlphabetType>, Option<SoftMask>),
+}
+
+/// The parameters of generating a pair.
+///
+/// The files of allowed regions and rate multipliers are already read,
+/// and their intervals are resolved once the length of each ancestor is known.
+#[derive(Clone)]
+pub struct PairParameters {
+    pub reference_ancestry_fraction: f64,
+    /// Resolves the amounts of modifications for the length of each ancestor.
+    pub modification_amount_resolver: ModificationAmountResolver,
+    /// If set, the total amount of modifications and the probabilities of their types, which replace the amounts.
+    pub modification_type_probabilities: Option<(usize, [f64; 3])>,
+    pub sequence_modification_parameters: SequenceModificationParameters,
+    pub position_bias: PositionBias,
+    /// If set, modifications are restricted to these regions of the ancestor.
+    pub allowed_intervals: Option<AllowedIntervals>,
+    /// If set, the modification rate varies along the ancestor with these intervals and multipliers, see [`RateMultipliers::new`].
+    pub rate_multiplier_intervals: Option<Vec<(Range<usize>, f64)>>,
+    /// If set, modifications are discarded once their total edit cost would exceed this budget,
+    /// see [`SequenceModifierPair::with_max_edit_distance`].
+    pub max_edit_distance: Option<usize>,
+    pub template_switch_edit_cost: f64,
+    /// If set, the first n-gram of a generated ancestor is drawn from the first n-grams of the training sequences.
+    pub realistic_starts: bool,
+    pub restart_limit: Option<RestartLimit>,
+    /// If set, generated ancestors are drawn again until their GC content lies within this range.
+    pub target_gc: Option<RangeInclusive<f64>>,
+    pub target_gc_tries: usize,
+    /// If set, generated ancestors are drawn again until they have no longer homopolymer.
+    pub max_homopolymer: Option<usize>,
+    pub max_homopolymer_tries: usize,
+    /// If set, pairs with fewer template switches in the reference or in the query are generated again.
+    pub require_template_switch: Option<usize>,
+    pub require_template_switch_tries: usize,
+    /// If set, the ancestor is retained after reference and query were derived from it.
+    pub retain_ancestor: bool,
+    /// If set, the ancestries of reference and query are tracked.
+    pub track_ancestry: bool,
+    /// If set, the original and the substituted characters of the substitutions are recorded.
+    pub track_substitutions: bool,
+}
+
+/// The random generators of one attempt of generating a pair.
+pub struct PairRngs {
+    /// Draws the length and the characters of the ancestor.
+    pub ancestor: StreamRng,
+    /// Draws the split of the modifications between reference and query.
+    pub split: StreamRng,
+    pub reference: StreamRng,
+    pub query: StreamRng,
+    /// The own random generators of the modification types of reference and query.
+    pub modification_types: [ModificationTypeRngs; 2],
+}
+
+/// A generated pair together with its ancestor and the modifications that derived it.
+///
+/// The sequences are stored as [`DefaultGenome`], which packs each character into the minimum number of bits,
+/// i.e. two bits per character for DNA.
+pub struct GeneratedPair<AlphabetType: Alphabet> {
+    /// The ancestor, if it is retained, see [`PairParameters::retain_ancestor`].
+    pub ancestor: Option<DefaultGenome<AlphabetType>>,
+    pub ancestor_length: usize,
+    pub reference: DefaultGenome<AlphabetType>,
+    pub query: DefaultGenome<AlphabetType>,
+    /// The modifications applied to the ancestor to derive the reference, in order.
+    pub reference_modifications: Vec<SequenceModification>,
+    /// The modifications applied to the ancestor to derive the query, in order.
+    pub query_modifications: Vec<SequenceModification>,
+    /// The ancestries of reference and query, if an alignment output is requested.
+    pub ancestries: Option<[Ancestry; 2]>,
+    /// The soft-masks of ancestor, reference and query, if the case of the ancestor is preserved.
+    pub soft_masks: Option<[SoftMask; 3]>,
+    /// The original and substituted characters of the substitutions of reference and query, if a spectrum output is requested.
+    pub substitutions: Option<[Vec<(u8, u8)>; 2]>,
+}
+
+/// Generates a pair in memory, without writing any output files.
+///
+/// The random generators of each attempt are created by `rngs`, which is called with the index of the attempt.
+/// The `pair_index` selects the ancestor length if the lengths are listed, see [`AncestorLengthDistribution::sample`].
+/// While an ancestor is generated, `progress` is called with the amount of generated characters and the ancestor length.
+///
+/// If [`PairParameters::require_template_switch`] is set, then pairs with too few template switches in the reference or in the query
+/// are generated again in the next attempt.
+///
+/// The ancestor is only retained if an output requires it.
+/// Otherwise, the query is modified in place of the ancestor, which reduces the peak memory from three to two sequences.
+/// This costs no time, and the pair is the same as if the ancestor was retained.
+pub fn generate_pair<
+    const N: usize,
+    const ALPHABET_SIZE: usize,
+    BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    AlphabetType: Alphabet,
+>(
+    ancestor_source: &AncestorSource<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+    parameters: &PairParameters,
+    pair_index: usize,
+    mut rngs: impl FnMut(usize) -> PairRngs,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<GeneratedPair<AlphabetType>> {
+    let Some(min) = parameters.require_template_switch else {
+        return generate_pair_attempt(ancestor_source, parameters, pair_index, rngs(0), progress);
+    };
+
+    let tries = parameters.require_template_switch_tries;
+    for attempt in 0..tries {
+        let pair = generate_pair_attempt(
+            ancestor_source,
+            parameters,
+            pair_index,
+            rngs(attempt),
+            &mut progress,
+        )?;
+        let [reference_template_switch_amount, query_template_switch_amount] =
+            [&pair.reference_modifications, &pair.query_modifications].map(|modifications| {
+                modifications
+                    .iter()
+                    .filter(|modification| {
+                        matches!(modification, SequenceModification::TemplateSwitch { .. })
+                    })
+                    .count()
+            });
+
+        if reference_template_switch_amount >= min && query_template_switch_amount >= min {
+            info!(
+                "Generated pair {pair_index} with {reference_template_switch_amount} template switches in the reference and {query_template_switch_amount} in the query after {attempt} retries"
+            );
+            return Ok(pair);
+        }
+        debug!(
+            "Pair {pair_index} has only {reference_template_switch_amount} template switches in the reference and {query_template_switch_amount} in the query, retrying"
+        );
+    }
+
+    Err(Error::RequiredTemplateSwitchesNotReached { min, tries })
+}
+
+/// Generates a pair like [`generate_pair`] with the random generators of one attempt.
+fn generate_pair_attempt<
+    const N: usize,
+    const ALPHABET_SIZE: usize,
+    BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    AlphabetType: Alphabet,
+>(
+    ancestor_source: &AncestorSource<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+    parameters: &PairParameters,
+    pair_index: usize,
+    rngs: PairRngs,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<GeneratedPair<AlphabetType>> {
+    let PairRngs {
+        ancestor: mut ancestor_rng,
+        split: mut split_rng,
+        reference: mut reference_rng,
+        query: mut query_rng,
+        modification_types: [reference_modification_type_rngs, query_modification_type_rngs],
+    } = rngs;
+
+    // Generate or copy ancestor.
+    let (ancestor, soft_mask): (DefaultGenome<_>, _) = match ancestor_source {
+        AncestorSource::Model {
+            model,
+            ancestor_length_distribution,
+            interpolation,
+        } => {
+            let ancestor_length =
+                ancestor_length_distribution.sample(pair_index, &mut ancestor_rng);
+            if ancestor_length < N {
+                return Err(Error::LengthLowerThanN {
+                    length: ancestor_length,
+                    n: N,
+                });
+            }
+            let target_gc = parameters.target_gc.clone();
+            let max_homopolymer = parameters.max_homopolymer;
+            let ancestor = if target_gc.is_some() || max_homopolymer.is_some() {
+                // Each limit has its own tries, so rejections by one limit do not use up the tries of the other.
+                let mut tries = 0;
+                let mut target_gc_tries = 0;
+                let mut max_homopolymer_tries = 0;
+                loop {
+                    let ancestor: DefaultGenome<_> = model.generate_sequence(
+                        ancestor_length,
+                        interpolation.as_ref(),
+                        parameters.realistic_starts,
+                        parameters.restart_limit,
+                        &mut ancestor_rng,
+                        |generated| progress(generated, ancestor_length),
+                    )?;
+                    let gc = gc_content(&ancestor);
+                    let homopolymer = longest_homopolymer(&ancestor);
+                    tries += 1;
+
+                    if let Some(target_gc) = target_gc
+                        .as_ref()
+                        .filter(|target_gc| !target_gc.contains(&gc))
+                    {
+                        target_gc_tries += 1;
+                        if target_gc_tries >= parameters.target_gc_tries {
+                            return Err(Error::TargetGcNotReached {
+                                min: *target_gc.start(),
+                                max: *target_gc.end(),
+                                tries: target_gc_tries,
+                            });
+                        }
+                    } else if let Some(max_homopolymer) =
+                        max_homopolymer.filter(|max_homopolymer| homopolymer > *max_homopolymer)
+                    {
+                        info!("Rejected ancestor with a homopolymer of length {homopolymer}");
+                        max_homopolymer_tries += 1;
+                        if max_homopolymer_tries >= parameters.max_homopolymer_tries {
+                            return Err(Error::MaxHomopolymerNotReached {
+                                max: max_homopolymer,
+                                tries: max_homopolymer_tries,
+                            });
+                        }
+                    } else {
+                        info!("Generated ancestor with GC content {gc:.4} and longest homopolymer {homopolymer} after {tries} tries");
+                        break ancestor;
+                    }
+                }
+            } else {
+                model.generate_sequence(
+                    ancestor_length,
+                    interpolation.as_ref(),
+                    parameters.realistic_starts,
+                    parameters.restart_limit,
+                    &mut ancestor_rng,
+                    |generated| progress(generated, ancestor_length),
+                )?
+            };
+            (ancestor, None)
+        }
+        AncestorSource::Sequence(ancestor, soft_mask) => (ancestor.clone(), soft_mask.clone()),
+    };
+    parameters
+        .sequence_modification_parameters
+        .verify_ancestor_length(ancestor.len())?;
+
+    // Resolve allowed regions and rate multipliers for the length of the ancestor.
+    let allowed_regions = parameters
+        .allowed_intervals
+        .as_ref()
+        .map(|allowed_intervals| allowed_intervals.allowed_regions(ancestor.len()));
+    let rate_multipliers = parameters
+        .rate_multiplier_intervals
+        .as_ref()
+        .map(|intervals| RateMultipliers::new(intervals.iter().cloned(), ancestor.len()))
+        .transpose()?;
+
+    // Derive reference from ancestor.
+    let mut reference = ancestor.clone();
+
+    // Create sequence modifiers.
+    let sequence_modifier_pair = SequenceModifier::new_modifier_pair(
+        parameters.reference_ancestry_fraction,
+        parameters
+            .modification_amount_resolver
+            .resolve(ancestor.len()),
+        parameters.sequence_modification_parameters,
+        allowed_regions,
+        parameters.position_bias.clone(),
+        rate_multipliers,
+        &mut split_rng,
+    );
+    let sequence_modifier_pair = if let Some((total_modifications, probabilities)) =
+        parameters.modification_type_probabilities
+    {
+        sequence_modifier_pair.with_modification_type_probabilities(
+            parameters.reference_ancestry_fraction,
+            total_modifications,
+            probabilities,
+            &mut split_rng,
+        )?
+    } else {
+        sequence_modifier_pair
+    };
+    let sequence_modifier_pair = if let Some(max_edit_distance) = parameters.max_edit_distance {
+        sequence_modifier_pair.with_max_edit_distance(
+            parameters.reference_ancestry_fraction,
+            max_edit_distance,
+            parameters.template_switch_edit_cost,
+            &mut split_rng,
+        )
+    } else {
+        sequence_modifier_pair
+    };
+    let SequenceModifierPair {
+        mut reference_modifier,
+        mut query_modifier,
+    } = sequence_modifier_pair;
+
+    // Draw the modifications of types with their own seed from their own random generators.
+    reference_modifier.use_modification_type_rngs(reference_modification_type_rngs);
+    query_modifier.use_modification_type_rngs(query_modification_type_rngs);
+
+    // Track ancestry if an alignment is requested.
+    if parameters.track_ancestry {
+        reference_modifier.track_ancestry(ancestor.len());
+        query_modifier.track_ancestry(ancestor.len());
+    }
+
+    // Record modifications.
+    reference_modifier.track_modifications();
+    query_modifier.track_modifications();
+
+    // Record substituted characters if a spectrum is requested.
+    if parameters.track_substitutions {
+        reference_modifier.track_substitutions();
+        query_modifier.track_substitutions();
+    }
+
+    // Track soft-mask if it is preserved.
+    if let Some(soft_mask) = &soft_mask {
+        reference_modifier.track_soft_mask(soft_mask.clone(), soft_mask.clone());
+    }
+
+    // Create overlap detector.
+    let mut template_switch_overlap_detector =
+        TemplateSwitchOverlapDetector::new(&parameters.sequence_modification_parameters);
+
+    // Modify reference, with the unmodified query being the ancestor.
+    reference_modifier.apply(
+        &mut reference,
+        &ancestor,
+        &mut template_switch_overlap_detector,
+        &mut reference_rng,
+    )?;
+    template_switch_overlap_detector.reset();
+    if let Some(soft_mask) = &soft_mask {
+        let reference_soft_mask = reference_modifier
+            .soft_mask()
+            .expect("soft-mask is tracked if it is preserved")
+            .clone();
+        query_modifier.track_soft_mask(soft_mask.clone(), reference_soft_mask);
+    }
+
+    // Derive query from ancestor, consuming the ancestor if it is not required anymore.
+    let ancestor_length = ancestor.len();
+    let (ancestor, mut query) = if parameters.retain_ancestor {
+        (Some(ancestor.clone()), ancestor)
+    } else {
+        (None, ancestor)
+    };
+
+    // Modify query.
+    query_modifier.apply(
+        &mut query,
+        &reference,
+        &mut template_switch_overlap_detector,
+        &mut query_rng,
+    )?;
+
+    let ancestries = reference_modifier
+        .take_ancestry()
+        .zip(query_modifier.take_ancestry())
+        .map(|(reference_ancestry, query_ancestry)| [reference_ancestry, query_ancestry]);
+    let soft_masks = soft_mask
+        .zip(
+            reference_modifier
+                .take_soft_mask()
+                .zip(query_modifier.take_soft_mask()),
+        )
+        .map(
+            |(ancestor_soft_mask, (reference_soft_mask, query_soft_mask))| {
+                [ancestor_soft_mask, reference_soft_mask, query_soft_mask]
+            },
+        );
+    let substitutions = reference_modifier
+        .take_substitutions()
+        .zip(query_modifier.take_substitutions())
+        .map(|(reference_substitutions, query_substitutions)| {
+            [reference_substitutions, query_substitutions]
+        });
+
+    Ok(GeneratedPair {
+        ancestor,
+        ancestor_length,
+        reference,
+        query,
+        reference_modifications: reference_modifier
+            .take_modifications()
+            .expect("modifications are recorded"),
+        query_modifications: query_modifier
+            .take_modifications()
+            .expect("modifications are recorded"),
+        ancestries,
+        soft_masks,
+        substitutions,
+    })
+}
+
+/// The length of the longest run of identical characters in the sequence.
+fn longest_homopolymer<AlphabetType: Alphabet>(sequence: &DefaultGenome<AlphabetType>) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+    for character in sequence.iter() {
+        let index = character.index();
+        current = if previous == Some(index) {
+            current + 1
+        } else {
+            1
+        };
+        previous = Some(index);
+        longest = longest.max(current);
+    }
+    longest
+}
+
+/// The fraction of `C` and `G` characters in the sequence, or zero if the sequence is empty.
+fn gc_content<AlphabetType: Alphabet>(sequence: &DefaultGenome<AlphabetType>) -> f64 {
+    if sequence.is_empty() {
+        return 0.0;
+    }
+
+    let gc_amount = sequence
+        .iter()
+        .filter(|character| {
+            let character: u8 = (*character).clone().into();
+            matches!(character.to_ascii_uppercase(), b'C' | b'G')
+        })
+        .count();
+    gc_amount as f64 / sequence.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::OwnedGenomeSequence,
+    };
+
+    use crate::{
+        ancestor_length::AncestorLengthDistribution,
+        cli::{Cli, CliCommands, GeneratePairCommand},
+        error::{Error, Result},
+        n_gram_model::NGramModel,
+        sequence_modifier::SequenceModification,
+    };
+
+    use super::{gc_content, generate_pair, longest_homopolymer, AncestorSource, GeneratedPair};
+
+    /// Generates a pair with the parameters and random generators of the command.
+    fn generate_command_pair<const N: usize>(
+        ancestor_source: &AncestorSource<N, 4, DnaAlphabet, u8>,
+        command: &GeneratePairCommand,
+        pair_index: usize,
+    ) -> Result<GeneratedPair<DnaAlphabet>> {
+        generate_pair(
+            ancestor_source,
+            &command.pair_parameters()?,
+            pair_index,
+            |attempt| command.pair_rngs(pair_index, attempt),
+            |_, _| {},
+        )
+    }
+
+    /// The returned modifications derive the returned reference and query from the returned ancestor.
+    #[test]
+    fn modifications_derive_pair() {
+        let CliCommands::GeneratePair(mut command) = Cli::parse_from([
+            "tsgen",
+            "generate-pair",
+            "--ancestor-input",
+            "ancestor.fa",
+            "--dry-run",
+            "--template-switch-amount",
+            "4",
+            "--inter-sequence-template-switch-probability",
+            "0.5",
+        ])
+        .command
+        else {
+            panic!("wrong subcommand");
+        };
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(50)).unwrap();
+        let ancestor_source =
+            AncestorSource::<0, 4, DnaAlphabet, u8>::Sequence(ancestor.clone(), None);
+
+        let GeneratedPair {
+            ancestor: retained_ancestor,
+            ancestor_length,
+            reference,
+            query,
+            reference_modifications,
+            query_modifications,
+            ancestries,
+            soft_masks,
+            substitutions,
+        } = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+        assert!(retained_ancestor.is_none());
+        assert_eq!(ancestor_length, 800);
+        assert!(ancestries.is_none());
+        assert!(soft_masks.is_none());
+        assert!(substitutions.is_none());
+        assert_eq!(
+            reference_modifications.len() + query_modifications.len(),
+            4 + 10 + 10
+        );
+
+        let mut derived_reference = ancestor.clone();
+        for modification in &reference_modifications {
+            modification
+                .apply(&mut derived_reference, &ancestor)
+                .unwrap();
+        }
+        assert_eq!(derived_reference, reference);
+        let mut derived_query = ancestor.clone();
+        for modification in &query_modifications {
+            modification.apply(&mut derived_query, &reference).unwrap();
+        }
+        assert_eq!(derived_query, query);
+
+        // Recording the substituted characters does not change the pair.
+        command.spectrum_output = Some("spectrum.json".into());
+        let pair = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+        assert_eq!(pair.reference, reference);
+        assert_eq!(pair.query, query);
+        let [reference_substitutions, _] = pair.substitutions.unwrap();
+        let mut replayed_reference = ancestor.clone();
+        let mut replayed_substitutions = Vec::new();
+        for modification in &reference_modifications {
+            let original_character = match *modification {
+                SequenceModification::Substitution { position, .. } => {
+                    Some((position, u8::from(replayed_reference[position])))
+                }
+                _ => None,
+            };
+            modification
+                .apply(&mut replayed_reference, &ancestor)
+                .unwrap();
+            if let Some((position, original_character)) = original_character {
+                replayed_substitutions
+                    .push((original_character, u8::from(replayed_reference[position])));
+            }
+        }
+        assert!(!reference_substitutions.is_empty());
+        assert_eq!(reference_substitutions, replayed_substitutions);
+
+        // Retaining the ancestor does not change the pair.
+        command.ancestor_output = Some("ancestor-output.fa".into());
+        let pair = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+        assert_eq!(pair.ancestor, Some(ancestor));
+        assert_eq!(pair.reference, reference);
+        assert_eq!(pair.query, query);
+    }
+
+    /// Varying the seed of one type of modifications only changes the modifications of that type.
+    #[test]
+    fn modification_type_seeds() {
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(50)).unwrap();
+        let ancestor_source =
+            AncestorSource::<0, 4, DnaAlphabet, u8>::Sequence(ancestor.clone(), None);
+        let modifications = |substitution_seed: &str| {
+            let CliCommands::GeneratePair(command) = Cli::parse_from([
+                "tsgen",
+                "generate-pair",
+                "--ancestor-input",
+                "ancestor.fa",
+                "--dry-run",
+                // Without overlap rejection, substitutions do not influence the other modifications.
+                "--template-switch-overlap",
+                "--template-switch-amount",
+                "4",
+                "--template-switch-seed",
+                "1",
+                "--gap-seed",
+                "2",
+                "--substitution-seed",
+                substitution_seed,
+            ])
+            .command
+            else {
+                panic!("wrong subcommand");
+            };
+            let pair = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+            [pair.reference_modifications, pair.query_modifications]
+                .map(|modifications| modifications.iter().map(|m| format!("{m:?}")).collect())
+        };
+
+        let first: [Vec<String>; 2] = modifications("3");
+        let second = modifications("4");
+        for (first, second) in first.iter().zip(&second) {
+            assert_eq!(first.len(), second.len());
+            let mut substitution_differs = false;
+            for (first, second) in first.iter().zip(second) {
+                if first.starts_with("Substitution") {
+                    assert!(second.starts_with("Substitution"));
+                    substitution_differs |= first != second;
+                } else {
+                    assert_eq!(first, second);
+                }
+            }
+            assert!(substitution_differs);
+        }
+    }
+
+    /// Template switches of the reference do not prevent template switches of the query at the same position of the ancestor.
+    #[test]
+    fn independent_template_switches() {
+        let CliCommands::GeneratePair(command) = Cli::parse_from([
+            "tsgen",
+            "generate-pair",
+            "--ancestor-input",
+            "ancestor.fa",
+            "--dry-run",
+            "--strict",
+            "--template-switch-amount",
+            "8",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "0",
+            "--template-switch-maximum-overlap-tries",
+            "100",
+        ])
+        .command
+        else {
+            panic!("wrong subcommand");
+        };
+        // The ancestor has room for the template switches of one sequence, but not for those of both.
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(30)).unwrap();
+        let ancestor_source = AncestorSource::<0, 4, DnaAlphabet, u8>::Sequence(ancestor, None);
+
+        for pair_index in 0..10 {
+            let pair = generate_command_pair(&ancestor_source, &command, pair_index).unwrap();
+            assert_eq!(
+                pair.reference_modifications.len() + pair.query_modifications.len(),
+                8
+            );
+        }
+    }
+
+    #[test]
+    fn require_template_switch() {
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(50)).unwrap();
+        let ancestor_source = AncestorSource::<0, 4, DnaAlphabet, u8>::Sequence(ancestor, None);
+        let generate = |template_switch_amount: &str, require: &[&str]| {
+            let arguments = [
+                "tsgen",
+                "generate-pair",
+                "--ancestor-input",
+                "ancestor.fa",
+                "--dry-run",
+                "--template-switch-amount",
+                template_switch_amount,
+                "--require-template-switch-tries",
+                "3",
+            ];
+            let CliCommands::GeneratePair(command) =
+                Cli::parse_from(arguments.iter().chain(require)).command
+            else {
+                panic!("wrong subcommand");
+            };
+            generate_command_pair(&ancestor_source, &command, 0)
+        };
+
+        // A pair that has the required template switches at once is the same as without the requirement.
+        let pair = generate("2", &[]).unwrap();
+        let required_pair = generate("2", &["--require-template-switch"]).unwrap();
+        assert_eq!(required_pair.reference, pair.reference);
+        assert_eq!(required_pair.query, pair.query);
+
+        assert!(matches!(
+            generate("0", &["--require-template-switch"]),
+            Err(Error::RequiredTemplateSwitchesNotReached { min: 1, tries: 3 })
+        ));
+        assert!(matches!(
+            generate("2", &["--require-template-switch", "3"]),
+            Err(Error::RequiredTemplateSwitchesNotReached { min: 3, tries: 3 })
+        ));
+
+        // All template switches land in the query, so the reference never has one.
+        let pair = generate("2", &["--reference-ancestry-fraction", "1"]).unwrap();
+        let is_template_switch = |modification: &&SequenceModification| {
+            matches!(modification, SequenceModification::TemplateSwitch { .. })
+        };
+        assert!(pair.reference_modifications.is_empty());
+        assert_eq!(
+            pair.query_modifications
+                .iter()
+                .filter(is_template_switch)
+                .count(),
+            2
+        );
+        assert!(matches!(
+            generate(
+                "2",
+                &[
+                    "--reference-ancestry-fraction",
+                    "1",
+                    "--require-template-switch"
+                ]
+            ),
+            Err(Error::RequiredTemplateSwitchesNotReached { min: 1, tries: 3 })
+        ));
+    }
+
+    #[test]
+    fn gc_contents() {
+        for (sequence, expected) in [
+            (&b""[..], 0.0),
+            (b"AT", 0.0),
+            (b"ACGT", 0.5),
+            (b"GGCG", 1.0),
+        ] {
+            let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap();
+            assert_eq!(gc_content(&sequence), expected);
+        }
+    }
+
+    #[test]
+    fn homopolymers() {
+        for (sequence, expected) in [
+            (&b""[..], 0),
+            (b"A", 1),
+            (b"ACGT", 1),
+            (b"AACCCGTT", 3),
+            (b"ACGTTTT", 4),
+        ] {
+            let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap();
+            assert_eq!(longest_homopolymer(&sequence), expected);
+        }
+    }
+
+    #[test]
+    fn max_homopolymer() {
+        // Each character may follow itself, so the model generates homopolymers of any length.
+        let sequence =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(10)).unwrap();
+        let model = NGramModel::<1, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let ancestor_source = AncestorSource::Model {
+            model: Box::new(model),
+            ancestor_length_distribution: AncestorLengthDistribution::Fixed(100),
+            interpolation: None,
+        };
+        let generate =
+            |random_seed: &str, max_homopolymer: &str, tries: &str, target_gc: &[&str]| {
+                let arguments = [
+                    "tsgen",
+                    "generate-pair",
+                    "--model",
+                    "model",
+                    "--ancestor-length",
+                    "100",
+                    "--dry-run",
+                    "--ancestor-output",
+                    "ancestor.fa",
+                    "--random-seed",
+                    random_seed,
+                    "--max-homopolymer",
+                    max_homopolymer,
+                    "--max-homopolymer-tries",
+                    tries,
+                ];
+                let CliCommands::GeneratePair(command) =
+                    Cli::parse_from(arguments.iter().chain(target_gc)).command
+                else {
+                    panic!("wrong subcommand");
+                };
+                generate_command_pair(&ancestor_source, &command, 0)
+            };
+
+        for random_seed in ["0", "1", "2"] {
+            let ancestor = generate(random_seed, "3", "1000", &[])
+                .unwrap()
+                .ancestor
+                .unwrap();
+            assert!(longest_homopolymer(&ancestor) <= 3);
+        }
+        assert!(matches!(
+            generate("0", "1", "3", &[]),
+            Err(Error::MaxHomopolymerNotReached { max: 1, tries: 3 })
+        ));
+
+        // Ancestors rejected for their homopolymers do not use up the tries of the GC content.
+        let target_gc = [
+            "--target-gc-min",
+            "0.4",
+            "--target-gc-max",
+            "0.6",
+            "--target-gc-tries",
+            "3",
+        ];
+        for random_seed in 0..10 {
+            let ancestor = generate(&random_seed.to_string(), "3", "1000", &target_gc)
+                .unwrap()
+                .ancestor
+                .unwrap();
+            assert!(longest_homopolymer(&ancestor) <= 3);
+            assert!((0.4..=0.6).contains(&gc_content(&ancestor)));
+        }
+    }
+
+    /// Each random generator algorithm gives the same pair for the same seed, and different pairs for different algorithms.
+    #[test]
+    fn rng_algorithms() {
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"AACGTTGCATGCCAGT".repeat(50)).unwrap();
+        let ancestor_source = AncestorSource::<0, 4, DnaAlphabet, u8>::Sequence(ancestor, None);
+
+        let mut pairs = Vec::new();
+        for rng in ["xoshiro256", "pcg64", "chacha8"] {
+            let CliCommands::GeneratePair(command) = Cli::parse_from([
+                "tsgen",
+                "generate-pair",
+                "--ancestor-input",
+                "ancestor.fa",
+                "--dry-run",
+                "--random-seed",
+                "7",
+                "--rng",
+                rng,
+            ])
+            .command
+            else {
+                panic!("wrong subcommand");
+            };
+
+            let pair = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+            let repeated_pair = generate_command_pair(&ancestor_source, &command, 0).unwrap();
+            assert_eq!(pair.reference, repeated_pair.reference);
+            assert_eq!(pair.query, repeated_pair.query);
+            assert!(pairs.iter().all(|(reference, query)| {
+                *reference != pair.reference || *query != pair.query
+            }));
+            pairs.push((pair.reference, pair.query));
+        }
+    }
+}
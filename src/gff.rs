@@ -0,0 +1,206 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    error::Result,
+    sequence_modifier::{ancestry::final_intervals, SequenceModification},
+};
+
+/// A derived sequence with the modifications that were applied in order to derive it from the ancestor.
+pub struct AnnotatedSequence<'a> {
+    pub id: &'a str,
+    /// The final length of the sequence.
+    pub length: usize,
+    /// If set, the features are written in the coordinates of the reverse complement of the sequence.
+    pub is_reverse_complement: bool,
+    pub modifications: &'a [SequenceModification],
+}
+
+/// Write the modifications of the given sequences as GFF3 features on the final sequences.
+///
+/// Each modification is a feature of type `substitution`, `insertion`, `deletion` or `template_switch`,
+/// which covers the characters that it wrote into the sequence, as far as they were not removed by later modifications,
+/// see [`final_intervals`].
+/// Deletions and features whose characters were all removed are written as zero-length features,
+/// whose start equals their end and which lie between the given base and the next one, like in the GFF3 specification.
+/// Zero-length features at the start of the sequence have no base before them, so they are left out.
+/// The features keep their ids, so the index in the id is still the index of the modification.
+///
+/// The attributes contain the position at which the modification was applied, and its length, offset, length difference,
+/// source and character increment where applicable.
+/// These refer to the sequence at the time the modification was applied, before it is reverse complemented.
+pub fn write_gff3_file<'a>(
+    path: impl AsRef<Path>,
+    sequences: impl IntoIterator<Item = AnnotatedSequence<'a>>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_gff3(&mut writer, sequences)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_gff3<'a>(
+    mut writer: impl Write,
+    sequences: impl IntoIterator<Item = AnnotatedSequence<'a>>,
+) -> Result<()> {
+    writeln!(writer, "##gff-version 3")?;
+
+    for sequence in sequences {
+        writeln!(
+            writer,
+            "##sequence-region {} 1 {}",
+            sequence.id, sequence.length
+        )?;
+
+        for (index, (modification, interval)) in sequence
+            .modifications
+            .iter()
+            .zip(final_intervals(sequence.modifications))
+            .enumerate()
+        {
+            let interval = if sequence.is_reverse_complement {
+                sequence.length - interval.end..sequence.length - interval.start
+            } else {
+                interval
+            };
+            let (feature_type, attributes) = match *modification {
+                SequenceModification::TemplateSwitch {
+                    length,
+                    offset,
+                    length_difference,
+                    inter_sequence,
+                    ..
+                } => (
+                    "template_switch",
+                    format!("length={length};offset={offset};length_difference={length_difference};source_strand=-;inter_sequence={inter_sequence}"),
+                ),
+                SequenceModification::Insertion { source, length, .. } => {
+                    ("insertion", format!("length={length};source={source}"))
+                }
+                SequenceModification::Deletion { length, .. } => {
+                    ("deletion", format!("length={length}"))
+                }
+                SequenceModification::Substitution {
+                    character_increment,
+                    ..
+                } => (
+                    "substitution",
+                    format!("character_increment={character_increment}"),
+                ),
+            };
+
+            // GFF3 is one-based and inclusive, and zero-length features use the base before the site.
+            let (start, end) = if interval.is_empty() {
+                if interval.start == 0 {
+                    continue;
+                }
+                (interval.start, interval.start)
+            } else {
+                (interval.start + 1, interval.end)
+            };
+            writeln!(
+                writer,
+                "{}\ttsgen\t{feature_type}\t{start}\t{end}\t.\t.\t.\tID={}.{index};applied_position={};{attributes}",
+                sequence.id,
+                sequence.id,
+                modification.position(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::{write_gff3, AnnotatedSequence};
+
+    #[test]
+    fn feature_coordinates() {
+        let modifications = [
+            // Writes the base at one-based position 3.
+            SequenceModification::Substitution {
+                position: 2,
+                character_increment: 1,
+            },
+            // Writes one-based positions 6 to 8.
+            SequenceModification::Insertion {
+                position: 5,
+                source: 0,
+                length: 3,
+            },
+            // Removes the first two characters, and moves everything back by two.
+            SequenceModification::Deletion {
+                position: 0,
+                length: 2,
+            },
+            // Copies 4 characters in place of 2 at zero-based position 10, i.e. one-based positions 11 to 14.
+            SequenceModification::TemplateSwitch {
+                position: 10,
+                length: 4,
+                offset: -2,
+                length_difference: 2,
+                inter_sequence: false,
+            },
+            // Removes the first inserted character, which is now at zero-based position 3.
+            SequenceModification::Deletion {
+                position: 3,
+                length: 1,
+            },
+        ];
+
+        let mut output = Vec::new();
+        write_gff3(
+            &mut output,
+            [
+                AnnotatedSequence {
+                    id: "r",
+                    length: 20,
+                    is_reverse_complement: false,
+                    modifications: &modifications,
+                },
+                AnnotatedSequence {
+                    id: "q",
+                    length: 20,
+                    is_reverse_complement: true,
+                    modifications: &modifications[..1],
+                },
+            ],
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let features: Vec<_> = output
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let columns: Vec<_> = line.split('\t').collect();
+                (columns[0], columns[2], columns[3], columns[4])
+            })
+            .collect();
+
+        assert!(output.starts_with("##gff-version 3\n##sequence-region r 1 20\n"));
+        assert_eq!(
+            features,
+            [
+                // The first deletion moved the substitution back by two, the insertion after it did not move it.
+                ("r", "substitution", "1", "1"),
+                // The first deletion moved the insertion back by two, and the second deletion removed its first character.
+                ("r", "insertion", "4", "5"),
+                // The deletion at the start of the sequence is left out.
+                // The second deletion moved the template switch back by one.
+                ("r", "template_switch", "10", "13"),
+                // Lies between one-based positions 3 and 4.
+                ("r", "deletion", "3", "3"),
+                // Position 3 of 20 is position 18 of the reverse complement.
+                ("q", "substitution", "18", "18"),
+            ]
+        );
+        assert!(output.contains("\ttemplate_switch\t10\t13\t.\t.\t.\tID=r.3;applied_position=10;length=4;offset=-2;length_difference=2;source_strand=-;inter_sequence=false\n"));
+    }
+}
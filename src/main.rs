@@ -1,13 +1,21 @@
 use std::{
+    ffi::OsString,
     fs::File,
-    io::{BufReader, BufWriter, Write},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    slice,
 };
 
 use crate::error::Result;
-use choose_alphabet_and_n::{call, ChooseAlphabetAndN};
-use clap::{Parser, ValueEnum};
+use alignment::{ancestor_cigar, Alignment, AlignmentReference};
+use ancestor_length::AncestorLengthDistribution;
+use bench::Bench;
+use check::check;
+use choose_alphabet_and_n::{call, ChooseAlphabetAndN, MAX_N};
+use clap::Parser;
 use cli::{
-    Cli, CliAlphabet, CliCommands, CreateModelCommand, GeneratePairCommand, IntoCliAlphabet,
+    Cli, CliAlphabet, CliCommands, CreateModelCommand, GenerateCommand, GeneratePairCommand,
+    IntoCliAlphabet, SweepCommand, ValidateCommand,
 };
 use compact_genome::{
     implementation::{
@@ -15,26 +23,66 @@ use compact_genome::{
         handle_sequence_store::HandleSequenceStore,
         DefaultGenome, DefaultSubGenome,
     },
-    interface::alphabet::Alphabet,
-    io::fasta::{read_fasta_file, write_fasta_file, FastaRecord},
+    interface::{alphabet::Alphabet, sequence::GenomeSequence},
+    io::fasta::{read_fasta_file, FastaRecord},
 };
+use divergence::Divergence;
 use error::Error;
-use log::{info, LevelFilter};
-use n_gram_model::NGramModel;
-use rand::SeedableRng;
-use rand_xoshiro::Xoshiro256PlusPlus;
+use fasta::{read_soft_mask, write_fasta_file, FastaSequenceReader, UnknownCharacters};
+use flate2::{write::GzEncoder, Compression};
+use fragments::{fragment_id, sample_fragments};
+use generate::{generate_pair, AncestorSource, GeneratedPair, PairParameters};
+use gff::{write_gff3_file, AnnotatedSequence};
+use indicatif::ProgressIterator;
+use log::{info, warn, LevelFilter};
+use manifest::{FileHash, Manifest};
+use msa::MultipleSequenceAlignment;
+use n_gram_model::{
+    read_model_file, write_model_file, Composition, Interpolation, ModelInput, NGramModel,
+};
+use paf::{write_paf_file, PafRecord};
+use progress::{progress_bar, progress_spinner};
+use provenance::{provenance, write_provenance_file, Origin};
+use random_stream::{sweep_combination_seed, RandomStream};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use sequence_modifier::{
-    template_switch_overlap_detector::TemplateSwitchOverlapDetector, SequenceModifier,
-    SequenceModifierPair,
+    ancestry::{list_modifications, Ancestry},
+    soft_mask::SoftMask,
+    SequenceModification,
 };
 use serde::{Deserialize, Serialize};
 use simplelog::{ColorChoice, TermLogger, TerminalMode};
+use spectrum::Spectrum;
+use summary::{PairStats, Summary};
+use sweep::{combinations, SweepCombination, SweepManifest};
+use template_switches::{template_switch_records, write_template_switch_json, DerivedSequence};
+use traitsequence::interface::Sequence;
 
+mod alignment;
+mod ancestor_length;
+mod bench;
+mod check;
 mod choose_alphabet_and_n;
 mod cli;
+mod divergence;
 mod error;
+mod fasta;
+mod fragments;
+mod generate;
+mod gff;
+mod manifest;
+mod msa;
 mod n_gram_model;
+mod paf;
+mod progress;
+mod provenance;
+mod random_stream;
+mod reduced_alphabet;
 mod sequence_modifier;
+mod spectrum;
+mod summary;
+mod sweep;
+mod template_switches;
 
 fn main() {
     TermLogger::init(
@@ -45,17 +93,143 @@ fn main() {
     )
     .unwrap();
 
-    let cli = Cli::parse();
+    if let Err(error) = cli::arguments_with_config(std::env::args_os())
+        .map(Cli::parse_from)
+        .and_then(run)
+    {
+        println!("Error: {error}");
+        std::process::exit(1);
+    }
+}
 
+fn run(cli: Cli) -> Result<()> {
     match cli.command {
-        CliCommands::CreateNGramModel(create_model_command) => call::<CreateNGramModel>(
-            create_model_command.alphabet,
-            create_model_command.n_gram_context_length,
-            create_model_command,
-        ),
-        CliCommands::GeneratePair(generate_pair_command) => generate_pair(generate_pair_command),
+        CliCommands::CreateNGramModel(create_model_command) => {
+            create_model_command.verify()?;
+            // A reduced model is created in the reduced alphabet, and the input is reduced while reading it.
+            call::<CreateNGramModel>(
+                create_model_command.model_alphabet(),
+                create_model_command.n_gram_context_length,
+                create_model_command,
+            )
+        }
+        CliCommands::GeneratePair(generate_pair_command) => generate_pairs(generate_pair_command),
+        CliCommands::Generate(generate_command) => generate_sequences(generate_command),
+        CliCommands::Validate(validate_command) => validate(validate_command),
+        CliCommands::Summarize(summarize_command) => Summary::read_directory(
+            &summarize_command.input_directory,
+            &summarize_command.stats_prefix,
+            &summarize_command.template_switch_prefix,
+        )?
+        .write(std::io::stdout().lock()),
+        CliCommands::Check(mut check_command) => {
+            check_command
+                .sequence_modification_parameters
+                .set_alphabet_defaults(check_command.alphabet);
+            check_command.verify()?;
+            check(&check_command)?.write(std::io::stdout().lock())
+        }
+        CliCommands::Sweep(sweep_command) => sweep(sweep_command),
+        CliCommands::Bench(bench_command) => call::<Bench>(
+            bench_command.alphabet,
+            bench_command.n_gram_context_length,
+            bench_command,
+        )
+        .map(|_| ()),
+    }
+}
+
+/// Generates the pairs of each combination of parameters of a sweep, as if generate-pair was run for each of them.
+///
+/// The shared arguments are parsed once, and each combination sets its values in a copy of the parsed command.
+fn sweep(sweep_command: SweepCommand) -> Result<()> {
+    let combinations = combinations(&sweep_command.parameters);
+    info!("Sweeping {} combinations...", combinations.len());
+    std::fs::create_dir_all(&sweep_command.output_directory)?;
+
+    let mut arguments: Vec<OsString> = vec!["tsgen".into(), "generate-pair".into()];
+    arguments.extend(sweep_command.arguments.iter().cloned());
+    // The output is required, and each combination moves it into its own directory.
+    arguments.extend([
+        "--output".into(),
+        sweep_command
+            .output_directory
+            .join("pair.fa")
+            .into_os_string(),
+    ]);
+    let cli = Cli::try_parse_from(cli::arguments_with_config(arguments)?)
+        .map_err(|error| Error::InvalidSweepArguments(error.to_string()))?;
+    let CliCommands::GeneratePair(shared_command) = cli.command else {
+        unreachable!("the arguments start with the generate-pair subcommand");
+    };
+
+    let mut manifest = SweepManifest {
+        version: env!("CARGO_PKG_VERSION"),
+        random_seed: sweep_command.random_seed,
+        combinations: Vec::new(),
+    };
+    for (index, combination) in combinations.into_iter().enumerate() {
+        let directory = sweep_command
+            .output_directory
+            .join(format!("combination{index}"));
+        std::fs::create_dir_all(&directory)?;
+        let random_seed = sweep_combination_seed(sweep_command.random_seed, index);
+
+        let mut generate_pair_command = shared_command.clone();
+        for (_, value) in &combination {
+            value.apply(&mut generate_pair_command)?;
+        }
+        generate_pair_command.move_outputs_into(&directory);
+        generate_pair_command.random_seed = random_seed;
+
+        info!("Generating combination {index}: {combination:?}");
+        let output_paths = generate_pair_command.output_paths();
+        generate_pairs(generate_pair_command)?;
+        manifest.combinations.push(SweepCombination {
+            index,
+            directory,
+            random_seed,
+            parameters: combination
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            outputs: output_paths
+                .into_iter()
+                .map(FileHash::new)
+                .collect::<Result<_>>()?,
+        });
+    }
+
+    manifest.write_json_file(sweep_command.output_directory.join("sweep.json"))
+}
+
+fn validate(validate_command: ValidateCommand) -> Result<()> {
+    let (n, alphabet, input) = read_model_file(&validate_command.model)?;
+
+    if n > MAX_N {
+        return Err(Error::UnsupportedN(n));
+    }
+
+    let structure = input.read_structure()?;
+    if structure.n_gram_amount == 0 {
+        return Err(Error::EmptyModel);
     }
-    .unwrap_or_else(|error| println!("Error: {error}"));
+    if structure.zero_weight_n_gram_amount == structure.n_gram_amount {
+        return Err(Error::ZeroWeightModel(structure.n_gram_amount));
+    }
+    if structure.zero_weight_n_gram_amount > 0 {
+        warn!(
+            "{} of {} n-grams have only zero successor abundances and are treated as unseen",
+            structure.zero_weight_n_gram_amount, structure.n_gram_amount
+        );
+    }
+
+    info!(
+        "Model is valid: n = {n}, alphabet = {}, {} n-grams",
+        alphabet.name(),
+        structure.n_gram_amount
+    );
+    Ok(())
 }
 
 struct CreateNGramModel;
@@ -68,7 +242,11 @@ impl ChooseAlphabetAndN for CreateNGramModel {
     fn call<
         const N: usize,
         const ALPHABET_SIZE: usize,
-        BitArrayType: BitViewSized + BitStore + Serialize + for<'de> Deserialize<'de>,
+        BitArrayType: BitViewSized
+            + BitStore
+            + BitView<Store = BitArrayType>
+            + Serialize
+            + for<'de> Deserialize<'de>,
         AlphabetType: 'static + Alphabet + IntoCliAlphabet,
     >(
         create_model_command: Self::Arguments,
@@ -76,62 +254,430 @@ impl ChooseAlphabetAndN for CreateNGramModel {
     where
         [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
     {
-        // Load sequences.
-        info!("Loading sequences...");
-        let mut sequence_store =
-            HandleSequenceStore::<AlphabetType, DefaultGenome<_>, DefaultSubGenome<_>>::new();
-        let sequences = read_fasta_file(
-            &create_model_command.input_fasta,
-            &mut sequence_store,
-            create_model_command.skip_unknown_characters,
-            create_model_command.capitalise_characters,
-        )?
-        .into_iter()
-        .map(|record| record.sequence_handle);
-
-        // Create model.
+        // Create model while reading the sequences, such that only one sequence is held in memory at a time.
         info!("Creating model...");
-        let model = NGramModel::<N, ALPHABET_SIZE, _, BitArrayType>::from_sequences(sequences);
+        let unknown_characters = if create_model_command.split_on_unknown {
+            UnknownCharacters::Split
+        } else if create_model_command.skip_unknown_characters {
+            UnknownCharacters::Skip
+        } else {
+            UnknownCharacters::Error
+        };
+        let mut readers = create_model_command
+            .weighted_inputs()
+            .map(|(input_fasta, weight)| {
+                Ok((
+                    FastaSequenceReader::<AlphabetType, _>::open(
+                        input_fasta,
+                        create_model_command.capitalise_characters,
+                        create_model_command.reduce_alphabet,
+                        unknown_characters,
+                    )?,
+                    weight,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut read_error = None;
+        let mut record_amount = 0;
+        let mut contributing_record_amount = 0;
+        let progress_bar = progress_spinner(create_model_command.progress, "records");
+        let sequences = readers
+            .iter_mut()
+            .flat_map(|(reader, weight)| {
+                let weight = *weight;
+                reader.map(move |sequence| sequence.map(|sequence| (sequence, weight)))
+            })
+            .map_while(|sequence| sequence.map_err(|error| read_error = Some(error)).ok())
+            .inspect(|(sequence, _)| {
+                record_amount += 1;
+                if NGramModel::<N, ALPHABET_SIZE, AlphabetType, BitArrayType>::window_amount(
+                    sequence.len(),
+                    create_model_command.circular,
+                ) > 0
+                {
+                    contributing_record_amount += 1;
+                }
+            })
+            .progress_with(progress_bar.clone());
+        let model = NGramModel::<N, ALPHABET_SIZE, _, BitArrayType>::from_weighted_sequences(
+            sequences,
+            create_model_command.circular,
+        );
+        progress_bar.finish_and_clear();
+        if let Some(error) = read_error {
+            return Err(error);
+        }
+        let model = model?;
+        if create_model_command.split_on_unknown {
+            info!("Split the records into {record_amount} segments");
+        }
+
+        // Report the characters removed by skipping or splitting.
+        let unknown_character_counts: Vec<_> = readers
+            .iter()
+            .flat_map(|(reader, _)| reader.unknown_character_counts())
+            .collect();
+        for count in &unknown_character_counts {
+            if count.unknown_amount > 0 {
+                warn!(
+                    "Removed {} of {} characters of record {:?} as unknown characters",
+                    count.unknown_amount, count.character_amount, count.record_id
+                );
+            }
+        }
+        let unknown_amount: usize = unknown_character_counts
+            .iter()
+            .map(|count| count.unknown_amount)
+            .sum();
+        if unknown_amount > 0 {
+            let character_amount: usize = unknown_character_counts
+                .iter()
+                .map(|count| count.character_amount)
+                .sum();
+            warn!("Removed {unknown_amount} of {character_amount} characters in total as unknown characters");
+        }
+        let degraded_records: Vec<_> = unknown_character_counts
+            .iter()
+            .filter(|count| {
+                count.unknown_fraction() > create_model_command.unknown_character_warning_fraction
+            })
+            .collect();
+
+        info!("Read {record_amount} records, of which {contributing_record_amount} are long enough for n = {N}");
+        if contributing_record_amount == 0 {
+            return Err(Error::UntrainableCorpus {
+                record_amount,
+                contributing_record_amount,
+                n: N,
+            });
+        }
+
+        let abundance_distribution = model.abundance_distribution();
+        info!(
+            "Model has {} n-grams covering {:.4} of all n-grams, with a conditional entropy of {:.4} bits and n-gram abundances between {} and {}",
+            model.n_gram_amount(),
+            model.coverage(),
+            model.conditional_entropy(),
+            abundance_distribution.keys().next().unwrap_or(&0),
+            abundance_distribution.keys().next_back().unwrap_or(&0),
+        );
 
         // Write model parameters and model.
         info!("Storing model...");
-        let mut output = BufWriter::new(File::create(&create_model_command.output)?);
-        ciborium::into_writer(&N, &mut output)?;
-        ciborium::into_writer(
-            &AlphabetType::into_cli_alphabet()
-                .to_possible_value()
-                .unwrap()
-                .get_name(),
-            &mut output,
-        )?;
-        ciborium::into_writer(&model, &mut output)?;
+        let write_model = |output: &mut dyn Write| {
+            write_model_file(
+                output,
+                &model,
+                AlphabetType::into_cli_alphabet(),
+                create_model_command.model_format,
+            )
+        };
+        let output = BufWriter::new(File::create(&create_model_command.output)?);
+        if create_model_command.compresses_output() {
+            let mut output = GzEncoder::new(output, Compression::default());
+            write_model(&mut output)?;
+            output.finish()?.flush()?;
+        } else {
+            let mut output = output;
+            write_model(&mut output)?;
+            output.flush()?;
+        }
+
+        // Repeat the warning at the end, where it cannot be missed.
+        if !degraded_records.is_empty() {
+            warn!(
+                "{} records lost more than {} of their characters as unknown characters, so the model may not represent them: {}",
+                degraded_records.len(),
+                create_model_command.unknown_character_warning_fraction,
+                degraded_records
+                    .iter()
+                    .map(|count| format!("{:?} ({:.4})", count.record_id, count.unknown_fraction()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
 
         Ok(())
     }
 }
 
-fn generate_pair(generate_pair_command: GeneratePairCommand) -> Result<()> {
+fn generate_pairs(mut generate_pair_command: GeneratePairCommand) -> Result<()> {
+    let (n, alphabet, input) = match (
+        &generate_pair_command.model,
+        &generate_pair_command.composition,
+    ) {
+        (Some(model), _) => {
+            let (n, alphabet, input) = read_model_file(model)?;
+            (n, alphabet, AncestorModel::File(input))
+        }
+        (None, Some(composition)) => (
+            0,
+            generate_pair_command.ancestor_alphabet,
+            AncestorModel::Composition(composition.clone()),
+        ),
+        (None, None) => {
+            if generate_pair_command.ancestor_input.is_none() {
+                return Err(Error::MissingAncestorSource);
+            }
+
+            generate_pair_command
+                .sequence_modification_parameters
+                .set_alphabet_defaults(generate_pair_command.ancestor_alphabet);
+            generate_pair_command.verify()?;
+            report_parameters(
+                &generate_pair_command,
+                None,
+                generate_pair_command.ancestor_alphabet,
+            )?;
+
+            // The ancestor is loaded from a file, so n is irrelevant.
+            return call::<GeneratePair>(
+                generate_pair_command.ancestor_alphabet,
+                0,
+                (None, generate_pair_command),
+            );
+        }
+    };
+
+    generate_pair_command
+        .sequence_modification_parameters
+        .set_alphabet_defaults(alphabet);
     generate_pair_command.verify()?;
+    report_parameters(&generate_pair_command, Some(n), alphabet)?;
+
+    let ancestor_length_distribution = generate_pair_command
+        .ancestor_length_distribution()?
+        .ok_or(Error::MissingAncestorLength)?;
+    generate_pair_command
+        .n_gram_generation_parameters
+        .verify_n(n)?;
+    if let Some(ancestor_length) = ancestor_length_distribution.min() {
+        if ancestor_length < n {
+            return Err(Error::LengthLowerThanN {
+                length: ancestor_length,
+                n,
+            });
+        }
+    }
+
+    call::<GeneratePair>(
+        alphabet,
+        n,
+        (
+            Some((input, ancestor_length_distribution)),
+            generate_pair_command,
+        ),
+    )
+}
+
+/// Logs the resolved parameters, or prints them if `--print-parameters` is set.
+///
+/// The parameters are formatted as config file, with `n` and the alphabet of the model as comments.
+fn report_parameters(
+    generate_pair_command: &GeneratePairCommand,
+    n: Option<usize>,
+    alphabet: CliAlphabet,
+) -> Result<()> {
+    let n = n.map_or_else(|| "none (ancestor input)".to_string(), |n| n.to_string());
+    let parameters = format!(
+        "# n = {n}\n# alphabet = {}\n{}",
+        alphabet.name(),
+        cli::resolved_config(generate_pair_command)?
+    );
 
-    let mut input = BufReader::new(File::open(&generate_pair_command.model)?);
-    let n: usize = ciborium::from_reader(&mut input)?;
-    let alphabet: String = ciborium::from_reader(&mut input)?;
-    let alphabet = CliAlphabet::from_str(&alphabet, false).map_err(Error::UnsupportedAlphabet)?;
+    if generate_pair_command.print_parameters {
+        print!("{parameters}");
+    } else {
+        info!("Resolved parameters:\n{parameters}");
+    }
+    Ok(())
+}
+
+/// The model from which the ancestor is generated.
+enum AncestorModel {
+    /// A model file whose header was read.
+    File(ModelInput),
+    /// A composition, from which a model of order zero is built.
+    Composition(Composition),
+}
+
+struct GeneratePair;
 
-    if generate_pair_command.ancestor_length < n {
+impl ChooseAlphabetAndN for GeneratePair {
+    /// The model input with the distribution of the ancestor length, or `None` if the ancestor is loaded from a fasta file.
+    type Arguments = (
+        Option<(AncestorModel, AncestorLengthDistribution)>,
+        GeneratePairCommand,
+    );
+
+    type Return = ();
+
+    fn call<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        BitArrayType: BitViewSized
+            + BitStore
+            + BitView<Store = BitArrayType>
+            + Serialize
+            + for<'de> Deserialize<'de>
+            + Send
+            + Sync,
+        AlphabetType: 'static + Alphabet + IntoCliAlphabet + Send + Sync,
+    >(
+        (input, generate_pair_command): Self::Arguments,
+    ) -> Result<Self::Return>
+    where
+        [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
+        AlphabetType::CharacterType: Send + Sync,
+    {
+        // If the ancestor is loaded from a file, then n is irrelevant.
+        let n = input.is_some().then_some(N);
+        if generate_pair_command
+            .sequence_modification_parameters
+            .reading_frame
+            .is_some()
+            && !AlphabetType::into_cli_alphabet().is_dna()
+        {
+            return Err(Error::ReadingFrameRequiresDna);
+        }
+        if generate_pair_command
+            .sequence_modification_parameters
+            .cpg_bias
+            != 1.0
+            && !AlphabetType::into_cli_alphabet().is_dna()
+        {
+            return Err(Error::CpgBiasRequiresDna);
+        }
+        if generate_pair_command.target_gc().is_some()
+            && !AlphabetType::into_cli_alphabet().is_dna()
+        {
+            return Err(Error::TargetGcRequiresDna);
+        }
+
+        // Load model or ancestor.
+        let ancestor_source: AncestorSource<N, ALPHABET_SIZE, AlphabetType, BitArrayType> =
+            if let Some((input, ancestor_length_distribution)) = input {
+                let model: NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType> = match input {
+                    AncestorModel::File(input) => {
+                        input.read_model(generate_pair_command.realistic_starts)?
+                    }
+                    AncestorModel::Composition(composition) => {
+                        NGramModel::from_composition(&composition)?
+                    }
+                };
+                let interpolation_weights = &generate_pair_command
+                    .n_gram_generation_parameters
+                    .interpolation_weights;
+                let interpolation = (!interpolation_weights.is_empty())
+                    .then(|| Interpolation::new(&model, interpolation_weights));
+                AncestorSource::Model {
+                    model: Box::new(model),
+                    ancestor_length_distribution,
+                    interpolation,
+                }
+            } else {
+                let ancestor_input = generate_pair_command
+                    .ancestor_input
+                    .as_ref()
+                    .ok_or(Error::MissingAncestorSource)?;
+
+                info!("Loading ancestor...");
+                let mut sequence_store = HandleSequenceStore::<
+                    AlphabetType,
+                    DefaultGenome<_>,
+                    DefaultSubGenome<_>,
+                >::new();
+                // Soft-masked characters are capitalised for the alphabet, and their case is kept in the soft-mask.
+                let skip_invalid_characters = false;
+                let capitalise_characters = generate_pair_command.preserve_case;
+                let mut records = read_fasta_file(
+                    ancestor_input,
+                    &mut sequence_store,
+                    skip_invalid_characters,
+                    capitalise_characters,
+                )?
+                .into_iter();
+                let record = if let Some(ancestor_id) = &generate_pair_command.ancestor_id {
+                    records
+                        .find(|record| &record.id == ancestor_id)
+                        .ok_or_else(|| Error::AncestorIdNotFound(ancestor_id.clone()))?
+                } else {
+                    records.next().ok_or(Error::EmptyAncestorInput)?
+                };
+
+                let soft_mask = generate_pair_command
+                    .preserve_case
+                    .then(|| {
+                        read_soft_mask(ancestor_input, generate_pair_command.ancestor_id.as_deref())
+                    })
+                    .transpose()?;
+
+                AncestorSource::Sequence(record.sequence_handle, soft_mask)
+            };
+
+        let pair_parameters = generate_pair_command.pair_parameters()?;
+        if generate_pair_command.num_pairs == 1 {
+            generate_single_pair(
+                &ancestor_source,
+                &pair_parameters,
+                &generate_pair_command,
+                None,
+            )?;
+        } else {
+            info!("Generating {} pairs...", generate_pair_command.num_pairs);
+            let thread_pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(generate_pair_command.threads)
+                .build()?;
+            let progress_bar = progress_bar(
+                generate_pair_command.progress,
+                generate_pair_command.num_pairs,
+                "pairs",
+            );
+            thread_pool.install(|| {
+                (0..generate_pair_command.num_pairs)
+                    .into_par_iter()
+                    .try_for_each(|pair_index| -> Result<()> {
+                        generate_single_pair(
+                            &ancestor_source,
+                            &pair_parameters,
+                            &generate_pair_command,
+                            Some(pair_index),
+                        )?;
+                        progress_bar.inc(1);
+                        Ok(())
+                    })
+            })?;
+            progress_bar.finish_and_clear();
+        }
+
+        if let Some(manifest_output) = &generate_pair_command.manifest_output {
+            Manifest::new(&generate_pair_command, n, AlphabetType::into_cli_alphabet())?
+                .write_json_file(manifest_output)?;
+        }
+        Ok(())
+    }
+}
+
+fn generate_sequences(generate_command: GenerateCommand) -> Result<()> {
+    generate_command.verify()?;
+    let (n, alphabet, input) = read_model_file(&generate_command.model)?;
+
+    generate_command.n_gram_generation_parameters.verify_n(n)?;
+    // A continuation does not start with a generated n-gram.
+    if generate_command.continue_from.is_none() && generate_command.length < n {
         return Err(Error::LengthLowerThanN {
-            length: generate_pair_command.ancestor_length,
+            length: generate_command.length,
             n,
         });
     }
 
-    call::<GeneratePair>(alphabet, n, (input, generate_pair_command))
+    call::<GenerateSequences>(alphabet, n, (input, generate_command))
 }
 
-struct GeneratePair;
+struct GenerateSequences;
 
-impl ChooseAlphabetAndN for GeneratePair {
-    type Arguments = (BufReader<File>, GeneratePairCommand);
+impl ChooseAlphabetAndN for GenerateSequences {
+    type Arguments = (ModelInput, GenerateCommand);
 
     type Return = ();
 
@@ -145,99 +691,1372 @@ impl ChooseAlphabetAndN for GeneratePair {
             + for<'de> Deserialize<'de>,
         AlphabetType: 'static + Alphabet + IntoCliAlphabet,
     >(
-        (input, generate_pair_command): Self::Arguments,
+        (input, generate_command): Self::Arguments,
     ) -> Result<Self::Return>
     where
         [u32; ALPHABET_SIZE]: Serialize + for<'de> Deserialize<'de>,
     {
-        // Load model.
         let model: NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType> =
-            ciborium::from_reader(input)?;
-
-        // Initialise random number generator.
-        let mut rng = Xoshiro256PlusPlus::seed_from_u64(generate_pair_command.random_seed);
-
-        // Generate ancestor.
-        let ancestor: DefaultGenome<_> =
-            model.generate_sequence(generate_pair_command.ancestor_length, &mut rng)?;
-        let ancestor = if let Some(ancestor_output) = &generate_pair_command.ancestor_output {
-            let records = [FastaRecord {
-                id: "ancestor".to_string(),
-                comment: String::new(),
-                sequence_handle: ancestor,
-            }];
-            write_fasta_file(ancestor_output, &records, &HandleSequenceStore::new())?;
-            let [ancestor] = records;
-            ancestor.sequence_handle
-        } else {
-            ancestor
-        };
+            input.read_model(generate_command.realistic_starts)?;
+        let interpolation_weights = &generate_command
+            .n_gram_generation_parameters
+            .interpolation_weights;
+        let interpolation = (!interpolation_weights.is_empty())
+            .then(|| Interpolation::new(&model, interpolation_weights));
+        let prefix: Option<Vec<_>> = generate_command
+            .continue_from
+            .as_ref()
+            .map(|continue_from| -> Result<_> {
+                info!("Loading sequence to continue from...");
+                let sequence = FastaSequenceReader::<AlphabetType, _>::open(
+                    continue_from,
+                    true,
+                    None,
+                    UnknownCharacters::Error,
+                )?
+                .next()
+                .ok_or(Error::EmptyContinuationInput)??;
+                Ok(sequence.iter().cloned().collect())
+            })
+            .transpose()?;
 
-        // Derive reference and query from ancestor.
-        let mut reference = ancestor.clone();
-        let mut query = ancestor.clone();
-
-        // Create sequence modifiers.
-        let SequenceModifierPair {
-            mut reference_modifier,
-            mut query_modifier,
-        } = SequenceModifier::new_modifier_pair(
-            generate_pair_command.reference_ancestry_fraction,
-            generate_pair_command.sequence_modification_amount,
-            generate_pair_command.sequence_modification_parameters,
-            &mut rng,
-        );
+        info!("Generating {} sequences...", generate_command.num_sequences);
+        let records = (0..generate_command.num_sequences)
+            .progress_with(progress_bar(
+                generate_command.progress,
+                generate_command.num_sequences,
+                "sequences",
+            ))
+            .map(|index| {
+                // Same random stream as the ancestor of the pair with the same index.
+                let mut rng = RandomStream::Ancestor.rng(
+                    generate_command.random_seed,
+                    index,
+                    generate_command.rng,
+                );
+                let sequence: DefaultGenome<_> = if let Some(prefix) = &prefix {
+                    let continuation: DefaultGenome<_> = model.continue_sequence(
+                        prefix,
+                        generate_command.length,
+                        interpolation.as_ref(),
+                        generate_command
+                            .n_gram_generation_parameters
+                            .restart_limit(),
+                        &mut rng,
+                        |_| {},
+                    )?;
+                    if generate_command.concatenate {
+                        prefix
+                            .iter()
+                            .cloned()
+                            .chain(continuation.iter().cloned())
+                            .collect()
+                    } else {
+                        continuation
+                    }
+                } else {
+                    let sequence: DefaultGenome<_> = model.generate_sequence(
+                        generate_command.length,
+                        interpolation.as_ref(),
+                        generate_command.realistic_starts,
+                        generate_command
+                            .n_gram_generation_parameters
+                            .restart_limit(),
+                        &mut rng,
+                        |_| {},
+                    )?;
+                    if generate_command.circular {
+                        let mut characters: Vec<_> = sequence.iter().cloned().collect();
+                        model.close_circle(&mut characters, &mut rng)?;
+                        characters.into_iter().collect()
+                    } else {
+                        sequence
+                    }
+                };
+                Ok(FastaRecord {
+                    id: generate_command.record_id(index),
+                    comment: String::new(),
+                    sequence_handle: sequence,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        // Create debug file if requested.
-        let mut debug_file = if let Some(debug_output) = &generate_pair_command.debug_output {
-            Some(BufWriter::new(File::create(debug_output)?))
-        } else {
-            None
-        };
+        write_fasta_file(
+            &generate_command.output,
+            &records,
+            None,
+            generate_command.line_width,
+        )
+    }
+}
 
-        // Create overlap detector.
-        let mut template_switch_overlap_detector = TemplateSwitchOverlapDetector::new(
-            &generate_pair_command.sequence_modification_parameters,
+/// Prints the modifications of reference and query.
+///
+/// The lines of a pair are printed at once, so pairs generated in parallel are not interleaved.
+fn print_modifications(
+    modifications: [(&str, &[SequenceModification]); 2],
+    pair_index: Option<usize>,
+) -> Result<()> {
+    let mut output = Vec::new();
+    if let Some(pair_index) = pair_index {
+        writeln!(output, "# Pair {pair_index}")?;
+    }
+    for (id, modifications) in modifications {
+        writeln!(output, ">{id}")?;
+        for modification in modifications {
+            writeln!(output, "{modification}")?;
+        }
+    }
+
+    std::io::stdout().lock().write_all(&output)?;
+    Ok(())
+}
+
+/// Generates a single pair and writes it to the output files.
+///
+/// If `pair_index` is given, then the index is inserted into the names of the output files.
+/// The pair itself is generated by [`generate_pair`], so each pair of a batch is independent of the thread that generates it,
+/// and the first pair of a batch is the same as a single pair generated with the same seed.
+fn generate_single_pair<
+    const N: usize,
+    const ALPHABET_SIZE: usize,
+    BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    AlphabetType: 'static + Alphabet + IntoCliAlphabet,
+>(
+    ancestor_source: &AncestorSource<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+    pair_parameters: &PairParameters,
+    generate_pair_command: &GeneratePairCommand,
+    pair_index: Option<usize>,
+) -> Result<()> {
+    // Multiple pairs are generated in parallel, so the progress of their ancestors is not reported.
+    let progress_bar = progress_bar(
+        generate_pair_command.progress && pair_index.is_none(),
+        0,
+        "characters",
+    );
+    let generated_pair = generate_pair(
+        ancestor_source,
+        pair_parameters,
+        pair_index.unwrap_or(0),
+        |attempt| generate_pair_command.pair_rngs(pair_index.unwrap_or(0), attempt),
+        |generated, ancestor_length| {
+            progress_bar.set_length(ancestor_length as u64);
+            progress_bar.set_position(generated as u64);
+        },
+    );
+    progress_bar.finish_and_clear();
+    let GeneratedPair {
+        ancestor,
+        ancestor_length,
+        reference,
+        mut query,
+        reference_modifications,
+        query_modifications,
+        ancestries,
+        mut soft_masks,
+        substitutions,
+    } = generated_pair?;
+
+    let [reference_id, query_id, ancestor_id] = generate_pair_command.record_ids(pair_index);
+
+    // Order and map the listed modifications, keeping the applied order and positions for the other outputs.
+    let [listed_reference_modifications, listed_query_modifications] =
+        [&reference_modifications, &query_modifications].map(|modifications| {
+            list_modifications(
+                ancestor_length,
+                modifications,
+                generate_pair_command.modification_frame,
+                generate_pair_command.sort_modifications,
+            )
+        });
+
+    if generate_pair_command.dry_run {
+        return print_modifications(
+            [
+                (&reference_id, &listed_reference_modifications),
+                (&query_id, &listed_query_modifications),
+            ],
+            pair_index,
         );
+    }
 
-        // Modify reference.
-        reference_modifier.apply(
-            &mut reference,
-            &mut template_switch_overlap_detector,
-            &mut rng,
+    // Describe parameters for fasta comments.
+    let comment = if generate_pair_command.no_comment {
+        String::new()
+    } else {
+        generate_pair_command.fasta_comment(
+            matches!(ancestor_source, AncestorSource::Model { .. }).then_some(N),
+            AlphabetType::into_cli_alphabet(),
+            ancestor_length,
+        )
+    };
+
+    let ancestor = if let Some(ancestor_output) = &generate_pair_command.ancestor_output {
+        let records = [FastaRecord {
+            id: ancestor_id.clone(),
+            comment: comment.clone(),
+            sequence_handle: ancestor.expect("the ancestor is retained if it is written"),
+        }];
+        write_fasta_file(
+            pair_output_path(ancestor_output, pair_index),
+            &records,
+            soft_masks
+                .as_ref()
+                .map(|[ancestor_soft_mask, ..]| slice::from_ref(ancestor_soft_mask)),
+            generate_pair_command.line_width,
         )?;
-        if let Some(debug_file) = &mut debug_file {
-            writeln!(debug_file, "Reference Modifications")?;
-            template_switch_overlap_detector.write_modifications(debug_file)?;
+        let [ancestor] = records;
+        Some(ancestor.sequence_handle)
+    } else {
+        ancestor
+    };
+
+    // Write debug file if requested.
+    if let Some(debug_output) = &generate_pair_command.debug_output {
+        let mut debug_file =
+            BufWriter::new(File::create(pair_output_path(debug_output, pair_index))?);
+        writeln!(debug_file, "Reference Modifications")?;
+        for modification in listed_reference_modifications.iter() {
+            writeln!(debug_file, "{modification}")?;
+        }
+        writeln!(debug_file, "\nQuery Modifications")?;
+        for modification in listed_query_modifications.iter() {
+            writeln!(debug_file, "{modification}")?;
         }
-        template_switch_overlap_detector.clear_modification_stack();
+        debug_file.flush()?;
+    }
 
-        // Modify query.
-        query_modifier.apply(&mut query, &mut template_switch_overlap_detector, &mut rng)?;
-        if let Some(debug_file) = &mut debug_file {
-            writeln!(debug_file, "\nQuery Modifications")?;
-            template_switch_overlap_detector.write_modifications(debug_file)?;
+    // Write alignment.
+    if let Some(paf_output) = &generate_pair_command.paf_output {
+        let [reference_ancestry, query_ancestry] = ancestries
+            .as_ref()
+            .expect("ancestry is tracked if an alignment is requested");
+        match generate_pair_command.alignment_reference {
+            AlignmentReference::Reference => {
+                let alignment =
+                    Alignment::compose(&reference, reference_ancestry, &query, query_ancestry);
+                write_paf_file(
+                    pair_output_path(paf_output, pair_index),
+                    &[PafRecord {
+                        query_name: &query_id,
+                        query_length: query.len(),
+                        query_is_reverse_complement: generate_pair_command.query_reverse_complement,
+                        target_name: &reference_id,
+                        target_length: reference.len(),
+                        alignment: &alignment,
+                    }],
+                )?;
+            }
+            AlignmentReference::Ancestor => {
+                let ancestor = ancestor
+                    .as_ref()
+                    .expect("the ancestor is retained if an alignment against it is requested");
+                let ancestor_ancestry = Ancestry::new(ancestor.len());
+                let alignments = [
+                    Alignment::compose(
+                        ancestor,
+                        &ancestor_ancestry,
+                        &reference,
+                        reference_ancestry,
+                    ),
+                    Alignment::compose(ancestor, &ancestor_ancestry, &query, query_ancestry),
+                ];
+                write_paf_file(
+                    pair_output_path(paf_output, pair_index),
+                    &[
+                        PafRecord {
+                            query_name: &reference_id,
+                            query_length: reference.len(),
+                            query_is_reverse_complement: false,
+                            target_name: &ancestor_id,
+                            target_length: ancestor.len(),
+                            alignment: &alignments[0],
+                        },
+                        PafRecord {
+                            query_name: &query_id,
+                            query_length: query.len(),
+                            query_is_reverse_complement: generate_pair_command
+                                .query_reverse_complement,
+                            target_name: &ancestor_id,
+                            target_length: ancestor.len(),
+                            alignment: &alignments[1],
+                        },
+                    ],
+                )?;
+            }
         }
-        drop(debug_file);
+    }
 
-        // Write sequences.
-        write_fasta_file(
-            &generate_pair_command.output,
-            &[
-                FastaRecord {
-                    id: "reference".to_string(),
-                    comment: String::new(),
-                    sequence_handle: reference,
+    // Write multiple sequence alignment.
+    if let Some(msa_output) = &generate_pair_command.msa_output {
+        let [reference_ancestry, query_ancestry] = ancestries
+            .as_ref()
+            .expect("ancestry is tracked if an alignment is requested");
+        let msa = MultipleSequenceAlignment::compose(
+            ancestor
+                .as_ref()
+                .expect("the ancestor is retained if an alignment is requested"),
+            &reference,
+            reference_ancestry,
+            &query,
+            query_ancestry,
+        );
+        msa.write_fasta_file(
+            pair_output_path(msa_output, pair_index),
+            [&ancestor_id, &reference_id, &query_id],
+            generate_pair_command.line_width,
+        )?;
+    }
+
+    // Write alignments against the ancestor.
+    for (cigar_output, derived, derived_ancestry) in [
+        (
+            &generate_pair_command.ancestor_reference_cigar,
+            &reference,
+            ancestries
+                .as_ref()
+                .map(|[reference_ancestry, _]| reference_ancestry),
+        ),
+        (
+            &generate_pair_command.ancestor_query_cigar,
+            &query,
+            ancestries
+                .as_ref()
+                .map(|[_, query_ancestry]| query_ancestry),
+        ),
+    ] {
+        if let Some(cigar_output) = cigar_output {
+            let derived_ancestry =
+                derived_ancestry.expect("ancestry is tracked if an alignment is requested");
+            let ancestor = ancestor
+                .as_ref()
+                .expect("the ancestor is retained if an alignment is requested");
+            let cigar = ancestor_cigar(ancestor, derived, derived_ancestry);
+            let mut cigar_file = File::create(pair_output_path(cigar_output, pair_index))?;
+            writeln!(cigar_file, "{cigar}")?;
+        }
+    }
+
+    // Write template switches.
+    if let Some(template_switch_json) = &generate_pair_command.template_switch_json {
+        let records = template_switch_records(
+            ancestor_length,
+            [
+                (
+                    DerivedSequence::Reference,
+                    &reference_id,
+                    &reference_modifications,
+                ),
+                (DerivedSequence::Query, &query_id, &query_modifications),
+            ],
+        );
+        write_template_switch_json(pair_output_path(template_switch_json, pair_index), &records)?;
+    }
+
+    // Write modifications as GFF3 features.
+    if let Some(gff3_output) = &generate_pair_command.gff3_output {
+        write_gff3_file(
+            pair_output_path(gff3_output, pair_index),
+            [
+                AnnotatedSequence {
+                    id: &reference_id,
+                    length: reference.len(),
+                    is_reverse_complement: false,
+                    modifications: &reference_modifications,
                 },
-                FastaRecord {
-                    id: "query".to_string(),
-                    comment: String::new(),
-                    sequence_handle: query,
+                AnnotatedSequence {
+                    id: &query_id,
+                    length: query.len(),
+                    is_reverse_complement: generate_pair_command.query_reverse_complement,
+                    modifications: &query_modifications,
                 },
             ],
-            &HandleSequenceStore::new(),
         )?;
+    }
 
-        Ok(())
+    // The origin of each character, in the orientation of the output.
+    let origins = generate_pair_command.provenance_output.is_some().then(|| {
+        let reference_origins = provenance(ancestor_length, &reference_modifications);
+        let mut query_origins = provenance(ancestor_length, &query_modifications);
+        if generate_pair_command.query_reverse_complement {
+            query_origins.reverse();
+        }
+        [reference_origins, query_origins]
+    });
+
+    // Write divergence.
+    if let Some(stats_output) = &generate_pair_command.stats_output {
+        let to_ascii = |sequence: &DefaultGenome<AlphabetType>| -> Vec<u8> {
+            sequence
+                .iter()
+                .map(|character| AlphabetType::character_to_ascii(character.clone()))
+                .collect()
+        };
+        let divergence = Divergence::compute(&to_ascii(&reference), &to_ascii(&query));
+        info!(
+            "Realized divergence: edit distance {}, identity {:.4}",
+            divergence.edit_distance,
+            divergence.identity()
+        );
+        PairStats::new(
+            divergence,
+            reference_modifications.iter().chain(&query_modifications),
+        )
+        .write_tsv_file(pair_output_path(stats_output, pair_index))?;
+    }
+
+    // Write mutation spectrum.
+    if let Some(spectrum_output) = &generate_pair_command.spectrum_output {
+        let [reference_substitutions, query_substitutions] =
+            substitutions.expect("substitutions are recorded if a spectrum is requested");
+        Spectrum::new(
+            reference_modifications.iter().chain(&query_modifications),
+            reference_substitutions
+                .into_iter()
+                .chain(query_substitutions),
+        )
+        .write_file(pair_output_path(spectrum_output, pair_index))?;
+    }
+
+    // Write sequences.
+    if generate_pair_command.query_reverse_complement {
+        query = query.reverse_complement_iter().collect();
+        if let Some([_, _, query_soft_mask]) = &mut soft_masks {
+            query_soft_mask.reverse();
+        }
+    }
+    let mut records: Vec<_> = ancestor
+        .map(|ancestor| FastaRecord {
+            id: ancestor_id,
+            comment: comment.clone(),
+            sequence_handle: ancestor,
+        })
+        .into_iter()
+        .collect();
+    records.extend([
+        FastaRecord {
+            id: reference_id,
+            comment: comment.clone(),
+            sequence_handle: reference,
+        },
+        FastaRecord {
+            id: query_id,
+            comment,
+            sequence_handle: query,
+        },
+    ]);
+    // The fragments of reference and query, if the output is fragmented.
+    let fragments = if let (Some(fragment_length), Some(fragment_count)) = (
+        generate_pair_command.fragment_length,
+        generate_pair_command.fragment_count,
+    ) {
+        let mut rng = RandomStream::Fragments.rng(
+            generate_pair_command.random_seed,
+            pair_index.unwrap_or(0),
+            generate_pair_command.rng,
+        );
+        Some(
+            records[records.len() - 2..]
+                .iter()
+                .map(|record| {
+                    sample_fragments(
+                        &record.id,
+                        record.sequence_handle.len(),
+                        fragment_length,
+                        fragment_count,
+                        &mut rng,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?,
+        )
+    } else {
+        None
+    };
+
+    // Write the origin of each character, with one record per record of the output.
+    if let (Some(provenance_output), Some(origins)) =
+        (&generate_pair_command.provenance_output, &origins)
+    {
+        let pair_records = &records[records.len() - 2..];
+        let provenance_records: Vec<(String, &[Origin])> = if let Some(fragments) = &fragments {
+            pair_records
+                .iter()
+                .zip(origins)
+                .zip(fragments)
+                .flat_map(|((record, origins), fragments)| {
+                    fragments.iter().map(|fragment| {
+                        (
+                            fragment_id(&record.id, fragment),
+                            &origins[fragment.clone()],
+                        )
+                    })
+                })
+                .collect()
+        } else {
+            pair_records
+                .iter()
+                .zip(origins)
+                .map(|(record, origins)| (record.id.clone(), origins.as_slice()))
+                .collect()
+        };
+        write_provenance_file(
+            pair_output_path(provenance_output, pair_index),
+            provenance_records
+                .iter()
+                .map(|(id, origins)| (id.as_str(), *origins)),
+            generate_pair_command.line_width,
+        )?;
+    }
+
+    if let Some(output) = &generate_pair_command.output {
+        if let Some(fragments) = &fragments {
+            let mut fragment_records = Vec::new();
+            let mut fragment_soft_masks = Vec::new();
+            for ((record_index, record), fragments) in records
+                .iter()
+                .enumerate()
+                .skip(records.len() - 2)
+                .zip(fragments)
+            {
+                for fragment in fragments {
+                    if let Some(soft_masks) = &soft_masks {
+                        let soft_mask = &soft_masks[record_index + 3 - records.len()];
+                        fragment_soft_masks.push(SoftMask::new(
+                            fragment
+                                .clone()
+                                .map(|position| soft_mask.is_lowercase(position))
+                                .collect(),
+                        ));
+                    }
+                    fragment_records.push(FastaRecord {
+                        id: fragment_id(&record.id, fragment),
+                        comment: record.comment.clone(),
+                        sequence_handle: record.sequence_handle[fragment.clone()]
+                            .iter()
+                            .cloned()
+                            .collect::<DefaultGenome<AlphabetType>>(),
+                    });
+                }
+            }
+            write_fasta_file(
+                pair_output_path(output, pair_index),
+                &fragment_records,
+                soft_masks.is_some().then_some(&fragment_soft_masks[..]),
+                generate_pair_command.line_width,
+            )?;
+        } else {
+            write_fasta_file(
+                pair_output_path(output, pair_index),
+                &records[records.len() - 2..],
+                soft_masks.as_ref().map(|soft_masks| &soft_masks[1..]),
+                generate_pair_command.line_width,
+            )?;
+        }
+    }
+    if let Some(combined_output) = &generate_pair_command.combined_output {
+        assert_eq!(
+            records.len(),
+            3,
+            "the ancestor is retained if the combined output is requested"
+        );
+        write_fasta_file(
+            pair_output_path(combined_output, pair_index),
+            &records,
+            soft_masks.as_ref().map(|soft_masks| &soft_masks[..]),
+            generate_pair_command.line_width,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The path of an output file of the pair with the given index.
+///
+/// The index is inserted before the extension, e.g. `pair.fa` becomes `pair.3.fa`.
+/// If no index is given, then the path is returned unchanged.
+fn pair_output_path(path: &Path, pair_index: Option<usize>) -> PathBuf {
+    let Some(pair_index) = pair_index else {
+        return path.to_path_buf();
+    };
+
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{pair_index}"));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::OsStr,
+        fs, iter,
+        path::{Path, PathBuf},
+    };
+
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    use crate::{
+        cli::Cli,
+        error::{Error, Result},
+        manifest::FileHash,
+        run,
+    };
+
+    /// Runs tsgen on the test files, with a unique temporary directory for the outputs that is removed when the test ends.
+    struct TestRun {
+        output_directory: TempDir,
+    }
+
+    impl TestRun {
+        fn new() -> Self {
+            Self {
+                output_directory: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        /// The path of a file in the `test_files` directory.
+        fn test_file(&self, path: impl AsRef<Path>) -> PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("test_files")
+                .join(path)
+        }
+
+        /// The path of a file in the output directory.
+        fn output(&self, path: impl AsRef<Path>) -> PathBuf {
+            self.output_directory.path().join(path)
+        }
+
+        /// Runs tsgen with the given arguments, which do not include the name of the program.
+        fn run(&self, arguments: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Result<()> {
+            run(Cli::parse_from(
+                iter::once(OsStr::new("tsgen").to_os_string()).chain(
+                    arguments
+                        .into_iter()
+                        .map(|argument| argument.as_ref().into()),
+                ),
+            ))
+        }
+    }
+
+    /// Generates a pair with a fixed seed and compares it against a golden file.
+    ///
+    /// The output must be identical on all platforms.
+    /// If it changes intentionally, then the golden file needs to be regenerated.
+    #[test]
+    fn golden_pair() {
+        let test = TestRun::new();
+        let model = test.output("simple.1000.model");
+        let output = test.output("pair.fa");
+
+        test.run([
+            "create-n-gram-model".as_ref(),
+            "--input-fasta".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--n-gram-context-length".as_ref(),
+            "3".as_ref(),
+            "--output".as_ref(),
+            model.as_os_str(),
+        ])
+        .unwrap();
+        test.run([
+            "generate-pair".as_ref(),
+            "--model".as_ref(),
+            model.as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--ancestor-length".as_ref(),
+            "1000".as_ref(),
+            "--random-seed".as_ref(),
+            "42".as_ref(),
+            "--line-width".as_ref(),
+            "80".as_ref(),
+            "--no-comment".as_ref(),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(output).unwrap(),
+            fs::read_to_string(test.test_file("golden").join("pair.fa")).unwrap(),
+        );
+    }
+
+    #[test]
+    fn compressed_model() {
+        let test = TestRun::new();
+
+        let outputs = ["simple.model", "simple.model.gz"].map(|model_name| {
+            let model = test.output(model_name);
+            let output = test.output(format!("{model_name}.fa"));
+            test.run([
+                "create-n-gram-model".as_ref(),
+                "--input-fasta".as_ref(),
+                test.test_file("simple.1000.fa").as_os_str(),
+                "--n-gram-context-length".as_ref(),
+                "3".as_ref(),
+                "--output".as_ref(),
+                model.as_os_str(),
+            ])
+            .unwrap();
+            test.run([
+                "generate-pair".as_ref(),
+                "--model".as_ref(),
+                model.as_os_str(),
+                "--output".as_ref(),
+                output.as_os_str(),
+                "--ancestor-length".as_ref(),
+                "1000".as_ref(),
+            ])
+            .unwrap();
+            (
+                fs::read(model).unwrap(),
+                fs::read_to_string(output).unwrap(),
+            )
+        });
+
+        let [(model, output), (compressed_model, compressed_output)] = outputs;
+        assert_eq!(&compressed_model[..2], [0x1f, 0x8b]);
+        assert!(compressed_model.len() < model.len());
+        assert_eq!(compressed_output, output);
+    }
+
+    #[test]
+    fn json_model() {
+        let test = TestRun::new();
+
+        let outputs = ["cbor", "json"].map(|model_format| {
+            let model = test.output(format!("simple.{model_format}.model"));
+            let output = test.output(format!("simple.{model_format}.fa"));
+            test.run([
+                "create-n-gram-model".as_ref(),
+                "--input-fasta".as_ref(),
+                test.test_file("simple.1000.fa").as_os_str(),
+                "--n-gram-context-length".as_ref(),
+                "3".as_ref(),
+                "--model-format".as_ref(),
+                model_format.as_ref(),
+                "--output".as_ref(),
+                model.as_os_str(),
+            ])
+            .unwrap();
+            test.run(["validate".as_ref(), "--model".as_ref(), model.as_os_str()])
+                .unwrap();
+            test.run([
+                "generate-pair".as_ref(),
+                "--model".as_ref(),
+                model.as_os_str(),
+                "--output".as_ref(),
+                output.as_os_str(),
+                "--ancestor-length".as_ref(),
+                "1000".as_ref(),
+                "--realistic-starts".as_ref(),
+            ])
+            .unwrap();
+            (
+                fs::read(model).unwrap(),
+                fs::read_to_string(output).unwrap(),
+            )
+        });
+
+        let [(_, cbor_output), (json_model, json_output)] = outputs;
+        let json_model: serde_json::Value = serde_json::from_slice(&json_model).unwrap();
+        assert_eq!(json_model["n"], 3);
+        assert_eq!(json_model["alphabet"], "dna");
+        assert!(json_model["n-grams"]["AAA"].is_array());
+        assert_eq!(json_output, cbor_output);
+    }
+
+    #[test]
+    fn generate_sequences() {
+        let test = TestRun::new();
+        let model = test.output("simple.model");
+        let output = test.output("sequences.fa");
+
+        test.run([
+            "create-n-gram-model".as_ref(),
+            "--input-fasta".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--n-gram-context-length".as_ref(),
+            "3".as_ref(),
+            "--output".as_ref(),
+            model.as_os_str(),
+        ])
+        .unwrap();
+        let generate = |id: &str| {
+            test.run([
+                "generate".as_ref(),
+                "--model".as_ref(),
+                model.as_os_str(),
+                "--output".as_ref(),
+                output.as_os_str(),
+                "--length".as_ref(),
+                "500".as_ref(),
+                "--num-sequences".as_ref(),
+                "2".as_ref(),
+                "--id".as_ref(),
+                id.as_ref(),
+            ])
+            .map(|()| fs::read_to_string(&output).unwrap())
+        };
+
+        assert!(matches!(
+            generate("sequence"),
+            Err(Error::DuplicateRecordId(_))
+        ));
+        let sequences = generate("seq{index}").unwrap();
+        assert_eq!(sequences, generate("seq{index}").unwrap());
+        let lines: Vec<_> = sequences.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], ">seq0");
+        assert_eq!(lines[2], ">seq1");
+        assert_eq!(lines[1].len(), 500);
+        assert_ne!(lines[1], lines[3]);
+
+        // Each sequence is the ancestor of the pair with the same index.
+        test.run([
+            "generate-pair".as_ref(),
+            "--model".as_ref(),
+            model.as_os_str(),
+            "--output".as_ref(),
+            test.output("pair.fa").as_os_str(),
+            "--ancestor-output".as_ref(),
+            test.output("ancestor.fa").as_os_str(),
+            "--ancestor-length".as_ref(),
+            "500".as_ref(),
+            "--num-pairs".as_ref(),
+            "2".as_ref(),
+        ])
+        .unwrap();
+        for index in 0..2 {
+            let ancestor = fs::read_to_string(test.output(format!("ancestor.{index}.fa"))).unwrap();
+            assert_eq!(ancestor.lines().nth(1).unwrap(), lines[2 * index + 1]);
+        }
+
+        // Circular sequences only redraw their last n characters.
+        test.run([
+            "generate".as_ref(),
+            "--model".as_ref(),
+            model.as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--length".as_ref(),
+            "500".as_ref(),
+            "--num-sequences".as_ref(),
+            "2".as_ref(),
+            "--id".as_ref(),
+            "seq{index}".as_ref(),
+            "--circular".as_ref(),
+        ])
+        .unwrap();
+        let circular_sequences = fs::read_to_string(&output).unwrap();
+        for (line, circular_line) in lines
+            .iter()
+            .zip(circular_sequences.lines())
+            .skip(1)
+            .step_by(2)
+        {
+            assert_eq!(circular_line.len(), 500);
+            assert_eq!(circular_line[..497], line[..497]);
+        }
+    }
+
+    #[test]
+    fn alignment_reference() {
+        let test = TestRun::new();
+        let paf = test.output("pair.paf");
+
+        let records = |alignment_reference: &str| {
+            test.run([
+                "generate-pair".as_ref(),
+                "--ancestor-input".as_ref(),
+                test.test_file("simple.1000.fa").as_os_str(),
+                "--output".as_ref(),
+                test.output("pair.fa").as_os_str(),
+                "--template-switch-amount".as_ref(),
+                "0".as_ref(),
+                "--gap-amount".as_ref(),
+                "0".as_ref(),
+                "--substitution-amount".as_ref(),
+                "2".as_ref(),
+                "--paf-output".as_ref(),
+                paf.as_os_str(),
+                "--alignment-reference".as_ref(),
+                alignment_reference.as_ref(),
+            ])
+            .unwrap();
+            fs::read_to_string(&paf)
+                .unwrap()
+                .lines()
+                .map(|line| line.split('\t').map(str::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        // The substitutions are split evenly, so reference and query each have one.
+        let records_against_reference = records("reference");
+        assert_eq!(records_against_reference.len(), 1);
+        let record = &records_against_reference[0];
+        assert_eq!([&record[0], &record[5]], ["query", "reference"]);
+        assert_eq!(record[13], "NM:i:2");
+
+        let records_against_ancestor = records("ancestor");
+        assert_eq!(records_against_ancestor.len(), 2);
+        for (record, query_name) in records_against_ancestor.iter().zip(["reference", "query"]) {
+            assert_eq!([&record[0], &record[5]], [query_name, "ancestor"]);
+            assert_eq!(record[13], "NM:i:1");
+            assert_eq!(record[14].matches('X').count(), 1);
+        }
+    }
+
+    #[test]
+    fn fragments() {
+        let test = TestRun::new();
+        let output = test.output("fragments.fa");
+        let combined_output = test.output("combined.fa");
+        let provenance_output = test.output("provenance.txt");
+
+        test.run([
+            "generate-pair".as_ref(),
+            "--ancestor-input".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--combined-output".as_ref(),
+            combined_output.as_os_str(),
+            "--provenance-output".as_ref(),
+            provenance_output.as_os_str(),
+            "--query-reverse-complement".as_ref(),
+            "--fragment-length".as_ref(),
+            "100".as_ref(),
+            "--fragment-count".as_ref(),
+            "5".as_ref(),
+        ])
+        .unwrap();
+
+        // The combined output contains the full sequences.
+        let read_records = |path: &Path| {
+            let content = fs::read_to_string(path).unwrap();
+            content
+                .lines()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|record| {
+                    // The header contains the parameters as comment after the id.
+                    let id = record[0][1..].split_whitespace().next().unwrap();
+                    (id.to_string(), record[1].to_string())
+                })
+                .collect::<Vec<_>>()
+        };
+        let sequences = read_records(&combined_output);
+        let fragments = read_records(&output);
+        assert_eq!(fragments.len(), 10);
+        for (index, (id, fragment)) in fragments.iter().enumerate() {
+            let (sequence_id, coordinates) = id.split_once(':').unwrap();
+            assert_eq!(sequence_id, if index < 5 { "reference" } else { "query" });
+            let (start, end) = coordinates.split_once('-').unwrap();
+            let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+            let sequence = &sequences
+                .iter()
+                .find(|(id, _)| id == sequence_id)
+                .unwrap()
+                .1;
+            assert!(1 <= start && end <= sequence.len());
+            assert_eq!(fragment, &sequence[start - 1..end]);
+        }
+
+        // The provenance has a record of the same length for each fragment.
+        assert_eq!(
+            read_provenance_lengths(&provenance_output),
+            fragments
+                .iter()
+                .map(|(id, fragment)| (id.clone(), fragment.len()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Returns the id and the number of codes of each record of a provenance file.
+    fn read_provenance_lengths(path: &Path) -> Vec<(String, usize)> {
+        let mut records: Vec<(String, usize)> = Vec::new();
+        for line in fs::read_to_string(path).unwrap().lines() {
+            if let Some(id) = line.strip_prefix('>') {
+                records.push((id.to_string(), 0));
+            } else {
+                records.last_mut().unwrap().1 += line.split(' ').count();
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn provenance() {
+        let test = TestRun::new();
+        let output = test.output("pair.fa");
+        let provenance_output = test.output("provenance.txt");
+
+        test.run([
+            "generate-pair".as_ref(),
+            "--ancestor-input".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--provenance-output".as_ref(),
+            provenance_output.as_os_str(),
+            "--query-reverse-complement".as_ref(),
+            "--line-width".as_ref(),
+            "60".as_ref(),
+        ])
+        .unwrap();
+
+        let content = fs::read_to_string(&output).unwrap();
+        let mut records: Vec<(String, usize)> = Vec::new();
+        for line in content.lines() {
+            if let Some(header) = line.strip_prefix('>') {
+                let id = header.split_whitespace().next().unwrap();
+                records.push((id.to_string(), 0));
+            } else {
+                records.last_mut().unwrap().1 += line.len();
+            }
+        }
+        assert_eq!(records.len(), 2);
+        assert_eq!(read_provenance_lengths(&provenance_output), records);
+    }
+
+    #[test]
+    fn sweep() {
+        let test = TestRun::new();
+        let output_directory = test.output_directory.path();
+        let stats_output = test.output("stats.tsv");
+
+        test.run([
+            "sweep".as_ref(),
+            "--output-directory".as_ref(),
+            output_directory.as_os_str(),
+            "--parameter".as_ref(),
+            "substitution-amount=0,20".as_ref(),
+            "--parameter".as_ref(),
+            "reference-ancestry-fraction=0.5".as_ref(),
+            "--".as_ref(),
+            "--ancestor-input".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--template-switch-amount".as_ref(),
+            "0".as_ref(),
+            "--gap-amount".as_ref(),
+            "0".as_ref(),
+            "--stats-output".as_ref(),
+            stats_output.as_os_str(),
+            "--no-comment".as_ref(),
+        ])
+        .unwrap();
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(test.output("sweep.json")).unwrap()).unwrap();
+        let combinations = manifest["combinations"].as_array().unwrap();
+        assert_eq!(combinations.len(), 2);
+        assert_ne!(
+            combinations[0]["random-seed"],
+            combinations[1]["random-seed"]
+        );
+        for (index, (combination, substitution_amount)) in
+            combinations.iter().zip(["0", "20"]).enumerate()
+        {
+            assert_eq!(combination["index"], index);
+            assert_eq!(
+                combination["parameters"]["substitution-amount"],
+                substitution_amount
+            );
+            assert_eq!(
+                combination["parameters"]["reference-ancestry-fraction"],
+                "0.5"
+            );
+            let output = output_directory
+                .join(format!("combination{index}"))
+                .join("pair.fa");
+            assert_eq!(combination["outputs"][0]["path"], output.to_str().unwrap());
+            assert!(output.exists());
+
+            // The other outputs are moved into the directory of the combination as well.
+            let stats_output = output_directory
+                .join(format!("combination{index}"))
+                .join("stats.tsv");
+            assert_eq!(
+                combination["outputs"][1]["path"],
+                stats_output.to_str().unwrap()
+            );
+            assert_eq!(
+                combination["outputs"][1]["sha256"],
+                FileHash::new(&stats_output).unwrap().sha256
+            );
+        }
+        assert!(!stats_output.exists());
+
+        // Without substitutions, reference and query equal the ancestor.
+        let pair = fs::read_to_string(test.output("combination0/pair.fa")).unwrap();
+        let lines: Vec<_> = pair.lines().collect();
+        assert_eq!(lines[1], lines[3]);
+        let pair = fs::read_to_string(test.output("combination1/pair.fa")).unwrap();
+        let lines: Vec<_> = pair.lines().collect();
+        assert_ne!(lines[1], lines[3]);
+    }
+
+    #[test]
+    fn manifest() {
+        let test = TestRun::new();
+        let manifest = test.output("manifest.json");
+
+        let generate = || {
+            test.run([
+                "generate-pair".as_ref(),
+                "--ancestor-input".as_ref(),
+                test.test_file("simple.1000.fa").as_os_str(),
+                "--output".as_ref(),
+                test.output("pair.fa").as_os_str(),
+                "--stats-output".as_ref(),
+                test.output("stats.tsv").as_os_str(),
+                "--num-pairs".as_ref(),
+                "2".as_ref(),
+                "--manifest-output".as_ref(),
+                manifest.as_os_str(),
+            ])
+            .unwrap();
+            fs::read_to_string(&manifest).unwrap()
+        };
+
+        let first = generate();
+        assert_eq!(generate(), first);
+
+        let manifest: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest["rng"], "xoshiro256");
+        assert_eq!(manifest["parameters"]["num-pairs"], 2);
+        let outputs = manifest["outputs"].as_array().unwrap();
+        assert_eq!(outputs.len(), 4);
+        for (output, file_name) in
+            outputs
+                .iter()
+                .zip(["pair.0.fa", "stats.0.tsv", "pair.1.fa", "stats.1.tsv"])
+        {
+            let path = test.output(file_name);
+            assert_eq!(output["path"], path.to_str().unwrap());
+            assert_eq!(output["sha256"], FileHash::new(path).unwrap().sha256);
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let test = TestRun::new();
+        let model = test.output("simple.1000.model");
+        let truncated_model = test.output("truncated.model");
+
+        test.run([
+            "create-n-gram-model".as_ref(),
+            "--input-fasta".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--n-gram-context-length".as_ref(),
+            "3".as_ref(),
+            "--output".as_ref(),
+            model.as_os_str(),
+        ])
+        .unwrap();
+        test.run(["validate".as_ref(), "--model".as_ref(), model.as_os_str()])
+            .unwrap();
+
+        let mut bytes = fs::read(&model).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        fs::write(&truncated_model, bytes).unwrap();
+        assert!(test
+            .run([
+                "validate".as_ref(),
+                "--model".as_ref(),
+                truncated_model.as_os_str(),
+            ])
+            .is_err());
+    }
+
+    /// Additional random draws of one stream do not change the randomness of the others.
+    #[test]
+    fn independent_random_streams() {
+        let test = TestRun::new();
+
+        let pairs = [&[][..], &["--max-edit-distance", "1000000"]].map(|arguments| {
+            let output = test.output("pair.fa");
+            test.run(
+                [
+                    "generate-pair".as_ref(),
+                    "--ancestor-input".as_ref(),
+                    test.test_file("simple.1000.fa").as_os_str(),
+                    "--output".as_ref(),
+                    output.as_os_str(),
+                    "--random-seed".as_ref(),
+                    "42".as_ref(),
+                    "--no-comment".as_ref(),
+                ]
+                .into_iter()
+                .chain(arguments.iter().map(AsRef::as_ref)),
+            )
+            .unwrap();
+            fs::read_to_string(output).unwrap()
+        });
+        // The budget draws its split from the split stream, after the amounts of each type.
+        assert_eq!(pairs[0], pairs[1]);
+    }
+
+    /// Generates batches of pairs with different numbers of threads and checks that they are identical.
+    #[test]
+    fn batch_is_independent_of_threads() {
+        let test = TestRun::new();
+        let model = test.output("simple.1000.model");
+
+        test.run([
+            "create-n-gram-model".as_ref(),
+            "--input-fasta".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--n-gram-context-length".as_ref(),
+            "3".as_ref(),
+            "--output".as_ref(),
+            model.as_os_str(),
+        ])
+        .unwrap();
+
+        for threads in ["1", "4"] {
+            test.run([
+                "generate-pair".as_ref(),
+                "--model".as_ref(),
+                model.as_os_str(),
+                "--output".as_ref(),
+                test.output(format!("pair.t{threads}.fa")).as_os_str(),
+                "--ancestor-length".as_ref(),
+                "1000".as_ref(),
+                "--random-seed".as_ref(),
+                "42".as_ref(),
+                "--num-pairs".as_ref(),
+                "8".as_ref(),
+                "--threads".as_ref(),
+                threads.as_ref(),
+            ])
+            .unwrap();
+        }
+
+        let pairs: Vec<_> = (0..8)
+            .map(|pair_index| {
+                let single_threaded =
+                    fs::read_to_string(test.output(format!("pair.t1.{pair_index}.fa"))).unwrap();
+                let multi_threaded =
+                    fs::read_to_string(test.output(format!("pair.t4.{pair_index}.fa"))).unwrap();
+                assert_eq!(single_threaded, multi_threaded);
+                single_threaded
+            })
+            .collect();
+        assert!(pairs[1..].iter().all(|pair| *pair != pairs[0]));
+    }
+
+    #[test]
+    fn target_gc() {
+        let test = TestRun::new();
+        let model = test.output("simple.1000.model");
+        let ancestor_output = test.output("ancestor.fa");
+
+        test.run([
+            "create-n-gram-model".as_ref(),
+            "--input-fasta".as_ref(),
+            test.test_file("simple.1000.fa").as_os_str(),
+            "--n-gram-context-length".as_ref(),
+            "3".as_ref(),
+            "--output".as_ref(),
+            model.as_os_str(),
+        ])
+        .unwrap();
+
+        // The GC content of the training sequences is about 0.14.
+        for seed in ["0", "1", "2"] {
+            test.run([
+                "generate-pair".as_ref(),
+                "--model".as_ref(),
+                model.as_os_str(),
+                "--output".as_ref(),
+                test.output("pair.fa").as_os_str(),
+                "--ancestor-output".as_ref(),
+                ancestor_output.as_os_str(),
+                "--ancestor-length".as_ref(),
+                "200".as_ref(),
+                "--random-seed".as_ref(),
+                seed.as_ref(),
+                "--target-gc-min".as_ref(),
+                "0.2".as_ref(),
+                "--target-gc-max".as_ref(),
+                "0.3".as_ref(),
+            ])
+            .unwrap();
+
+            let ancestor: String = fs::read_to_string(&ancestor_output)
+                .unwrap()
+                .lines()
+                .filter(|line| !line.starts_with('>'))
+                .collect();
+            let gc = ancestor
+                .chars()
+                .filter(|character| matches!(character, 'C' | 'G'))
+                .count() as f64
+                / ancestor.len() as f64;
+            assert!((0.2..=0.3).contains(&gc), "{gc}");
+        }
+    }
+
+    #[test]
+    fn composition() {
+        let test = TestRun::new();
+        let ancestor_output = test.output("ancestor.fa");
+        let manifest = test.output("manifest.json");
+
+        test.run([
+            "generate-pair".as_ref(),
+            "--composition".as_ref(),
+            "A=0.1,C=0.2,G=0.3,T=0.4".as_ref(),
+            "--output".as_ref(),
+            test.output("pair.fa").as_os_str(),
+            "--ancestor-output".as_ref(),
+            ancestor_output.as_os_str(),
+            "--manifest-output".as_ref(),
+            manifest.as_os_str(),
+            "--ancestor-length".as_ref(),
+            "20000".as_ref(),
+        ])
+        .unwrap();
+
+        let ancestor: String = fs::read_to_string(&ancestor_output)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with('>'))
+            .collect();
+        assert_eq!(ancestor.len(), 20000);
+        for (character, frequency) in [('A', 0.1), ('C', 0.2), ('G', 0.3), ('T', 0.4)] {
+            let count = ancestor.chars().filter(|c| *c == character).count();
+            assert!((count as f64 / ancestor.len() as f64 - frequency).abs() < 0.02);
+        }
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(manifest["n"], 0);
+        assert!(manifest["input"].is_null());
+        assert_eq!(
+            manifest["parameters"]["composition"],
+            "A=0.1,C=0.2,G=0.3,T=0.4"
+        );
+
+        // The characters must match the alphabet.
+        assert!(matches!(
+            test.run([
+                "generate-pair",
+                "--composition",
+                "A=0.5,G=0.5",
+                "--ancestor-alphabet",
+                "purine-pyrimidine",
+                "--dry-run",
+                "--ancestor-length",
+                "100",
+            ]),
+            Err(Error::CompositionAlphabetMismatch(_))
+        ));
     }
 }
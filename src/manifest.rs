@@ -0,0 +1,122 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use toml::Table;
+
+use crate::{
+    cli::{resolved_config, CliAlphabet, GeneratePairCommand},
+    error::{Error, Result},
+    random_stream::RngAlgorithm,
+};
+
+/// A record of a run of the generate-pair subcommand that ties its parameters to the files it read and wrote.
+///
+/// The manifest contains no timestamps or other properties of the environment,
+/// so repeating a run with the same command line reproduces it exactly.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    pub version: &'static str,
+    pub command_line: Vec<String>,
+    /// The `n` of the model, or `None` if the ancestor is loaded from a file.
+    pub n: Option<usize>,
+    pub alphabet: CliAlphabet,
+    pub random_seed: u64,
+    pub rng: RngAlgorithm,
+    /// The parameters as printed by `--print-parameters`.
+    pub parameters: Table,
+    /// The model or the ancestor input file, or `None` if the model was built from a composition.
+    pub input: Option<FileHash>,
+    /// The written output files, in the order of [`GeneratePairCommand::output_paths`].
+    pub outputs: Vec<FileHash>,
+}
+
+/// The SHA-256 hash of a file.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct FileHash {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Creates the manifest of a run after all its outputs were written.
+    pub fn new(
+        generate_pair_command: &GeneratePairCommand,
+        n: Option<usize>,
+        alphabet: CliAlphabet,
+    ) -> Result<Self> {
+        let input = generate_pair_command
+            .model
+            .as_ref()
+            .or(generate_pair_command.ancestor_input.as_ref());
+        if input.is_none() && generate_pair_command.composition.is_none() {
+            return Err(Error::MissingAncestorSource);
+        }
+
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION"),
+            command_line: std::env::args_os()
+                .map(|argument| argument.to_string_lossy().into_owned())
+                .collect(),
+            n,
+            alphabet,
+            random_seed: generate_pair_command.random_seed,
+            rng: generate_pair_command.rng,
+            parameters: toml::from_str(&resolved_config(generate_pair_command)?)?,
+            input: input.map(FileHash::new).transpose()?,
+            outputs: generate_pair_command
+                .output_paths()
+                .into_iter()
+                .map(FileHash::new)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn write_json_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl FileHash {
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut reader = File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut reader, &mut hasher)?;
+
+        let mut sha256 = String::new();
+        for byte in hasher.finalize() {
+            write!(sha256, "{byte:02x}").unwrap();
+        }
+        Ok(Self { path, sha256 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::FileHash;
+
+    #[test]
+    fn file_hash() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("file-hash.txt");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            FileHash::new(&path).unwrap().sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
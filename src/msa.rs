@@ -0,0 +1,232 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+
+use crate::{
+    error::Result,
+    sequence_modifier::ancestry::{Ancestry, CharacterOrigin},
+};
+
+/// The gap character of the rows.
+const GAP: u8 = b'-';
+
+/// The true multiple sequence alignment of the ancestor, the reference and the query.
+///
+/// Characters that descend from the same ancestral character are aligned in the same column.
+/// Inserted characters get their own columns, with the inserted characters of the reference before those of the query.
+/// Characters copied by template switches are written in lower case.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MultipleSequenceAlignment {
+    /// The aligned rows of ancestor, reference and query, in this order.
+    rows: [Vec<u8>; 3],
+}
+
+impl MultipleSequenceAlignment {
+    pub fn compose<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        ancestor: &SequenceType,
+        reference: &SequenceType,
+        reference_ancestry: &Ancestry,
+        query: &SequenceType,
+        query_ancestry: &Ancestry,
+    ) -> Self {
+        let ancestor: Vec<_> = ancestor
+            .iter()
+            .map(|character| AlphabetType::character_to_ascii(character.clone()))
+            .collect();
+        let mut derived = [
+            Derived::new(reference, reference_ancestry),
+            Derived::new(query, query_ancestry),
+        ];
+        let mut rows = [Vec::new(), Vec::new(), Vec::new()];
+
+        for ancestor_position in 0..=ancestor.len() {
+            // Inserted characters come before the next ancestral character.
+            for (derived_index, derived) in derived.iter_mut().enumerate() {
+                while let Some(character) = derived.next_inserted() {
+                    for (row_index, row) in rows.iter_mut().enumerate() {
+                        row.push(if row_index == derived_index + 1 {
+                            character
+                        } else {
+                            GAP
+                        });
+                    }
+                }
+            }
+
+            if let Some(&character) = ancestor.get(ancestor_position) {
+                rows[0].push(character);
+                for (derived, row) in derived.iter_mut().zip(&mut rows[1..]) {
+                    row.push(derived.next_ancestral(ancestor_position).unwrap_or(GAP));
+                }
+            }
+        }
+
+        debug_assert!(derived.iter().all(Derived::is_finished));
+        Self { rows }
+    }
+
+    /// Writes the alignment as aligned fasta file with the given ids for ancestor, reference and query.
+    ///
+    /// If `line_width` is zero, then each row is written on a single line.
+    pub fn write_fasta_file(
+        &self,
+        path: impl AsRef<Path>,
+        ids: [&str; 3],
+        line_width: usize,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_fasta(&mut writer, ids, line_width)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_fasta(&self, mut writer: impl Write, ids: [&str; 3], line_width: usize) -> Result<()> {
+        for (id, row) in ids.into_iter().zip(&self.rows) {
+            writeln!(writer, ">{id}")?;
+            if row.is_empty() {
+                writeln!(writer)?;
+                continue;
+            }
+            for line in row.chunks(if line_width == 0 {
+                row.len()
+            } else {
+                line_width
+            }) {
+                writer.write_all(line)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A derived sequence that is consumed column by column.
+struct Derived<'ancestry> {
+    characters: Vec<u8>,
+    origins: &'ancestry [CharacterOrigin],
+    index: usize,
+}
+
+impl<'ancestry> Derived<'ancestry> {
+    fn new<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequence: &SequenceType,
+        ancestry: &'ancestry Ancestry,
+    ) -> Self {
+        debug_assert_eq!(sequence.len(), ancestry.origins().len());
+        Self {
+            characters: sequence
+                .iter()
+                .map(|character| AlphabetType::character_to_ascii(character.clone()))
+                .collect(),
+            origins: ancestry.origins(),
+            index: 0,
+        }
+    }
+
+    /// Consumes the next character if it is inserted.
+    fn next_inserted(&mut self) -> Option<u8> {
+        let origin = self.origins.get(self.index)?;
+        if !origin.is_inserted() {
+            return None;
+        }
+
+        let character = self.characters[self.index];
+        self.index += 1;
+        Some(if *origin == CharacterOrigin::TemplateSwitch {
+            character.to_ascii_lowercase()
+        } else {
+            character
+        })
+    }
+
+    /// Consumes the next character if it descends from the given ancestral position.
+    fn next_ancestral(&mut self, ancestor_position: usize) -> Option<u8> {
+        if self.origins.get(self.index)? == &(CharacterOrigin::Ancestral { ancestor_position }) {
+            self.index += 1;
+            Some(self.characters[self.index - 1])
+        } else {
+            None
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.index == self.characters.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::OwnedGenomeSequence,
+    };
+    use traitsequence::interface::Sequence;
+
+    use crate::sequence_modifier::{ancestry::Ancestry, SequenceModification};
+
+    use super::MultipleSequenceAlignment;
+
+    #[test]
+    fn compose() {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AACCGGTTAC").unwrap();
+        let mut reference = ancestor.clone();
+        let mut reference_ancestry = Ancestry::new(ancestor.len());
+        let mut query = ancestor.clone();
+        let mut query_ancestry = Ancestry::new(ancestor.len());
+
+        for modification in [
+            SequenceModification::Substitution {
+                position: 0,
+                character_increment: 1,
+            },
+            SequenceModification::Deletion {
+                position: 4,
+                length: 2,
+            },
+            SequenceModification::TemplateSwitch {
+                position: 5,
+                length: 2,
+                offset: -2,
+                length_difference: 0,
+                inter_sequence: false,
+            },
+        ] {
+            let sibling = query.clone();
+            modification.apply(&mut reference, &sibling).unwrap();
+            reference_ancestry.apply_modification(&modification);
+        }
+        let modification = SequenceModification::Insertion {
+            position: 2,
+            source: 0,
+            length: 2,
+        };
+        modification.apply(&mut query, &ancestor).unwrap();
+        query_ancestry.apply_modification(&modification);
+
+        let msa = MultipleSequenceAlignment::compose(
+            &ancestor,
+            &reference,
+            &reference_ancestry,
+            &query,
+            &query_ancestry,
+        );
+        let mut output = Vec::new();
+        msa.write_fasta(&mut output, ["a", "r", "q"], 0).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            ">a\nAA--CCGGT--TAC\n>r\nCA--CC--Tgg--C\n>q\nAAAACCGGT--TAC\n"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::{fmt::Display, str::FromStr};
+
+use compact_genome::{
+    implementation::bit_array_kmer::{BitArrayKmer, BitStore, BitView, BitViewSized},
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+use serde::{Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+use super::NGramModel;
+
+/// The abundance of a character of frequency one in a model built from a composition.
+///
+/// The frequencies are rounded to multiples of its inverse.
+const COMPOSITION_RESOLUTION: f64 = 1_000_000.0;
+
+/// The maximum deviation of the sum of the frequencies of a composition from one.
+const COMPOSITION_SUM_TOLERANCE: f64 = 0.01;
+
+/// The frequencies of the characters of an alphabet, e.g. `A=0.3,C=0.2,G=0.2,T=0.3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composition {
+    frequencies: Vec<(u8, f64)>,
+}
+
+impl Display for Composition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let frequencies: Vec<_> = self
+            .frequencies
+            .iter()
+            .map(|(character, frequency)| format!("{}={frequency}", char::from(*character)))
+            .collect();
+        write!(f, "{}", frequencies.join(","))
+    }
+}
+
+impl Serialize for Composition {
+    /// Serialises the composition in the same format as it is parsed from the command line.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for Composition {
+    type Err = Error;
+
+    /// Parses `CHARACTER=FREQUENCY,CHARACTER=FREQUENCY,...`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || Error::InvalidComposition(s.to_string());
+        let mut frequencies: Vec<(u8, f64)> = Vec::new();
+        for entry in s.split(',') {
+            let (character, frequency) = entry.split_once('=').ok_or_else(error)?;
+            let &[character] = character.trim().as_bytes() else {
+                return Err(error());
+            };
+            let frequency: f64 = frequency.trim().parse().map_err(|_| error())?;
+            if !frequency.is_finite()
+                || frequency < 0.0
+                || frequencies.iter().any(|(other, _)| *other == character)
+            {
+                return Err(error());
+            }
+            frequencies.push((character, frequency));
+        }
+
+        let sum: f64 = frequencies.iter().map(|(_, frequency)| frequency).sum();
+        if (sum - 1.0).abs() > COMPOSITION_SUM_TOLERANCE {
+            return Err(Error::CompositionSumNotOne(sum));
+        }
+        Ok(Self { frequencies })
+    }
+}
+
+impl<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    > NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>
+{
+    /// Builds a model of order zero that generates each character independently with its frequency in the composition.
+    ///
+    /// The composition must give a frequency for exactly the characters of the alphabet.
+    /// Since the model has order zero, its only start n-gram is the empty n-gram.
+    pub fn from_composition(composition: &Composition) -> Result<Self> {
+        if N != 0 {
+            return Err(Error::UnsupportedN(N));
+        }
+
+        let mut abundances = [0; ALPHABET_SIZE];
+        for (character, frequency) in &composition.frequencies {
+            let character = AlphabetType::ascii_to_character(*character)
+                .map_err(|_| Error::CompositionAlphabetMismatch(composition.to_string()))?;
+            abundances[character.index()] = (frequency * COMPOSITION_RESOLUTION).round() as u32;
+        }
+        if composition.frequencies.len() != ALPHABET_SIZE {
+            return Err(Error::CompositionAlphabetMismatch(composition.to_string()));
+        }
+
+        let mut model = Self {
+            model: Default::default(),
+            kmers: Default::default(),
+            kmer_sampler: None,
+            zero_weight_n_gram_amount: 0,
+            start_abundances: Default::default(),
+            start_kmers: Default::default(),
+            start_sampler: None,
+            unigram_sampler: None,
+        };
+        let empty_kmer = BitArrayKmer::from_iter(std::iter::empty());
+        model.model.insert(empty_kmer.clone(), abundances);
+        model.index_kmers();
+        model.set_start_abundances([(empty_kmer, 1)].into());
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::GenomeSequence,
+    };
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use crate::error::Error;
+
+    use super::{Composition, NGramModel};
+
+    #[test]
+    fn composition() {
+        let composition: Composition = "A=0.4, C=0.1,G=0.2,T=0.3".parse().unwrap();
+        assert_eq!(composition.to_string(), "A=0.4,C=0.1,G=0.2,T=0.3");
+        let model = NGramModel::<0, 4, DnaAlphabet, u8>::from_composition(&composition).unwrap();
+
+        let length = 100_000;
+        let sequence: DefaultGenome<DnaAlphabet> = model
+            .generate_sequence(
+                length,
+                None,
+                false,
+                None,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                |_| {},
+            )
+            .unwrap();
+        let sequence = sequence.as_string();
+        for (character, frequency) in [('A', 0.4), ('C', 0.1), ('G', 0.2), ('T', 0.3)] {
+            let count = sequence.chars().filter(|c| *c == character).count();
+            assert!((count as f64 / length as f64 - frequency).abs() < 0.01);
+        }
+
+        assert!(matches!(
+            "A=0.5,C=0.5,G=0.2".parse::<Composition>(),
+            Err(Error::CompositionSumNotOne(_))
+        ));
+        for invalid in ["A=0.5,A=0.5", "A=-0.5,C=1.5", "AC=1", "A:1"] {
+            assert!(matches!(
+                invalid.parse::<Composition>(),
+                Err(Error::InvalidComposition(_))
+            ));
+        }
+        for mismatch in ["A=0.5,C=0.5", "A=0.25,C=0.25,G=0.25,N=0.25"] {
+            assert!(matches!(
+                NGramModel::<0, 4, DnaAlphabet, u8>::from_composition(&mismatch.parse().unwrap()),
+                Err(Error::CompositionAlphabetMismatch(_))
+            ));
+        }
+    }
+}
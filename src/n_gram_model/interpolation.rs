@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use compact_genome::{
+    implementation::bit_array_kmer::{BitArrayKmer, BitStore, BitView, BitViewSized},
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+use rand::{
+    distributions::{Uniform, WeightedIndex},
+    prelude::Distribution,
+    Rng,
+};
+use traitsequence::interface::Sequence;
+
+use super::{successor_index, NGramModel};
+
+/// Mixes the successor distributions of the n-gram model with those of its lower orders.
+///
+/// The lower-order tables are derived from the n-gram counts of the model,
+/// by summing the successor abundances of all n-grams that end in the same shorter context.
+#[derive(Debug, Clone)]
+pub struct Interpolation<const ALPHABET_SIZE: usize> {
+    /// The weight of each order, indexed by its context length.
+    weights: Vec<f64>,
+    /// The successor abundances of each context shorter than n, indexed by the context length.
+    /// Contexts are stored as the indices of their characters.
+    tables: Vec<HashMap<Vec<u8>, [u64; ALPHABET_SIZE]>>,
+}
+
+impl<const ALPHABET_SIZE: usize> Interpolation<ALPHABET_SIZE> {
+    /// Derives the lower-order tables of `model` for the given weights.
+    ///
+    /// The weights are ordered from order n downwards, and missing weights of low orders are zero.
+    /// Tables are only derived for orders with a positive weight.
+    pub fn new<
+        const N: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    >(
+        model: &NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+        weights: &[f64],
+    ) -> Self {
+        assert!(weights.len() <= N + 1);
+        let mut weights: Vec<_> = weights.iter().rev().copied().collect();
+        weights.splice(0..0, (weights.len()..N + 1).map(|_| 0.0));
+
+        let mut tables = vec![HashMap::new(); N];
+        for (kmer, abundances) in model.iter() {
+            let context = context(kmer);
+            for (context_length, table) in tables.iter_mut().enumerate() {
+                if weights[context_length] == 0.0 {
+                    continue;
+                }
+
+                let table_abundances = table
+                    .entry(context[N - context_length..].to_vec())
+                    .or_insert([0; ALPHABET_SIZE]);
+                for (table_abundance, abundance) in table_abundances.iter_mut().zip(abundances) {
+                    *table_abundance += u64::from(*abundance);
+                }
+            }
+        }
+
+        Self { weights, tables }
+    }
+
+    /// Samples the index of the successor of `kmer` from the mixture of all orders with a positive weight.
+    ///
+    /// Orders whose context does not occur in the model or has no successor abundance are left out,
+    /// and the weights of the others are normalised.
+    /// If only one order remains, then its abundances are sampled like by the n-gram model itself,
+    /// such that the weights `1, 0, ..., 0` reproduce the n-gram model exactly.
+    /// Returns `None` if no order remains.
+    pub fn sample_successor<
+        const N: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    >(
+        &self,
+        model: &NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+        kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>,
+        rng: &mut impl Rng,
+    ) -> Option<usize> {
+        let context = context(kmer);
+        let mut orders = Vec::new();
+        for (context_length, weight) in self.weights.iter().copied().enumerate() {
+            if weight == 0.0 {
+                continue;
+            }
+
+            let abundances = if context_length == N {
+                model
+                    .successor_counts(kmer)
+                    .map(|abundances| abundances.map(u64::from))
+            } else {
+                self.tables[context_length]
+                    .get(&context[N - context_length..])
+                    .copied()
+            };
+            // Contexts without any successor abundance are treated as unseen.
+            if let Some(abundances) =
+                abundances.filter(|abundances| abundances.iter().any(|abundance| *abundance > 0))
+            {
+                orders.push((weight, abundances));
+            }
+        }
+
+        match orders.as_slice() {
+            [] => None,
+            [(_, abundances)] => {
+                // Sample like the n-gram model, unless the abundances of a lower order are too large.
+                if let Ok(sum) = u32::try_from(abundances.iter().sum::<u64>()) {
+                    let abundances = abundances.map(|abundance| abundance as u32);
+                    let sample = Uniform::new(0, sum).sample(rng);
+                    Some(successor_index(&abundances, sample))
+                } else {
+                    Some(mixture_sample(&orders, rng))
+                }
+            }
+            _ => Some(mixture_sample(&orders, rng)),
+        }
+    }
+}
+
+/// Samples from the weighted mixture of the normalised abundances.
+fn mixture_sample<const ALPHABET_SIZE: usize>(
+    orders: &[(f64, [u64; ALPHABET_SIZE])],
+    rng: &mut impl Rng,
+) -> usize {
+    let mut probabilities = [0.0; ALPHABET_SIZE];
+    for (weight, abundances) in orders {
+        let sum: u64 = abundances.iter().sum();
+        for (probability, abundance) in probabilities.iter_mut().zip(abundances) {
+            *probability += weight * *abundance as f64 / sum as f64;
+        }
+    }
+    WeightedIndex::new(probabilities).unwrap().sample(rng)
+}
+
+/// The indices of the characters of `kmer`.
+fn context<
+    const N: usize,
+    AlphabetType: Alphabet,
+    BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+>(
+    kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>,
+) -> Vec<u8> {
+    kmer.iter()
+        .map(|character| u8::try_from(character.index()).unwrap())
+        .collect()
+}
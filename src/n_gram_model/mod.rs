@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use compact_genome::{
     implementation::bit_array_kmer::{BitArrayKmer, BitStore, BitView, BitViewSized},
@@ -16,15 +16,62 @@ use rand::{
 
 use crate::error::{Error, Result};
 
+mod composition;
+mod interpolation;
 mod serde;
 
+pub use self::composition::Composition;
+pub use self::interpolation::Interpolation;
+pub use self::serde::{read_model_file, write_model_file, ModelFormat, ModelInput};
+
+/// A model of the successor of each n-gram.
+///
+/// For n = 0, the model contains only the empty n-gram,
+/// so it counts the unconditioned character frequencies and generates each character independently.
 pub struct NGramModel<
     const N: usize,
     const ALPHABET_SIZE: usize,
     AlphabetType: Alphabet,
     BitArrayType: BitViewSized + BitStore,
 > {
-    model: BTreeMap<BitArrayKmer<N, AlphabetType, BitArrayType>, [u32; ALPHABET_SIZE]>,
+    /// The abundances of the successors of each n-gram.
+    model: HashMap<BitArrayKmer<N, AlphabetType, BitArrayType>, [u32; ALPHABET_SIZE]>,
+    /// The n-grams of the model in ascending order.
+    ///
+    /// Start n-grams are sampled by their index in this vector,
+    /// which does not depend on the iteration order of the hash map.
+    kmers: Vec<BitArrayKmer<N, AlphabetType, BitArrayType>>,
+    /// Samples the index of an n-gram in `kmers` proportionally to its total successor abundance,
+    /// or `None` if the model is empty.
+    ///
+    /// It is built once together with `kmers` and reused by every generated sequence.
+    kmer_sampler: Option<WeightedIndex<u64>>,
+    /// The amount of n-grams that were removed when indexing, since all their successor abundances are zero.
+    zero_weight_n_gram_amount: usize,
+    /// The abundances of the first n-gram of each training sequence.
+    ///
+    /// This is empty for models created by older versions, which did not store it.
+    start_abundances: BTreeMap<BitArrayKmer<N, AlphabetType, BitArrayType>, u32>,
+    /// The keys of `start_abundances` in ascending order, such that a sampled start n-gram can be looked up by its index.
+    start_kmers: Vec<BitArrayKmer<N, AlphabetType, BitArrayType>>,
+    /// Samples the index of an n-gram in `start_kmers`, or `None` if there are no start n-grams.
+    start_sampler: Option<WeightedIndex<u64>>,
+    /// Samples the index of a character proportionally to its total abundance as successor of any n-gram,
+    /// or `None` if the model is empty.
+    unigram_sampler: Option<WeightedIndex<u64>>,
+}
+
+/// A bound on the number of times the generation of a sequence restarts at an n-gram without successor.
+///
+/// Without restarts and with the unigram fallback, the generation never restarts,
+/// but continues from each n-gram without successor with a character of the unigram distribution.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RestartLimit {
+    /// The maximum number of restarts per generated sequence.
+    pub max_restarts: usize,
+    /// If set, each further n-gram without successor is followed by a character drawn from the unigram distribution,
+    /// i.e. the total successor abundances of all n-grams, instead of aborting with an error.
+    pub unigram_fallback: bool,
 }
 
 impl<
@@ -34,43 +81,263 @@ impl<
         BitArrayType: BitViewSized + BitStore,
     > NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>
 {
+    /// Counts the n-grams of the given sequences.
+    ///
+    /// If `circular` is set, then the sequences are treated as circular,
+    /// i.e. the n-grams wrapping around from the end to the start of each sequence are counted as well.
     pub fn from_sequences<
         SequenceType: GenomeSequence<AlphabetType, SubsequenceType>,
         SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
     >(
         sequences: impl IntoIterator<Item = SequenceType>,
+        circular: bool,
     ) -> Self {
+        Self::from_weighted_sequences(
+            sequences.into_iter().map(|sequence| (sequence, 1)),
+            circular,
+        )
+        .expect("the unweighted n-gram counts fit into u32")
+    }
+
+    /// Creates a model like [`Self::from_sequences`], where the n-grams of each sequence are counted as often as its weight.
+    ///
+    /// Returns an error if a weighted count does not fit into a `u32`.
+    pub fn from_weighted_sequences<
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType>,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequences: impl IntoIterator<Item = (SequenceType, u32)>,
+        circular: bool,
+    ) -> Result<Self> {
         assert_eq!(ALPHABET_SIZE, AlphabetType::SIZE);
-        let mut result = Self {
+        let mut model = Self {
             model: Default::default(),
+            kmers: Default::default(),
+            kmer_sampler: None,
+            zero_weight_n_gram_amount: 0,
+            start_abundances: Default::default(),
+            start_kmers: Default::default(),
+            start_sampler: None,
+            unigram_sampler: None,
         };
 
-        for sequence in sequences {
-            for offset in 0..sequence.len() - N - 1 {
-                let kmer = BitArrayKmer::from_iter(sequence[offset..offset + N].iter().cloned());
-                let successor = sequence[offset + N].clone();
-
-                if let Some(abundances) = result.model.get_mut(&kmer) {
-                    abundances[successor.index()] =
-                        abundances[successor.index()].checked_add(1).unwrap();
-                } else {
-                    let mut abundances = [0; ALPHABET_SIZE];
-                    abundances[successor.index()] = 1;
-                    result.model.insert(kmer, abundances);
+        for (sequence, weight) in sequences {
+            let window_amount = Self::window_amount(sequence.len(), circular);
+            if window_amount > 0 {
+                let start = BitArrayKmer::from_iter(
+                    (0..N).map(|index| sequence[index % sequence.len()].clone()),
+                );
+                let abundance = model.start_abundances.entry(start).or_default();
+                *abundance = abundance
+                    .checked_add(weight)
+                    .ok_or(Error::NGramCountOverflow)?;
+            }
+            if circular {
+                let length = sequence.len();
+                for offset in 0..window_amount {
+                    let kmer = BitArrayKmer::from_iter(
+                        (offset..offset + N).map(|index| sequence[index % length].clone()),
+                    );
+                    let successor = sequence[(offset + N) % length].clone();
+                    model.add_successor(kmer, successor, weight)?;
+                }
+            } else {
+                for offset in 0..window_amount {
+                    let kmer =
+                        BitArrayKmer::from_iter(sequence[offset..offset + N].iter().cloned());
+                    let successor = sequence[offset + N].clone();
+                    model.add_successor(kmer, successor, weight)?;
                 }
             }
         }
 
-        result
+        model.index_kmers();
+        model.index_start_abundances();
+        Ok(model)
+    }
+
+    /// The number of n-grams with successor that [`Self::from_sequences`] counts in a sequence of the given length.
+    ///
+    /// For n = 0, each character is counted as successor of the empty n-gram.
+    pub fn window_amount(sequence_length: usize, circular: bool) -> usize {
+        if circular {
+            sequence_length
+        } else {
+            sequence_length.saturating_sub(N)
+        }
+    }
+
+    /// The abundances of the first n-gram of each training sequence.
+    pub fn start_abundances(&self) -> &BTreeMap<BitArrayKmer<N, AlphabetType, BitArrayType>, u32> {
+        &self.start_abundances
+    }
+
+    /// Sets the abundances of the first n-gram of each training sequence, e.g. after deserialising them.
+    pub fn set_start_abundances(
+        &mut self,
+        start_abundances: BTreeMap<BitArrayKmer<N, AlphabetType, BitArrayType>, u32>,
+    ) {
+        self.start_abundances = start_abundances;
+        self.index_start_abundances();
+    }
+
+    /// Returns true if the model contains the n-gram, i.e. if the n-gram has at least one successor.
+    pub fn contains_kmer(&self, kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>) -> bool {
+        self.model.contains_key(kmer)
     }
 
+    /// The abundances of the successors of the n-gram, indexed by character, or `None` if the model does not contain the n-gram.
+    pub fn successor_counts(
+        &self,
+        kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>,
+    ) -> Option<&[u32; ALPHABET_SIZE]> {
+        self.model.get(kmer)
+    }
+
+    /// Iterates over the n-grams of the model and the abundances of their successors, in ascending order of the n-grams.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            &BitArrayKmer<N, AlphabetType, BitArrayType>,
+            &[u32; ALPHABET_SIZE],
+        ),
+    > {
+        self.kmers.iter().map(|kmer| (kmer, &self.model[kmer]))
+    }
+
+    /// The number of n-grams with at least one successor.
+    pub fn n_gram_amount(&self) -> usize {
+        self.model.len()
+    }
+
+    /// The fraction of all possible n-grams that have at least one successor, between 0.0 and 1.0.
+    pub fn coverage(&self) -> f64 {
+        self.model.len() as f64 / libm::pow(ALPHABET_SIZE as f64, N as f64)
+    }
+
+    /// The conditional entropy of the successor in bits, i.e. the entropy of the successor distribution of each n-gram,
+    /// averaged with the n-gram abundances as weights.
+    ///
+    /// Returns zero for an empty model.
+    pub fn conditional_entropy(&self) -> f64 {
+        let mut weighted_entropy = 0.0;
+        let mut total_abundance = 0.0;
+
+        // Sum in the order of the n-grams, which does not depend on the iteration order of the hash map.
+        for (_, abundances) in self.iter() {
+            let sum = abundances
+                .iter()
+                .map(|abundance| f64::from(*abundance))
+                .sum::<f64>();
+            let entropy: f64 = abundances
+                .iter()
+                .filter(|abundance| **abundance > 0)
+                .map(|abundance| {
+                    let probability = f64::from(*abundance) / sum;
+                    -probability * libm::log2(probability)
+                })
+                .sum();
+            weighted_entropy += sum * entropy;
+            total_abundance += sum;
+        }
+
+        if total_abundance == 0.0 {
+            0.0
+        } else {
+            weighted_entropy / total_abundance
+        }
+    }
+
+    /// The distribution of the total successor abundances of the n-grams,
+    /// mapping each total abundance to the number of n-grams with that total.
+    pub fn abundance_distribution(&self) -> BTreeMap<u64, usize> {
+        let mut distribution = BTreeMap::new();
+        for abundances in self.model.values() {
+            let total = abundances
+                .iter()
+                .map(|abundance| u64::from(*abundance))
+                .sum();
+            *distribution.entry(total).or_default() += 1;
+        }
+        distribution
+    }
+
+    /// Collects the n-grams of the model in ascending order and builds their sampler.
+    ///
+    /// N-grams whose successor abundances are all zero are removed first, such that they are treated as unseen.
+    fn index_kmers(&mut self) {
+        let n_gram_amount = self.model.len();
+        self.model
+            .retain(|_, abundances| abundances.iter().any(|abundance| *abundance > 0));
+        self.zero_weight_n_gram_amount += n_gram_amount - self.model.len();
+        self.kmers = self.model.keys().cloned().collect();
+        self.kmers.sort_unstable();
+
+        // The weights are `u64` instead of `usize`, since sampling from a `usize` range depends on the pointer width.
+        // The order of the weights is the ascending order of the n-grams, which does not depend on the platform either.
+        self.kmer_sampler = WeightedIndex::new(self.kmers.iter().map(|kmer| {
+            self.model[kmer]
+                .iter()
+                .map(|abundance| u64::from(*abundance))
+                .sum::<u64>()
+        }))
+        .ok();
+
+        let mut unigram_abundances = [0u64; ALPHABET_SIZE];
+        for abundances in self.model.values() {
+            for (unigram_abundance, abundance) in unigram_abundances.iter_mut().zip(abundances) {
+                *unigram_abundance += u64::from(*abundance);
+            }
+        }
+        self.unigram_sampler = WeightedIndex::new(unigram_abundances).ok();
+    }
+
+    /// Builds the sampler of the start n-grams.
+    fn index_start_abundances(&mut self) {
+        self.start_kmers = self.start_abundances.keys().cloned().collect();
+        self.start_sampler = WeightedIndex::new(
+            self.start_abundances
+                .values()
+                .map(|abundance| u64::from(*abundance)),
+        )
+        .ok();
+    }
+
+    fn add_successor(
+        &mut self,
+        kmer: BitArrayKmer<N, AlphabetType, BitArrayType>,
+        successor: AlphabetType::CharacterType,
+        weight: u32,
+    ) -> Result<()> {
+        let abundance =
+            &mut self.model.entry(kmer).or_insert([0; ALPHABET_SIZE])[successor.index()];
+        *abundance = abundance
+            .checked_add(weight)
+            .ok_or(Error::NGramCountOverflow)?;
+        Ok(())
+    }
+
+    /// Generates a sequence of the given length.
+    ///
+    /// If `interpolation` is set, then each successor is drawn from the mixture of the orders of the model.
+    /// If `realistic_starts` is set, then the first n-gram is drawn from the first n-grams of the training sequences.
+    /// Otherwise, and whenever the generation restarts at an n-gram without successor,
+    /// the n-gram is drawn proportionally to its total successor abundance.
+    /// If `restart_limit` is set, then the generation restarts at most that many times,
+    /// and afterwards either falls back to the unigram distribution or aborts with an error, see [`RestartLimit`].
+    /// After each generated character, `progress` is called with the amount of characters generated so far.
     pub fn generate_sequence<
         SequenceType: OwnedGenomeSequence<AlphabetType, SubsequenceType>,
         SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
     >(
         &self,
         length: usize,
+        interpolation: Option<&Interpolation<ALPHABET_SIZE>>,
+        realistic_starts: bool,
+        restart_limit: Option<RestartLimit>,
         rng: &mut impl Rng,
+        progress: impl FnMut(usize),
     ) -> Result<SequenceType>
     where
         BitArrayType: BitView<Store = BitArrayType>,
@@ -79,15 +346,238 @@ impl<
             return Err(Error::LengthLowerThanN { length, n: N });
         }
 
-        let kmer_sampler = WeightedIndex::<usize>::new(self.model.values().map(|abundances| {
-            abundances
-                .iter()
-                .map(|abundance| *abundance as usize)
-                .sum::<usize>()
-        }))
-        .map_err(|_| Error::EmptyModel)?;
-        let generator = NGramSequenceGenerator::new(self, rng, kmer_sampler);
-        Ok(SequenceType::from_iter(generator.take(length)))
+        self.generate(
+            length,
+            None,
+            interpolation,
+            realistic_starts,
+            restart_limit,
+            rng,
+            progress,
+        )
+    }
+
+    /// Generates a sequence of the given length that continues `prefix`, without repeating it.
+    ///
+    /// The last n characters of `prefix` are the first n-gram, so the first character is drawn as its successor.
+    /// The n-gram must have a successor in the model, otherwise an error is returned.
+    /// Afterwards, the generation proceeds like [`Self::generate_sequence`] without realistic starts.
+    pub fn continue_sequence<
+        SequenceType: OwnedGenomeSequence<AlphabetType, SubsequenceType>,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        prefix: &[AlphabetType::CharacterType],
+        length: usize,
+        interpolation: Option<&Interpolation<ALPHABET_SIZE>>,
+        restart_limit: Option<RestartLimit>,
+        rng: &mut impl Rng,
+        progress: impl FnMut(usize),
+    ) -> Result<SequenceType>
+    where
+        BitArrayType: BitView<Store = BitArrayType>,
+    {
+        if prefix.len() < N {
+            return Err(Error::ContinuationShorterThanN {
+                length: prefix.len(),
+                n: N,
+            });
+        }
+
+        let start = BitArrayKmer::from_iter(prefix[prefix.len() - N..].iter().cloned());
+        if !self.contains_kmer(&start) {
+            return Err(Error::ContinuationNGramNotInModel(
+                prefix[prefix.len() - N..]
+                    .iter()
+                    .map(|character| {
+                        char::from(AlphabetType::character_to_ascii(character.clone()))
+                    })
+                    .collect(),
+            ));
+        }
+
+        self.generate(
+            length,
+            Some(start),
+            interpolation,
+            false,
+            restart_limit,
+            rng,
+            progress,
+        )
+    }
+
+    /// Generates a sequence of the given length, which starts after the given n-gram if `start` is set.
+    #[allow(clippy::too_many_arguments)]
+    fn generate<
+        SequenceType: OwnedGenomeSequence<AlphabetType, SubsequenceType>,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        length: usize,
+        start: Option<BitArrayKmer<N, AlphabetType, BitArrayType>>,
+        interpolation: Option<&Interpolation<ALPHABET_SIZE>>,
+        realistic_starts: bool,
+        restart_limit: Option<RestartLimit>,
+        rng: &mut impl Rng,
+        mut progress: impl FnMut(usize),
+    ) -> Result<SequenceType>
+    where
+        BitArrayType: BitView<Store = BitArrayType>,
+    {
+        let kmer_sampler =
+            self.kmer_sampler
+                .as_ref()
+                .ok_or(if self.zero_weight_n_gram_amount > 0 {
+                    Error::ZeroWeightModel(self.zero_weight_n_gram_amount)
+                } else {
+                    Error::EmptyModel
+                })?;
+        let start_sampler = realistic_starts
+            .then(|| {
+                self.start_sampler
+                    .as_ref()
+                    .ok_or(if self.start_abundances.is_empty() {
+                        Error::MissingStartNGrams
+                    } else {
+                        Error::ZeroWeightStartNGrams(self.start_abundances.len())
+                    })
+            })
+            .transpose()?;
+        let mut generator = NGramSequenceGenerator::new(
+            self,
+            interpolation,
+            rng,
+            kmer_sampler,
+            start_sampler,
+            restart_limit,
+        );
+        if let Some(start) = start {
+            // The characters of the start n-gram are part of the continued sequence, so they are not generated again.
+            generator.kmer = Some(start);
+            generator.next_index = N;
+        }
+        let sequence = SequenceType::from_iter(generator.by_ref().take(length).enumerate().map(
+            |(index, character)| {
+                progress(index + 1);
+                character
+            },
+        ));
+        if let Some(max_restarts) = generator.exceeded_max_restarts {
+            return Err(Error::MaxRestartsExceeded(max_restarts));
+        }
+        Ok(sequence)
+    }
+}
+
+impl<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    > NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>
+{
+    /// Redraws the last n characters of `sequence`, such that it can be read circularly.
+    ///
+    /// The n-grams that wrap around from the end to the start of the sequence then have the following characters as successors in the model.
+    /// The replacement is drawn proportionally to the product of the successor probabilities of the replaced characters
+    /// and of the first n characters, since the predecessors of these include replaced characters.
+    /// The probabilities are those of order n, without interpolation.
+    pub fn close_circle(
+        &self,
+        sequence: &mut [AlphabetType::CharacterType],
+        rng: &mut impl Rng,
+    ) -> Result<()> {
+        let length = sequence.len();
+        if N == 0 {
+            return Ok(());
+        }
+        if length < 2 * N {
+            return Err(Error::CircularLengthLowerThanTwiceN { length, n: N });
+        }
+
+        let mut closures = Vec::new();
+        self.collect_closures(sequence, length - N, 1.0, &mut closures);
+        let distribution = WeightedIndex::new(closures.iter().map(|(_, weight)| *weight))
+            .map_err(|_| Error::NoCircularClosure)?;
+        let (closure, _) = &closures[distribution.sample(rng)];
+        sequence[length - N..].clone_from_slice(closure);
+
+        Ok(())
+    }
+
+    /// Enumerates the replacements of the characters from `position` to the end of `sequence` with a positive weight,
+    /// see [`Self::close_circle`].
+    fn collect_closures(
+        &self,
+        sequence: &mut [AlphabetType::CharacterType],
+        position: usize,
+        weight: f64,
+        closures: &mut Vec<(Vec<AlphabetType::CharacterType>, f64)>,
+    ) {
+        let length = sequence.len();
+        if position == length {
+            let mut weight = weight;
+            for index in 0..N {
+                let context: Vec<_> = (index + length - N..index + length)
+                    .map(|context_index| sequence[context_index % length].clone())
+                    .collect();
+                weight *= self.successor_probability(&context, &sequence[index]);
+                if weight == 0.0 {
+                    return;
+                }
+            }
+            closures.push((sequence[length - N..].to_vec(), weight));
+            return;
+        }
+
+        for index in 0..ALPHABET_SIZE {
+            let character = AlphabetType::CharacterType::from_index(index).unwrap();
+            let probability =
+                self.successor_probability(&sequence[position - N..position], &character);
+            if probability > 0.0 {
+                sequence[position] = character;
+                self.collect_closures(sequence, position + 1, weight * probability, closures);
+            }
+        }
+    }
+
+    /// The probability of `successor` after the n-gram `context`, which is zero if the n-gram has no successor.
+    fn successor_probability(
+        &self,
+        context: &[AlphabetType::CharacterType],
+        successor: &AlphabetType::CharacterType,
+    ) -> f64 {
+        let kmer = BitArrayKmer::from_iter(context.iter().cloned());
+        let Some(abundances) = self.successor_counts(&kmer) else {
+            return 0.0;
+        };
+        let sum: u32 = abundances.iter().sum();
+        if sum == 0 {
+            return 0.0;
+        }
+        f64::from(abundances[successor.index()]) / f64::from(sum)
+    }
+
+    /// Samples the index of the successor of `kmer`, or returns `None` if the model contains no successor.
+    fn sample_successor(
+        &self,
+        kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>,
+        interpolation: Option<&Interpolation<ALPHABET_SIZE>>,
+        rng: &mut impl Rng,
+    ) -> Option<usize> {
+        if let Some(interpolation) = interpolation {
+            return interpolation.sample_successor(self, kmer, rng);
+        }
+
+        let abundances = self.successor_counts(kmer)?;
+        let sum: u32 = abundances.iter().cloned().sum();
+        // N-grams without any successor abundance are treated as unseen.
+        if sum == 0 {
+            return None;
+        }
+        let sample = Uniform::new(0, sum).sample(rng);
+        Some(successor_index(abundances, sample))
     }
 }
 
@@ -103,8 +593,16 @@ struct NGramSequenceGenerator<
     kmer: Option<BitArrayKmer<N, AlphabetType, BitArrayType>>,
     next_index: usize,
     model: &'model NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+    interpolation: Option<&'model Interpolation<ALPHABET_SIZE>>,
     rng: &'rng mut RandomNumberGenerator,
-    kmer_sampler: WeightedIndex<usize>,
+    kmer_sampler: &'model WeightedIndex<u64>,
+    /// Samples the index of the first n-gram in the start abundances, and is removed after its use.
+    start_sampler: Option<&'model WeightedIndex<u64>>,
+    restart_limit: Option<RestartLimit>,
+    /// The number of restarts at n-grams without successor so far.
+    restart_amount: usize,
+    /// Set to the maximum number of restarts if it was exceeded, which ends the generation.
+    exceeded_max_restarts: Option<usize>,
 }
 
 impl<
@@ -128,15 +626,23 @@ impl<
 {
     fn new(
         model: &'model NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+        interpolation: Option<&'model Interpolation<ALPHABET_SIZE>>,
         rng: &'rng mut RandomNumberGenerator,
-        kmer_sampler: WeightedIndex<usize>,
+        kmer_sampler: &'model WeightedIndex<u64>,
+        start_sampler: Option<&'model WeightedIndex<u64>>,
+        restart_limit: Option<RestartLimit>,
     ) -> Self {
         Self {
             kmer: None,
             next_index: 0,
             model,
+            interpolation,
             rng,
             kmer_sampler,
+            start_sampler,
+            restart_limit,
+            restart_amount: 0,
+            exceeded_max_restarts: None,
         }
     }
 }
@@ -166,40 +672,642 @@ impl<
                 let result = kmer[self.next_index].clone();
                 self.next_index += 1;
                 Some(result)
-            } else if let Some(abundances) = self.model.model.get(kmer) {
-                let sum: u32 = abundances.iter().cloned().sum();
-                let distribution = Uniform::new(0, sum);
-                let sample = distribution.sample(self.rng);
-
-                let mut index = usize::MAX;
-                let mut current_sum = 0;
-                for (current_index, value) in abundances.iter().cloned().enumerate() {
-                    current_sum += value;
-                    if sample < current_sum {
-                        index = current_index;
-                        break;
-                    }
-                }
-                debug_assert_ne!(index, usize::MAX);
-
+            } else if let Some(index) =
+                self.model
+                    .sample_successor(kmer, self.interpolation, self.rng)
+            {
                 let character = AlphabetType::CharacterType::from_index(index).unwrap();
-                self.kmer = Some(kmer.successor(character.clone()));
+                // The empty n-gram of a zero-order model is its own successor.
+                if N > 0 {
+                    *kmer = kmer.successor(character.clone());
+                }
                 Some(character)
+            } else if let Some(restart_limit) = self
+                .restart_limit
+                .filter(|restart_limit| self.restart_amount >= restart_limit.max_restarts)
+            {
+                if restart_limit.unigram_fallback {
+                    // The model is not empty, so it has a unigram distribution.
+                    let index = self
+                        .model
+                        .unigram_sampler
+                        .as_ref()
+                        .unwrap()
+                        .sample(self.rng);
+                    let character = AlphabetType::CharacterType::from_index(index).unwrap();
+                    *kmer = kmer.successor(character.clone());
+                    Some(character)
+                } else {
+                    self.exceeded_max_restarts = Some(restart_limit.max_restarts);
+                    None
+                }
             } else {
+                self.restart_amount += 1;
                 self.kmer = None;
                 self.next()
             }
         } else {
-            self.kmer = Some(
-                self.model
-                    .model
-                    .keys()
-                    .nth(self.kmer_sampler.sample(self.rng))
-                    .unwrap()
-                    .clone(),
-            );
+            self.kmer = Some(if let Some(start_sampler) = self.start_sampler.take() {
+                self.model.start_kmers[start_sampler.sample(self.rng)].clone()
+            } else {
+                self.model.kmers[self.kmer_sampler.sample(self.rng)].clone()
+            });
             self.next_index = 0;
             self.next()
         }
     }
 }
+
+/// Returns the index of the successor selected by `sample`, which must be lower than the sum of the `abundances`.
+///
+/// Each index owns the half-open interval `[a, a + abundances[index])` of samples,
+/// where `a` is the sum of the abundances of all lower indices.
+/// Hence the intervals are ordered by character index, and characters with zero abundance are never selected.
+///
+/// The sample is drawn from `Uniform::new(0, sum)`, so each generated character consumes one uniform sample.
+/// The uniform sample itself uses rejection sampling, which may draw more than one value from the random generator,
+/// but the number of drawn values depends only on the state of the random generator.
+/// Hence generation is reproducible for a fixed seed.
+fn successor_index(abundances: &[u32], sample: u32) -> usize {
+    let mut current_sum = 0;
+    for (index, abundance) in abundances.iter().enumerate() {
+        current_sum += abundance;
+        if sample < current_sum {
+            return index;
+        }
+    }
+    panic!("the sample {sample} is not lower than the sum of the abundances {current_sum}");
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{
+            alphabets::dna_alphabet::{DnaAlphabet, DnaCharacter},
+            bit_array_kmer::BitArrayKmer,
+            DefaultGenome,
+        },
+        interface::{
+            alphabet::AlphabetCharacter,
+            sequence::{GenomeSequence, OwnedGenomeSequence},
+        },
+    };
+
+    use std::collections::BTreeSet;
+
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use traitsequence::interface::Sequence;
+
+    use crate::{cli::CliAlphabet, error::Error};
+
+    use super::{
+        serde::{read_model_header, write_model_header},
+        successor_index, Interpolation, NGramModel, RestartLimit,
+    };
+
+    #[test]
+    fn circular() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AACGT").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence.clone()], true);
+
+        // One n-gram for each offset, including "GTA", "TAA" which wrap around.
+        assert_eq!(
+            model
+                .model
+                .values()
+                .map(|abundances| abundances.iter().sum::<u32>())
+                .sum::<u32>(),
+            5
+        );
+        for (kmer, successor) in [(b"GT", b'A'), (b"TA", b'A')] {
+            let kmer: BitArrayKmer<2, DnaAlphabet, u8> = kmer
+                .iter()
+                .map(|character| DnaCharacter::try_from(*character).unwrap())
+                .collect();
+            let successor = DnaCharacter::try_from(successor).unwrap();
+            assert_eq!(model.model.get(&kmer).unwrap()[successor.index()], 1);
+        }
+
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        assert!(model
+            .model
+            .values()
+            .flat_map(|abundances| abundances.iter())
+            .all(|abundance| *abundance <= 1));
+        assert_eq!(model.model.len(), 3);
+    }
+
+    #[test]
+    fn close_circle() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AACGT").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], true);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let characters = |sequence: &[u8]| -> Vec<_> {
+            sequence
+                .iter()
+                .map(|character| DnaCharacter::try_from(*character).unwrap())
+                .collect()
+        };
+
+        // The only successors of "TA" and "AA" that lead back into the start are "AC", i.e. the sequence repeats "AACGT".
+        let mut sequence = characters(b"AACGTAACGG");
+        model.close_circle(&mut sequence, &mut rng).unwrap();
+        assert_eq!(sequence, characters(b"AACGTAACGT"));
+
+        // The cycle of the model has length five, so a sequence of length seven cannot be circular.
+        let mut sequence = characters(b"AACGTAA");
+        assert!(matches!(
+            model.close_circle(&mut sequence, &mut rng),
+            Err(Error::NoCircularClosure)
+        ));
+        let mut sequence = characters(b"AAC");
+        assert!(matches!(
+            model.close_circle(&mut sequence, &mut rng),
+            Err(Error::CircularLengthLowerThanTwiceN { length: 3, n: 2 })
+        ));
+    }
+
+    #[test]
+    fn short_sequences() {
+        // The only n-gram "AC" has no successor.
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AC").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        assert!(model.model.is_empty());
+        assert_eq!(
+            NGramModel::<2, 4, DnaAlphabet, u8>::window_amount(2, false),
+            0
+        );
+        assert_eq!(
+            NGramModel::<2, 4, DnaAlphabet, u8>::window_amount(3, false),
+            1
+        );
+        assert_eq!(
+            NGramModel::<0, 4, DnaAlphabet, u8>::window_amount(3, false),
+            3
+        );
+        assert_eq!(
+            NGramModel::<2, 4, DnaAlphabet, u8>::window_amount(3, true),
+            3
+        );
+    }
+
+    #[test]
+    fn weighted_sequences() {
+        let sequences = [b"ACGTACGT".as_slice(), b"AACCGGTT"]
+            .map(|sequence| DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap());
+        let weighted_model = |weights: [u32; 2]| {
+            NGramModel::<2, 4, DnaAlphabet, u8>::from_weighted_sequences(
+                sequences.iter().cloned().zip(weights),
+                false,
+            )
+            .unwrap()
+        };
+
+        let model = weighted_model([1, 1]);
+        let doubled_model = weighted_model([1, 2]);
+        let second_model =
+            NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequences[1].clone()], false);
+        // Doubling the weight of the second sequence adds its counts once more.
+        for (kmer, counts) in doubled_model.iter() {
+            let expected: Vec<_> = (0..4)
+                .map(|index| {
+                    model
+                        .successor_counts(kmer)
+                        .map_or(0, |counts| counts[index])
+                        + second_model
+                            .successor_counts(kmer)
+                            .map_or(0, |counts| counts[index])
+                })
+                .collect();
+            assert_eq!(counts.as_slice(), expected);
+        }
+        assert_eq!(doubled_model.n_gram_amount(), model.n_gram_amount());
+        assert_eq!(doubled_model.start_abundances().values().sum::<u32>(), 3);
+
+        assert!(matches!(
+            NGramModel::<2, 4, DnaAlphabet, u8>::from_weighted_sequences(
+                sequences
+                    .iter()
+                    .map(|sequence| (sequence.clone(), u32::MAX)),
+                false
+            ),
+            Err(Error::NGramCountOverflow)
+        ));
+    }
+
+    #[test]
+    fn accessors() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGACTA").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let kmer = |kmer: &[u8]| -> BitArrayKmer<2, DnaAlphabet, u8> {
+            kmer.iter()
+                .map(|character| DnaCharacter::try_from(*character).unwrap())
+                .collect()
+        };
+
+        assert!(model.contains_kmer(&kmer(b"AC")));
+        assert!(!model.contains_kmer(&kmer(b"TA")));
+        assert_eq!(model.successor_counts(&kmer(b"AC")), Some(&[0, 0, 1, 1]));
+        assert_eq!(model.successor_counts(&kmer(b"TT")), None);
+        let kmers: Vec<_> = model.iter().map(|(kmer, _)| *kmer).collect();
+        assert_eq!(kmers.len(), 4);
+        assert!(kmers.windows(2).all(|kmers| kmers[0] < kmers[1]));
+        assert!(model
+            .iter()
+            .all(|(kmer, counts)| model.successor_counts(kmer) == Some(counts)));
+    }
+
+    #[test]
+    fn model_header() {
+        let mut file = Vec::new();
+        write_model_header(&mut file, 3, CliAlphabet::PurinePyrimidine).unwrap();
+        ciborium::into_writer(&0u32, &mut file).unwrap();
+        let mut reader = file.as_slice();
+        assert!(matches!(
+            read_model_header(&mut reader),
+            Ok((3, CliAlphabet::PurinePyrimidine))
+        ));
+        // The reader is positioned after the header.
+        assert_eq!(ciborium::from_reader::<u32, _>(reader).unwrap(), 0);
+
+        let mut file = Vec::new();
+        ciborium::into_writer(&3usize, &mut file).unwrap();
+        ciborium::into_writer("protein", &mut file).unwrap();
+        assert!(matches!(
+            read_model_header(file.as_slice()),
+            Err(Error::UnsupportedAlphabet(alphabet)) if alphabet == "protein"
+        ));
+    }
+
+    #[test]
+    fn zero_order() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AAAAACCCGT").unwrap();
+        let model = NGramModel::<0, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        assert_eq!(model.model.len(), 1);
+        assert_eq!(model.model.values().next().unwrap(), &[5, 3, 1, 1]);
+
+        let length = 100_000;
+        let sequence: DefaultGenome<DnaAlphabet> = model
+            .generate_sequence(
+                length,
+                None,
+                false,
+                None,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                |_| {},
+            )
+            .unwrap();
+        let mut abundances = [0; 4];
+        for character in sequence.iter() {
+            abundances[character.index()] += 1;
+        }
+        for (abundance, expected) in abundances.into_iter().zip([0.5, 0.3, 0.1, 0.1]) {
+            let frequency = abundance as f64 / length as f64;
+            assert!(
+                (frequency - expected).abs() < 0.01,
+                "{frequency} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        // "AC" is followed by "G" and "T", all other n-grams have one successor.
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGACTACG").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+
+        assert_eq!(model.n_gram_amount(), 5);
+        assert_eq!(model.coverage(), 5.0 / 16.0);
+        assert_eq!(
+            model
+                .abundance_distribution()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [(1, 4), (3, 1)]
+        );
+
+        // Only "AC" has an uncertain successor, which is "G" twice and "T" once, with a weight of 3 of 7.
+        let entropy = -(2.0 / 3.0 * (2.0f64 / 3.0).log2() + 1.0 / 3.0 * (1.0f64 / 3.0).log2());
+        assert!((model.conditional_entropy() - 3.0 / 7.0 * entropy).abs() < 1e-12);
+    }
+
+    #[test]
+    fn successor_sampling() {
+        let abundances = [2, 0, 3, 1];
+        let indices: Vec<_> = (0..6)
+            .map(|sample| successor_index(&abundances, sample))
+            .collect();
+        assert_eq!(indices, [0, 0, 2, 2, 2, 3]);
+
+        let sequences = [b"ACGTTGCAACGGATCCTA".as_slice(), b"GGATCCATTAG"]
+            .map(|sequence| DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap());
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences(sequences, false);
+        let generate = || -> DefaultGenome<DnaAlphabet> {
+            model
+                .generate_sequence(
+                    1000,
+                    None,
+                    false,
+                    None,
+                    &mut Xoshiro256PlusPlus::seed_from_u64(42),
+                    |_| {},
+                )
+                .unwrap()
+        };
+        assert_eq!(generate().as_string(), generate().as_string());
+    }
+
+    #[test]
+    fn interpolation() {
+        // The model is circular, so each n-gram has a successor and the generation never restarts.
+        let corpus = b"ACGTTGCAACGGATCCTA";
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(corpus).unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], true);
+        let generate = |interpolation: Option<&Interpolation<4>>| -> String {
+            let sequence: DefaultGenome<DnaAlphabet> = model
+                .generate_sequence(
+                    1000,
+                    interpolation,
+                    false,
+                    None,
+                    &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                    |_| {},
+                )
+                .unwrap();
+            sequence.as_string()
+        };
+        let is_corpus_trigram = |trigram: &[u8]| {
+            (0..corpus.len()).any(|offset| {
+                (0..3).all(|index| corpus[(offset + index) % corpus.len()] == trigram[index])
+            })
+        };
+
+        // Only the n-gram order reproduces the n-gram model.
+        let n_gram = generate(None);
+        assert!(n_gram.as_bytes().windows(3).all(is_corpus_trigram));
+        for weights in [[1.0].as_slice(), &[1.0, 0.0, 0.0], &[0.5]] {
+            let interpolation = Interpolation::new(&model, weights);
+            assert_eq!(generate(Some(&interpolation)), n_gram);
+        }
+
+        // The unigram order creates trigrams that do not occur in the corpus.
+        let interpolation = Interpolation::new(&model, &[0.5, 0.0, 0.5]);
+        let interpolated = generate(Some(&interpolation));
+        assert!(!interpolated.as_bytes().windows(3).all(is_corpus_trigram));
+    }
+
+    #[test]
+    fn realistic_starts() {
+        let sequences = [b"ACGTTGCA".as_slice(), b"GGATCCAT", b"ACCTGGAT"]
+            .map(|sequence| DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap());
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences(sequences, false);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut generate_start = |realistic_starts| {
+            let sequence: DefaultGenome<DnaAlphabet> = model
+                .generate_sequence(2, None, realistic_starts, None, &mut rng, |_| {})
+                .unwrap();
+            sequence.as_string()
+        };
+
+        let starts: BTreeSet<_> = (0..100).map(|_| generate_start(true)).collect();
+        assert_eq!(starts, BTreeSet::from(["AC".to_string(), "GG".to_string()]));
+        let starts: BTreeSet<_> = (0..100).map(|_| generate_start(false)).collect();
+        assert!(starts.len() > 2);
+    }
+
+    /// Generating several sequences with the cached samplers gives the same result as rebuilding them for each sequence.
+    #[test]
+    fn cached_samplers() {
+        let sequences = [b"ACGTTGCA".as_slice(), b"GGATCCAT", b"ACCTGGAT"]
+            .map(|sequence| DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap());
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences(sequences.clone(), false);
+
+        for realistic_starts in [false, true] {
+            let mut cached_rng = Xoshiro256PlusPlus::seed_from_u64(0);
+            let mut rebuilt_rng = Xoshiro256PlusPlus::seed_from_u64(0);
+            for _ in 0..10 {
+                let cached: DefaultGenome<DnaAlphabet> = model
+                    .generate_sequence(50, None, realistic_starts, None, &mut cached_rng, |_| {})
+                    .unwrap();
+                let rebuilt: DefaultGenome<DnaAlphabet> =
+                    NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences(sequences.clone(), false)
+                        .generate_sequence(
+                            50,
+                            None,
+                            realistic_starts,
+                            None,
+                            &mut rebuilt_rng,
+                            |_| {},
+                        )
+                        .unwrap();
+                assert_eq!(cached, rebuilt);
+            }
+        }
+    }
+
+    #[test]
+    fn continue_sequence() {
+        // Each n-gram has exactly one successor, so the continuation is deterministic.
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTACGT").unwrap();
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let continue_sequence = |prefix: &[u8], length| {
+            let prefix: Vec<_> = DefaultGenome::<DnaAlphabet>::from_slice_u8(prefix)
+                .unwrap()
+                .iter()
+                .cloned()
+                .collect();
+            model
+                .continue_sequence::<DefaultGenome<DnaAlphabet>, _>(
+                    &prefix,
+                    length,
+                    None,
+                    None,
+                    &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                    |_| {},
+                )
+                .map(|sequence| sequence.as_string())
+        };
+
+        assert_eq!(continue_sequence(b"GGAC", 5).unwrap(), "GTACG");
+        assert_eq!(continue_sequence(b"TA", 1).unwrap(), "C");
+        assert_eq!(continue_sequence(b"CG", 0).unwrap(), "");
+        assert!(matches!(
+            continue_sequence(b"A", 5),
+            Err(Error::ContinuationShorterThanN { length: 1, n: 2 })
+        ));
+        assert!(matches!(
+            continue_sequence(b"CAA", 5),
+            Err(Error::ContinuationNGramNotInModel(n_gram)) if n_gram == "AA"
+        ));
+    }
+
+    /// Start n-grams are sampled by index from the sorted n-grams, as they were sampled from the ordered map before.
+    #[test]
+    fn start_kmer_index() {
+        // The last n-gram "TT" of each sequence has no successor, so generation restarts frequently.
+        let sequences = [b"ACGTT".as_slice(), b"CAGGCTT", b"GATT"]
+            .map(|sequence| DefaultGenome::<DnaAlphabet>::from_slice_u8(sequence).unwrap());
+        let model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences(sequences, false);
+
+        let sorted_kmers: Vec<_> = model
+            .model
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(model.kmers, sorted_kmers);
+
+        let sequence: DefaultGenome<DnaAlphabet> = model
+            .generate_sequence(
+                30,
+                None,
+                false,
+                None,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                |_| {},
+            )
+            .unwrap();
+        assert_eq!(sequence.as_string(), "GATTAGGCTTATTGATTAGGCTTGATTACG");
+    }
+
+    #[test]
+    fn zero_sum_successors() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTT").unwrap();
+        let mut model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let n_gram_amount = model.n_gram_amount();
+        // The last n-gram "TT" has no successor, unless it is inserted with a zero-sum row.
+        let kmer: BitArrayKmer<2, DnaAlphabet, u8> = b"TT"
+            .iter()
+            .map(|character| DnaCharacter::try_from(*character).unwrap())
+            .collect();
+        model.model.insert(kmer, [0; 4]);
+
+        // Generation restarts at the zero-sum row, with and without interpolation.
+        let interpolation = Interpolation::new(&model, &[1.0, 1.0]);
+        for interpolation in [None, Some(&interpolation)] {
+            let sequence: DefaultGenome<DnaAlphabet> = model
+                .generate_sequence(
+                    100,
+                    interpolation,
+                    false,
+                    None,
+                    &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                    |_| {},
+                )
+                .unwrap();
+            assert_eq!(sequence.len(), 100);
+        }
+
+        // Indexing drops the zero-sum row.
+        model.index_kmers();
+        assert_eq!(model.n_gram_amount(), n_gram_amount);
+        assert!(!model.kmers.contains(&kmer));
+    }
+
+    #[test]
+    fn empty_and_zero_weight_models() {
+        let generate = |model: &NGramModel<2, 4, DnaAlphabet, u8>, realistic_starts| {
+            model.generate_sequence::<DefaultGenome<DnaAlphabet>, _>(
+                10,
+                None,
+                realistic_starts,
+                None,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                |_| {},
+            )
+        };
+
+        let mut model =
+            NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences::<DefaultGenome<_>, _>([], false);
+        assert!(matches!(generate(&model, false), Err(Error::EmptyModel)));
+
+        // The n-grams are kept until indexing, which removes them as unseen.
+        for n_gram in [b"AC", b"GT"] {
+            let kmer: BitArrayKmer<2, DnaAlphabet, u8> = n_gram
+                .iter()
+                .map(|character| DnaCharacter::try_from(*character).unwrap())
+                .collect();
+            model.model.insert(kmer, [0; 4]);
+        }
+        model.index_kmers();
+        assert_eq!(model.n_gram_amount(), 0);
+        assert!(matches!(
+            generate(&model, false),
+            Err(Error::ZeroWeightModel(2))
+        ));
+
+        // Start n-grams with zero abundance are distinguished from missing ones.
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTT").unwrap();
+        let mut model = NGramModel::<2, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let start_abundances = model.start_abundances().clone();
+        model.set_start_abundances(Default::default());
+        assert!(matches!(
+            generate(&model, true),
+            Err(Error::MissingStartNGrams)
+        ));
+        model.set_start_abundances(start_abundances.keys().map(|kmer| (*kmer, 0)).collect());
+        assert!(matches!(
+            generate(&model, true),
+            Err(Error::ZeroWeightStartNGrams(1))
+        ));
+        assert!(generate(&model, false).is_ok());
+    }
+
+    #[test]
+    fn restart_limit() {
+        // The only n-gram "ACGT" is followed by "A", and the n-gram "CGTA" has no successor,
+        // so the generation restarts after every five characters.
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTA").unwrap();
+        let model = NGramModel::<4, 4, DnaAlphabet, u8>::from_sequences([sequence], false);
+        let generate = |restart_limit| -> Result<DefaultGenome<DnaAlphabet>, Error> {
+            model.generate_sequence(
+                100,
+                None,
+                false,
+                restart_limit,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                |_| {},
+            )
+        };
+
+        assert_eq!(generate(None).unwrap().as_string(), "ACGTA".repeat(20));
+        assert!(matches!(
+            generate(Some(RestartLimit {
+                max_restarts: 3,
+                unigram_fallback: false,
+            })),
+            Err(Error::MaxRestartsExceeded(3))
+        ));
+        // The only character of the unigram distribution is "A".
+        assert_eq!(
+            generate(Some(RestartLimit {
+                max_restarts: 3,
+                unigram_fallback: true,
+            }))
+            .unwrap()
+            .as_string(),
+            "ACGTA".repeat(4) + &"A".repeat(80)
+        );
+        assert_eq!(
+            generate(Some(RestartLimit {
+                max_restarts: 0,
+                unigram_fallback: true,
+            }))
+            .unwrap()
+            .as_string(),
+            "ACGTA".to_string() + &"A".repeat(95)
+        );
+        // Without a fourth restart, the limit is not exceeded.
+        assert_eq!(
+            generate(Some(RestartLimit {
+                max_restarts: 19,
+                unigram_fallback: false,
+            }))
+            .unwrap()
+            .as_string(),
+            "ACGTA".repeat(20)
+        );
+    }
+}
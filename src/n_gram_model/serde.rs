@@ -1,11 +1,286 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
 use compact_genome::{
-    implementation::bit_array_kmer::{BitStore, BitViewSized},
+    implementation::bit_array_kmer::{BitArrayKmer, BitStore, BitView, BitViewSized},
     interface::alphabet::Alphabet,
 };
-use serde::{Deserialize, Serialize};
+use flate2::read::GzDecoder;
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Serialize,
+};
+
+use traitsequence::interface::Sequence;
+
+use crate::{cli::CliAlphabet, error::Error};
 
 use super::NGramModel;
 
+/// The first two bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Opens a model file for reading, gzip-decompressing it if it starts like a gzip stream.
+///
+/// An uncompressed model file never starts like this, since it starts with the small integer `n`.
+pub fn open_model_file(path: impl AsRef<Path>) -> crate::error::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Writes the `n` and the alphabet of a model, which precede the model in a model file.
+pub fn write_model_header(
+    mut writer: impl Write,
+    n: usize,
+    alphabet: CliAlphabet,
+) -> crate::error::Result<()> {
+    ciborium::into_writer(&n, &mut writer)?;
+    ciborium::into_writer(&alphabet.name(), &mut writer)?;
+    Ok(())
+}
+
+/// The format of a model file.
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ModelFormat {
+    /// The header and the model as consecutive CBOR values, which is compact.
+    Cbor,
+    /// A single JSON object with the header and the model, see [`JsonModel`].
+    Json,
+}
+
+/// A model file in JSON format, which contains the header and the model in a single object.
+///
+/// The n-grams are written as strings of their characters,
+/// and their successor abundances are ordered like the characters of the alphabet.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonModel {
+    pub n: usize,
+    pub alphabet: String,
+    pub n_grams: BTreeMap<String, Vec<u32>>,
+    pub start_n_grams: BTreeMap<String, u32>,
+}
+
+/// A model file whose header was read.
+pub enum ModelInput {
+    /// A CBOR model file, positioned at the model after the header.
+    Cbor(Box<dyn BufRead>),
+    /// A JSON model file, which is read as a whole.
+    Json(JsonModel),
+}
+
+/// Opens a model file and reads its `n` and alphabet.
+///
+/// Gzip-compressed models are decompressed, and the format is detected from the first byte,
+/// since a CBOR model starts with the small integer `n`, which is never encoded as `{`.
+pub fn read_model_file(
+    path: impl AsRef<Path>,
+) -> crate::error::Result<(usize, CliAlphabet, ModelInput)> {
+    let mut input = open_model_file(path)?;
+    if input.fill_buf()?.starts_with(b"{") {
+        let json_model: JsonModel = serde_json::from_reader(input)?;
+        let alphabet = CliAlphabet::from_str(&json_model.alphabet, false)
+            .map_err(|_| Error::UnsupportedAlphabet(json_model.alphabet.clone()))?;
+        Ok((json_model.n, alphabet, ModelInput::Json(json_model)))
+    } else {
+        let (n, alphabet) = read_model_header(&mut input)?;
+        Ok((n, alphabet, ModelInput::Cbor(input)))
+    }
+}
+
+/// Writes a model file with the given header in the given format.
+///
+/// The writer should be buffered for performance.
+pub fn write_model_file<
+    const N: usize,
+    const ALPHABET_SIZE: usize,
+    AlphabetType: Alphabet,
+    BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType> + Serialize,
+>(
+    mut writer: impl Write,
+    model: &NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>,
+    alphabet: CliAlphabet,
+    format: ModelFormat,
+) -> crate::error::Result<()>
+where
+    [u32; ALPHABET_SIZE]: Serialize,
+{
+    match format {
+        ModelFormat::Cbor => {
+            write_model_header(&mut writer, N, alphabet)?;
+            ciborium::into_writer(model, &mut writer)?;
+            ciborium::into_writer(model.start_abundances(), &mut writer)?;
+        }
+        ModelFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &model.to_json_model(alphabet))?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+impl ModelInput {
+    /// Reads the model, and its start n-grams if `read_start_abundances` is set.
+    ///
+    /// CBOR models created by older versions end before the start n-grams, so reading them fails.
+    pub fn read_model<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType> + for<'de> Deserialize<'de>,
+    >(
+        self,
+        read_start_abundances: bool,
+    ) -> crate::error::Result<NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>>
+    where
+        [u32; ALPHABET_SIZE]: for<'de> Deserialize<'de>,
+    {
+        match self {
+            ModelInput::Cbor(mut input) => {
+                let mut model: NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType> =
+                    ciborium::from_reader(&mut input)?;
+                if read_start_abundances {
+                    match ciborium::from_reader(&mut input) {
+                        Ok(start_abundances) => model.set_start_abundances(start_abundances),
+                        Err(ciborium::de::Error::Io(error))
+                            if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+                        {
+                            return Err(Error::MissingStartNGrams)
+                        }
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+                Ok(model)
+            }
+            ModelInput::Json(json_model) => {
+                let mut model = NGramModel::from_json_model(&json_model)?;
+                if !read_start_abundances {
+                    model.set_start_abundances(Default::default());
+                }
+                Ok(model)
+            }
+        }
+    }
+
+    /// Reads the structure of the model without interpreting the n-grams.
+    pub fn read_structure(self) -> crate::error::Result<NGramModelStructure> {
+        match self {
+            ModelInput::Cbor(input) => Ok(ciborium::from_reader(input)?),
+            ModelInput::Json(json_model) => Ok(NGramModelStructure {
+                n_gram_amount: json_model.n_grams.len(),
+                zero_weight_n_gram_amount: json_model
+                    .n_grams
+                    .values()
+                    .filter(|abundances| abundances.iter().all(|abundance| *abundance == 0))
+                    .count(),
+            }),
+        }
+    }
+}
+
+impl<
+        const N: usize,
+        const ALPHABET_SIZE: usize,
+        AlphabetType: Alphabet,
+        BitArrayType: BitViewSized + BitStore + BitView<Store = BitArrayType>,
+    > NGramModel<N, ALPHABET_SIZE, AlphabetType, BitArrayType>
+{
+    /// Converts the model into the JSON format, including its start n-grams.
+    pub fn to_json_model(&self, alphabet: CliAlphabet) -> JsonModel {
+        let n_gram_string = |kmer: &BitArrayKmer<N, AlphabetType, BitArrayType>| {
+            kmer.iter()
+                .map(|character| char::from(AlphabetType::character_to_ascii(character.clone())))
+                .collect()
+        };
+
+        JsonModel {
+            n: N,
+            alphabet: alphabet.name(),
+            n_grams: self
+                .iter()
+                .map(|(kmer, abundances)| (n_gram_string(kmer), abundances.to_vec()))
+                .collect(),
+            start_n_grams: self
+                .start_abundances
+                .iter()
+                .map(|(kmer, abundance)| (n_gram_string(kmer), *abundance))
+                .collect(),
+        }
+    }
+
+    /// Converts a model from the JSON format.
+    ///
+    /// The header of the JSON model is not checked, since it determines `N` and the alphabet of the caller.
+    pub fn from_json_model(json_model: &JsonModel) -> crate::error::Result<Self> {
+        let kmer = |n_gram: &String| {
+            if n_gram.len() != N {
+                return Err(Error::InvalidJsonNGram(n_gram.clone()));
+            }
+            n_gram
+                .bytes()
+                .map(|character| {
+                    AlphabetType::ascii_to_character(character)
+                        .map_err(|_| Error::InvalidJsonNGram(n_gram.clone()))
+                })
+                .collect::<crate::error::Result<Vec<_>>>()
+                .map(BitArrayKmer::from_iter)
+        };
+
+        let mut model = Self {
+            model: Default::default(),
+            kmers: Default::default(),
+            kmer_sampler: None,
+            zero_weight_n_gram_amount: 0,
+            start_abundances: Default::default(),
+            start_kmers: Default::default(),
+            start_sampler: None,
+            unigram_sampler: None,
+        };
+        for (n_gram, abundances) in &json_model.n_grams {
+            let abundances =
+                abundances
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidJsonAbundances {
+                        n_gram: n_gram.clone(),
+                        amount: abundances.len(),
+                        alphabet_size: ALPHABET_SIZE,
+                    })?;
+            model.model.insert(kmer(n_gram)?, abundances);
+        }
+        model.index_kmers();
+
+        let start_abundances = json_model
+            .start_n_grams
+            .iter()
+            .map(|(n_gram, abundance)| Ok((kmer(n_gram)?, *abundance)))
+            .collect::<crate::error::Result<_>>()?;
+        model.set_start_abundances(start_abundances);
+        Ok(model)
+    }
+}
+
+/// Reads the `n` and the alphabet of a model file written by [`write_model_header`].
+///
+/// Afterwards, the reader is positioned at the model itself,
+/// which can then be read with the `N` and alphabet of the header, or as [`NGramModelStructure`].
+pub fn read_model_header(mut reader: impl Read) -> crate::error::Result<(usize, CliAlphabet)> {
+    let n: usize = ciborium::from_reader(&mut reader)?;
+    let alphabet: String = ciborium::from_reader(&mut reader)?;
+    let cli_alphabet = CliAlphabet::from_str(&alphabet, false)
+        .map_err(|_| Error::UnsupportedAlphabet(alphabet))?;
+    Ok((n, cli_alphabet))
+}
+
 impl<
         const N: usize,
         const ALPHABET_SIZE: usize,
@@ -19,7 +294,8 @@ where
     where
         S: serde::Serializer,
     {
-        self.model.serialize(serializer)
+        // Serialise in ascending order of the n-grams, such that the output does not depend on the hash map.
+        serializer.collect_map(self.kmers.iter().map(|kmer| (kmer, &self.model[kmer])))
     }
 }
 
@@ -37,8 +313,63 @@ where
     where
         D: serde::Deserializer<'de>,
     {
-        Ok(Self {
+        let mut model = Self {
             model: Deserialize::deserialize(deserializer)?,
-        })
+            kmers: Default::default(),
+            kmer_sampler: None,
+            zero_weight_n_gram_amount: 0,
+            start_abundances: Default::default(),
+            start_kmers: Default::default(),
+            start_sampler: None,
+            unigram_sampler: None,
+        };
+        model.index_kmers();
+        Ok(model)
+    }
+}
+
+/// The structure of a serialised n-gram model, read without interpreting the n-grams.
+///
+/// This allows to check that a model is well-formed without knowing its `N` and alphabet at compile time.
+pub struct NGramModelStructure {
+    pub n_gram_amount: usize,
+    /// The amount of n-grams whose successor abundances are all zero.
+    pub zero_weight_n_gram_amount: usize,
+}
+
+impl<'de> Deserialize<'de> for NGramModelStructure {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct NGramModelStructureVisitor;
+
+        impl<'de> Visitor<'de> for NGramModelStructureVisitor {
+            type Value = NGramModelStructure;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a map from n-grams to successor counts")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut n_gram_amount = 0;
+                let mut zero_weight_n_gram_amount = 0;
+                while let Some((_, abundances)) = map.next_entry::<IgnoredAny, Vec<u32>>()? {
+                    n_gram_amount += 1;
+                    if abundances.iter().all(|abundance| *abundance == 0) {
+                        zero_weight_n_gram_amount += 1;
+                    }
+                }
+                Ok(NGramModelStructure {
+                    n_gram_amount,
+                    zero_weight_n_gram_amount,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(NGramModelStructureVisitor)
     }
 }
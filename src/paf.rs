@@ -0,0 +1,128 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{alignment::Alignment, error::Result};
+
+/// A PAF record of the alignment of a query against a target.
+pub struct PafRecord<'a> {
+    pub query_name: &'a str,
+    pub query_length: usize,
+    /// If set, then the alignment is of the reverse complement of the query.
+    /// The record is then written on the reverse strand, with the query range converted to the coordinates of the query.
+    pub query_is_reverse_complement: bool,
+    pub target_name: &'a str,
+    pub target_length: usize,
+    pub alignment: &'a Alignment,
+}
+
+/// Write a PAF file containing one line for each of the given records.
+///
+/// The alignment is stored as CIGAR string in the `cg:Z:` tag, and its edit distance in the `NM:i:` tag.
+pub fn write_paf_file(path: impl AsRef<Path>, records: &[PafRecord]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for record in records {
+        write_paf_record(&mut writer, record)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_paf_record(mut writer: impl Write, record: &PafRecord) -> Result<()> {
+    let PafRecord {
+        query_name,
+        query_length,
+        query_is_reverse_complement,
+        target_name,
+        target_length,
+        alignment,
+    } = record;
+    let mut query_range = alignment.query_range();
+    let target_range = alignment.reference_range();
+    let strand = if *query_is_reverse_complement {
+        query_range = query_length - query_range.end..query_length - query_range.start;
+        '-'
+    } else {
+        '+'
+    };
+
+    writeln!(
+        writer,
+        "{query_name}\t{query_length}\t{}\t{}\t{strand}\t{target_name}\t{target_length}\t{}\t{}\t{}\t{}\t60\ttp:A:P\tNM:i:{}\tcg:Z:{}",
+        query_range.start,
+        query_range.end,
+        target_range.start,
+        target_range.end,
+        alignment.match_amount(),
+        alignment.column_amount(),
+        alignment.edit_distance(),
+        alignment.cigar(),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::OwnedGenomeSequence,
+    };
+    use traitsequence::interface::Sequence;
+
+    use crate::{
+        alignment::Alignment,
+        sequence_modifier::{ancestry::Ancestry, SequenceModification},
+    };
+
+    use super::{write_paf_record, PafRecord};
+
+    #[test]
+    fn reverse_strand() {
+        let reference = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTACGTAC").unwrap();
+        let mut query = reference.clone();
+        let mut query_ancestry = Ancestry::new(reference.len());
+        let modification = SequenceModification::Insertion {
+            position: 0,
+            source: 5,
+            length: 2,
+        };
+        modification.apply(&mut query, &reference).unwrap();
+        query_ancestry.apply_modification(&modification);
+        let alignment = Alignment::compose(
+            &reference,
+            &Ancestry::new(reference.len()),
+            &query,
+            &query_ancestry,
+        );
+
+        for (query_is_reverse_complement, expected) in [
+            (
+                false,
+                "q\t12\t2\t12\t+\tr\t10\t0\t10\t10\t10\t60\ttp:A:P\tNM:i:0\tcg:Z:10=\n",
+            ),
+            (
+                true,
+                "q\t12\t0\t10\t-\tr\t10\t0\t10\t10\t10\t60\ttp:A:P\tNM:i:0\tcg:Z:10=\n",
+            ),
+        ] {
+            let mut output = Vec::new();
+            write_paf_record(
+                &mut output,
+                &PafRecord {
+                    query_name: "q",
+                    query_length: query.len(),
+                    query_is_reverse_complement,
+                    target_name: "r",
+                    target_length: reference.len(),
+                    alignment: &alignment,
+                },
+            )
+            .unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), expected);
+        }
+    }
+}
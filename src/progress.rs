@@ -0,0 +1,30 @@
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// Creates a progress bar over `length` items of the given unit, or a hidden progress bar if `enabled` is not set.
+///
+/// The progress bar is drawn to standard error, and only if standard error is a terminal.
+pub fn progress_bar(enabled: bool, length: usize, unit: &str) -> ProgressBar {
+    if !enabled {
+        return ProgressBar::hidden();
+    }
+
+    ProgressBar::new(length as u64).with_style(
+        ProgressStyle::with_template(&format!(
+            "{{wide_bar}} {{human_pos}}/{{human_len}} {unit} ({{eta}})"
+        ))
+        .unwrap(),
+    )
+}
+
+/// Creates a progress spinner counting items of the given unit, for when their total amount is not known in advance,
+/// or a hidden progress bar if `enabled` is not set.
+pub fn progress_spinner(enabled: bool, unit: &str) -> ProgressBar {
+    if !enabled {
+        return ProgressBar::hidden();
+    }
+
+    ProgressBar::new_spinner().with_style(
+        ProgressStyle::with_template(&format!("{{spinner}} {{human_pos}} {unit} ({{elapsed}})"))
+            .unwrap(),
+    )
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    error::Result,
+    sequence_modifier::{AppliedInterval, SequenceModification},
+};
+
+/// The origin of a character of a derived sequence.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Origin {
+    /// The character was copied from the ancestor without modification.
+    Ancestral,
+    /// The character was written by a substitution.
+    Substituted,
+    /// The character was written by an insertion.
+    Inserted,
+    /// The character was written by the template switch with the given index in the modifications of the sequence.
+    TemplateSwitch(usize),
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Ancestral => write!(f, "."),
+            Origin::Substituted => write!(f, "S"),
+            Origin::Inserted => write!(f, "I"),
+            Origin::TemplateSwitch(index) => write!(f, "T{index}"),
+        }
+    }
+}
+
+/// Returns the origin of each character of the sequence derived from an ancestor of the given length
+/// by applying the given modifications in order.
+///
+/// Each modification overwrites the origin of the characters it writes,
+/// so e.g. a substitution within a template switch marks its character as substituted.
+pub fn provenance(ancestor_length: usize, modifications: &[SequenceModification]) -> Vec<Origin> {
+    let mut origins = vec![Origin::Ancestral; ancestor_length];
+    for (index, modification) in modifications.iter().enumerate() {
+        let AppliedInterval {
+            position,
+            replaced_length,
+            inserted_length,
+        } = modification.applied_interval();
+        let origin = match modification {
+            SequenceModification::TemplateSwitch { .. } => Origin::TemplateSwitch(index),
+            SequenceModification::Insertion { .. } => Origin::Inserted,
+            SequenceModification::Deletion { .. } => Origin::Ancestral,
+            SequenceModification::Substitution { .. } => Origin::Substituted,
+        };
+        origins.splice(
+            position..position + replaced_length,
+            vec![origin; inserted_length],
+        );
+    }
+    origins
+}
+
+/// Writes the origins of the characters of the given sequences in a fasta-like format.
+///
+/// Each sequence is written as a record with its id, followed by the codes of its characters separated by spaces:
+/// `.` for ancestral characters, `S` for substituted characters, `I` for inserted characters,
+/// and `T` followed by the index of the modification for characters of a template switch.
+/// The lines are wrapped after `line_width` codes like the sequences in the fasta output, so the lines of both correspond.
+/// If `line_width` is zero, then each record is written on a single line.
+pub fn write_provenance_file<'a>(
+    path: impl AsRef<Path>,
+    sequences: impl IntoIterator<Item = (&'a str, &'a [Origin])>,
+    line_width: usize,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_provenance(&mut writer, sequences, line_width)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_provenance<'a>(
+    mut writer: impl Write,
+    sequences: impl IntoIterator<Item = (&'a str, &'a [Origin])>,
+    line_width: usize,
+) -> Result<()> {
+    for (id, origins) in sequences {
+        writeln!(writer, ">{id}")?;
+        let line_width = if line_width == 0 {
+            origins.len().max(1)
+        } else {
+            line_width
+        };
+        for line in origins.chunks(line_width) {
+            let line: Vec<_> = line.iter().map(Origin::to_string).collect();
+            writeln!(writer, "{}", line.join(" "))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::{provenance, write_provenance, Origin};
+
+    #[test]
+    fn origins() {
+        let modifications = [
+            SequenceModification::TemplateSwitch {
+                position: 2,
+                length: 4,
+                offset: 3,
+                length_difference: 1,
+                inter_sequence: false,
+            },
+            SequenceModification::Substitution {
+                position: 3,
+                character_increment: 1,
+            },
+            SequenceModification::Deletion {
+                position: 0,
+                length: 1,
+            },
+            SequenceModification::Insertion {
+                position: 6,
+                source: 0,
+                length: 2,
+            },
+        ];
+        let origins = provenance(10, &modifications);
+        assert_eq!(origins.len(), 10 + 1 - 1 + 2);
+
+        let mut output = Vec::new();
+        write_provenance(&mut output, [("r", origins.as_slice())], 5).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            ">r\n. T0 S T0 T0\n. I I . .\n. .\n"
+        );
+        assert_eq!(
+            provenance(3, &[]),
+            [Origin::Ancestral, Origin::Ancestral, Origin::Ancestral]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use clap::ValueEnum;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_pcg::Pcg64;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::Serialize;
+
+/// The algorithm of the random generators.
+///
+/// The generated pairs depend on the algorithm, so changing it changes the output for a given seed.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RngAlgorithm {
+    /// Xoshiro256++, a fast generator with good statistical properties.
+    Xoshiro256,
+    /// PCG64 (XSL-RR 128/64), a permuted congruential generator.
+    Pcg64,
+    /// ChaCha with eight rounds, a cryptographically strong generator.
+    Chacha8,
+}
+
+/// A random generator of one of the supported algorithms.
+#[derive(Debug, Clone)]
+pub enum StreamRng {
+    Xoshiro256(Xoshiro256PlusPlus),
+    Pcg64(Pcg64),
+    Chacha8(Box<ChaCha8Rng>),
+}
+
+impl RngCore for StreamRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            StreamRng::Xoshiro256(rng) => rng.next_u32(),
+            StreamRng::Pcg64(rng) => rng.next_u32(),
+            StreamRng::Chacha8(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            StreamRng::Xoshiro256(rng) => rng.next_u64(),
+            StreamRng::Pcg64(rng) => rng.next_u64(),
+            StreamRng::Chacha8(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            StreamRng::Xoshiro256(rng) => rng.fill_bytes(dest),
+            StreamRng::Pcg64(rng) => rng.fill_bytes(dest),
+            StreamRng::Chacha8(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            StreamRng::Xoshiro256(rng) => rng.try_fill_bytes(dest),
+            StreamRng::Pcg64(rng) => rng.try_fill_bytes(dest),
+            StreamRng::Chacha8(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+/// An independent stream of randomness of a pair.
+///
+/// Each step of the generation of a pair draws from its own random generator,
+/// which is seeded from the master seed, the index of the pair and the label of the stream.
+/// Hence adding or removing random draws in one step does not change the randomness of the other steps.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RandomStream {
+    /// The length and the characters of the ancestor.
+    Ancestor,
+    /// The split of the modifications between reference and query.
+    Split,
+    /// The modifications of the reference.
+    Reference,
+    /// The modifications of the query.
+    Query,
+    /// The fragments of reference and query written instead of the full sequences.
+    Fragments,
+}
+
+impl RandomStream {
+    /// The label of the stream, which is part of its seed and must never change.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RandomStream::Ancestor => "ancestor",
+            RandomStream::Split => "split",
+            RandomStream::Reference => "reference",
+            RandomStream::Query => "query",
+            RandomStream::Fragments => "fragments",
+        }
+    }
+
+    /// Creates the random generator of this stream for the pair with the given index.
+    ///
+    /// The seed of the generator is a hash of the master seed, the pair index and the label.
+    /// The hash is computed explicitly instead of with the hasher of the standard library,
+    /// whose output may change between Rust versions.
+    pub fn rng(&self, seed: u64, pair_index: usize, algorithm: RngAlgorithm) -> StreamRng {
+        seeded_rng(self.hash(seed, pair_index), algorithm)
+    }
+
+    /// Creates the random generator of the modifications of the given type in this stream,
+    /// for the pair with the given index.
+    ///
+    /// The seed is a separate seed of the modification type instead of the master seed,
+    /// and the type label, e.g. `substitution`, is hashed after the label of the stream.
+    pub fn modification_type_rng(
+        &self,
+        modification_type: &str,
+        seed: u64,
+        pair_index: usize,
+        algorithm: RngAlgorithm,
+    ) -> StreamRng {
+        let hash = fnv1a(self.hash(seed, pair_index), b"/");
+        seeded_rng(fnv1a(hash, modification_type.as_bytes()), algorithm)
+    }
+
+    fn hash(&self, seed: u64, pair_index: usize) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, &seed.to_le_bytes());
+        hash = fnv1a(hash, &(pair_index as u64).to_le_bytes());
+        fnv1a(hash, self.label().as_bytes())
+    }
+}
+
+/// The seed of the combination of parameters with the given index in a sweep.
+///
+/// Like the seeds of the streams, it is an explicit hash of the seed of the sweep and the index.
+pub fn sweep_combination_seed(seed: u64, combination_index: usize) -> u64 {
+    let hash = fnv1a(FNV_OFFSET_BASIS, &seed.to_le_bytes());
+    let hash = fnv1a(hash, &(combination_index as u64).to_le_bytes());
+    fnv1a(hash, b"sweep")
+}
+
+/// The seed that replaces the given seed when a pair is generated again,
+/// e.g. because it did not realize the required template switches.
+///
+/// The first attempt uses the given seed itself, so the retries do not change pairs that succeed at once.
+pub fn retry_seed(seed: u64, attempt: usize) -> u64 {
+    if attempt == 0 {
+        return seed;
+    }
+
+    let hash = fnv1a(FNV_OFFSET_BASIS, &seed.to_le_bytes());
+    let hash = fnv1a(hash, &(attempt as u64).to_le_bytes());
+    fnv1a(hash, b"retry")
+}
+
+/// Creates a random generator of the given algorithm from a hash.
+fn seeded_rng(hash: u64, algorithm: RngAlgorithm) -> StreamRng {
+    // Seeding from a `u64` mixes the hash with SplitMix64, so similar hashes still give unrelated generators.
+    match algorithm {
+        RngAlgorithm::Xoshiro256 => StreamRng::Xoshiro256(Xoshiro256PlusPlus::seed_from_u64(hash)),
+        RngAlgorithm::Pcg64 => StreamRng::Pcg64(Pcg64::seed_from_u64(hash)),
+        RngAlgorithm::Chacha8 => StreamRng::Chacha8(Box::new(ChaCha8Rng::seed_from_u64(hash))),
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Continues the 64-bit FNV-1a hash `hash` with `bytes`.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rand::Rng;
+
+    use clap::ValueEnum;
+
+    use super::{fnv1a, RandomStream, RngAlgorithm, FNV_OFFSET_BASIS};
+
+    #[test]
+    fn independent_streams() {
+        // Reference value of the FNV-1a specification.
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63dc4c8601ec8c);
+
+        let streams = [
+            RandomStream::Ancestor,
+            RandomStream::Split,
+            RandomStream::Reference,
+            RandomStream::Query,
+            RandomStream::Fragments,
+        ];
+        let algorithms = RngAlgorithm::value_variants();
+        let mut first_values = BTreeSet::new();
+        for seed in [0, 1] {
+            for pair_index in [0, 1] {
+                for stream in streams {
+                    for &algorithm in algorithms {
+                        let value: u64 = stream.rng(seed, pair_index, algorithm).gen();
+                        assert_eq!(value, stream.rng(seed, pair_index, algorithm).gen::<u64>());
+                        first_values.insert(value);
+                    }
+                }
+            }
+        }
+        assert_eq!(first_values.len(), 2 * 2 * streams.len() * algorithms.len());
+    }
+}
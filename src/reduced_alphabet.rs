@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+
+use clap::ValueEnum;
+use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter, AlphabetError};
+use serde::Serialize;
+
+use crate::cli::{CliAlphabet, IntoCliAlphabet};
+
+/// A scheme that collapses the characters of an alphabet into a smaller alphabet.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlphabetReduction {
+    /// Collapses the DNA purines `A` and `G` into `R`, and the pyrimidines `C` and `T` into `Y`.
+    PurinePyrimidine,
+}
+
+impl AlphabetReduction {
+    /// The alphabet of the characters that are reduced.
+    pub fn input_alphabet(&self) -> CliAlphabet {
+        match self {
+            AlphabetReduction::PurinePyrimidine => CliAlphabet::Dna,
+        }
+    }
+
+    /// The alphabet of the reduced characters.
+    pub fn reduced_alphabet(&self) -> CliAlphabet {
+        match self {
+            AlphabetReduction::PurinePyrimidine => CliAlphabet::PurinePyrimidine,
+        }
+    }
+
+    /// Maps an ASCII character of the input alphabet to the ASCII character of the reduced alphabet.
+    ///
+    /// Characters that are not part of the input alphabet are returned unchanged.
+    pub fn reduce_ascii(&self, ascii: u8) -> u8 {
+        match self {
+            AlphabetReduction::PurinePyrimidine => match ascii {
+                b'A' | b'G' => b'R',
+                b'C' | b'T' => b'Y',
+                ascii => ascii,
+            },
+        }
+    }
+}
+
+/// A character of the purine/pyrimidine alphabet: `R` or `Y`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PurinePyrimidineCharacter {
+    index: u8,
+}
+
+/// The purine/pyrimidine alphabet, consisting of the characters `R` and `Y`.
+///
+/// `R` and `Y` are each other's complement, since the complement of a purine is a pyrimidine.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct PurinePyrimidineAlphabet;
+
+static PURINE_PYRIMIDINE_CHARACTERS: [PurinePyrimidineCharacter; 2] = [
+    PurinePyrimidineCharacter { index: 0 },
+    PurinePyrimidineCharacter { index: 1 },
+];
+
+static PURINE_PYRIMIDINE_ASCII: [u8; 2] = [b'R', b'Y'];
+
+impl From<PurinePyrimidineCharacter> for u8 {
+    fn from(character: PurinePyrimidineCharacter) -> u8 {
+        PURINE_PYRIMIDINE_ASCII[usize::from(character.index)]
+    }
+}
+
+impl From<PurinePyrimidineCharacter> for char {
+    fn from(character: PurinePyrimidineCharacter) -> Self {
+        u8::from(character).into()
+    }
+}
+
+impl TryFrom<u8> for PurinePyrimidineCharacter {
+    type Error = ();
+
+    fn try_from(ascii: u8) -> Result<Self, Self::Error> {
+        match ascii {
+            b'R' => Ok(PURINE_PYRIMIDINE_CHARACTERS[0]),
+            b'Y' => Ok(PURINE_PYRIMIDINE_CHARACTERS[1]),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<char> for PurinePyrimidineCharacter {
+    type Error = ();
+
+    fn try_from(character: char) -> Result<Self, Self::Error> {
+        u8::try_from(character).map_err(|_| ())?.try_into()
+    }
+}
+
+impl Display for PurinePyrimidineCharacter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl AlphabetCharacter for PurinePyrimidineCharacter {
+    const ALPHABET_SIZE: usize = 2;
+
+    fn index(&self) -> usize {
+        usize::from(self.index)
+    }
+
+    fn from_index(index: usize) -> Result<Self, AlphabetError> {
+        Self::from_index_ref(index).copied()
+    }
+
+    fn from_index_ref(index: usize) -> Result<&'static Self, AlphabetError> {
+        PURINE_PYRIMIDINE_CHARACTERS
+            .get(index)
+            .ok_or(AlphabetError::IndexNotPartOfAlphabet { index })
+    }
+
+    fn complement(&self) -> Self {
+        PURINE_PYRIMIDINE_CHARACTERS[1 - usize::from(self.index)]
+    }
+}
+
+impl Alphabet for PurinePyrimidineAlphabet {
+    type CharacterType = PurinePyrimidineCharacter;
+}
+
+impl IntoCliAlphabet for PurinePyrimidineAlphabet {
+    fn into_cli_alphabet() -> CliAlphabet {
+        CliAlphabet::PurinePyrimidine
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::Range,
+    path::Path,
+};
+
+use rand::{seq::IteratorRandom, Rng};
+
+use crate::error::{Error, Result};
+
+use super::SequenceModification;
+
+/// The modifiable and masked intervals of a sequence, as read from BED files before the length of the sequence is known.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AllowedIntervals {
+    /// The modifiable intervals, or `None` if the whole sequence is modifiable.
+    pub modifiable: Option<Vec<Range<usize>>>,
+    pub masked: Vec<Range<usize>>,
+}
+
+impl AllowedIntervals {
+    /// Reads the intervals from BED files.
+    ///
+    /// If neither modifiable nor masked regions are given, then `None` is returned.
+    /// The chromosome column of the BED files is ignored.
+    pub fn from_bed_files(
+        modifiable_regions: Option<&Path>,
+        masked_regions: Option<&Path>,
+    ) -> Result<Option<Self>> {
+        if modifiable_regions.is_none() && masked_regions.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            modifiable: modifiable_regions.map(read_bed_file).transpose()?,
+            masked: masked_regions
+                .map(read_bed_file)
+                .transpose()?
+                .unwrap_or_default(),
+        }))
+    }
+
+    /// The allowed regions of a sequence of the given length.
+    pub fn allowed_regions(&self, sequence_length: usize) -> AllowedRegions {
+        #[allow(clippy::single_range_in_vec_init)]
+        let modifiable = self
+            .modifiable
+            .clone()
+            .unwrap_or_else(|| vec![0..sequence_length]);
+        AllowedRegions::new(modifiable, self.masked.iter().cloned(), sequence_length)
+    }
+}
+
+/// The regions of a sequence that may be modified.
+///
+/// The regions are tracked through the modifications applied to the sequence,
+/// such that they always refer to the current coordinates of the sequence.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AllowedRegions {
+    /// Sorted, non-overlapping and non-empty intervals.
+    intervals: Vec<Range<usize>>,
+}
+
+impl AllowedRegions {
+    /// Creates the allowed regions of a sequence of the given length as the modifiable intervals minus the masked intervals.
+    pub fn new(
+        modifiable: impl IntoIterator<Item = Range<usize>>,
+        masked: impl IntoIterator<Item = Range<usize>>,
+        sequence_length: usize,
+    ) -> Self {
+        let modifiable = normalise(modifiable, sequence_length);
+        let masked = normalise(masked, sequence_length);
+
+        Self {
+            intervals: subtract(modifiable, &masked),
+        }
+    }
+
+    /// Chooses a position `p` uniformly at random from `range` such that `p..p + length` lies within an allowed region.
+    ///
+    /// A `length` of zero is treated like a length of one.
+    /// Returns `None` if there is no such position.
+    pub fn choose(&self, range: Range<usize>, length: usize, rng: &mut impl Rng) -> Option<usize> {
+        let length = length.max(1);
+        let candidates: Vec<_> = self
+            .intervals
+            .iter()
+            .filter_map(|interval| {
+                let start = interval.start.max(range.start);
+                let end = (interval.end.checked_sub(length)? + 1).min(range.end);
+                (start < end).then_some(start..end)
+            })
+            .collect();
+
+        let candidate_amount: usize = candidates.iter().map(ExactSizeIterator::len).sum();
+        let mut index = (0..candidate_amount).choose(rng)?;
+        for candidate in candidates {
+            if index < candidate.len() {
+                return Some(candidate.start + index);
+            }
+            index -= candidate.len();
+        }
+
+        unreachable!("index is smaller than the sum of candidate lengths")
+    }
+
+    /// Returns the parts of the given sorted ranges that lie within an allowed region.
+    pub fn intersect(&self, ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+        ranges
+            .iter()
+            .flat_map(|range| {
+                self.intervals.iter().filter_map(move |interval| {
+                    let start = interval.start.max(range.start);
+                    let end = interval.end.min(range.end);
+                    (start < end).then_some(start..end)
+                })
+            })
+            .collect()
+    }
+
+    /// Updates the regions to the coordinates of the sequence after applying the given modification.
+    pub fn apply_modification(&mut self, modification: &SequenceModification) {
+        match *modification {
+            SequenceModification::TemplateSwitch {
+                position,
+                length,
+                length_difference,
+                ..
+            } => {
+                let replaced_length = (length as isize - length_difference) as usize;
+                self.delete(position, replaced_length);
+                self.insert(position, length);
+            }
+            SequenceModification::Insertion {
+                position, length, ..
+            } => self.insert(position, length),
+            SequenceModification::Deletion { position, length } => self.delete(position, length),
+            SequenceModification::Substitution { .. } => {
+                // No coordinate change.
+            }
+        }
+    }
+
+    /// Characters inserted strictly inside a region become part of it.
+    fn insert(&mut self, position: usize, length: usize) {
+        for interval in &mut self.intervals {
+            if interval.start >= position {
+                interval.start += length;
+            }
+            if interval.end > position {
+                interval.end += length;
+            }
+        }
+    }
+
+    fn delete(&mut self, position: usize, length: usize) {
+        let map = |coordinate: usize| {
+            if coordinate <= position {
+                coordinate
+            } else if coordinate >= position + length {
+                coordinate - length
+            } else {
+                position
+            }
+        };
+
+        for interval in &mut self.intervals {
+            *interval = map(interval.start)..map(interval.end);
+        }
+        self.intervals.retain(|interval| !interval.is_empty());
+    }
+}
+
+fn read_bed_file(path: &Path) -> Result<Vec<Range<usize>>> {
+    let mut intervals = Vec::new();
+
+    for (line_index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("track")
+            || trimmed.starts_with("browser")
+        {
+            continue;
+        }
+
+        let mut columns = trimmed.split_whitespace().skip(1);
+        let (Some(Ok(start)), Some(Ok(end))) = (
+            columns.next().map(str::parse::<usize>),
+            columns.next().map(str::parse::<usize>),
+        ) else {
+            return Err(Error::InvalidBedLine {
+                line_number: line_index + 1,
+                line,
+            });
+        };
+        if start > end {
+            return Err(Error::InvalidBedLine {
+                line_number: line_index + 1,
+                line,
+            });
+        }
+
+        intervals.push(start..end);
+    }
+
+    Ok(intervals)
+}
+
+/// Sorts and merges the intervals, clamps them to the sequence length and removes empty intervals.
+fn normalise(
+    intervals: impl IntoIterator<Item = Range<usize>>,
+    sequence_length: usize,
+) -> Vec<Range<usize>> {
+    let mut intervals: Vec<_> = intervals
+        .into_iter()
+        .map(|interval| interval.start.min(sequence_length)..interval.end.min(sequence_length))
+        .filter(|interval| !interval.is_empty())
+        .collect();
+    intervals.sort_unstable_by_key(|interval| interval.start);
+
+    let mut result: Vec<Range<usize>> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        if let Some(last) = result.last_mut() {
+            if interval.start <= last.end {
+                last.end = last.end.max(interval.end);
+                continue;
+            }
+        }
+        result.push(interval);
+    }
+
+    result
+}
+
+/// Removes the masked intervals from the given intervals.
+/// Both must be normalised.
+fn subtract(intervals: Vec<Range<usize>>, masked: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+
+    for mut interval in intervals {
+        for mask in masked {
+            if mask.end <= interval.start {
+                continue;
+            }
+            if mask.start >= interval.end {
+                break;
+            }
+
+            if mask.start > interval.start {
+                result.push(interval.start..mask.start);
+            }
+            interval.start = mask.end.min(interval.end);
+        }
+
+        if !interval.is_empty() {
+            result.push(interval);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::AllowedRegions;
+
+    #[test]
+    fn new() {
+        let allowed_regions = AllowedRegions::new([30..60, 0..20, 10..25], [5..8, 40..50], 55);
+        assert_eq!(allowed_regions.intervals, [0..5, 8..25, 30..40, 50..55]);
+
+        let allowed_regions = AllowedRegions::new([0..100], [0..100], 100);
+        assert!(allowed_regions.intervals.is_empty());
+    }
+
+    #[test]
+    fn apply_modification() {
+        let mut allowed_regions = AllowedRegions::new([10..20, 30..40], [], 100);
+
+        allowed_regions.apply_modification(&SequenceModification::Insertion {
+            position: 15,
+            source: 0,
+            length: 5,
+        });
+        assert_eq!(allowed_regions.intervals, [10..25, 35..45]);
+
+        allowed_regions.apply_modification(&SequenceModification::Insertion {
+            position: 35,
+            source: 0,
+            length: 5,
+        });
+        assert_eq!(allowed_regions.intervals, [10..25, 40..50]);
+
+        allowed_regions.apply_modification(&SequenceModification::Deletion {
+            position: 20,
+            length: 25,
+        });
+        assert_eq!(allowed_regions.intervals, [10..20, 20..25]);
+
+        allowed_regions.apply_modification(&SequenceModification::TemplateSwitch {
+            position: 12,
+            length: 4,
+            offset: -2,
+            length_difference: 2,
+            inter_sequence: false,
+        });
+        assert_eq!(allowed_regions.intervals, [10..22, 22..27]);
+
+        allowed_regions.apply_modification(&SequenceModification::Deletion {
+            position: 5,
+            length: 20,
+        });
+        assert_eq!(allowed_regions.intervals, [5..7]);
+    }
+
+    #[test]
+    fn choose() {
+        let allowed_regions = AllowedRegions::new([10..20, 30..33], [], 100);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let position = allowed_regions.choose(0..100, 3, &mut rng).unwrap();
+            assert!((10..=17).contains(&position) || position == 30);
+
+            let position = allowed_regions.choose(15..100, 0, &mut rng).unwrap();
+            assert!((15..20).contains(&position) || (30..33).contains(&position));
+        }
+
+        assert_eq!(allowed_regions.choose(0..100, 11, &mut rng), None);
+        assert_eq!(allowed_regions.choose(20..30, 1, &mut rng), None);
+    }
+}
@@ -0,0 +1,427 @@
+use std::ops::Range;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::{
+    template_switch_overlap_detector::AncestorFrame, AppliedInterval, SequenceModification,
+};
+
+/// The origin of a single character of a derived sequence.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CharacterOrigin {
+    /// The character descends from the character at the given position of the ancestor.
+    /// It may have been substituted.
+    Ancestral { ancestor_position: usize },
+    /// The character was inserted by a gap.
+    Inserted,
+    /// The character was copied by a template switch.
+    TemplateSwitch,
+}
+
+impl CharacterOrigin {
+    /// Returns true if the character does not descend from the ancestor.
+    pub fn is_inserted(&self) -> bool {
+        matches!(
+            self,
+            CharacterOrigin::Inserted | CharacterOrigin::TemplateSwitch
+        )
+    }
+}
+
+/// Tracks for each character of a derived sequence where it originates from in the ancestor.
+///
+/// The ancestral positions are strictly increasing along the derived sequence,
+/// since no modification reorders ancestral characters.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ancestry {
+    origins: Vec<CharacterOrigin>,
+    ancestor_length: usize,
+}
+
+impl Ancestry {
+    /// Creates the ancestry of an unmodified copy of an ancestor of the given length.
+    pub fn new(ancestor_length: usize) -> Self {
+        Self {
+            origins: (0..ancestor_length)
+                .map(|ancestor_position| CharacterOrigin::Ancestral { ancestor_position })
+                .collect(),
+            ancestor_length,
+        }
+    }
+
+    pub fn origins(&self) -> &[CharacterOrigin] {
+        &self.origins
+    }
+
+    /// Maps a position of the derived sequence to the ancestor.
+    ///
+    /// This is the ancestral position of the first character at or after `position` that descends from the ancestor,
+    /// or the ancestor length if there is none.
+    /// Hence an interval of the derived sequence maps to the interval of the ancestor that it replaces.
+    pub fn ancestor_position(&self, position: usize) -> usize {
+        self.origins[position.min(self.origins.len())..]
+            .iter()
+            .find_map(|origin| match origin {
+                CharacterOrigin::Ancestral { ancestor_position } => Some(*ancestor_position),
+                _ => None,
+            })
+            .unwrap_or(self.ancestor_length)
+    }
+
+    /// Updates the ancestry in the same way as `modification` changes the derived sequence.
+    pub fn apply_modification(&mut self, modification: &SequenceModification) {
+        let origin = match modification {
+            SequenceModification::TemplateSwitch { .. } => CharacterOrigin::TemplateSwitch,
+            // Deletions insert no characters.
+            SequenceModification::Insertion { .. } | SequenceModification::Deletion { .. } => {
+                CharacterOrigin::Inserted
+            }
+            // No change in origin.
+            SequenceModification::Substitution { .. } => return,
+        };
+        let AppliedInterval {
+            position,
+            replaced_length,
+            inserted_length,
+        } = modification.applied_interval();
+        self.origins.splice(
+            position..position + replaced_length,
+            vec![origin; inserted_length],
+        );
+    }
+}
+
+/// The coordinate frame in which the positions of listed modifications are expressed.
+///
+/// Only the positions are mapped, the lengths and offsets of the modifications are kept.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModificationFrame {
+    /// The position in the derived sequence at the time the modification was applied,
+    /// i.e. after all earlier modifications and before all later ones.
+    Applied,
+    /// The position in the ancestor, as given by [`Ancestry::ancestor_position`] at the time the modification was applied.
+    ///
+    /// This is the ancestral position of the first character at or after the applied position that descends from the ancestor.
+    Ancestor,
+    /// The position in the final derived sequence, before it is reverse complemented for the output.
+    ///
+    /// This is the final position of the first character at or after the applied position that is neither deleted
+    /// nor replaced by a later template switch, or the final length if there is none.
+    /// Substituted characters are kept.
+    Final,
+}
+
+/// Maps the positions of the modifications, given in the order in which they were applied, into the given frame.
+pub fn positions_in_frame(
+    frame: ModificationFrame,
+    ancestor_length: usize,
+    modifications: &[SequenceModification],
+) -> Vec<usize> {
+    match frame {
+        ModificationFrame::Applied => modifications
+            .iter()
+            .map(SequenceModification::position)
+            .collect(),
+        ModificationFrame::Ancestor => {
+            // Like the ancestry, but in logarithmic instead of linear time in the sequence length.
+            let mut ancestor_frame = AncestorFrame::default();
+            modifications
+                .iter()
+                .map(|modification| {
+                    // The frame does not know the end of the ancestor, so it is clamped like in the ancestry.
+                    let ancestor_position = ancestor_frame
+                        .next_ancestral_position(modification.position())
+                        .min(ancestor_length);
+                    // The replaced characters become inserted characters, see [`Ancestry::apply_modification`].
+                    if !matches!(modification, SequenceModification::Substitution { .. }) {
+                        let applied_interval = modification.applied_interval();
+                        ancestor_frame
+                            .delete(applied_interval.position, applied_interval.replaced_length);
+                        ancestor_frame
+                            .insert(applied_interval.position, applied_interval.inserted_length);
+                    }
+                    ancestor_position
+                })
+                .collect()
+        }
+        ModificationFrame::Final => final_intervals(modifications)
+            .into_iter()
+            .map(|interval| interval.start)
+            .collect(),
+    }
+}
+
+/// Returns the interval of the final derived sequence that each modification wrote, given in the order in which they were applied.
+///
+/// The interval starts at the position of the modification in the [`ModificationFrame::Final`] frame.
+/// Characters removed by later deletions or template switches are not part of the interval, while substituted characters are kept.
+/// If all characters were removed, or the modification is a deletion, then the interval is empty.
+pub fn final_intervals(modifications: &[SequenceModification]) -> Vec<Range<usize>> {
+    let mut intervals: Vec<Range<usize>> = Vec::with_capacity(modifications.len());
+    for modification in modifications {
+        let applied_interval = modification.applied_interval();
+        let AppliedInterval {
+            position: start,
+            replaced_length,
+            inserted_length,
+        } = applied_interval;
+
+        // Move the intervals of the earlier modifications forward through this one.
+        // Substituted characters are kept, so substitutions move no intervals.
+        if !matches!(modification, SequenceModification::Substitution { .. }) {
+            let move_start = |position: usize| {
+                if position >= start + replaced_length {
+                    position + inserted_length - replaced_length
+                } else if position >= start {
+                    start + inserted_length
+                } else {
+                    position
+                }
+            };
+            let move_end = |position: usize| {
+                if position >= start + replaced_length {
+                    position + inserted_length - replaced_length
+                } else if position > start {
+                    start
+                } else {
+                    position
+                }
+            };
+            for interval in &mut intervals {
+                let moved_start = move_start(interval.start);
+                let moved_end = move_end(interval.end);
+                // If all characters were removed, then the interval is empty at its start.
+                *interval = moved_start..moved_end.max(moved_start);
+            }
+        }
+        intervals.push(applied_interval.inserted_range());
+    }
+    intervals
+}
+
+/// Returns the indices of the modifications ordered by the position of the ancestor at which they start.
+///
+/// The modifications are given in the order in which they were applied to an ancestor of the given length,
+/// and their start is mapped to the ancestor with [`Ancestry::ancestor_position`].
+/// Modifications with the same start are ordered by type, i.e. template switches, insertions, deletions and then substitutions,
+/// and modifications of the same type keep their order.
+pub fn ancestor_position_order(
+    ancestor_length: usize,
+    modifications: &[SequenceModification],
+) -> Vec<usize> {
+    let ancestor_positions =
+        positions_in_frame(ModificationFrame::Ancestor, ancestor_length, modifications);
+    let mut order: Vec<_> = (0..modifications.len()).collect();
+    order.sort_by_key(|&index| {
+        let type_order = match modifications[index] {
+            SequenceModification::TemplateSwitch { .. } => 0,
+            SequenceModification::Insertion { .. } => 1,
+            SequenceModification::Deletion { .. } => 2,
+            SequenceModification::Substitution { .. } => 3,
+        };
+        (ancestor_positions[index], type_order)
+    });
+    order
+}
+
+/// Lists the modifications with their positions in the given frame,
+/// sorted by [`ancestor_position_order`] if `sort` is set and in the order in which they were applied otherwise.
+pub fn list_modifications(
+    ancestor_length: usize,
+    modifications: &[SequenceModification],
+    frame: ModificationFrame,
+    sort: bool,
+) -> Vec<SequenceModification> {
+    let positions = positions_in_frame(frame, ancestor_length, modifications);
+    let listed = |index: usize| modifications[index].with_position(positions[index]);
+    if sort {
+        ancestor_position_order(ancestor_length, modifications)
+            .into_iter()
+            .map(listed)
+            .collect()
+    } else {
+        (0..modifications.len()).map(listed).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::{list_modifications, positions_in_frame, Ancestry, ModificationFrame};
+
+    /// The ancestor frame maps positions like a per-character ancestry.
+    #[test]
+    fn ancestor_frame_positions() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        for ancestor_length in [0, 1, 50, 300] {
+            let mut length = ancestor_length;
+            let mut ancestry = Ancestry::new(ancestor_length);
+            let mut modifications = Vec::new();
+            let mut expected = Vec::new();
+            for _ in 0..300 {
+                let position = rng.gen_range(0..=length);
+                let remaining = length - position;
+                let modification = match rng.gen_range(0..4) {
+                    0 => {
+                        let length = rng.gen_range(1..20);
+                        let replaced_length = rng.gen_range(0..=remaining.min(20));
+                        SequenceModification::TemplateSwitch {
+                            position,
+                            length,
+                            offset: 0,
+                            length_difference: length as isize - replaced_length as isize,
+                            inter_sequence: false,
+                        }
+                    }
+                    1 => SequenceModification::Insertion {
+                        position,
+                        source: 0,
+                        length: rng.gen_range(1..20),
+                    },
+                    2 if remaining > 0 => SequenceModification::Deletion {
+                        position,
+                        length: rng.gen_range(1..=remaining.min(20)),
+                    },
+                    _ if remaining > 0 => SequenceModification::Substitution {
+                        position,
+                        character_increment: 1,
+                    },
+                    _ => continue,
+                };
+                let applied_interval = modification.applied_interval();
+                expected.push(ancestry.ancestor_position(position));
+                ancestry.apply_modification(&modification);
+                length =
+                    length + applied_interval.inserted_length - applied_interval.replaced_length;
+                modifications.push(modification);
+            }
+
+            assert_eq!(
+                positions_in_frame(ModificationFrame::Ancestor, ancestor_length, &modifications),
+                expected,
+                "ancestor length {ancestor_length}"
+            );
+        }
+    }
+
+    #[test]
+    fn sorted_modifications() {
+        let modifications = [
+            SequenceModification::Substitution {
+                position: 12,
+                character_increment: 1,
+            },
+            SequenceModification::Deletion {
+                position: 2,
+                length: 3,
+            },
+            // Ancestral position 12, since the deletion shifted it.
+            SequenceModification::Insertion {
+                position: 9,
+                source: 0,
+                length: 2,
+            },
+            // Ancestral position 5, as the first character after the deletion.
+            SequenceModification::TemplateSwitch {
+                position: 2,
+                length: 4,
+                offset: 1,
+                length_difference: 0,
+                inter_sequence: false,
+            },
+            SequenceModification::Substitution {
+                position: 0,
+                character_increment: 1,
+            },
+        ];
+
+        let sorted: Vec<_> =
+            list_modifications(20, &modifications, ModificationFrame::Applied, true)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+        assert_eq!(
+            sorted,
+            [
+                "Substitution at 0",
+                "Deletion at 2 of length 3",
+                "TS at 2 with offset = 1, length = 4 and length_difference = 0",
+                "Insertion at 9 of length 2",
+                "Substitution at 12",
+            ]
+        );
+    }
+
+    #[test]
+    fn modification_frames() {
+        let listed = |modifications: &[SequenceModification], frame| -> Vec<_> {
+            list_modifications(10, modifications, frame, false)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+
+        // The deletion starts at the fourth ancestral character, behind the inserted characters.
+        let modifications = [
+            SequenceModification::Insertion {
+                position: 2,
+                source: 0,
+                length: 3,
+            },
+            SequenceModification::Deletion {
+                position: 6,
+                length: 2,
+            },
+        ];
+        for (frame, expected) in [
+            (
+                ModificationFrame::Applied,
+                ["Insertion at 2 of length 3", "Deletion at 6 of length 2"],
+            ),
+            (
+                ModificationFrame::Ancestor,
+                ["Insertion at 2 of length 3", "Deletion at 3 of length 2"],
+            ),
+            (
+                ModificationFrame::Final,
+                ["Insertion at 2 of length 3", "Deletion at 6 of length 2"],
+            ),
+        ] {
+            assert_eq!(listed(&modifications, frame), expected, "{frame:?}");
+        }
+
+        // The substituted character is deleted later, so its final position is that of the first character after the deletion.
+        let modifications = [
+            SequenceModification::Substitution {
+                position: 5,
+                character_increment: 1,
+            },
+            SequenceModification::Deletion {
+                position: 3,
+                length: 4,
+            },
+        ];
+        for (frame, expected) in [
+            (
+                ModificationFrame::Applied,
+                ["Substitution at 5", "Deletion at 3 of length 4"],
+            ),
+            (
+                ModificationFrame::Ancestor,
+                ["Substitution at 5", "Deletion at 3 of length 4"],
+            ),
+            (
+                ModificationFrame::Final,
+                ["Substitution at 3", "Deletion at 3 of length 4"],
+            ),
+        ] {
+            assert_eq!(listed(&modifications, frame), expected, "{frame:?}");
+        }
+    }
+}
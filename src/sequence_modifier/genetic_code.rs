@@ -0,0 +1,40 @@
+/// The amino acids of the standard genetic code, with `*` for stop codons.
+///
+/// The codons are ordered lexicographically with the base order `TCAG`.
+const STANDARD_GENETIC_CODE: &[u8; 64] =
+    b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG";
+
+/// Translates a DNA codon given as ASCII characters with the standard genetic code.
+///
+/// Stop codons are translated to `*`.
+/// Returns `None` if the codon contains a character other than `A`, `C`, `G` or `T`.
+pub fn translate_codon(codon: [u8; 3]) -> Option<u8> {
+    let mut index = 0;
+    for base in codon {
+        index = index * 4
+            + match base.to_ascii_uppercase() {
+                b'T' => 0,
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => return None,
+            };
+    }
+
+    Some(STANDARD_GENETIC_CODE[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::translate_codon;
+
+    #[test]
+    fn translate() {
+        assert_eq!(translate_codon(*b"ATG"), Some(b'M'));
+        assert_eq!(translate_codon(*b"TGG"), Some(b'W'));
+        assert_eq!(translate_codon(*b"TAA"), Some(b'*'));
+        assert_eq!(translate_codon(*b"GGT"), Some(b'G'));
+        assert_eq!(translate_codon(*b"CTA"), Some(b'L'));
+        assert_eq!(translate_codon(*b"ANG"), None);
+    }
+}
@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use rand::{seq::IteratorRandom, Rng};
+
+use super::AppliedInterval;
+
+/// Finds the maximal runs of identical characters that are at least `minimum_length` characters long.
+///
+/// The runs are returned in ascending order.
+pub fn homopolymer_runs<
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    sequence: &SequenceType,
+    minimum_length: usize,
+) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    push_runs(sequence, 0..sequence.len(), minimum_length, &mut runs);
+    runs
+}
+
+/// Appends the runs within `range` to `runs`, where `range` must not cut through a run of identical characters.
+fn push_runs<
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    sequence: &SequenceType,
+    range: Range<usize>,
+    minimum_length: usize,
+    runs: &mut Vec<Range<usize>>,
+) {
+    let mut start = range.start;
+
+    for end in range.start + 1..=range.end {
+        if end == range.end || sequence[end] != sequence[start] {
+            if end - start >= minimum_length {
+                runs.push(start..end);
+            }
+            start = end;
+        }
+    }
+}
+
+/// The homopolymer runs of a sequence, which are kept up to date while the sequence is modified.
+pub struct HomopolymerRuns {
+    runs: Vec<Range<usize>>,
+    minimum_length: usize,
+}
+
+impl HomopolymerRuns {
+    pub fn new<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequence: &SequenceType,
+        minimum_length: usize,
+    ) -> Self {
+        Self {
+            runs: homopolymer_runs(sequence, minimum_length),
+            minimum_length,
+        }
+    }
+
+    /// The runs in ascending order, see [`homopolymer_runs`].
+    pub fn runs(&self) -> &[Range<usize>] {
+        &self.runs
+    }
+
+    /// Updates the runs after the given interval of the sequence was replaced, where `sequence` is the modified sequence.
+    ///
+    /// Only the part of the sequence around the replacement is scanned again.
+    pub fn apply_modification<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        sequence: &SequenceType,
+        applied_interval: AppliedInterval,
+    ) {
+        let AppliedInterval {
+            position,
+            replaced_length,
+            inserted_length,
+        } = applied_interval;
+        let replaced_end = position + replaced_length;
+        let inserted_end = position + inserted_length;
+        let map_end = |end: usize| {
+            if end > replaced_end {
+                end - replaced_end + inserted_end
+            } else {
+                inserted_end
+            }
+        };
+
+        // Runs that touch the replaced interval may be shortened, extended or merged.
+        let first = self.runs.partition_point(|run| run.end < position);
+        let last = self.runs.partition_point(|run| run.start <= replaced_end);
+        let mut start = self.runs[first..last]
+            .first()
+            .map_or(position, |run| run.start.min(position));
+        let mut end = self.runs[first..last]
+            .last()
+            .map_or(inserted_end, |run| map_end(run.end));
+
+        // Characters next to the scanned part may form a run with its characters.
+        // This stops at the untouched runs, since they are maximal.
+        while start > 0 && start < sequence.len() && sequence[start - 1] == sequence[start] {
+            start -= 1;
+        }
+        while end > 0 && end < sequence.len() && sequence[end] == sequence[end - 1] {
+            end += 1;
+        }
+
+        for run in &mut self.runs[last..] {
+            *run = map_end(run.start)..map_end(run.end);
+        }
+        let mut runs = Vec::new();
+        push_runs(sequence, start..end, self.minimum_length, &mut runs);
+        self.runs.splice(first..last, runs);
+    }
+}
+
+/// Chooses a run with probability proportional to its length.
+///
+/// Returns `None` if there are no runs.
+pub fn choose_homopolymer_run(runs: &[Range<usize>], rng: &mut impl Rng) -> Option<Range<usize>> {
+    let total_length: usize = runs.iter().map(ExactSizeIterator::len).sum();
+    let mut index = (0..total_length).choose(rng)?;
+
+    for run in runs {
+        if index < run.len() {
+            return Some(run.clone());
+        }
+        index -= run.len();
+    }
+
+    unreachable!("index is smaller than the sum of run lengths")
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, DefaultGenome},
+        interface::sequence::OwnedGenomeSequence,
+    };
+
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use traitsequence::interface::Sequence;
+
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::{homopolymer_runs, HomopolymerRuns};
+
+    #[test]
+    fn runs() {
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AAACGGTTTTCAA").unwrap();
+        assert_eq!(homopolymer_runs(&sequence, 3), vec![0..3, 6..10]);
+        assert_eq!(
+            homopolymer_runs(&sequence, 2),
+            vec![0..3, 4..6, 6..10, 11..13]
+        );
+        assert_eq!(homopolymer_runs(&sequence, 5), Vec::new());
+
+        let sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"").unwrap();
+        assert_eq!(homopolymer_runs(&sequence, 1), Vec::new());
+    }
+
+    #[test]
+    fn updated_runs() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let characters: Vec<_> = (0..200)
+            .map(|_| if rng.gen_bool(0.7) { b'A' } else { b'C' })
+            .collect();
+        let mut sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(&characters).unwrap();
+        let mut runs = HomopolymerRuns::new(&sequence, 3);
+
+        for _ in 0..500 {
+            let length = rng.gen_range(1..=4);
+            let modification = match rng.gen_range(0..4) {
+                0 => SequenceModification::Insertion {
+                    position: rng.gen_range(0..=sequence.len()),
+                    source: rng.gen_range(0..=sequence.len() - length),
+                    length,
+                },
+                1 if sequence.len() > 100 => SequenceModification::Deletion {
+                    position: rng.gen_range(0..=sequence.len() - length),
+                    length,
+                },
+                _ => SequenceModification::Substitution {
+                    position: rng.gen_range(0..sequence.len()),
+                    character_increment: 1,
+                },
+            };
+            let ancestor = sequence.clone();
+            let applied_interval = modification.apply(&mut sequence, &ancestor).unwrap();
+            runs.apply_modification(&sequence, applied_interval);
+            assert_eq!(
+                runs.runs(),
+                homopolymer_runs(&sequence, 3),
+                "{modification}"
+            );
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::IteratorRandom,
+    Rng,
+};
+use serde::{Serialize, Serializer};
+
+use crate::error::Error;
+
+/// The distribution from which the length difference of each template switch is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthDifferenceDistribution {
+    /// All length differences are equally likely.
+    Uniform,
+    /// A normal distribution centered at zero, discretised to the integers.
+    Normal { standard_deviation: f64 },
+    /// A Laplace distribution centered at zero, discretised to the integers.
+    Laplace { scale: f64 },
+}
+
+impl LengthDifferenceDistribution {
+    /// Draws a length difference that is a multiple of `multiple` from the inclusive range,
+    /// or returns `None` if the range contains no such multiple.
+    ///
+    /// The distribution is truncated to the multiples in the range, i.e. each of them is drawn with a probability proportional to its density.
+    /// The densities are computed with the software functions of `libm`, such that the result only depends on the random number generator.
+    pub fn sample(
+        &self,
+        range: RangeInclusive<isize>,
+        multiple: isize,
+        rng: &mut impl Rng,
+    ) -> Option<isize> {
+        debug_assert!(multiple > 0);
+        // Draw the factor of the multiple, rounding the start up and the end down.
+        let range = -(-range.start()).div_euclid(multiple)..=range.end().div_euclid(multiple);
+        if *self == LengthDifferenceDistribution::Uniform {
+            return range.choose(rng).map(|factor| factor * multiple);
+        }
+        if range.is_empty() {
+            return None;
+        }
+
+        // Normalising by the largest density keeps the weights from underflowing if the range lies far from zero.
+        let log_densities: Vec<_> = range
+            .clone()
+            .map(|factor| self.log_density(factor * multiple))
+            .collect();
+        let maximum = log_densities
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights = log_densities
+            .iter()
+            .map(|log_density| libm::exp(log_density - maximum));
+        let index = WeightedIndex::new(weights).unwrap().sample(rng);
+        Some((range.start() + index as isize) * multiple)
+    }
+
+    /// The logarithm of the unnormalised density at `length_difference`.
+    fn log_density(&self, length_difference: isize) -> f64 {
+        let length_difference = length_difference as f64;
+        match self {
+            LengthDifferenceDistribution::Uniform => 0.0,
+            LengthDifferenceDistribution::Normal { standard_deviation } => {
+                let distance = length_difference / standard_deviation;
+                -0.5 * distance * distance
+            }
+            LengthDifferenceDistribution::Laplace { scale } => -length_difference.abs() / scale,
+        }
+    }
+}
+
+impl std::fmt::Display for LengthDifferenceDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LengthDifferenceDistribution::Uniform => write!(f, "uniform"),
+            LengthDifferenceDistribution::Normal { standard_deviation } => {
+                write!(f, "normal:{standard_deviation}")
+            }
+            LengthDifferenceDistribution::Laplace { scale } => write!(f, "laplace:{scale}"),
+        }
+    }
+}
+
+impl Serialize for LengthDifferenceDistribution {
+    /// Serialises the distribution in the same format as it is parsed from the command line.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for LengthDifferenceDistribution {
+    type Err = Error;
+
+    /// Parses `uniform`, `normal:STANDARD_DEVIATION` or `laplace:SCALE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || Error::InvalidLengthDifferenceDistribution(s.to_string());
+        if s == "uniform" {
+            return Ok(Self::Uniform);
+        }
+
+        let (kind, parameter) = s.split_once(':').ok_or_else(error)?;
+        let parameter: f64 = parameter.trim().parse().map_err(|_| error())?;
+        if !parameter.is_finite() || parameter <= 0.0 {
+            return Err(error());
+        }
+
+        match kind {
+            "normal" => Ok(Self::Normal {
+                standard_deviation: parameter,
+            }),
+            "laplace" => Ok(Self::Laplace { scale: parameter }),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::LengthDifferenceDistribution;
+
+    #[test]
+    fn concentrates_near_zero() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let near_zero_fraction = |distribution: LengthDifferenceDistribution,
+                                  rng: &mut Xoshiro256PlusPlus| {
+            let samples: Vec<_> = (0..10_000)
+                .map(|_| distribution.sample(-10..=10, 1, rng).unwrap())
+                .collect();
+            assert!(samples
+                .iter()
+                .all(|length_difference| (-10..=10).contains(length_difference)));
+            samples
+                .iter()
+                .filter(|length_difference| length_difference.abs() <= 2)
+                .count() as f64
+                / samples.len() as f64
+        };
+
+        let uniform = near_zero_fraction("uniform".parse().unwrap(), &mut rng);
+        assert!((uniform - 5.0 / 21.0).abs() < 0.02, "{uniform}");
+        let normal = near_zero_fraction("normal:1.5".parse().unwrap(), &mut rng);
+        assert!(normal > 0.85, "{normal}");
+        let laplace = near_zero_fraction("laplace:1".parse().unwrap(), &mut rng);
+        assert!(laplace > 0.85, "{laplace}");
+
+        // A range far from zero is drawn from without underflow, favouring its end closest to zero.
+        let normal = LengthDifferenceDistribution::Normal {
+            standard_deviation: 1.0,
+        };
+        assert_eq!(normal.sample(100..=110, 1, &mut rng), Some(100));
+        let maximum_length_difference = 0;
+        assert_eq!(
+            normal.sample(1..=maximum_length_difference, 1, &mut rng),
+            None
+        );
+
+        // Only multiples are drawn, and they may be missing from the range.
+        for distribution in ["uniform", "normal:3", "laplace:3"] {
+            let distribution: LengthDifferenceDistribution = distribution.parse().unwrap();
+            for _ in 0..100 {
+                let length_difference = distribution.sample(-10..=10, 3, &mut rng).unwrap();
+                assert!((-9..=9).contains(&length_difference));
+                assert_eq!(length_difference % 3, 0);
+            }
+            assert_eq!(distribution.sample(-8..=-7, 3, &mut rng), None);
+            assert_eq!(distribution.sample(4..=5, 3, &mut rng), None);
+        }
+
+        for invalid in ["normal:0", "laplace:-1", "normal", "gamma:1", "uniform:1"] {
+            assert!(invalid.parse::<LengthDifferenceDistribution>().is_err());
+        }
+    }
+}
@@ -1,21 +1,127 @@
+use std::ops::{Range, RangeInclusive};
+
+use allowed_regions::AllowedRegions;
+use ancestry::Ancestry;
 use compact_genome::interface::{
     alphabet::{Alphabet, AlphabetCharacter},
     sequence::{EditableGenomeSequence, GenomeSequence},
 };
-use rand::{seq::IteratorRandom, Rng};
-use rand_distr::{Distribution, Exp};
-use template_switch_overlap_detector::TemplateSwitchOverlapDetector;
+use genetic_code::translate_codon;
+use homopolymer::{choose_homopolymer_run, HomopolymerRuns};
+use log::{debug, warn};
+use position_bias::PositionBias;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+use rate_multipliers::RateMultipliers;
+use soft_mask::SoftMask;
+use template_switch_overlap_detector::{
+    template_switch_range, TemplateSwitchCollision, TemplateSwitchOverlapDetector,
+};
 
 use crate::{
     cli::{SequenceModificationAmount, SequenceModificationParameters},
     error::{Error, Result},
+    random_stream::StreamRng,
 };
 
+pub mod allowed_regions;
+pub mod ancestry;
+pub mod genetic_code;
+pub mod homopolymer;
+pub mod length_difference;
+pub mod position_bias;
+pub mod rate_multipliers;
+pub mod soft_mask;
 pub mod template_switch_overlap_detector;
 
+/// The maximum number of positions tried when drawing a (non-)synonymous substitution.
+const MAXIMUM_CODON_SUBSTITUTION_TRIES: usize = 10_000;
+
+/// The maximum number of rejected positions when drawing a CpG-biased substitution.
+/// If it is reached, then the last position is used regardless of its weight.
+const MAXIMUM_CPG_REJECTIONS: usize = 10_000;
+
 pub struct SequenceModifier {
+    /// The amount of modifications that are still to be generated.
     sequence_modification_amount: SequenceModificationAmount,
+    /// The amount of modifications that were requested initially.
+    requested_modification_amount: SequenceModificationAmount,
+    /// The amount of modifications that were actually applied.
+    realized_modification_amount: SequenceModificationAmount,
     sequence_modification_parameters: SequenceModificationParameters,
+    /// If set, modifications are only drawn within these regions.
+    allowed_regions: Option<AllowedRegions>,
+    position_bias: PositionBias,
+    /// If set, the density of modifications is proportional to these multipliers.
+    rate_multipliers: Option<RateMultipliers>,
+    /// If set, the origins of the characters of the modified sequence are tracked.
+    ancestry: Option<Ancestry>,
+    /// If set, the soft-mask of the modified sequence and of its sibling are tracked.
+    soft_mask: Option<(SoftMask, SoftMask)>,
+    /// If set, the applied modifications are recorded in order.
+    modifications: Option<Vec<SequenceModification>>,
+    /// If set, the original and the substituted character of each applied substitution are recorded in order, as ASCII.
+    substitutions: Option<Vec<(u8, u8)>>,
+    /// If set, the type of each modification is drawn from these probabilities instead of from the remaining amounts.
+    modification_type_probabilities: Option<ModificationTypeProbabilities>,
+    /// If set, modifications whose edit cost would exceed this budget are discarded.
+    edit_cost_budget: Option<EditCostBudget>,
+    /// The random generators of template switches, gaps and substitutions.
+    /// Types without own generator draw from the generator passed to [`Self::apply`].
+    modification_type_rngs: ModificationTypeRngs,
+}
+
+/// The own random generators of the modification types, see [`SequenceModifier::use_modification_type_rngs`].
+#[derive(Debug, Clone, Default)]
+pub struct ModificationTypeRngs {
+    pub template_switch: Option<StreamRng>,
+    pub gap: Option<StreamRng>,
+    pub substitution: Option<StreamRng>,
+}
+
+impl ModificationTypeRngs {
+    fn get_mut(&mut self, modification_type: ModificationType) -> &mut Option<StreamRng> {
+        match modification_type {
+            ModificationType::TemplateSwitch => &mut self.template_switch,
+            ModificationType::Gap => &mut self.gap,
+            ModificationType::Substitution => &mut self.substitution,
+        }
+    }
+}
+
+/// Limits the total edit cost of the applied modifications.
+#[derive(Debug, Clone, Copy)]
+struct EditCostBudget {
+    /// The edit cost that may still be spent.
+    remaining_cost: f64,
+    /// The edit cost of each character of a template switch.
+    template_switch_cost: f64,
+    /// The modifications that were discarded because they exceeded the budget.
+    discarded_amount: SequenceModificationAmount,
+}
+
+/// Draws the types of a fixed total amount of modifications with fixed probabilities.
+#[derive(Debug, Clone)]
+struct ModificationTypeProbabilities {
+    /// The weights of template switches, gaps and substitutions.
+    distribution: WeightedIndex<f64>,
+    /// The amount of modifications that are still to be generated.
+    remaining_amount: usize,
+    /// The amount of modifications that were requested initially.
+    requested_amount: usize,
+    /// True if gaps have a positive probability.
+    draws_gaps: bool,
+}
+
+/// The types of modifications, whose discriminants index per-type arrays.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ModificationType {
+    TemplateSwitch,
+    Gap,
+    Substitution,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -25,6 +131,8 @@ pub enum SequenceModification {
         length: usize,
         offset: isize,
         length_difference: isize,
+        /// If set, the replacement is copied from the sibling sequence instead of from the sequence itself.
+        inter_sequence: bool,
     },
     Insertion {
         position: usize,
@@ -37,6 +145,10 @@ pub enum SequenceModification {
     },
     Substitution {
         position: usize,
+        /// The increment of the index of the character at `position` when the substitution is applied, modulo the alphabet size.
+        ///
+        /// The increment is relative to the character present at application time, not to the one it was drawn for,
+        /// so a substitution of a position that was substituted before builds on the earlier substitution.
         character_increment: usize,
     },
 }
@@ -46,11 +158,83 @@ pub struct SequenceModifierPair {
     pub query_modifier: SequenceModifier,
 }
 
+impl SequenceModifierPair {
+    /// Draws the type of each modification from the given probabilities of template switches, gaps and substitutions,
+    /// instead of generating fixed amounts of each type.
+    ///
+    /// The `total_amount` of modifications is split between reference and query like the amounts of each type.
+    /// The probabilities are normalised and must have a positive sum.
+    pub fn with_modification_type_probabilities(
+        mut self,
+        reference_ancestry_fraction: f64,
+        total_amount: usize,
+        probabilities: [f64; 3],
+        rng: &mut impl Rng,
+    ) -> Result<Self> {
+        let distribution = WeightedIndex::new(probabilities)
+            .map_err(|_| Error::InvalidModificationProbabilities(probabilities))?;
+        let (query_amount, reference_amount) =
+            split_int_random(total_amount, reference_ancestry_fraction, rng);
+
+        for (modifier, amount) in [
+            (&mut self.reference_modifier, reference_amount),
+            (&mut self.query_modifier, query_amount),
+        ] {
+            modifier.modification_type_probabilities = Some(ModificationTypeProbabilities {
+                distribution: distribution.clone(),
+                remaining_amount: amount,
+                requested_amount: amount,
+                draws_gaps: probabilities[ModificationType::Gap as usize] > 0.0,
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Discards each modification whose edit cost would make the total exceed `max_edit_distance`, even if the amounts are not exhausted.
+    ///
+    /// The budget is split between reference and query like the amounts of each type.
+    /// Substitutions cost one, gaps cost their length, and template switches cost
+    /// `template_switch_cost` times the longer of their copied and their replaced interval.
+    /// Hence the edit distance between ancestor and each modified sequence stays within its part of the budget
+    /// if `template_switch_cost` is at least one.
+    pub fn with_max_edit_distance(
+        mut self,
+        reference_ancestry_fraction: f64,
+        max_edit_distance: usize,
+        template_switch_cost: f64,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let (query_cost, reference_cost) =
+            split_int_random(max_edit_distance, reference_ancestry_fraction, rng);
+
+        for (modifier, cost) in [
+            (&mut self.reference_modifier, reference_cost),
+            (&mut self.query_modifier, query_cost),
+        ] {
+            modifier.edit_cost_budget = Some(EditCostBudget {
+                remaining_cost: cost as f64,
+                template_switch_cost,
+                discarded_amount: SequenceModificationAmount {
+                    template_switch_amount: 0,
+                    gap_amount: 0,
+                    substitution_amount: 0,
+                },
+            });
+        }
+
+        self
+    }
+}
+
 impl SequenceModifier {
     pub fn new_modifier_pair(
         reference_ancestry_fraction: f64,
         sequence_modification_amount: SequenceModificationAmount,
         sequence_modification_parameters: SequenceModificationParameters,
+        allowed_regions: Option<AllowedRegions>,
+        position_bias: PositionBias,
+        rate_multipliers: Option<RateMultipliers>,
         rng: &mut impl Rng,
     ) -> SequenceModifierPair {
         let (query_template_switch_amount, reference_template_switch_amount) = split_int_random(
@@ -70,190 +254,381 @@ impl SequenceModifier {
         );
 
         SequenceModifierPair {
-            reference_modifier: SequenceModifier {
-                sequence_modification_amount: SequenceModificationAmount {
+            reference_modifier: SequenceModifier::new(
+                SequenceModificationAmount {
                     template_switch_amount: reference_template_switch_amount,
                     gap_amount: reference_gap_amount,
                     substitution_amount: reference_substitution_amount,
                 },
                 sequence_modification_parameters,
-            },
-            query_modifier: SequenceModifier {
-                sequence_modification_amount: SequenceModificationAmount {
+                allowed_regions.clone(),
+                position_bias.clone(),
+                rate_multipliers.clone(),
+            ),
+            query_modifier: SequenceModifier::new(
+                SequenceModificationAmount {
                     template_switch_amount: query_template_switch_amount,
                     gap_amount: query_gap_amount,
                     substitution_amount: query_substitution_amount,
                 },
                 sequence_modification_parameters,
+                allowed_regions,
+                position_bias,
+                rate_multipliers,
+            ),
+        }
+    }
+
+    fn new(
+        sequence_modification_amount: SequenceModificationAmount,
+        sequence_modification_parameters: SequenceModificationParameters,
+        allowed_regions: Option<AllowedRegions>,
+        position_bias: PositionBias,
+        rate_multipliers: Option<RateMultipliers>,
+    ) -> Self {
+        Self {
+            sequence_modification_amount,
+            requested_modification_amount: sequence_modification_amount,
+            realized_modification_amount: SequenceModificationAmount {
+                template_switch_amount: 0,
+                gap_amount: 0,
+                substitution_amount: 0,
             },
+            sequence_modification_parameters,
+            allowed_regions,
+            position_bias,
+            rate_multipliers,
+            ancestry: None,
+            soft_mask: None,
+            modifications: None,
+            substitutions: None,
+            modification_type_probabilities: None,
+            edit_cost_budget: None,
+            modification_type_rngs: Default::default(),
         }
     }
 
-    pub fn next(
+    /// Track the origin of each character of the sequence this modifier is applied to.
+    ///
+    /// The sequence must be an unmodified copy of the ancestor.
+    pub fn track_ancestry(&mut self, ancestor_length: usize) {
+        self.ancestry = Some(Ancestry::new(ancestor_length));
+    }
+
+    /// Removes and returns the ancestry of the modified sequence, if it is tracked.
+    pub fn take_ancestry(&mut self) -> Option<Ancestry> {
+        self.ancestry.take()
+    }
+
+    /// Record the modifications applied to the sequence.
+    pub fn track_modifications(&mut self) {
+        self.modifications = Some(Vec::new());
+    }
+
+    /// Removes and returns the applied modifications in order, if they are recorded.
+    pub fn take_modifications(&mut self) -> Option<Vec<SequenceModification>> {
+        self.modifications.take()
+    }
+
+    /// Record the original and the substituted character of each applied substitution.
+    pub fn track_substitutions(&mut self) {
+        self.substitutions = Some(Vec::new());
+    }
+
+    /// Removes and returns the original and the substituted characters of the applied substitutions in order, if they are recorded.
+    pub fn take_substitutions(&mut self) -> Option<Vec<(u8, u8)>> {
+        self.substitutions.take()
+    }
+
+    /// Track the soft-mask of the sequence this modifier is applied to, starting from `soft_mask`.
+    ///
+    /// The `sibling_soft_mask` is the mask of the sibling sequence as it is passed to [`Self::apply`].
+    pub fn track_soft_mask(&mut self, soft_mask: SoftMask, sibling_soft_mask: SoftMask) {
+        self.soft_mask = Some((soft_mask, sibling_soft_mask));
+    }
+
+    /// Returns the soft-mask of the modified sequence, if it is tracked.
+    pub fn soft_mask(&self) -> Option<&SoftMask> {
+        self.soft_mask.as_ref().map(|(soft_mask, _)| soft_mask)
+    }
+
+    /// Removes and returns the soft-mask of the modified sequence, if it is tracked.
+    pub fn take_soft_mask(&mut self) -> Option<SoftMask> {
+        self.soft_mask.take().map(|(soft_mask, _)| soft_mask)
+    }
+
+    /// Draw the modifications of each type from their own random generator.
+    ///
+    /// The type of each modification is still drawn from the random generator passed to [`Self::apply`],
+    /// as are the modifications of types without own generator.
+    /// Modifications of different types still interact, since a modification is drawn again if it overlaps a template switch,
+    /// and positions refer to the sequence modified by all previous modifications.
+    /// Hence changing the generator of one type may change where modifications of other types land.
+    pub fn use_modification_type_rngs(&mut self, rngs: ModificationTypeRngs) {
+        self.modification_type_rngs = rngs;
+    }
+
+    /// Generates the next modification, or `None` if all modifications were generated.
+    ///
+    /// If `homopolymer_runs` is given, then gaps are placed into the parts of these runs within the allowed regions.
+    pub fn next<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
         &mut self,
-        sequence_length: usize,
-        alphabet_size: usize,
+        sequence: &SequenceType,
+        sibling_length: usize,
+        homopolymer_runs: Option<&[Range<usize>]>,
         template_switch_overlap_detector: &mut TemplateSwitchOverlapDetector,
         rng: &mut impl Rng,
     ) -> Result<Option<SequenceModification>> {
+        let sequence_length = sequence.len();
+        let alphabet_size = AlphabetType::SIZE;
         assert!(alphabet_size > 1);
         if sequence_length == 0 {
             return Err(Error::SequenceBecameEmpty);
         }
 
-        let sum = self.sequence_modification_amount.template_switch_amount
-            + self.sequence_modification_amount.gap_amount
-            + self.sequence_modification_amount.substitution_amount;
+        let Some(modification_type) = self.choose_modification_type(rng) else {
+            return Ok(None);
+        };
 
-        if let Some(index) = (0..sum).choose(rng) {
-            Ok(Some(
-                if index < self.sequence_modification_amount.template_switch_amount {
-                    debug_assert!(self.sequence_modification_amount.template_switch_amount > 0);
-                    self.sequence_modification_amount.template_switch_amount -= 1;
-                    let mut tries = 0;
+        // The type is always drawn from the main random generator, so the types do not depend on the sub-seeds.
+        if let Some(mut type_rng) = self
+            .modification_type_rngs
+            .get_mut(modification_type)
+            .take()
+        {
+            let result = self.next_of_type(
+                modification_type,
+                sequence,
+                sibling_length,
+                homopolymer_runs,
+                template_switch_overlap_detector,
+                &mut type_rng,
+            );
+            *self.modification_type_rngs.get_mut(modification_type) = Some(type_rng);
+            result
+        } else {
+            self.next_of_type(
+                modification_type,
+                sequence,
+                sibling_length,
+                homopolymer_runs,
+                template_switch_overlap_detector,
+                rng,
+            )
+        }
+        .map(Some)
+    }
 
-                    loop {
-                        if tries
-                            < self
-                                .sequence_modification_parameters
-                                .template_switch_maximum_overlap_tries
-                        {
-                            tries += 1;
-                        } else {
-                            return Err(Error::TemplateSwitchOverlap);
-                        }
+    /// Generates a modification of the given type.
+    fn next_of_type<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        modification_type: ModificationType,
+        sequence: &SequenceType,
+        sibling_length: usize,
+        homopolymer_runs: Option<&[Range<usize>]>,
+        template_switch_overlap_detector: &mut TemplateSwitchOverlapDetector,
+        rng: &mut impl Rng,
+    ) -> Result<SequenceModification> {
+        let sequence_length = sequence.len();
+        let alphabet_size = AlphabetType::SIZE;
 
-                        let offset = (self
-                            .sequence_modification_parameters
-                            .template_switch_min_offset
-                            ..=self
-                                .sequence_modification_parameters
-                                .template_switch_max_offset)
-                            .choose(rng)
-                            .unwrap();
-                        let length = (self
-                            .sequence_modification_parameters
-                            .template_switch_min_length
-                            ..=self
-                                .sequence_modification_parameters
-                                .template_switch_max_length)
-                            .choose(rng)
-                            .unwrap() as isize;
-                        let length_difference = (self
-                            .sequence_modification_parameters
-                            .template_switch_min_length_difference
+        Ok(if modification_type == ModificationType::TemplateSwitch {
+            let template_switch_margin = self
+                .sequence_modification_parameters
+                .resolve_template_switch_margin(sequence_length);
+            template_switch_overlap_detector.set_margin(template_switch_margin);
+            let mut tries = 0;
+            // Set if the last try did not fit into the allowed regions.
+            let mut allowed_regions_too_short = None;
+
+            loop {
+                if tries
+                    < self
+                        .sequence_modification_parameters
+                        .template_switch_maximum_overlap_tries
+                {
+                    tries += 1;
+                } else if let Some(modification_length) = allowed_regions_too_short {
+                    return Err(Error::AllowedRegionsTooShort {
+                        modification_length,
+                    });
+                } else {
+                    return Err(Error::TemplateSwitchOverlap);
+                }
+                allowed_regions_too_short = None;
+
+                let offset = self.choose_template_switch_offset(rng);
+                let length = (self
+                    .sequence_modification_parameters
+                    .template_switch_min_length()
+                    ..=self
+                        .sequence_modification_parameters
+                        .template_switch_max_length())
+                    .choose(rng)
+                    .unwrap() as isize;
+                let min_length_difference = self
+                    .sequence_modification_parameters
+                    .template_switch_min_length_difference;
+                let length_difference = self
+                    .sequence_modification_parameters
+                    .template_switch_length_difference_distribution
+                    .sample(
+                        min_length_difference
                             ..=(self
                                 .sequence_modification_parameters
                                 .template_switch_max_length_difference)
-                                .min(length))
-                            .choose(rng)
-                            .unwrap();
-                        let position_range = 0.max(offset - length)
-                            + self.sequence_modification_parameters.template_switch_margin as isize
-                            ..(sequence_length as isize
-                                - 0.max(offset).max(length).max(length + length_difference))
-                                - self.sequence_modification_parameters.template_switch_margin
-                                    as isize;
-
-                        let position = position_range.clone().choose(rng).ok_or_else(|| {
-                            Error::SequenceTooShortForTemplateSwitch {
-                                sequence_length,
-                                template_switch_required_sequence_length: (position_range.start
-                                    + (sequence_length as isize - position_range.end))
-                                    as usize,
-                            }
-                        })?;
-
-                        let result = SequenceModification::TemplateSwitch {
-                            position: position as usize,
-                            length: length as usize,
-                            offset,
-                            length_difference,
-                        };
-
-                        if self
-                            .sequence_modification_parameters
-                            .template_switch_overlap
-                        {
-                            break result;
-                        } else {
-                            match template_switch_overlap_detector
-                                .apply_modification(result) {
-                                    template_switch_overlap_detector::TemplateSwitchCollision::Overlap => { /* retry */ }
-                                    template_switch_overlap_detector::TemplateSwitchCollision::Independent => break result,
-                                }
-                        }
-                    }
-                } else if index
-                    < self.sequence_modification_amount.template_switch_amount
-                        + self.sequence_modification_amount.gap_amount
-                {
-                    debug_assert!(self.sequence_modification_amount.gap_amount > 0);
-                    self.sequence_modification_amount.gap_amount -= 1;
-
-                    let mean_gap_length = self.sequence_modification_parameters.gap_length_mean;
-                    let gap_length = Exp::new(1.0 / mean_gap_length)
-                        .map_err(|error| match error {
-                            rand_distr::ExpError::LambdaTooSmall => Error::GapLengthMeanLambda {
-                                lambda: 1.0 / mean_gap_length,
-                                mean: mean_gap_length,
-                            },
-                        })?
-                        .sample(rng);
-                    let gap_length = if gap_length < 1.0 {
-                        1
-                    } else {
-                        gap_length.round() as usize
-                    };
-
-                    if gap_length > sequence_length {
-                        return Err(Error::SequenceTooShortForGap {
-                            sequence_length,
-                            gap_length,
-                        });
+                                .min(length),
+                        self.sequence_modification_parameters
+                            .template_switch_length_difference_multiple(),
+                        rng,
+                    )
+                    .ok_or(Error::TemplateSwitchLengthDifferenceExceedsLength {
+                        length: length as usize,
+                        length_difference: min_length_difference,
+                    })?;
+                let inter_sequence_template_switch_probability = self
+                    .sequence_modification_parameters
+                    .inter_sequence_template_switch_probability;
+                let inter_sequence = inter_sequence_template_switch_probability > 0.0
+                    && rng.gen_bool(inter_sequence_template_switch_probability);
+                // The source of an inter-sequence template switch lies in the sibling,
+                // so the template switch needs to fit into both sequences.
+                let available_length = if inter_sequence {
+                    sequence_length.min(sibling_length)
+                } else {
+                    sequence_length
+                };
+                let position_range = template_switch_position_range(
+                    available_length,
+                    length as usize,
+                    offset,
+                    length_difference,
+                    template_switch_margin,
+                )?;
+                // The length difference is at most the length.
+                let position = match self.choose_position(
+                    position_range,
+                    (length - length_difference) as usize,
+                    sequence_length,
+                    rng,
+                ) {
+                    Ok(position) => position,
+                    Err(Error::AllowedRegionsTooShort {
+                        modification_length,
+                    }) => {
+                        debug!("No template switch replacing {modification_length} characters fits into the allowed regions, retrying");
+                        allowed_regions_too_short = Some(modification_length);
+                        continue;
                     }
+                    Err(error) => return Err(error),
+                };
 
-                    let result = if rng.gen_bool(0.5) {
-                        SequenceModification::Insertion {
-                            position: (0..sequence_length).choose(rng).unwrap(),
-                            source: (0..sequence_length - gap_length).choose(rng).unwrap(),
-                            length: gap_length,
-                        }
-                    } else {
-                        SequenceModification::Deletion {
-                            position: (0..sequence_length - gap_length).choose(rng).unwrap(),
-                            length: gap_length,
-                        }
-                    };
+                let result = SequenceModification::TemplateSwitch {
+                    position,
+                    length: length as usize,
+                    offset,
+                    length_difference,
+                    inter_sequence,
+                };
 
-                    if !self
+                if self
+                    .sequence_modification_parameters
+                    .template_switch_forbid_self_overlap
+                    && result.is_self_overlapping()
+                {
+                    debug!("{result} copies from the interval it replaces, retrying");
+                } else {
+                    // The budget is checked before the template switch is reserved in the overlap detector.
+                    self.check_edit_cost(modification_type, &result)?;
+                    if self
                         .sequence_modification_parameters
                         .template_switch_overlap
                     {
-                        template_switch_overlap_detector.apply_modification(result);
+                        break result;
                     }
-                    result
-                } else {
-                    debug_assert!(self.sequence_modification_amount.substitution_amount > 0);
-                    self.sequence_modification_amount.substitution_amount -= 1;
 
-                    let result = SequenceModification::Substitution {
-                        position: (0..sequence_length).choose(rng).unwrap(),
-                        character_increment: (1..alphabet_size).choose(rng).unwrap(),
-                    };
+                    match template_switch_overlap_detector.apply_modification(result) {
+                        TemplateSwitchCollision::Overlap { conflicting } => {
+                            debug!("{result} overlaps with the template switch at {conflicting:?}, retrying");
+                        }
+                        TemplateSwitchCollision::OutOfBounds => {
+                            debug!("{result} reaches outside the sequence including the margin, retrying");
+                        }
+                        TemplateSwitchCollision::Independent => break result,
+                    }
+                }
+            }
+        } else if modification_type == ModificationType::Gap {
+            let homopolymer_runs = homopolymer_runs.map(|runs| self.allowed_homopolymer_runs(runs));
+            let mut tries = 0;
+            let result = loop {
+                let result = self.gap(sequence_length, homopolymer_runs.as_deref(), rng)?;
+                let SequenceModification::Deletion { length, .. } = result else {
+                    break result;
+                };
+                let Some(min_sequence_length) =
+                    self.sequence_modification_parameters.min_sequence_length
+                else {
+                    break result;
+                };
+                if sequence_length - length >= min_sequence_length {
+                    break result;
+                }
 
-                    if !self
+                tries += 1;
+                if tries
+                    >= self
                         .sequence_modification_parameters
-                        .template_switch_overlap
-                    {
-                        template_switch_overlap_detector.apply_modification(result);
-                    }
-                    result
-                },
-            ))
+                        .min_sequence_length_tries
+                {
+                    return Err(Error::MinSequenceLengthNotKept {
+                        min_sequence_length,
+                        tries,
+                    });
+                }
+                debug!("{result} would shorten the sequence below {min_sequence_length} characters, retrying");
+            };
+
+            self.check_edit_cost(modification_type, &result)?;
+            if !self
+                .sequence_modification_parameters
+                .template_switch_overlap
+            {
+                template_switch_overlap_detector.apply_modification(result);
+            }
+            result
         } else {
-            Ok(None)
-        }
+            let result = if let (Some(reading_frame), Some(synonymous_fraction)) = (
+                self.sequence_modification_parameters.reading_frame,
+                self.sequence_modification_parameters.synonymous_fraction(),
+            ) {
+                self.codon_substitution(sequence, reading_frame, synonymous_fraction, rng)?
+            } else if self.sequence_modification_parameters.cpg_bias != 1.0 {
+                self.cpg_biased_substitution(sequence, rng)?
+            } else {
+                SequenceModification::Substitution {
+                    position: self.choose_position(0..sequence_length, 1, sequence_length, rng)?,
+                    character_increment: self.substitution_character_increment(alphabet_size, rng),
+                }
+            };
+
+            self.check_edit_cost(modification_type, &result)?;
+            if !self
+                .sequence_modification_parameters
+                .template_switch_overlap
+            {
+                template_switch_overlap_detector.apply_modification(result);
+            }
+            result
+        })
     }
 
     pub fn apply<
@@ -263,23 +638,659 @@ impl SequenceModifier {
     >(
         &mut self,
         sequence: &mut SequenceType,
+        sibling: &SequenceType,
         template_switch_overlap_detector: &mut TemplateSwitchOverlapDetector,
         rng: &mut impl Rng,
     ) -> Result<()> {
-        while let Some(modification) = self.next(
-            sequence.len(),
-            AlphabetType::SIZE,
-            template_switch_overlap_detector,
-            rng,
-        )? {
-            modification.apply(sequence)?;
+        // Copied intervals are staged in the same buffer for all modifications.
+        let mut buffer = Vec::new();
+        // The runs are found once and then updated with each modification.
+        let mut homopolymer_runs =
+            self.sequence_modification_parameters
+                .homopolymer_gaps
+                .then(|| {
+                    HomopolymerRuns::new(
+                        sequence,
+                        self.sequence_modification_parameters.homopolymer_min_length,
+                    )
+                });
+        loop {
+            // The runs are only needed if there are gaps left to generate.
+            if !self.may_draw_gaps() {
+                homopolymer_runs = None;
+            }
+
+            let modification = match self.next(
+                &*sequence,
+                sibling.len(),
+                homopolymer_runs.as_ref().map(HomopolymerRuns::runs),
+                template_switch_overlap_detector,
+                rng,
+            ) {
+                Ok(Some(modification)) => modification,
+                Ok(None) => break,
+                Err(
+                    error @ (Error::SequenceTooShortForTemplateSwitch { .. }
+                    | Error::SequenceTooShortForGap { .. }
+                    | Error::TemplateSwitchOverlap
+                    | Error::AllowedRegionsTooShort { .. }
+                    | Error::NoCodonSubstitution { .. }),
+                ) => {
+                    // The failed modification was already removed from the remaining amount.
+                    warn!("Skipping modification: {error}");
+                    continue;
+                }
+                // Cheaper modifications may still fit into the budget.
+                Err(error @ Error::EditCostBudgetExceeded { .. }) => {
+                    debug!("Skipping modification: {error}");
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            if let Some(budget) = &mut self.edit_cost_budget {
+                budget.remaining_cost -= modification.edit_cost(budget.template_switch_cost);
+            }
+
+            // The original character is only known before the substitution is applied.
+            let original_character = match modification {
+                SequenceModification::Substitution { position, .. }
+                    if self.substitutions.is_some() =>
+                {
+                    Some((position, sequence[position].clone()))
+                }
+                _ => None,
+            };
+
+            let applied_interval =
+                modification.apply_with_buffer(sequence, sibling, &mut buffer)?;
+            if let (Some(substitutions), Some((position, original_character))) =
+                (&mut self.substitutions, original_character)
+            {
+                substitutions.push((
+                    AlphabetType::character_to_ascii(original_character),
+                    AlphabetType::character_to_ascii(sequence[position].clone()),
+                ));
+            }
+            if let Some(homopolymer_runs) = &mut homopolymer_runs {
+                homopolymer_runs.apply_modification(&*sequence, applied_interval);
+            }
+            if let Some(allowed_regions) = &mut self.allowed_regions {
+                allowed_regions.apply_modification(&modification);
+            }
+            if let Some(rate_multipliers) = &mut self.rate_multipliers {
+                rate_multipliers.apply_modification(&modification);
+            }
+            if let Some(ancestry) = &mut self.ancestry {
+                ancestry.apply_modification(&modification);
+            }
+            if let Some((soft_mask, sibling_soft_mask)) = &mut self.soft_mask {
+                soft_mask.apply_modification(&modification, sibling_soft_mask);
+            }
+            if let Some(modifications) = &mut self.modifications {
+                modifications.push(modification);
+            }
+
+            match modification {
+                SequenceModification::TemplateSwitch { .. } => {
+                    self.realized_modification_amount.template_switch_amount += 1
+                }
+                SequenceModification::Insertion { .. } | SequenceModification::Deletion { .. } => {
+                    self.realized_modification_amount.gap_amount += 1
+                }
+                SequenceModification::Substitution { .. } => {
+                    self.realized_modification_amount.substitution_amount += 1
+                }
+            }
+        }
+
+        self.check_realized_modification_amount()
+    }
+
+    /// Draws the type of the next modification and removes it from the remaining amount,
+    /// or returns `None` if all modifications were generated.
+    ///
+    /// Without modification type probabilities, the type is drawn proportionally to the remaining amounts of each type.
+    fn choose_modification_type(&mut self, rng: &mut impl Rng) -> Option<ModificationType> {
+        if let Some(probabilities) = &mut self.modification_type_probabilities {
+            probabilities.remaining_amount = probabilities.remaining_amount.checked_sub(1)?;
+            return Some(
+                [
+                    ModificationType::TemplateSwitch,
+                    ModificationType::Gap,
+                    ModificationType::Substitution,
+                ][probabilities.distribution.sample(rng)],
+            );
+        }
+
+        let amount = &mut self.sequence_modification_amount;
+        let index =
+            (0..amount.template_switch_amount + amount.gap_amount + amount.substitution_amount)
+                .choose(rng)?;
+
+        Some(if index < amount.template_switch_amount {
+            amount.template_switch_amount -= 1;
+            ModificationType::TemplateSwitch
+        } else if index < amount.template_switch_amount + amount.gap_amount {
+            amount.gap_amount -= 1;
+            ModificationType::Gap
+        } else {
+            amount.substitution_amount -= 1;
+            ModificationType::Substitution
+        })
+    }
+
+    /// Returns true if gaps may still be drawn, either from the remaining amount or with a positive probability.
+    fn may_draw_gaps(&self) -> bool {
+        if let Some(probabilities) = &self.modification_type_probabilities {
+            probabilities.remaining_amount > 0 && probabilities.draws_gaps
+        } else {
+            self.sequence_modification_amount.gap_amount > 0
+        }
+    }
+
+    /// Returns an error if the edit cost of the modification exceeds the remaining budget,
+    /// and counts the modification as discarded.
+    fn check_edit_cost(
+        &mut self,
+        modification_type: ModificationType,
+        modification: &SequenceModification,
+    ) -> Result<()> {
+        let Some(budget) = &mut self.edit_cost_budget else {
+            return Ok(());
+        };
+        let cost = modification.edit_cost(budget.template_switch_cost);
+        if cost <= budget.remaining_cost {
+            return Ok(());
+        }
+
+        let discarded_amount = &mut budget.discarded_amount;
+        match modification_type {
+            ModificationType::TemplateSwitch => discarded_amount.template_switch_amount += 1,
+            ModificationType::Gap => discarded_amount.gap_amount += 1,
+            ModificationType::Substitution => discarded_amount.substitution_amount += 1,
+        }
+        Err(Error::EditCostBudgetExceeded {
+            modification: modification.to_string(),
+            cost,
+            remaining_cost: budget.remaining_cost,
+        })
+    }
+
+    /// Compares the realized against the requested amount of modifications.
+    ///
+    /// Modifications discarded by the edit cost budget are not requested anymore, so they are no shortfall.
+    /// Returns an error for the first shortfall if strict mode is enabled, and otherwise warns about every shortfall.
+    fn check_realized_modification_amount(&self) -> Result<()> {
+        let mut requested = self.requested_modification_amount;
+        let mut discarded_total = 0;
+        if let Some(budget) = &self.edit_cost_budget {
+            let discarded = &budget.discarded_amount;
+            requested.template_switch_amount = requested
+                .template_switch_amount
+                .saturating_sub(discarded.template_switch_amount);
+            requested.gap_amount = requested.gap_amount.saturating_sub(discarded.gap_amount);
+            requested.substitution_amount = requested
+                .substitution_amount
+                .saturating_sub(discarded.substitution_amount);
+            discarded_total = discarded.template_switch_amount
+                + discarded.gap_amount
+                + discarded.substitution_amount;
+        }
+
+        let requested = &requested;
+        let realized = &self.realized_modification_amount;
+        let (requested_total, realized_total) =
+            if let Some(probabilities) = &self.modification_type_probabilities {
+                (
+                    probabilities.requested_amount - discarded_total,
+                    realized.template_switch_amount
+                        + realized.gap_amount
+                        + realized.substitution_amount,
+                )
+            } else {
+                (0, 0)
+            };
+
+        for (modification_type, requested, realized) in [
+            ("modifications", requested_total, realized_total),
+            (
+                "template switches",
+                requested.template_switch_amount,
+                realized.template_switch_amount,
+            ),
+            ("gaps", requested.gap_amount, realized.gap_amount),
+            (
+                "substitutions",
+                requested.substitution_amount,
+                realized.substitution_amount,
+            ),
+        ] {
+            if realized < requested {
+                let error = Error::ModificationAmountNotReached {
+                    modification_type,
+                    requested,
+                    realized,
+                };
+
+                if self.sequence_modification_parameters.strict {
+                    return Err(error);
+                } else {
+                    warn!("{error}");
+                }
+            }
         }
 
         Ok(())
     }
+
+    /// Draws a substitution within a codon of the given reading frame
+    /// that is synonymous with probability `synonymous_fraction` and non-synonymous otherwise.
+    ///
+    /// Codons are counted from position `reading_frame` of the current sequence,
+    /// and characters outside of complete codons are never substituted.
+    fn codon_substitution<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        sequence: &SequenceType,
+        reading_frame: usize,
+        synonymous_fraction: f64,
+        rng: &mut impl Rng,
+    ) -> Result<SequenceModification> {
+        let synonymous = rng.gen_bool(synonymous_fraction);
+        let codon_amount = sequence.len().saturating_sub(reading_frame) / 3;
+        if codon_amount == 0 {
+            return Err(Error::NoCodonSubstitution { synonymous });
+        }
+
+        for _ in 0..MAXIMUM_CODON_SUBSTITUTION_TRIES {
+            let position = self.choose_position(
+                reading_frame..reading_frame + 3 * codon_amount,
+                1,
+                sequence.len(),
+                rng,
+            )?;
+            let codon_offset = (position - reading_frame) % 3;
+            let codon_start = position - codon_offset;
+            let codon: [u8; 3] =
+                std::array::from_fn(|index| sequence[codon_start + index].clone().into());
+            let Some(amino_acid) = translate_codon(codon) else {
+                continue;
+            };
+
+            let character_increments: Vec<_> = (1..AlphabetType::SIZE)
+                .filter(|character_increment| {
+                    let character_index =
+                        (sequence[position].index() + character_increment) % AlphabetType::SIZE;
+                    let mut substituted_codon = codon;
+                    substituted_codon[codon_offset] =
+                        AlphabetType::CharacterType::from_index(character_index)
+                            .unwrap()
+                            .into();
+                    (translate_codon(substituted_codon) == Some(amino_acid)) == synonymous
+                })
+                .collect();
+
+            if let Some(&character_increment) = character_increments.choose(rng) {
+                return Ok(SequenceModification::Substitution {
+                    position,
+                    character_increment,
+                });
+            }
+        }
+
+        Err(Error::NoCodonSubstitution { synonymous })
+    }
+
+    /// Draws a substitution where CpG sites are `cpg_bias` times as likely to be chosen as other positions.
+    ///
+    /// A chosen CpG site undergoes the transition C to T or G to A with probability `1 - 1 / cpg_bias`,
+    /// and otherwise a uniformly random substitution.
+    fn cpg_biased_substitution<
+        AlphabetType: Alphabet,
+        SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        sequence: &SequenceType,
+        rng: &mut impl Rng,
+    ) -> Result<SequenceModification> {
+        let cpg_bias = self.sequence_modification_parameters.cpg_bias;
+        let maximum_weight = cpg_bias.max(1.0);
+        let sequence_length = sequence.len();
+
+        let mut position = self.choose_position(0..sequence_length, 1, sequence_length, rng)?;
+        for _ in 0..MAXIMUM_CPG_REJECTIONS {
+            let weight = if is_cpg_site(sequence, position) {
+                cpg_bias
+            } else {
+                1.0
+            };
+            if rng.gen_bool(weight / maximum_weight) {
+                break;
+            }
+            position = self.choose_position(0..sequence_length, 1, sequence_length, rng)?;
+        }
+
+        let character_index = sequence[position].index();
+        let character_increment = if is_cpg_site(sequence, position)
+            && cpg_bias > 1.0
+            && rng.gen_bool(1.0 - 1.0 / cpg_bias)
+        {
+            let character: u8 = sequence[position].clone().into();
+            let target = if character.eq_ignore_ascii_case(&b'C') {
+                b'T'
+            } else {
+                b'A'
+            };
+            (1..AlphabetType::SIZE).find(|character_increment| {
+                let character: u8 = AlphabetType::CharacterType::from_index(
+                    (character_index + character_increment) % AlphabetType::SIZE,
+                )
+                .unwrap()
+                .into();
+                character.eq_ignore_ascii_case(&target)
+            })
+        } else {
+            None
+        };
+        let character_increment = character_increment
+            .unwrap_or_else(|| self.substitution_character_increment(AlphabetType::SIZE, rng));
+
+        Ok(SequenceModification::Substitution {
+            position,
+            character_increment,
+        })
+    }
+
+    /// Draws the increment of the index of a substituted character,
+    /// which is zero only if identity substitutions are allowed.
+    fn substitution_character_increment(&self, alphabet_size: usize, rng: &mut impl Rng) -> usize {
+        let min_character_increment = if self
+            .sequence_modification_parameters
+            .allow_identity_substitution
+        {
+            0
+        } else {
+            1
+        };
+        (min_character_increment..alphabet_size)
+            .choose(rng)
+            .unwrap()
+    }
+
+    /// Returns the parts of the given homopolymer runs within the allowed regions that are still long enough.
+    fn allowed_homopolymer_runs(&self, runs: &[Range<usize>]) -> Vec<Range<usize>> {
+        let Some(allowed_regions) = &self.allowed_regions else {
+            return runs.to_vec();
+        };
+        let minimum_length = self.sequence_modification_parameters.homopolymer_min_length;
+        let mut runs = allowed_regions.intersect(runs);
+        runs.retain(|run| run.len() >= minimum_length);
+        runs
+    }
+
+    /// Draws a gap, either within a homopolymer run if `homopolymer_runs` is given, or anywhere in the sequence.
+    fn gap(
+        &self,
+        sequence_length: usize,
+        homopolymer_runs: Option<&[Range<usize>]>,
+        rng: &mut impl Rng,
+    ) -> Result<SequenceModification> {
+        let homopolymer_run = homopolymer_runs.and_then(|runs| choose_homopolymer_run(runs, rng));
+        Ok(if let Some(run) = homopolymer_run {
+            let gap_length = (1..=run.len().div_ceil(2)).choose(rng).unwrap();
+
+            if rng.gen_bool(0.5) {
+                // Inserting a prefix of the run at any position within or adjacent to the run extends it.
+                SequenceModification::Insertion {
+                    position: (run.start..=run.end).choose(rng).unwrap(),
+                    source: run.start,
+                    length: gap_length,
+                }
+            } else {
+                SequenceModification::Deletion {
+                    position: (run.start..=run.end - gap_length).choose(rng).unwrap(),
+                    length: gap_length,
+                }
+            }
+        } else {
+            let gap_length =
+                sample_exponential(self.sequence_modification_parameters.gap_length_mean(), rng);
+            // `round` rounds half-way cases away from zero, which does not depend on the platform.
+            let gap_length = if gap_length < 1.0 {
+                1
+            } else {
+                gap_length.round() as usize
+            };
+
+            // Both insertion sources and deletions need a character outside of the gap.
+            if gap_length >= sequence_length {
+                return Err(Error::SequenceTooShortForGap {
+                    sequence_length,
+                    gap_length,
+                });
+            }
+
+            if rng.gen_bool(0.5) {
+                SequenceModification::Insertion {
+                    position: self.choose_position(0..sequence_length, 1, sequence_length, rng)?,
+                    source: (0..sequence_length - gap_length).choose(rng).unwrap(),
+                    length: gap_length,
+                }
+            } else {
+                SequenceModification::Deletion {
+                    position: self.choose_position(
+                        0..sequence_length - gap_length,
+                        gap_length,
+                        sequence_length,
+                        rng,
+                    )?,
+                    length: gap_length,
+                }
+            }
+        })
+    }
+
+    /// Chooses the offset of a template switch.
+    ///
+    /// If an upstream fraction is given, then the offset is negative with this probability,
+    /// and otherwise it is non-negative.
+    /// Within the chosen direction, or within the whole offset range if no fraction is given, the offset is uniform,
+    /// or decays exponentially with its magnitude if an offset decay is given.
+    fn choose_template_switch_offset(&self, rng: &mut impl Rng) -> isize {
+        let min_offset = self
+            .sequence_modification_parameters
+            .template_switch_min_offset;
+        let max_offset = self
+            .sequence_modification_parameters
+            .template_switch_max_offset;
+        let offsets = match self
+            .sequence_modification_parameters
+            .template_switch_upstream_fraction
+        {
+            None => min_offset..=max_offset,
+            Some(upstream_fraction) => {
+                if rng.gen_bool(upstream_fraction) {
+                    min_offset..=max_offset.min(-1)
+                } else {
+                    min_offset.max(0)..=max_offset
+                }
+            }
+        };
+
+        if let Some(decay) = self
+            .sequence_modification_parameters
+            .template_switch_offset_decay
+        {
+            let (start, end) = (*offsets.start(), *offsets.end());
+            // The magnitudes of the non-negative and of the negative offsets in ascending order, if there are any.
+            // On each side of zero, the offsets are distributed like a truncated geometric distribution of their magnitude.
+            let downstream = (end >= 0).then(|| start.max(0).unsigned_abs()..=end.unsigned_abs());
+            let upstream = (start < 0).then(|| end.min(-1).unsigned_abs()..=start.unsigned_abs());
+            // Weigh relative to the smallest magnitude, so the weights do not all underflow for ranges far from zero.
+            let min_magnitude = [&downstream, &upstream]
+                .into_iter()
+                .flatten()
+                .map(|magnitudes| *magnitudes.start())
+                .min()
+                .unwrap();
+            let side_weight = |magnitudes: &RangeInclusive<usize>| {
+                (-((magnitudes.start() - min_magnitude) as f64) / decay).exp()
+                    * -(-((magnitudes.end() - magnitudes.start() + 1) as f64) / decay).exp_m1()
+            };
+
+            let (magnitudes, is_upstream) = match (downstream, upstream) {
+                (Some(downstream), Some(upstream)) => {
+                    let downstream_weight = side_weight(&downstream);
+                    let upstream_weight = side_weight(&upstream);
+                    if rng.gen_bool(upstream_weight / (downstream_weight + upstream_weight)) {
+                        (upstream, true)
+                    } else {
+                        (downstream, false)
+                    }
+                }
+                (Some(downstream), None) => (downstream, false),
+                (None, Some(upstream)) => (upstream, true),
+                (None, None) => unreachable!("the offset range is not empty"),
+            };
+            let magnitude = magnitudes.start()
+                + truncated_geometric(magnitudes.end() - magnitudes.start() + 1, decay, rng);
+            if is_upstream {
+                -(magnitude as isize)
+            } else {
+                magnitude as isize
+            }
+        } else {
+            offsets.choose(rng).unwrap()
+        }
+    }
+
+    /// Chooses a position from `range` such that `length` characters starting from it may be modified.
+    ///
+    /// The range must not be empty.
+    /// The position is drawn according to the position bias and the rate multipliers.
+    fn choose_position<RngType: Rng>(
+        &self,
+        range: Range<usize>,
+        length: usize,
+        sequence_length: usize,
+        rng: &mut RngType,
+    ) -> Result<usize> {
+        let sample_candidate = |rng: &mut RngType| {
+            if let Some(allowed_regions) = &self.allowed_regions {
+                allowed_regions.choose(range.clone(), length, rng).ok_or(
+                    Error::AllowedRegionsTooShort {
+                        modification_length: length,
+                    },
+                )
+            } else {
+                Ok(range.clone().choose(rng).unwrap())
+            }
+        };
+
+        self.position_bias.choose(sequence_length, rng, |rng| {
+            if let Some(rate_multipliers) = &self.rate_multipliers {
+                rate_multipliers.choose(rng, sample_candidate)
+            } else {
+                sample_candidate(rng)
+            }
+        })
+    }
 }
 
 impl SequenceModification {
+    /// The position at which the modification starts.
+    pub fn position(&self) -> usize {
+        match *self {
+            SequenceModification::TemplateSwitch { position, .. }
+            | SequenceModification::Insertion { position, .. }
+            | SequenceModification::Deletion { position, .. }
+            | SequenceModification::Substitution { position, .. } => position,
+        }
+    }
+
+    /// Returns a copy of the modification that starts at the given position.
+    pub fn with_position(mut self, new_position: usize) -> Self {
+        match &mut self {
+            SequenceModification::TemplateSwitch { position, .. }
+            | SequenceModification::Insertion { position, .. }
+            | SequenceModification::Deletion { position, .. }
+            | SequenceModification::Substitution { position, .. } => *position = new_position,
+        }
+        self
+    }
+
+    /// Returns true if this is a template switch that copies from within the sequence
+    /// and whose source overlaps the interval it replaces.
+    ///
+    /// This does not depend on the position, since source and replaced interval move together.
+    pub fn is_self_overlapping(&self) -> bool {
+        match *self {
+            SequenceModification::TemplateSwitch {
+                length,
+                offset,
+                length_difference,
+                inter_sequence,
+                ..
+            } => {
+                !inter_sequence
+                    && template_switch_self_overlaps(
+                        length,
+                        offset,
+                        length as isize - length_difference,
+                    )
+            }
+            _ => false,
+        }
+    }
+
+    /// The edit cost of this modification, where each character of a template switch costs `template_switch_cost`.
+    ///
+    /// Substitutions cost one and gaps cost their length.
+    /// Template switches are charged for the longer of their copied and their replaced interval,
+    /// which is an upper bound of their edit distance.
+    pub fn edit_cost(&self, template_switch_cost: f64) -> f64 {
+        match *self {
+            SequenceModification::TemplateSwitch {
+                length,
+                length_difference,
+                ..
+            } => {
+                let replaced_length = length as isize - length_difference;
+                template_switch_cost * (length as isize).max(replaced_length) as f64
+            }
+            SequenceModification::Insertion { length, .. }
+            | SequenceModification::Deletion { length, .. } => length as f64,
+            SequenceModification::Substitution { .. } => 1.0,
+        }
+    }
+
+    /// The interval that this modification replaces, and the length of its replacement.
+    pub fn applied_interval(&self) -> AppliedInterval {
+        let (replaced_length, inserted_length) = match *self {
+            SequenceModification::TemplateSwitch {
+                length,
+                length_difference,
+                ..
+            } => ((length as isize - length_difference) as usize, length),
+            SequenceModification::Insertion { length, .. } => (0, length),
+            SequenceModification::Deletion { length, .. } => (length, 0),
+            SequenceModification::Substitution { .. } => (1, 1),
+        };
+        AppliedInterval {
+            position: self.position(),
+            replaced_length,
+            inserted_length,
+        }
+    }
+
+    /// Applies the modification to `sequence`, copying inter-sequence template switches from `sibling`.
+    ///
+    /// Returns the interval that was replaced, see [`Self::applied_interval`].
     pub fn apply<
         AlphabetType: Alphabet,
         SequenceType: EditableGenomeSequence<AlphabetType, SubsequenceType>,
@@ -287,23 +1298,54 @@ impl SequenceModification {
     >(
         &self,
         sequence: &mut SequenceType,
-    ) -> Result<()> {
+        sibling: &SequenceType,
+    ) -> Result<AppliedInterval> {
+        self.apply_with_buffer(sequence, sibling, &mut Vec::new())
+    }
+
+    /// Applies the modification like [`Self::apply`], but copies the characters of template switches and insertions through `buffer`.
+    ///
+    /// Reusing the same buffer for many modifications avoids allocating a new copy for each of them.
+    pub fn apply_with_buffer<
+        AlphabetType: Alphabet,
+        SequenceType: EditableGenomeSequence<AlphabetType, SubsequenceType>,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        sequence: &mut SequenceType,
+        sibling: &SequenceType,
+        buffer: &mut Vec<AlphabetType::CharacterType>,
+    ) -> Result<AppliedInterval> {
+        buffer.clear();
         match *self {
             SequenceModification::TemplateSwitch {
                 position,
                 length,
                 offset,
                 length_difference,
+                inter_sequence,
             } => {
-                let replacement: SequenceType = sequence
-                    .reverse_complement_iter()
-                    .skip(sequence.len() - (position as isize + offset + 1) as usize)
-                    .take(length)
-                    .collect();
-                sequence.splice(
-                    position..((position as isize + length as isize - length_difference) as usize),
-                    replacement,
+                // The replacement is copied before splicing, so a source that overlaps the replaced interval
+                // is read as it was before the template switch, see `--template-switch-forbid-self-overlap`.
+                let source = if inter_sequence { sibling } else { &*sequence };
+                let source_end = (position as isize + offset + 1) as usize;
+                buffer.extend(
+                    (source_end - length..source_end)
+                        .rev()
+                        .map(|index| source[index].complement()),
                 );
+                if length_difference == 0 {
+                    // Overwriting in place avoids moving the suffix of the sequence.
+                    for (index, character) in buffer.drain(..).enumerate() {
+                        sequence.set(position + index, character);
+                    }
+                } else {
+                    sequence.splice(
+                        position
+                            ..((position as isize + length as isize - length_difference) as usize),
+                        buffer.drain(..),
+                    );
+                }
             }
 
             SequenceModification::Insertion {
@@ -311,9 +1353,8 @@ impl SequenceModification {
                 source,
                 length,
             } => {
-                let insertion: SequenceType =
-                    sequence.iter().skip(source).take(length).cloned().collect();
-                sequence.splice(position..position, insertion);
+                buffer.extend((source..source + length).map(|index| sequence[index].clone()));
+                sequence.splice(position..position, buffer.drain(..));
             }
 
             SequenceModification::Deletion { position, length } => {
@@ -324,7 +1365,7 @@ impl SequenceModification {
                 position,
                 character_increment,
             } => {
-                assert!(character_increment > 0);
+                // An increment of zero is an identity substitution, which is only drawn if it is allowed.
                 let character_index = sequence[position].index();
                 let character_index = (character_index + character_increment) % AlphabetType::SIZE;
                 sequence.set(
@@ -334,11 +1375,156 @@ impl SequenceModification {
             }
         }
 
-        Ok(())
+        Ok(self.applied_interval())
+    }
+}
+
+/// The interval of a sequence that was replaced by a modification, and the length of its replacement.
+///
+/// Positions before the interval are unchanged by the modification,
+/// and positions after it are shifted by [`Self::length_delta`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppliedInterval {
+    pub position: usize,
+    pub replaced_length: usize,
+    pub inserted_length: usize,
+}
+
+impl AppliedInterval {
+    /// The interval of the modified sequence that holds the replacement.
+    pub fn inserted_range(&self) -> Range<usize> {
+        self.position..self.position + self.inserted_length
+    }
+
+    /// The change of the length of the sequence.
+    pub fn length_delta(&self) -> isize {
+        self.inserted_length as isize - self.replaced_length as isize
     }
 }
 
-fn split_int_random(int: usize, fraction: f64, rng: &mut impl Rng) -> (usize, usize) {
+/// Draws `k` from `0..amount` with a probability proportional to `exp(-k / decay)`.
+///
+/// This inverts the cumulative distribution function, so it takes constant time regardless of `amount`.
+fn truncated_geometric(amount: usize, decay: f64, rng: &mut impl Rng) -> usize {
+    // The probability of a value below `k` is `(1 - exp(-k / decay)) / (1 - exp(-amount / decay))`.
+    let uniform: f64 = rng.gen();
+    let k = -decay * (uniform * (-(amount as f64) / decay).exp_m1()).ln_1p();
+    (k as usize).min(amount - 1)
+}
+
+/// Returns true if the source of a template switch overlaps the interval it replaces within the same sequence.
+///
+/// The source of a template switch at `position` is `position + offset + 1 - length..position + offset + 1`,
+/// and it replaces `position..position + replaced_length`.
+pub fn template_switch_self_overlaps(length: usize, offset: isize, replaced_length: isize) -> bool {
+    length > 0
+        && replaced_length > 0
+        && offset >= 0
+        && offset + 1 < length as isize + replaced_length
+}
+
+/// Returns the positions at which a template switch can be placed in a sequence of length `sequence_length`,
+/// such that its source and its replaced interval lie within the sequence with a distance of at least `margin` to both ends.
+///
+/// The source of a template switch at `position` is the reverse complement of `position + offset + 1 - length..position + offset + 1`,
+/// and it replaces `position..position + length - length_difference`.
+/// Like the [`TemplateSwitchOverlapDetector`], this reserves one additional character before the source.
+pub fn template_switch_position_range(
+    sequence_length: usize,
+    length: usize,
+    offset: isize,
+    length_difference: isize,
+    margin: usize,
+) -> Result<Range<usize>> {
+    let overflow = |_| Error::TemplateSwitchPositionOverflow;
+    let sequence_length_signed = isize::try_from(sequence_length).map_err(overflow)?;
+    let length_signed = isize::try_from(length).map_err(overflow)?;
+    let margin = isize::try_from(margin).map_err(overflow)?;
+    let overflow = || Error::TemplateSwitchPositionOverflow;
+
+    let replaced_length = length_signed
+        .checked_sub(length_difference)
+        .ok_or_else(overflow)?;
+    if replaced_length < 0 {
+        return Err(Error::TemplateSwitchLengthDifferenceExceedsLength {
+            length,
+            length_difference,
+        });
+    }
+
+    // Relative to the position, the template switch touches the interval `before..after`.
+    let before = offset
+        .checked_sub(length_signed)
+        .ok_or_else(overflow)?
+        .min(0);
+    let after = offset
+        .checked_add(1)
+        .ok_or_else(overflow)?
+        .max(replaced_length)
+        .max(1);
+
+    let start = margin.checked_sub(before).ok_or_else(overflow)?;
+    let end = sequence_length_signed
+        .checked_sub(after)
+        .and_then(|end| end.checked_sub(margin))
+        .and_then(|end| end.checked_add(1))
+        .ok_or_else(overflow)?;
+
+    if start < end {
+        // Both bounds are positive.
+        Ok(start as usize..end as usize)
+    } else {
+        let template_switch_required_sequence_length = margin
+            .checked_mul(2)
+            .and_then(|required| required.checked_sub(before))
+            .and_then(|required| required.checked_add(after))
+            .ok_or_else(overflow)? as usize;
+        Err(Error::SequenceTooShortForTemplateSwitch {
+            sequence_length,
+            template_switch_required_sequence_length,
+        })
+    }
+}
+
+/// Returns true if the character at `position` is the C or the G of a CpG dinucleotide.
+fn is_cpg_site<
+    AlphabetType: Alphabet,
+    SequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    sequence: &SequenceType,
+    position: usize,
+) -> bool {
+    let character = |position: usize| -> u8 {
+        let character: u8 = sequence[position].clone().into();
+        character.to_ascii_uppercase()
+    };
+
+    match character(position) {
+        b'C' => position + 1 < sequence.len() && character(position + 1) == b'G',
+        b'G' => position > 0 && character(position - 1) == b'C',
+        _ => false,
+    }
+}
+
+/// Samples from the exponential distribution with the given mean by inversion.
+///
+/// This uses the software logarithm of `libm` instead of the logarithm of the platform,
+/// such that the result only depends on the random number generator.
+fn sample_exponential(mean: f64, rng: &mut impl Rng) -> f64 {
+    // A uniform sample from (0, 1] with 53 random bits, which is exact in an f64.
+    let uniform = ((rng.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    -mean * libm::log(uniform)
+}
+
+/// Splits `int` into two parts, where the first part is `fraction * int` and the second part is the rest.
+///
+/// The two parts always sum up to `int`.
+/// If `fraction * int` is not an integer, then both parts are rounded down, and the remaining unit is added
+/// to the first part with the probability of the fractional part of `fraction * int`, and to the second part otherwise.
+/// Hence the expected value of the first part is `fraction * int`, up to the rounding of the floating point arithmetic.
+/// Randomness is only consumed if there is a remaining unit.
+pub(crate) fn split_int_random(int: usize, fraction: f64, rng: &mut impl Rng) -> (usize, usize) {
     assert!(fraction >= 0.0);
     assert!(fraction <= 1.0);
     assert!(fraction.is_finite());
@@ -348,8 +1534,9 @@ fn split_int_random(int: usize, fraction: f64, rng: &mut impl Rng) -> (usize, us
     let amount1_int = amount1.floor() as usize;
     let amount2_int = amount2.floor() as usize;
 
-    if amount1_int + amount2_int == int - 1 {
-        if rng.gen_bool(fraction) {
+    if amount1_int + amount2_int + 1 == int {
+        // Rounding the first part up with the probability of its fractional part keeps its expected value.
+        if rng.gen_bool(amount1 - amount1_int as f64) {
             (amount1_int + 1, amount2_int)
         } else {
             (amount1_int, amount2_int + 1)
@@ -363,10 +1550,1342 @@ fn split_int_random(int: usize, fraction: f64, rng: &mut impl Rng) -> (usize, us
 impl std::fmt::Display for SequenceModification {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            SequenceModification::TemplateSwitch { position, length, offset, length_difference } => write!(f, "TS at {position} with offset = {offset}, length = {length} and length_difference = {length_difference}"),
+            SequenceModification::TemplateSwitch { position, length, offset, length_difference, inter_sequence } => write!(f, "{}TS at {position} with offset = {offset}, length = {length} and length_difference = {length_difference}", if *inter_sequence { "Inter-sequence " } else { "" }),
             SequenceModification::Insertion { position,  length, .. } => write!(f, "Insertion at {position} of length {length}"),
             SequenceModification::Deletion { position, length } => write!(f, "Deletion at {position} of length {length}"),
             SequenceModification::Substitution { position, .. } => write!(f, "Substitution at {position}"),
         }
     }
 }
+
+/// Returns the shortest sequence length that fits a single template switch,
+/// and the smallest range including margins that a template switch reserves in the [`TemplateSwitchOverlapDetector`].
+///
+/// Both are minimal for the longest valid length difference of each length,
+/// and for an offset at the ends of the offset range or at the ends of the interval of offsets
+/// whose source overlaps the replaced interval.
+pub fn smallest_template_switch(
+    parameters: &SequenceModificationParameters,
+    margin: usize,
+) -> Result<(usize, usize)> {
+    let (mut min_offset, mut max_offset) = (
+        parameters.template_switch_min_offset,
+        parameters.template_switch_max_offset,
+    );
+    // Offsets in the direction that is never drawn do not count.
+    match parameters.template_switch_upstream_fraction {
+        Some(1.0) => max_offset = max_offset.min(-1),
+        Some(0.0) => min_offset = min_offset.max(0),
+        _ => {}
+    }
+    let multiple = parameters.template_switch_length_difference_multiple();
+
+    let mut single_length = usize::MAX;
+    let mut packed_width = usize::MAX;
+    for length in parameters.template_switch_min_length()..=parameters.template_switch_max_length()
+    {
+        let length_signed =
+            isize::try_from(length).map_err(|_| Error::TemplateSwitchPositionOverflow)?;
+        let length_difference = parameters
+            .template_switch_max_length_difference
+            .min(length_signed)
+            .div_euclid(multiple)
+            * multiple;
+        if length_difference < parameters.template_switch_min_length_difference {
+            continue;
+        }
+        let replaced_length = length_signed - length_difference;
+
+        for offset in [
+            min_offset,
+            max_offset,
+            (replaced_length.max(1) - 1).clamp(min_offset, max_offset),
+            length_signed.clamp(min_offset, max_offset),
+        ] {
+            let required_length = match template_switch_position_range(
+                0,
+                length,
+                offset,
+                length_difference,
+                margin,
+            ) {
+                Err(Error::SequenceTooShortForTemplateSwitch {
+                    template_switch_required_sequence_length,
+                    ..
+                }) => template_switch_required_sequence_length,
+                Err(error) => return Err(error),
+                Ok(_) => unreachable!("no template switch fits into an empty sequence"),
+            };
+            single_length = single_length.min(required_length);
+
+            // A position after the source keeps the range from starting before the sequence.
+            let position = length
+                .checked_add(offset.unsigned_abs())
+                .and_then(|position| position.checked_add(margin))
+                .ok_or(Error::TemplateSwitchPositionOverflow)?;
+            let range = template_switch_range(position, length, offset, length_difference, margin)
+                .ok_or(Error::TemplateSwitchPositionOverflow)?;
+            packed_width = packed_width.min(range.len());
+        }
+    }
+
+    Ok((single_length, packed_width))
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use std::{collections::BTreeMap, ops::Range};
+
+    use clap::Parser;
+    use compact_genome::{
+        implementation::{
+            alphabets::dna_alphabet::{DnaAlphabet, DnaCharacter},
+            DefaultGenome,
+        },
+        interface::{
+            alphabet::AlphabetCharacter,
+            sequence::{GenomeSequence, OwnedGenomeSequence},
+        },
+    };
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use traitsequence::interface::Sequence;
+
+    use crate::{
+        cli::{SequenceModificationAmount, SequenceModificationParameters},
+        divergence::edit_distance,
+        error::Error,
+    };
+
+    use super::{
+        allowed_regions::AllowedRegions,
+        genetic_code::translate_codon,
+        position_bias::PositionBias,
+        rate_multipliers::RateMultipliers,
+        template_switch_overlap_detector::{
+            TemplateSwitchCollision, TemplateSwitchOverlapDetector,
+        },
+        AppliedInterval, SequenceModification, SequenceModifier,
+    };
+
+    #[derive(Parser)]
+    struct TestArguments {
+        #[command(flatten)]
+        sequence_modification_amount: SequenceModificationAmount,
+
+        #[command(flatten)]
+        sequence_modification_parameters: SequenceModificationParameters,
+    }
+
+    #[test]
+    fn masked_regions() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "0",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "100",
+        ]);
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&[b'A'; 200]).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            Some(AllowedRegions::new([0..200], [50..150], 200)),
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        assert_eq!(sequence[50..150].as_string(), ancestor[50..150].as_string());
+        assert_ne!(sequence.as_string(), ancestor.as_string());
+    }
+
+    #[test]
+    fn template_switches_in_allowed_regions() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "5",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "0",
+        ]);
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&[b'A'; 1000]).unwrap();
+        // The allowed regions have length 20, which is shorter than many template switches.
+        let masked_regions: Vec<_> = (0..40)
+            .map(|index| index * 25 + 20..index * 25 + 25)
+            .collect();
+        let allowed_regions = AllowedRegions::new([0..1000], masked_regions.clone(), 1000);
+
+        for seed in 0..10 {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let mut sequence = ancestor.clone();
+            let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                Some(allowed_regions.clone()),
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .reference_modifier;
+            sequence_modifier.track_modifications();
+            sequence_modifier
+                .apply(
+                    &mut sequence,
+                    &ancestor,
+                    &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                    &mut rng,
+                )
+                .unwrap();
+
+            // Template switches that do not fit are drawn again instead of being skipped.
+            let modifications = sequence_modifier.take_modifications().unwrap();
+            assert_eq!(modifications.len(), 5);
+            // The modifications are applied in order, so the replaced intervals are shifted by the earlier ones.
+            let mut allowed_regions = allowed_regions.clone();
+            for modification in modifications {
+                let AppliedInterval {
+                    position,
+                    replaced_length,
+                    ..
+                } = modification.applied_interval();
+                assert!(allowed_regions
+                    .choose(position..position + 1, replaced_length, &mut rng)
+                    .is_some());
+                allowed_regions.apply_modification(&modification);
+            }
+        }
+    }
+
+    #[test]
+    fn gap_length_equal_to_sequence_length() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from(["test", "--gap-amount", "100"]);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+
+        // Every gap is at least as long as a sequence of length one.
+        for _ in 0..100 {
+            assert!(matches!(
+                sequence_modifier.gap(1, None, &mut rng),
+                Err(Error::SequenceTooShortForGap {
+                    sequence_length: 1,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn inter_sequence_template_switch() {
+        let mut sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AAAAAAAAAA").unwrap();
+        let sibling = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"CCCCATCCCC").unwrap();
+
+        SequenceModification::TemplateSwitch {
+            position: 2,
+            length: 3,
+            offset: 4,
+            length_difference: 1,
+            inter_sequence: true,
+        }
+        .apply(&mut sequence, &sibling)
+        .unwrap();
+        assert_eq!(sequence.as_string(), "AAGATAAAAAA");
+    }
+
+    #[test]
+    fn repeated_substitution() {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AAAA").unwrap();
+        let mut sequence = ancestor.clone();
+        // A to C, and then C to T instead of A to G.
+        for character_increment in [1, 2] {
+            SequenceModification::Substitution {
+                position: 1,
+                character_increment,
+            }
+            .apply(&mut sequence, &ancestor)
+            .unwrap();
+        }
+        assert_eq!(sequence.as_string(), "ATAA");
+
+        // The increment wraps around the alphabet.
+        SequenceModification::Substitution {
+            position: 1,
+            character_increment: 3,
+        }
+        .apply(&mut sequence, &ancestor)
+        .unwrap();
+        assert_eq!(sequence.as_string(), "AGAA");
+    }
+
+    #[test]
+    fn applied_interval() {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"ACGTACGTAC").unwrap();
+        for (modification, expected) in [
+            (
+                SequenceModification::TemplateSwitch {
+                    position: 2,
+                    length: 3,
+                    offset: 4,
+                    length_difference: -1,
+                    inter_sequence: false,
+                },
+                AppliedInterval {
+                    position: 2,
+                    replaced_length: 4,
+                    inserted_length: 3,
+                },
+            ),
+            (
+                SequenceModification::Insertion {
+                    position: 10,
+                    source: 0,
+                    length: 2,
+                },
+                AppliedInterval {
+                    position: 10,
+                    replaced_length: 0,
+                    inserted_length: 2,
+                },
+            ),
+            (
+                SequenceModification::Deletion {
+                    position: 0,
+                    length: 3,
+                },
+                AppliedInterval {
+                    position: 0,
+                    replaced_length: 3,
+                    inserted_length: 0,
+                },
+            ),
+            (
+                SequenceModification::Substitution {
+                    position: 9,
+                    character_increment: 1,
+                },
+                AppliedInterval {
+                    position: 9,
+                    replaced_length: 1,
+                    inserted_length: 1,
+                },
+            ),
+        ] {
+            let mut sequence = ancestor.clone();
+            let applied_interval = modification.apply(&mut sequence, &ancestor).unwrap();
+            assert_eq!(applied_interval, expected);
+            assert_eq!(
+                sequence.len() as isize - ancestor.len() as isize,
+                applied_interval.length_delta()
+            );
+
+            // The characters around the applied interval are unchanged.
+            let inserted_range = applied_interval.inserted_range();
+            assert_eq!(
+                sequence[..inserted_range.start].as_string(),
+                ancestor[..expected.position].as_string()
+            );
+            assert_eq!(
+                sequence[inserted_range.end..].as_string(),
+                ancestor[expected.position + expected.replaced_length..].as_string()
+            );
+        }
+    }
+
+    #[test]
+    fn template_switch_position_range() {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGT".repeat(25)).unwrap();
+
+        // The previous computation allowed positions whose source starts before the sequence,
+        // or whose replaced interval ends after the sequence.
+        for (length, offset, length_difference, margin, expected) in [
+            (10, -30, 0, 0, 40..91),
+            (10, -30, 0, 10, 50..81),
+            (10, 30, 0, 0, 0..70),
+            (10, 0, -10, 0, 10..81),
+            (5, 50, 5, 0, 0..50),
+            (0, -1, 0, 0, 1..100),
+        ] {
+            let position_range = super::template_switch_position_range(
+                ancestor.len(),
+                length,
+                offset,
+                length_difference,
+                margin,
+            )
+            .unwrap();
+            assert_eq!(
+                position_range, expected,
+                "length: {length}, offset: {offset}, length_difference: {length_difference}, margin: {margin}"
+            );
+
+            for position in position_range {
+                let mut sequence = ancestor.clone();
+                SequenceModification::TemplateSwitch {
+                    position,
+                    length,
+                    offset,
+                    length_difference,
+                    inter_sequence: false,
+                }
+                .apply(&mut sequence, &ancestor)
+                .unwrap();
+                assert_eq!(
+                    sequence.len() as isize,
+                    ancestor.len() as isize + length_difference
+                );
+            }
+        }
+
+        assert!(matches!(
+            super::template_switch_position_range(20, 10, -5, 0, 10),
+            Err(Error::SequenceTooShortForTemplateSwitch {
+                sequence_length: 20,
+                template_switch_required_sequence_length: 45,
+            })
+        ));
+        assert!(matches!(
+            super::template_switch_position_range(100, 10, -5, 11, 0),
+            Err(Error::TemplateSwitchLengthDifferenceExceedsLength {
+                length: 10,
+                length_difference: 11,
+            })
+        ));
+        for (length, offset, length_difference) in [
+            (usize::MAX, 0, 0),
+            (10, isize::MIN, 0),
+            (10, isize::MAX, 0),
+            (10, 0, isize::MIN),
+        ] {
+            assert!(
+                matches!(
+                    super::template_switch_position_range(
+                        100,
+                        length,
+                        offset,
+                        length_difference,
+                        0
+                    ),
+                    Err(Error::TemplateSwitchPositionOverflow)
+                ),
+                "length: {length}, offset: {offset}, length_difference: {length_difference}"
+            );
+        }
+    }
+
+    #[test]
+    fn strict() {
+        for strict in [false, true] {
+            let mut arguments = vec![
+                "test",
+                "--template-switch-amount",
+                "1",
+                "--gap-amount",
+                "0",
+                "--substitution-amount",
+                "5",
+            ];
+            if strict {
+                arguments.push("--strict");
+            }
+            let TestArguments {
+                sequence_modification_amount,
+                sequence_modification_parameters,
+            } = TestArguments::parse_from(arguments);
+            // Too short for a template switch with the default margin.
+            let mut sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(&[b'A'; 20]).unwrap();
+            let sibling = sequence.clone();
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+            let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .reference_modifier;
+            let result = sequence_modifier.apply(
+                &mut sequence,
+                &sibling,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            );
+
+            assert_eq!(
+                sequence_modifier
+                    .realized_modification_amount
+                    .template_switch_amount,
+                0
+            );
+            assert_eq!(
+                sequence_modifier
+                    .realized_modification_amount
+                    .substitution_amount,
+                5
+            );
+            if strict {
+                assert!(matches!(
+                    result,
+                    Err(Error::ModificationAmountNotReached {
+                        requested: 1,
+                        realized: 0,
+                        ..
+                    })
+                ));
+            } else {
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn homopolymer_gaps() {
+        let prefix = b"ACGT".repeat(10);
+        let suffix = b"CGTA".repeat(10);
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(
+            &[prefix.as_slice(), &[b'A'; 100], suffix.as_slice()].concat(),
+        )
+        .unwrap();
+
+        // The gaps are either given as amount, or drawn with fixed probabilities.
+        for gap_amount in ["5", "0"] {
+            let TestArguments {
+                sequence_modification_amount,
+                sequence_modification_parameters,
+            } = TestArguments::parse_from([
+                "test",
+                "--template-switch-amount",
+                "0",
+                "--gap-amount",
+                gap_amount,
+                "--substitution-amount",
+                "0",
+                "--homopolymer-gaps",
+            ]);
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+            let mut sequence = ancestor.clone();
+            let mut modifier_pair = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            );
+            if gap_amount == "0" {
+                modifier_pair = modifier_pair
+                    .with_modification_type_probabilities(0.0, 5, [0.0, 1.0, 0.0], &mut rng)
+                    .unwrap();
+            }
+            let mut sequence_modifier = modifier_pair.reference_modifier;
+            sequence_modifier
+                .apply(
+                    &mut sequence,
+                    &ancestor,
+                    &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                    &mut rng,
+                )
+                .unwrap();
+
+            // All gaps lie within the poly-A tract.
+            let sequence = sequence.as_string();
+            assert_eq!(sequence_modifier.realized_modification_amount.gap_amount, 5);
+            assert!(sequence.starts_with(std::str::from_utf8(&prefix).unwrap()));
+            assert!(sequence.ends_with(std::str::from_utf8(&suffix).unwrap()));
+            assert!(sequence[prefix.len()..sequence.len() - suffix.len()]
+                .chars()
+                .all(|character| character == 'A'));
+        }
+    }
+
+    #[test]
+    fn min_sequence_length() {
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGT".repeat(25)).unwrap();
+        let shortest_length = |min_sequence_length: Option<&str>, seed| {
+            let mut arguments = vec![
+                "test",
+                "--template-switch-amount",
+                "0",
+                "--gap-amount",
+                "30",
+                "--substitution-amount",
+                "0",
+            ];
+            if let Some(min_sequence_length) = min_sequence_length {
+                arguments.extend(["--min-sequence-length", min_sequence_length]);
+            }
+            let TestArguments {
+                sequence_modification_amount,
+                sequence_modification_parameters,
+            } = TestArguments::parse_from(arguments);
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .reference_modifier;
+            let mut detector =
+                TemplateSwitchOverlapDetector::new(&sequence_modification_parameters);
+
+            // Apply the gaps one by one to observe the length after each of them.
+            let mut sequence = ancestor.clone();
+            let mut shortest_length = sequence.len();
+            while let Some(modification) = sequence_modifier
+                .next(&sequence, ancestor.len(), None, &mut detector, &mut rng)
+                .unwrap()
+            {
+                modification.apply(&mut sequence, &ancestor).unwrap();
+                shortest_length = shortest_length.min(sequence.len());
+            }
+            shortest_length
+        };
+
+        assert!((0..10).any(|seed| shortest_length(None, seed) < 95));
+        for seed in 0..10 {
+            assert!(shortest_length(Some("95"), seed) >= 95);
+        }
+    }
+
+    #[test]
+    fn identity_substitutions() {
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGTTACGCA".repeat(100)).unwrap();
+        let identity_amount = |extra_arguments: &[&str]| {
+            let mut arguments = vec![
+                "test",
+                "--template-switch-amount",
+                "0",
+                "--gap-amount",
+                "0",
+                "--substitution-amount",
+                "400",
+            ];
+            arguments.extend(extra_arguments);
+            let TestArguments {
+                sequence_modification_amount,
+                sequence_modification_parameters,
+            } = TestArguments::parse_from(arguments);
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+            let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .reference_modifier;
+            sequence_modifier.track_substitutions();
+            let mut detector =
+                TemplateSwitchOverlapDetector::new(&sequence_modification_parameters);
+
+            let mut sequence = ancestor.clone();
+            sequence_modifier
+                .apply(&mut sequence, &ancestor, &mut detector, &mut rng)
+                .unwrap();
+            let substitutions = sequence_modifier.take_substitutions().unwrap();
+            assert_eq!(substitutions.len(), 400);
+            substitutions
+                .iter()
+                .filter(|(original, substituted)| original == substituted)
+                .count()
+        };
+
+        // By default, every sampling path changes the substituted character.
+        assert_eq!(identity_amount(&[]), 0);
+        assert_eq!(identity_amount(&["--cpg-bias", "5"]), 0);
+        assert_eq!(
+            identity_amount(&["--reading-frame", "0", "--synonymous-fraction", "0.5"]),
+            0
+        );
+
+        // Identity substitutions are drawn like each of the other characters.
+        let allowed = identity_amount(&["--allow-identity-substitution"]);
+        assert!((60..140).contains(&allowed), "{allowed}");
+        assert!(identity_amount(&["--allow-identity-substitution", "--cpg-bias", "5"]) > 0);
+    }
+
+    #[test]
+    fn synonymous_substitutions() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "0",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "20",
+            "--reading-frame",
+            "1",
+            "--synonymous-only",
+        ]);
+        // Leucine and arginine codons can also change synonymously at the first position.
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(
+            &[b"A".as_slice(), &b"CTAAGAGCT".repeat(10)].concat(),
+        )
+        .unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        let translate = |sequence: &str| -> Vec<_> {
+            sequence.as_bytes()[1..]
+                .chunks(3)
+                .map(|codon| translate_codon(codon.try_into().unwrap()).unwrap())
+                .collect()
+        };
+        assert_eq!(
+            sequence_modifier
+                .realized_modification_amount
+                .substitution_amount,
+            20
+        );
+        assert_ne!(sequence.as_string(), ancestor.as_string());
+        assert_eq!(sequence.as_string()[..1], ancestor.as_string()[..1]);
+        assert_eq!(
+            translate(&sequence.as_string()),
+            translate(&ancestor.as_string())
+        );
+    }
+
+    #[test]
+    fn cpg_bias() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "0",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "20",
+            "--cpg-bias",
+            "1000",
+        ]);
+        // Two of ten positions are CpG sites.
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGTTTAAAT".repeat(20)).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        let sequence = sequence.as_string();
+        let ancestor = ancestor.as_string();
+        let substitutions: Vec<_> = ancestor
+            .bytes()
+            .zip(sequence.bytes())
+            .enumerate()
+            .filter(|(_, (ancestral, derived))| ancestral != derived)
+            .collect();
+        let transitions = substitutions
+            .iter()
+            .filter(|(_, substitution)| matches!(substitution, (b'C', b'T') | (b'G', b'A')))
+            .count();
+        assert!(substitutions
+            .iter()
+            .all(|(position, _)| matches!(position % 10, 1 | 2)));
+        assert!(transitions * 10 >= substitutions.len() * 9);
+        assert!(substitutions.len() >= 15);
+    }
+
+    #[test]
+    fn rate_multipliers() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "0",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "200",
+        ]);
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&[b'A'; 1000]).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            Some(RateMultipliers::new([(0..500, 1.0), (500..1000, 4.0)], 1000).unwrap()),
+            &mut rng,
+        )
+        .reference_modifier;
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        let substituted_amount = |range: Range<usize>| {
+            sequence[range]
+                .as_string()
+                .chars()
+                .filter(|character| *character != 'A')
+                .count()
+        };
+        let low = substituted_amount(0..500);
+        let high = substituted_amount(500..1000);
+        // The expected densities are 40 and 160 substitutions, minus repeated substitutions of the same position.
+        assert!(high > 3 * low, "{low} {high}");
+    }
+
+    #[test]
+    fn template_switch_upstream_fraction() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        for (upstream_fraction, min_offset, max_offset) in
+            [(None, -30, 9), (Some("0.8"), -30, 10), (Some("1.0"), -5, 5)]
+        {
+            let mut arguments = vec![
+                "test".to_string(),
+                format!("--template-switch-min-offset={min_offset}"),
+                format!("--template-switch-max-offset={max_offset}"),
+            ];
+            if let Some(upstream_fraction) = upstream_fraction {
+                arguments.push(format!(
+                    "--template-switch-upstream-fraction={upstream_fraction}"
+                ));
+            }
+            let TestArguments {
+                sequence_modification_amount,
+                sequence_modification_parameters,
+            } = TestArguments::parse_from(arguments);
+            let sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .reference_modifier;
+
+            let offsets: Vec<_> = (0..10_000)
+                .map(|_| sequence_modifier.choose_template_switch_offset(&mut rng))
+                .collect();
+            assert!(offsets
+                .iter()
+                .all(|offset| (min_offset..=max_offset).contains(offset)));
+            let realized_fraction =
+                offsets.iter().filter(|offset| **offset < 0).count() as f64 / offsets.len() as f64;
+            // Without a fraction, 30 of the 40 offsets are negative.
+            let expected_fraction = upstream_fraction
+                .map_or(0.75, |upstream_fraction| upstream_fraction.parse().unwrap());
+            assert!(
+                (realized_fraction - expected_fraction).abs() < 0.02,
+                "{realized_fraction} != {expected_fraction}"
+            );
+        }
+    }
+
+    #[test]
+    fn template_switch_offset_decay() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-min-offset=-30",
+            "--template-switch-max-offset=10",
+            "--template-switch-offset-decay=4",
+        ]);
+        let sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+
+        let amount = 100_000;
+        let mut counts = BTreeMap::new();
+        for _ in 0..amount {
+            *counts
+                .entry(sequence_modifier.choose_template_switch_offset(&mut rng))
+                .or_insert(0) += 1;
+        }
+
+        let weight = |offset: isize| (-(offset.unsigned_abs() as f64) / 4.0).exp();
+        let total_weight: f64 = (-30..=10).map(weight).sum();
+        for offset in -30..=10 {
+            let expected = weight(offset) / total_weight;
+            let realized = f64::from(counts.get(&offset).copied().unwrap_or(0)) / amount as f64;
+            assert!(
+                (realized - expected).abs() < 0.005,
+                "{offset}: {realized} != {expected}"
+            );
+        }
+        assert!(counts.keys().all(|offset| (-30..=10).contains(offset)));
+
+        // Offsets far from zero are weighted relative to the nearest one.
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-min-offset=-5000",
+            "--template-switch-max-offset=-4990",
+            "--template-switch-offset-decay=0.5",
+        ]);
+        let sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        let offsets: Vec<_> = (0..1000)
+            .map(|_| sequence_modifier.choose_template_switch_offset(&mut rng))
+            .collect();
+        assert!(offsets
+            .iter()
+            .all(|offset| (-4995..=-4990).contains(offset)));
+        assert!(offsets.iter().filter(|offset| **offset == -4990).count() > 800);
+
+        // The time per offset does not depend on the size of the offset range.
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-min-offset=-1000000000000",
+            "--template-switch-max-offset=1000000000000",
+            "--template-switch-offset-decay=2",
+        ]);
+        let sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        let offsets: Vec<_> = (0..100_000)
+            .map(|_| sequence_modifier.choose_template_switch_offset(&mut rng))
+            .collect();
+        assert!(offsets.iter().all(|offset| offset.abs() < 100));
+        assert!(offsets.iter().any(|offset| *offset < 0));
+        assert!(offsets.iter().any(|offset| *offset > 0));
+    }
+
+    #[test]
+    fn split_int_random() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        for int in [0, 1, 2, 3, 7, 10, 101, 1_000_000] {
+            for fraction in [0.0, 0.5, 1.0] {
+                let (amount1, amount2) = super::split_int_random(int, fraction, &mut rng);
+                assert_eq!(amount1 + amount2, int, "int: {int}, fraction: {fraction}");
+
+                if fraction == 0.0 {
+                    assert_eq!(amount1, 0);
+                } else if fraction == 1.0 {
+                    assert_eq!(amount2, 0);
+                } else {
+                    assert!(amount1.abs_diff(amount2) <= 1);
+                }
+            }
+        }
+
+        // The remaining unit goes to the first part with the probability of the fractional part of `fraction * int`.
+        for fraction in [0.25, 0.5, 0.9] {
+            let samples = 10_000;
+            let sum: usize = (0..samples)
+                .map(|_| {
+                    let (amount1, amount2) = super::split_int_random(3, fraction, &mut rng);
+                    assert_eq!(amount1 + amount2, 3);
+                    amount1
+                })
+                .sum();
+            let mean = sum as f64 / samples as f64;
+            assert!(
+                (mean - 3.0 * fraction).abs() < 0.02,
+                "fraction: {fraction}, mean: {mean}"
+            );
+        }
+    }
+
+    #[test]
+    fn modification_type_probabilities() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from(["test", "--template-switch-margin", "0"]);
+        let ancestor = DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGT".repeat(500)).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .with_modification_type_probabilities(0.0, 500, [0.0, 1.0, 4.0], &mut rng)
+        .unwrap()
+        .reference_modifier;
+        // The amounts of the individual types are ignored.
+        sequence_modifier.sequence_modification_amount = SequenceModificationAmount {
+            template_switch_amount: 0,
+            gap_amount: 0,
+            substitution_amount: 0,
+        };
+        sequence_modifier.requested_modification_amount =
+            sequence_modifier.sequence_modification_amount;
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        let realized = sequence_modifier.realized_modification_amount;
+        assert_eq!(realized.template_switch_amount, 0);
+        assert_eq!(realized.gap_amount + realized.substitution_amount, 500);
+        // The expected amount of gaps is 100 with a standard deviation of about 9.
+        assert!(
+            (70..130).contains(&realized.gap_amount),
+            "{}",
+            realized.gap_amount
+        );
+    }
+
+    #[test]
+    fn max_edit_distance() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "10",
+            "--gap-amount",
+            "100",
+            "--substitution-amount",
+            "100",
+        ]);
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGTTGCA".repeat(250)).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        for max_edit_distance in [0, 20, 100] {
+            let mut sequence = ancestor.clone();
+            let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+                0.0,
+                sequence_modification_amount,
+                sequence_modification_parameters,
+                None,
+                PositionBias::Uniform,
+                None,
+                &mut rng,
+            )
+            .with_max_edit_distance(0.0, max_edit_distance, 1.0, &mut rng)
+            .reference_modifier;
+            sequence_modifier
+                .apply(
+                    &mut sequence,
+                    &ancestor,
+                    &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                    &mut rng,
+                )
+                .unwrap();
+
+            let to_ascii =
+                |sequence: &DefaultGenome<DnaAlphabet>| sequence.as_string().into_bytes();
+            let edit_distance = edit_distance(&to_ascii(&ancestor), &to_ascii(&sequence));
+            assert!(
+                edit_distance <= max_edit_distance,
+                "{edit_distance} > {max_edit_distance}"
+            );
+            // The budget is reached long before the amounts are exhausted.
+            let discarded_amount = sequence_modifier.edit_cost_budget.unwrap().discarded_amount;
+            assert!(
+                discarded_amount.template_switch_amount
+                    + discarded_amount.gap_amount
+                    + discarded_amount.substitution_amount
+                    > 0
+            );
+        }
+    }
+
+    /// Modifications that exceed the budget are discarded without ending the loop or reserving a template switch range,
+    /// and are not reported as shortfall in strict mode.
+    #[test]
+    fn edit_cost_budget_discards_expensive_modifications() {
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "5",
+            "--template-switch-min-length",
+            "30",
+            "--template-switch-max-length",
+            "30",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "20",
+            "--strict",
+        ]);
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGTTGCA".repeat(250)).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .with_max_edit_distance(0.0, 25, 1.0, &mut rng)
+        .reference_modifier;
+        let mut template_switch_overlap_detector =
+            TemplateSwitchOverlapDetector::new(&sequence_modification_parameters);
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut template_switch_overlap_detector,
+                &mut rng,
+            )
+            .unwrap();
+
+        // Every template switch costs 30, but all substitutions fit into the budget.
+        let realized = sequence_modifier.realized_modification_amount;
+        assert_eq!(realized.template_switch_amount, 0);
+        assert_eq!(realized.substitution_amount, 20);
+        let budget = sequence_modifier.edit_cost_budget.unwrap();
+        assert_eq!(budget.discarded_amount.template_switch_amount, 5);
+        assert_eq!(budget.remaining_cost, 5.0);
+        assert_eq!(
+            template_switch_overlap_detector.apply_modification(
+                SequenceModification::TemplateSwitch {
+                    position: 1000,
+                    length: 30,
+                    offset: 0,
+                    length_difference: 0,
+                    inter_sequence: false,
+                }
+            ),
+            TemplateSwitchCollision::Independent
+        );
+    }
+
+    #[test]
+    fn self_overlapping_template_switch() {
+        // The source `3..7` overlaps the replaced interval `4..8`,
+        // and is copied as it was before the template switch.
+        let template_switch = SequenceModification::TemplateSwitch {
+            position: 4,
+            length: 4,
+            offset: 2,
+            length_difference: 0,
+            inter_sequence: false,
+        };
+        assert!(template_switch.is_self_overlapping());
+        let mut sequence = DefaultGenome::<DnaAlphabet>::from_slice_u8(b"AAACCCTGGTTT").unwrap();
+        let sibling = sequence.clone();
+        template_switch.apply(&mut sequence, &sibling).unwrap();
+        assert_eq!(sequence.as_string(), "AAACAGGGGTTT");
+
+        // A reused buffer with leftover characters gives the same result.
+        let mut buffered_sequence = sibling.clone();
+        let mut buffer = vec![DnaCharacter::from_index(0).unwrap(); 10];
+        template_switch
+            .apply_with_buffer(&mut buffered_sequence, &sibling, &mut buffer)
+            .unwrap();
+        assert_eq!(buffered_sequence, sequence);
+
+        // The source `0..4` ends right before the replaced interval.
+        assert!(!SequenceModification::TemplateSwitch {
+            position: 4,
+            length: 4,
+            offset: -1,
+            length_difference: 0,
+            inter_sequence: false,
+        }
+        .is_self_overlapping());
+        // The source `4..8` starts right after the empty replaced interval.
+        assert!(!SequenceModification::TemplateSwitch {
+            position: 4,
+            length: 4,
+            offset: 3,
+            length_difference: 4,
+            inter_sequence: false,
+        }
+        .is_self_overlapping());
+        assert!(!SequenceModification::TemplateSwitch {
+            position: 4,
+            length: 4,
+            offset: 2,
+            length_difference: 0,
+            inter_sequence: true,
+        }
+        .is_self_overlapping());
+
+        let TestArguments {
+            sequence_modification_amount,
+            sequence_modification_parameters,
+        } = TestArguments::parse_from([
+            "test",
+            "--template-switch-amount",
+            "50",
+            "--gap-amount",
+            "0",
+            "--substitution-amount",
+            "0",
+            "--template-switch-overlap",
+            "--template-switch-forbid-self-overlap",
+            "--template-switch-min-offset=-5",
+            "--template-switch-max-offset",
+            "5",
+        ]);
+        let ancestor =
+            DefaultGenome::<DnaAlphabet>::from_slice_u8(&b"ACGTTGCA".repeat(250)).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let mut sequence = ancestor.clone();
+        let mut sequence_modifier = SequenceModifier::new_modifier_pair(
+            0.0,
+            sequence_modification_amount,
+            sequence_modification_parameters,
+            None,
+            PositionBias::Uniform,
+            None,
+            &mut rng,
+        )
+        .reference_modifier;
+        sequence_modifier.track_modifications();
+        sequence_modifier
+            .apply(
+                &mut sequence,
+                &ancestor,
+                &mut TemplateSwitchOverlapDetector::new(&sequence_modification_parameters),
+                &mut rng,
+            )
+            .unwrap();
+
+        let modifications = sequence_modifier.take_modifications().unwrap();
+        assert_eq!(modifications.len(), 50);
+        assert!(modifications
+            .iter()
+            .all(|modification| !modification.is_self_overlapping()));
+    }
+}
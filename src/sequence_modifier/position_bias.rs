@@ -0,0 +1,117 @@
+use rand::Rng;
+
+use crate::error::Result;
+
+/// The maximum number of rejected candidates when drawing a biased position.
+/// If it is reached, then the last candidate is used regardless of its weight.
+const MAXIMUM_REJECTIONS: usize = 10_000;
+
+/// A bias of the positions of modifications along the sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionBias {
+    /// All positions are equally likely.
+    Uniform,
+    /// The density increases linearly towards both ends of the sequence.
+    Ends,
+    /// The density increases linearly towards the center of the sequence.
+    Center,
+    /// The density is a Gaussian kernel around the closest of the given positions.
+    Hotspots { positions: Vec<usize>, spread: f64 },
+}
+
+impl PositionBias {
+    /// The relative weight of `position` in a sequence of length `sequence_length`, between 0.0 and 1.0.
+    ///
+    /// The weight is computed without platform-dependent floating point functions.
+    pub fn weight(&self, position: usize, sequence_length: usize) -> f64 {
+        let relative_position = (position as f64 + 0.5) / sequence_length as f64;
+
+        match self {
+            PositionBias::Uniform => 1.0,
+            PositionBias::Ends => (2.0 * relative_position - 1.0).abs().min(1.0),
+            PositionBias::Center => 1.0 - (2.0 * relative_position - 1.0).abs().min(1.0),
+            PositionBias::Hotspots { positions, spread } => positions
+                .iter()
+                .map(|hotspot| {
+                    let distance = (position as f64 - *hotspot as f64) / spread;
+                    libm::exp(-0.5 * distance * distance)
+                })
+                .fold(0.0, f64::max),
+        }
+    }
+
+    /// Draws a position according to the bias by rejection sampling candidates from `sample_candidate`.
+    ///
+    /// For the uniform bias, the first candidate is returned without consuming further randomness.
+    pub fn choose<RngType: Rng>(
+        &self,
+        sequence_length: usize,
+        rng: &mut RngType,
+        mut sample_candidate: impl FnMut(&mut RngType) -> Result<usize>,
+    ) -> Result<usize> {
+        let mut candidate = sample_candidate(rng)?;
+        if *self == PositionBias::Uniform {
+            return Ok(candidate);
+        }
+
+        for _ in 0..MAXIMUM_REJECTIONS {
+            if rng.gen_bool(self.weight(candidate, sequence_length)) {
+                break;
+            }
+            candidate = sample_candidate(rng)?;
+        }
+
+        Ok(candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{seq::IteratorRandom, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::PositionBias;
+
+    /// Returns the fraction of sampled positions within `0..1000` that lie within `interval`.
+    fn sampled_fraction(position_bias: &PositionBias, interval: impl Fn(usize) -> bool) -> f64 {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let samples = 20_000;
+
+        let hits = (0..samples)
+            .filter(|_| {
+                let position = position_bias
+                    .choose(1000, &mut rng, |rng| Ok((0..1000).choose(rng).unwrap()))
+                    .unwrap();
+                interval(position)
+            })
+            .count();
+        hits as f64 / samples as f64
+    }
+
+    #[test]
+    fn density() {
+        let outer_quarters = |position: usize| !(250..750).contains(&position);
+
+        for (position_bias, expected) in [
+            (PositionBias::Uniform, 0.5),
+            (PositionBias::Ends, 0.75),
+            (PositionBias::Center, 0.25),
+        ] {
+            let fraction = sampled_fraction(&position_bias, outer_quarters);
+            assert!(
+                (fraction - expected).abs() < 0.02,
+                "{position_bias:?}: {fraction} != {expected}"
+            );
+        }
+
+        // About 68% of the density of a normal distribution lies within one standard deviation.
+        let fraction = sampled_fraction(
+            &PositionBias::Hotspots {
+                positions: vec![500],
+                spread: 50.0,
+            },
+            |position| (450..=550).contains(&position),
+        );
+        assert!((fraction - 0.68).abs() < 0.02, "{fraction} != 0.68");
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::Range,
+    path::Path,
+};
+
+use rand::Rng;
+
+use crate::error::{Error, Result};
+
+use super::SequenceModification;
+
+/// The maximum number of rejected candidates when drawing a position according to the rate multipliers.
+/// If it is reached, then the last candidate is used regardless of its multiplier.
+const MAXIMUM_REJECTIONS: usize = 10_000;
+
+/// A piecewise constant multiplier of the modification rate along a sequence.
+///
+/// Like the allowed regions, the intervals are tracked through the modifications applied to the sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateMultipliers {
+    /// Sorted intervals that partition the sequence, each with its multiplier.
+    intervals: Vec<(Range<usize>, f64)>,
+    maximum_multiplier: f64,
+}
+
+impl RateMultipliers {
+    /// Creates the rate multipliers from intervals that partition a sequence of the given length.
+    ///
+    /// The multipliers must be finite and non-negative, and at least one must be positive.
+    pub fn new(
+        intervals: impl IntoIterator<Item = (Range<usize>, f64)>,
+        sequence_length: usize,
+    ) -> Result<Self> {
+        let mut intervals: Vec<_> = intervals.into_iter().collect();
+        intervals.sort_unstable_by_key(|(interval, _)| interval.start);
+
+        let mut covered = 0;
+        for (interval, multiplier) in &intervals {
+            if interval.start != covered || interval.is_empty() {
+                return Err(Error::RateMultipliersNotPartitioning { position: covered });
+            }
+            if !multiplier.is_finite() || *multiplier < 0.0 {
+                return Err(Error::InvalidRateMultiplier(*multiplier));
+            }
+            covered = interval.end;
+        }
+        if covered != sequence_length {
+            return Err(Error::RateMultipliersNotPartitioning { position: covered });
+        }
+
+        let maximum_multiplier = intervals
+            .iter()
+            .map(|(_, multiplier)| *multiplier)
+            .fold(0.0, f64::max);
+        if maximum_multiplier == 0.0 {
+            return Err(Error::InvalidRateMultiplier(maximum_multiplier));
+        }
+
+        Ok(Self {
+            intervals,
+            maximum_multiplier,
+        })
+    }
+
+    /// Reads the intervals and their multipliers from a TSV file with the columns start, end and multiplier,
+    /// to be passed to [`Self::new`] once the sequence length is known.
+    ///
+    /// The intervals are zero-based and half-open, and must partition the sequence.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn read_tsv_file(path: &Path) -> Result<Vec<(Range<usize>, f64)>> {
+        let mut intervals = Vec::new();
+
+        for (line_index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = trimmed.split_whitespace();
+            let (Some(Ok(start)), Some(Ok(end)), Some(Ok(multiplier)), None) = (
+                columns.next().map(str::parse::<usize>),
+                columns.next().map(str::parse::<usize>),
+                columns.next().map(str::parse::<f64>),
+                columns.next(),
+            ) else {
+                return Err(Error::InvalidRateMultiplierLine {
+                    line_number: line_index + 1,
+                    line,
+                });
+            };
+
+            intervals.push((start..end, multiplier));
+        }
+
+        Ok(intervals)
+    }
+
+    /// The multiplier at the given position.
+    pub fn multiplier(&self, position: usize) -> f64 {
+        let index = self
+            .intervals
+            .partition_point(|(interval, _)| interval.end <= position)
+            .min(self.intervals.len() - 1);
+        self.intervals[index].1
+    }
+
+    /// Draws a position by rejection sampling candidates from `sample_candidate`,
+    /// such that the density of each position is proportional to its multiplier.
+    pub fn choose<RngType: Rng>(
+        &self,
+        rng: &mut RngType,
+        mut sample_candidate: impl FnMut(&mut RngType) -> Result<usize>,
+    ) -> Result<usize> {
+        let mut candidate = sample_candidate(rng)?;
+
+        for _ in 0..MAXIMUM_REJECTIONS {
+            if rng.gen_bool(self.multiplier(candidate) / self.maximum_multiplier) {
+                break;
+            }
+            candidate = sample_candidate(rng)?;
+        }
+
+        Ok(candidate)
+    }
+
+    /// Updates the intervals to the coordinates of the sequence after applying the given modification.
+    pub fn apply_modification(&mut self, modification: &SequenceModification) {
+        match *modification {
+            SequenceModification::TemplateSwitch {
+                position,
+                length,
+                length_difference,
+                ..
+            } => {
+                let replaced_length = (length as isize - length_difference) as usize;
+                self.delete(position, replaced_length);
+                self.insert(position, length);
+            }
+            SequenceModification::Insertion {
+                position, length, ..
+            } => self.insert(position, length),
+            SequenceModification::Deletion { position, length } => self.delete(position, length),
+            SequenceModification::Substitution { .. } => {
+                // No coordinate change.
+            }
+        }
+    }
+
+    /// Inserted characters get the multiplier of the interval that starts at or contains the position,
+    /// or of the last interval if they are appended.
+    fn insert(&mut self, position: usize, length: usize) {
+        let last_index = self.intervals.len() - 1;
+        for (index, (interval, _)) in self.intervals.iter_mut().enumerate() {
+            if interval.start > position {
+                interval.start += length;
+            }
+            if interval.end > position || (index == last_index && interval.end == position) {
+                interval.end += length;
+            }
+        }
+    }
+
+    /// Removes intervals that become empty, but never the last remaining interval.
+    fn delete(&mut self, position: usize, length: usize) {
+        let map = |coordinate: usize| {
+            if coordinate <= position {
+                coordinate
+            } else if coordinate >= position + length {
+                coordinate - length
+            } else {
+                position
+            }
+        };
+
+        for (interval, _) in &mut self.intervals {
+            *interval = map(interval.start)..map(interval.end);
+        }
+        if self
+            .intervals
+            .iter()
+            .any(|(interval, _)| !interval.is_empty())
+        {
+            self.intervals.retain(|(interval, _)| !interval.is_empty());
+        } else {
+            self.intervals.truncate(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, sequence_modifier::SequenceModification};
+
+    use super::RateMultipliers;
+
+    #[test]
+    fn coverage() {
+        assert!(RateMultipliers::new([(0..5, 1.0), (5..10, 2.0)], 10).is_ok());
+        assert!(matches!(
+            RateMultipliers::new([(0..5, 1.0), (6..10, 2.0)], 10),
+            Err(Error::RateMultipliersNotPartitioning { position: 5 })
+        ));
+        assert!(matches!(
+            RateMultipliers::new([(0..5, 1.0), (4..10, 2.0)], 10),
+            Err(Error::RateMultipliersNotPartitioning { position: 5 })
+        ));
+        assert!(matches!(
+            RateMultipliers::new([(0..5, 1.0)], 10),
+            Err(Error::RateMultipliersNotPartitioning { position: 5 })
+        ));
+        assert!(matches!(
+            RateMultipliers::new([(0..10, -1.0)], 10),
+            Err(Error::InvalidRateMultiplier(_))
+        ));
+    }
+
+    #[test]
+    fn tracking() {
+        let mut rate_multipliers =
+            RateMultipliers::new([(0..5, 1.0), (5..10, 2.0), (10..15, 3.0)], 15).unwrap();
+        rate_multipliers.apply_modification(&SequenceModification::Insertion {
+            position: 5,
+            source: 0,
+            length: 2,
+        });
+        rate_multipliers.apply_modification(&SequenceModification::Deletion {
+            position: 9,
+            length: 8,
+        });
+
+        assert_eq!(rate_multipliers.intervals, vec![(0..5, 1.0), (5..9, 2.0)]);
+        assert_eq!(rate_multipliers.multiplier(4), 1.0);
+        assert_eq!(rate_multipliers.multiplier(5), 2.0);
+        assert_eq!(rate_multipliers.multiplier(9), 2.0);
+    }
+}
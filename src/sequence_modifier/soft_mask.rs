@@ -0,0 +1,134 @@
+use super::SequenceModification;
+
+/// Tracks which characters of a sequence are soft-masked, i.e. written in lower case.
+///
+/// The mask is updated in the same way as the modifications change the sequence.
+/// Inserted characters are masked like the characters they are copied from.
+/// Template switches copy the mask of their source in reverse order,
+/// so a reverse-complemented masked region stays masked.
+/// The mask is not recomputed, hence e.g. a template switch that copies only a part of a masked repeat
+/// creates a masked fragment that does not correspond to a complete repeat.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SoftMask {
+    lowercase: Vec<bool>,
+}
+
+impl SoftMask {
+    pub fn new(lowercase: Vec<bool>) -> Self {
+        Self { lowercase }
+    }
+
+    /// Returns true if the character at `position` is soft-masked.
+    pub fn is_lowercase(&self, position: usize) -> bool {
+        self.lowercase[position]
+    }
+
+    /// Reverses the mask, e.g. when the sequence is reverse complemented.
+    pub fn reverse(&mut self) {
+        self.lowercase.reverse();
+    }
+
+    /// Updates the mask in the same way as `modification` changes the sequence.
+    ///
+    /// The `sibling` is the mask of the sibling sequence, which is the source of inter-sequence template switches.
+    pub fn apply_modification(&mut self, modification: &SequenceModification, sibling: &SoftMask) {
+        match *modification {
+            SequenceModification::TemplateSwitch {
+                position,
+                length,
+                offset,
+                length_difference,
+                inter_sequence,
+            } => {
+                let source = if inter_sequence { sibling } else { &*self };
+                let source_end = (position as isize + offset + 1) as usize;
+                let replacement: Vec<_> = source.lowercase[source_end - length..source_end]
+                    .iter()
+                    .rev()
+                    .copied()
+                    .collect();
+                let replaced_length = (length as isize - length_difference) as usize;
+                self.lowercase
+                    .splice(position..position + replaced_length, replacement);
+            }
+            SequenceModification::Insertion {
+                position,
+                source,
+                length,
+            } => {
+                let insertion = self.lowercase[source..source + length].to_vec();
+                self.lowercase.splice(position..position, insertion);
+            }
+            SequenceModification::Deletion { position, length } => {
+                self.lowercase.drain(position..position + length);
+            }
+            SequenceModification::Substitution { .. } => {
+                // Substitutions keep the case.
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::SoftMask;
+
+    fn mask(mask: &str) -> SoftMask {
+        SoftMask::new(mask.bytes().map(|character| character == b'x').collect())
+    }
+
+    #[test]
+    fn apply_modification() {
+        let sibling = mask("xxxxx.....");
+        for (modification, expected) in [
+            (
+                SequenceModification::Substitution {
+                    position: 2,
+                    character_increment: 1,
+                },
+                "..xx....x.",
+            ),
+            (
+                SequenceModification::Insertion {
+                    position: 1,
+                    source: 2,
+                    length: 3,
+                },
+                ".xx..xx....x.",
+            ),
+            (
+                SequenceModification::Deletion {
+                    position: 3,
+                    length: 2,
+                },
+                "..x...x.",
+            ),
+            (
+                SequenceModification::TemplateSwitch {
+                    position: 5,
+                    length: 4,
+                    offset: -1,
+                    length_difference: 1,
+                    inter_sequence: false,
+                },
+                "..xx..xx.x.",
+            ),
+            (
+                SequenceModification::TemplateSwitch {
+                    position: 5,
+                    length: 4,
+                    offset: -1,
+                    length_difference: 1,
+                    inter_sequence: true,
+                },
+                "..xx.xxxxx.",
+            ),
+        ] {
+            let mut soft_mask = mask("..xx....x.");
+            soft_mask.apply_modification(&modification, &sibling);
+            assert_eq!(soft_mask, mask(expected), "{modification}");
+        }
+    }
+}
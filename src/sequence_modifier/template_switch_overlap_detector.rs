@@ -1,19 +1,35 @@
-use std::{io::Write, ops::Range};
+use std::ops::Range;
 
-use crate::{cli::SequenceModificationParameters, error::Result};
+use crate::cli::SequenceModificationParameters;
 
 use super::SequenceModification;
 
+/// Detects template switches that overlap previous template switches of the same sequence.
+///
+/// The ranges of template switches are stored at their position in the ancestor,
+/// by undoing the modifications applied to the sequence before each template switch.
+/// Reference and query are different sequences, so their template switches do not constrain each other,
+/// and the detector is [reset](Self::reset) between them.
+/// Only the sources of inter-sequence template switches of the reference lie in the query,
+/// so they are kept as reserved ranges of the query.
 #[derive(Debug)]
 pub struct TemplateSwitchOverlapDetector {
+    /// The ranges of the template switches in the ancestor, sorted by position.
     template_switches: Vec<Range<usize>>,
-    modification_stack: Vec<SequenceModification>,
+    /// The sources of inter-sequence template switches including margin, in the coordinates of the sibling.
+    sibling_sources: Vec<Range<usize>>,
+    ancestor_frame: AncestorFrame,
     margin: usize,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum TemplateSwitchCollision {
-    Overlap,
+    /// The template switch overlaps with the existing template switch whose range (including margin) is `conflicting`.
+    Overlap {
+        conflicting: Range<usize>,
+    },
+    /// The template switch including its margin reaches outside of the sequence.
+    OutOfBounds,
     Independent,
 }
 
@@ -25,129 +41,401 @@ impl TemplateSwitchOverlapDetector {
     fn from_template_switch_margin(margin: usize) -> Self {
         Self {
             template_switches: Default::default(),
-            modification_stack: Default::default(),
+            sibling_sources: Default::default(),
+            ancestor_frame: Default::default(),
             margin,
         }
     }
 
-    pub fn clear_modification_stack(&mut self) {
-        self.modification_stack.clear();
+    /// Sets the margin that is applied to subsequent template switches.
+    ///
+    /// The margin may change between template switches if it is relative to the sequence length.
+    pub fn set_margin(&mut self, margin: usize) {
+        self.margin = margin;
+    }
+
+    /// Forgets all modifications and template switches, such that the detector can be reused for the next sequence.
+    ///
+    /// The sources of the inter-sequence template switches of the previous sequence stay reserved for the next sequence.
+    /// This requires that the sibling of the previous sequence was the unmodified ancestor of the next sequence,
+    /// such that the coordinates of the sources are ancestor coordinates of the next sequence.
+    pub fn reset(&mut self) {
+        let mut sibling_sources = std::mem::take(&mut self.sibling_sources);
+        sibling_sources.sort_unstable_by_key(|range| range.start);
+        // Sources may overlap each other, so they are merged to keep the reserved ranges disjoint.
+        self.template_switches.clear();
+        for source in sibling_sources {
+            match self.template_switches.last_mut() {
+                Some(last) if source.start <= last.end => last.end = last.end.max(source.end),
+                _ => self.template_switches.push(source),
+            }
+        }
+        self.ancestor_frame = Default::default();
     }
 
     pub fn apply_modification(
         &mut self,
         sequence_modification: SequenceModification,
     ) -> TemplateSwitchCollision {
-        match sequence_modification {
+        let collision = match sequence_modification {
             SequenceModification::TemplateSwitch {
                 position,
                 length,
                 offset,
                 length_difference,
+                inter_sequence,
             } => {
-                let range_offset =
-                    position.min((position as isize - length as isize + offset) as usize);
-                let range_limit = position
-                    .max((position as isize + length as isize - length_difference) as usize)
-                    .max((position as isize + offset) as usize);
-
-                debug_assert!(range_offset <= range_limit);
-                if range_offset < self.margin
-                    || range_offset > isize::MAX as usize
-                    || range_limit > isize::MAX as usize
-                {
-                    return TemplateSwitchCollision::Overlap;
-                }
-
-                let new_range = (range_offset - self.margin)..(range_limit + self.margin);
-
-                let new_range = self.modification_stack.iter().rev().fold(
-                    new_range,
-                    |mut new_range, sequence_modification| match *sequence_modification {
-                        SequenceModification::TemplateSwitch {
-                            position,
-                            length_difference,
-                            ..
-                        } => {
-                            if new_range.start > position {
-                                new_range.start = position
-                                    .max((new_range.start as isize - length_difference) as usize);
-                            }
-                            if new_range.end > position {
-                                new_range.end = position
-                                    .max((new_range.end as isize - length_difference) as usize);
-                            }
-                            new_range
-                        }
-
-                        SequenceModification::Insertion {
-                            position, length, ..
-                        } => {
-                            if new_range.start > position {
-                                new_range.start = position.max(new_range.start - length);
-                            }
-                            if new_range.end > position {
-                                new_range.end = position.max(new_range.end - length);
-                            }
-                            new_range
-                        }
-
-                        SequenceModification::Deletion { position, length } => {
-                            if new_range.start > position {
-                                new_range.start = position.max(new_range.start + length);
-                            }
-                            if new_range.end > position {
-                                new_range.end = position.max(new_range.end + length);
-                            }
-                            new_range
-                        }
-
-                        SequenceModification::Substitution { .. } => {
-                            // No range modification.
-                            new_range
-                        }
-                    },
-                );
+                let Some(new_range) =
+                    template_switch_range(position, length, offset, length_difference, self.margin)
+                else {
+                    return TemplateSwitchCollision::OutOfBounds;
+                };
+                // The source lies at the same coordinates in the sibling, and since the range includes the source,
+                // the source is within bounds as well.
+                let sibling_source = inter_sequence.then(|| {
+                    let source_end = position.checked_add_signed(offset).unwrap();
+                    source_end - length - self.margin..source_end + self.margin
+                });
+                let new_range = self.ancestor_frame.ancestor_position(new_range.start)
+                    ..self.ancestor_frame.ancestor_position(new_range.end);
 
+                // The ranges are disjoint, so their ends are sorted as well.
                 let insertion_offset = self
                     .template_switches
-                    .iter()
-                    .take_while(|range| range.end <= new_range.start)
-                    .count();
+                    .partition_point(|range| range.end <= new_range.start);
                 if let Some(range) = self.template_switches.get(insertion_offset) {
                     if new_range.start < range.end && range.start < new_range.end {
-                        TemplateSwitchCollision::Overlap
-                    } else {
-                        self.template_switches
-                            .insert(insertion_offset, new_range.clone());
-                        self.modification_stack.push(sequence_modification);
-                        TemplateSwitchCollision::Independent
+                        return TemplateSwitchCollision::Overlap {
+                            conflicting: range.clone(),
+                        };
                     }
-                } else {
-                    self.template_switches
-                        .insert(insertion_offset, new_range.clone());
-                    self.modification_stack.push(sequence_modification);
-                    TemplateSwitchCollision::Independent
                 }
+
+                self.template_switches.insert(insertion_offset, new_range);
+                self.sibling_sources.extend(sibling_source);
+                TemplateSwitchCollision::Independent
             }
 
-            SequenceModification::Insertion { .. }
-            | SequenceModification::Deletion { .. }
-            | SequenceModification::Substitution { .. } => {
-                self.modification_stack.push(sequence_modification);
+            _ => TemplateSwitchCollision::Independent,
+        };
 
-                TemplateSwitchCollision::Independent
+        // For the coordinates, each modification inserts or deletes its length difference at its position.
+        let applied_interval = sequence_modification.applied_interval();
+        let length_delta = applied_interval.length_delta();
+        if length_delta >= 0 {
+            self.ancestor_frame
+                .insert(applied_interval.position, length_delta.unsigned_abs());
+        } else {
+            self.ancestor_frame
+                .delete(applied_interval.position, length_delta.unsigned_abs());
+        }
+        collision
+    }
+}
+
+/// Maps positions of the modified sequence to positions of the ancestor.
+///
+/// Each character of the modified sequence is assigned the ancestor position directly after it:
+/// ancestral characters are assigned the position after their origin,
+/// and inserted characters the position assigned to the position before the insertion.
+/// Deleted characters are removed with their assigned positions.
+/// A position of the modified sequence is then mapped to the position assigned to the character before it.
+/// This is the same as undoing the modifications one by one, with positions within an insertion moved to its start,
+/// but takes logarithmic instead of linear time in the number of modifications.
+///
+/// The assigned positions are stored as runs of consecutive or equal positions in a treap ordered by the sequence,
+/// followed by the unmodified remainder of the ancestor, which is not stored.
+/// Ancestral characters keep their origin, so the frame also maps positions like [`Ancestry::ancestor_position`](super::ancestry::Ancestry::ancestor_position),
+/// see [`Self::next_ancestral_position`].
+#[derive(Debug)]
+pub struct AncestorFrame {
+    root: Option<Box<RunNode>>,
+    /// The ancestor position assigned to the first character of the unmodified remainder.
+    remainder_start: usize,
+    /// The state of the generator of the priorities of the treap.
+    priority_state: u64,
+}
+
+/// A run of characters of the modified sequence with consecutive or equal assigned ancestor positions.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    length: usize,
+    /// The ancestor position assigned to the first character.
+    first: usize,
+    /// True if the assigned positions are consecutive, i.e. the characters are ancestral.
+    ancestral: bool,
+}
+
+#[derive(Debug)]
+struct RunNode {
+    run: Run,
+    priority: u64,
+    /// The total length of the runs in this subtree.
+    length: usize,
+    /// True if this subtree contains an ancestral run.
+    contains_ancestral: bool,
+    left: Option<Box<RunNode>>,
+    right: Option<Box<RunNode>>,
+}
+
+impl Default for AncestorFrame {
+    fn default() -> Self {
+        Self {
+            root: None,
+            remainder_start: 1,
+            priority_state: 0,
+        }
+    }
+}
+
+impl AncestorFrame {
+    /// Returns the ancestor position of the given position of the modified sequence.
+    pub fn ancestor_position(&self, position: usize) -> usize {
+        let Some(mut index) = position.checked_sub(1) else {
+            return 0;
+        };
+
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            let left_length = subtree_length(&current.left);
+            if index < left_length {
+                node = current.left.as_deref();
+            } else if index - left_length < current.run.length {
+                return current.run.position(index - left_length);
+            } else {
+                index -= left_length + current.run.length;
+                node = current.right.as_deref();
             }
         }
+
+        self.remainder_start.saturating_add(index)
+    }
+
+    /// Returns the ancestor position of the first ancestral character at or after the given position of the modified sequence.
+    ///
+    /// Unlike [`Self::ancestor_position`], this skips inserted characters forwards,
+    /// and is the same as [`Ancestry::ancestor_position`](super::ancestry::Ancestry::ancestor_position) if the frame was modified in the same way as the ancestry.
+    pub fn next_ancestral_position(&self, position: usize) -> usize {
+        // The assigned position of an ancestral character is the position after its origin.
+        next_ancestral_position(&self.root, position).map_or_else(
+            || {
+                let remainder_index = position.saturating_sub(subtree_length(&self.root));
+                (self.remainder_start - 1).saturating_add(remainder_index)
+            },
+            |assigned_position| assigned_position - 1,
+        )
     }
 
-    pub fn write_modifications(&self, output: &mut impl Write) -> Result<()> {
-        for modification in &self.modification_stack {
-            writeln!(output, "{modification}")?;
+    /// Inserts `length` characters at `position` of the modified sequence.
+    pub fn insert(&mut self, position: usize, length: usize) {
+        if length == 0 {
+            return;
         }
 
-        Ok(())
+        let run = Run {
+            length,
+            first: self.ancestor_position(position),
+            ancestral: false,
+        };
+        self.store_remainder(position);
+        let root = self.root.take();
+        let (left, right) = self.split(root, position);
+        let node = self.new_node(run);
+        self.root = merge(merge(left, node), right);
+    }
+
+    /// Deletes `length` characters at `position` of the modified sequence.
+    pub fn delete(&mut self, position: usize, length: usize) {
+        self.store_remainder(position);
+        let end = position.saturating_add(length);
+        let stored_length = subtree_length(&self.root);
+        // Deleted characters of the unmodified remainder are skipped.
+        self.remainder_start = self
+            .remainder_start
+            .saturating_add(end.saturating_sub(stored_length));
+
+        let root = self.root.take();
+        let (left, right) = self.split(root, position);
+        let (_, right) = self.split(right, end.min(stored_length) - position);
+        self.root = merge(left, right);
+    }
+
+    /// Moves the characters of the unmodified remainder before `position` into the treap.
+    fn store_remainder(&mut self, position: usize) {
+        let stored_length = subtree_length(&self.root);
+        if position > stored_length {
+            let run = Run {
+                length: position - stored_length,
+                first: self.remainder_start,
+                ancestral: true,
+            };
+            self.remainder_start = self.remainder_start.saturating_add(run.length);
+            let node = self.new_node(run);
+            self.root = merge(self.root.take(), node);
+        }
     }
+
+    /// Splits the treap into the first `length` characters and the rest, splitting a run if necessary.
+    fn split(
+        &mut self,
+        node: Option<Box<RunNode>>,
+        length: usize,
+    ) -> (Option<Box<RunNode>>, Option<Box<RunNode>>) {
+        let Some(mut node) = node else {
+            return (None, None);
+        };
+
+        let left_length = subtree_length(&node.left);
+        if length <= left_length {
+            let (left, right) = self.split(node.left.take(), length);
+            node.left = right;
+            node.update_subtree();
+            (left, Some(node))
+        } else if length - left_length >= node.run.length {
+            let (left, right) =
+                self.split(node.right.take(), length - left_length - node.run.length);
+            node.right = left;
+            node.update_subtree();
+            (Some(node), right)
+        } else {
+            let (first, second) = node.run.split(length - left_length);
+            node.run = first;
+            let right = merge(self.new_node(second), node.right.take());
+            node.update_subtree();
+            (Some(node), right)
+        }
+    }
+
+    fn new_node(&mut self, run: Run) -> Option<Box<RunNode>> {
+        // SplitMix64, such that the shape of the treap is deterministic.
+        self.priority_state = self.priority_state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut priority = self.priority_state;
+        priority = (priority ^ (priority >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        priority = (priority ^ (priority >> 27)).wrapping_mul(0x94d049bb133111eb);
+        priority ^= priority >> 31;
+
+        Some(Box::new(RunNode {
+            run,
+            priority,
+            length: run.length,
+            contains_ancestral: run.ancestral,
+            left: None,
+            right: None,
+        }))
+    }
+}
+
+impl Run {
+    /// Returns the ancestor position assigned to the character at `index` of the run.
+    fn position(&self, index: usize) -> usize {
+        if self.ancestral {
+            self.first.saturating_add(index)
+        } else {
+            self.first
+        }
+    }
+
+    /// Splits the run into its first `length` characters and the rest.
+    fn split(self, length: usize) -> (Self, Self) {
+        (
+            Self { length, ..self },
+            Self {
+                length: self.length - length,
+                first: self.position(length),
+                ..self
+            },
+        )
+    }
+}
+
+impl RunNode {
+    /// Updates the length of the subtree and whether it contains an ancestral run from the children.
+    fn update_subtree(&mut self) {
+        self.length = subtree_length(&self.left)
+            .saturating_add(self.run.length)
+            .saturating_add(subtree_length(&self.right));
+        self.contains_ancestral = self.run.ancestral
+            || [&self.left, &self.right]
+                .into_iter()
+                .flatten()
+                .any(|child| child.contains_ancestral);
+    }
+}
+
+fn subtree_length(node: &Option<Box<RunNode>>) -> usize {
+    node.as_ref().map_or(0, |node| node.length)
+}
+
+/// Returns the assigned position of the first ancestral character at or after `index` of the subtree,
+/// or `None` if there is none.
+fn next_ancestral_position(node: &Option<Box<RunNode>>, index: usize) -> Option<usize> {
+    let node = node
+        .as_deref()
+        .filter(|node| node.contains_ancestral && index < node.length)?;
+
+    let left_length = subtree_length(&node.left);
+    if let Some(position) = next_ancestral_position(&node.left, index) {
+        return Some(position);
+    }
+    let run_index = index.saturating_sub(left_length);
+    if node.run.ancestral && run_index < node.run.length {
+        return Some(node.run.position(run_index));
+    }
+    next_ancestral_position(
+        &node.right,
+        index.saturating_sub(left_length.saturating_add(node.run.length)),
+    )
+}
+
+/// Concatenates two treaps.
+fn merge(left: Option<Box<RunNode>>, right: Option<Box<RunNode>>) -> Option<Box<RunNode>> {
+    match (left, right) {
+        (None, node) | (node, None) => node,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority >= right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update_subtree();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update_subtree();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Returns the range of the sequence that is touched by a template switch,
+/// i.e. the union of its source and its replaced interval, extended by `margin` on both sides.
+/// For inter-sequence template switches, the source lies at the same coordinates in the sibling sequence,
+/// so it is reserved in both sequences, see [`TemplateSwitchOverlapDetector::reset`].
+///
+/// Returns `None` if the range cannot be represented without overflow, lies partially before the start of the sequence,
+/// or if the template switch would replace an interval of negative length.
+pub fn template_switch_range(
+    position: usize,
+    length: usize,
+    offset: isize,
+    length_difference: isize,
+    margin: usize,
+) -> Option<Range<usize>> {
+    let position = isize::try_from(position).ok()?;
+    let length = isize::try_from(length).ok()?;
+
+    let source_start = position.checked_sub(length)?.checked_add(offset)?;
+    let source_end = position.checked_add(offset)?;
+    let replaced_end = position
+        .checked_add(length)?
+        .checked_sub(length_difference)?;
+    if replaced_end < position {
+        return None;
+    }
+
+    let start = usize::try_from(position.min(source_start)).ok()?;
+    let end = usize::try_from(position.max(source_end).max(replaced_end)).ok()?;
+    debug_assert!(start <= end);
+
+    Some(start.checked_sub(margin)?..end.checked_add(margin)?)
 }
 
 #[cfg(test)]
@@ -157,7 +445,51 @@ mod tests {
         template_switch_overlap_detector::TemplateSwitchCollision, SequenceModification,
     };
 
-    use super::TemplateSwitchOverlapDetector;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::{AncestorFrame, TemplateSwitchOverlapDetector};
+
+    /// Maps a position to the ancestor by undoing the modifications one by one in reverse order.
+    fn undo_modifications(mut position: usize, modifications: &[(usize, isize)]) -> usize {
+        for &(modification_position, inserted_length) in modifications.iter().rev() {
+            if position > modification_position {
+                position = modification_position.max(if inserted_length >= 0 {
+                    position.saturating_sub(inserted_length.unsigned_abs())
+                } else {
+                    position + inserted_length.unsigned_abs()
+                });
+            }
+        }
+        position
+    }
+
+    #[test]
+    fn ancestor_frame() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        for _ in 0..20 {
+            let mut ancestor_frame = AncestorFrame::default();
+            let mut modifications = Vec::new();
+            for _ in 0..100 {
+                let position = rng.gen_range(0..300);
+                let inserted_length: isize = rng.gen_range(-20..=20);
+                if inserted_length >= 0 {
+                    ancestor_frame.insert(position, inserted_length.unsigned_abs());
+                } else {
+                    ancestor_frame.delete(position, inserted_length.unsigned_abs());
+                }
+                modifications.push((position, inserted_length));
+
+                for position in 0..400 {
+                    assert_eq!(
+                        ancestor_frame.ancestor_position(position),
+                        undo_modifications(position, &modifications),
+                        "position {position} after {modifications:?}"
+                    );
+                }
+            }
+        }
+    }
 
     #[test]
     fn simple() {
@@ -168,6 +500,7 @@ mod tests {
                 length: 10,
                 offset: -5,
                 length_difference: 5,
+                inter_sequence: false,
             }),
             TemplateSwitchCollision::Independent
         );
@@ -178,6 +511,7 @@ mod tests {
                 length: 10,
                 offset: -5,
                 length_difference: 5,
+                inter_sequence: false,
             }),
             TemplateSwitchCollision::Independent
         );
@@ -188,6 +522,7 @@ mod tests {
                 length: 10,
                 offset: -5,
                 length_difference: -10,
+                inter_sequence: false,
             }),
             TemplateSwitchCollision::Independent
         );
@@ -223,6 +558,7 @@ mod tests {
                 length: 10,
                 offset: 20,
                 length_difference: -1,
+                inter_sequence: false,
             }),
             TemplateSwitchCollision::Independent
         );
@@ -252,6 +588,7 @@ mod tests {
                 length: 20,
                 offset: 10,
                 length_difference: 0,
+                inter_sequence: false,
             }),
             TemplateSwitchCollision::Independent
         );
@@ -266,12 +603,170 @@ mod tests {
                 length: 20,
                 offset: 10,
                 length_difference: 0,
+                inter_sequence: false,
             }),
-            TemplateSwitchCollision::Overlap
+            TemplateSwitchCollision::Overlap {
+                conflicting: 190..230
+            }
         );
         assert_eq!(
             tsod.template_switches.as_slice(),
             [25..65, 70..110, 115..170, 190..230, 240..290]
         );
     }
+
+    /// Template switches of the next sequence are independent of those of the previous sequence.
+    #[test]
+    fn reset() {
+        let template_switch = |position| SequenceModification::TemplateSwitch {
+            position,
+            length: 10,
+            offset: -5,
+            length_difference: 5,
+            inter_sequence: false,
+        };
+        let mut tsod = TemplateSwitchOverlapDetector::from_template_switch_margin(0);
+        assert_eq!(
+            tsod.apply_modification(SequenceModification::Insertion {
+                position: 0,
+                source: 0,
+                length: 100,
+            }),
+            TemplateSwitchCollision::Independent
+        );
+        assert_eq!(
+            tsod.apply_modification(template_switch(150)),
+            TemplateSwitchCollision::Independent
+        );
+        assert_eq!(tsod.template_switches.as_slice(), [35..55]);
+
+        // Without reset, the insertion maps the template switch to the same range.
+        assert_eq!(
+            tsod.apply_modification(template_switch(150)),
+            TemplateSwitchCollision::Overlap {
+                conflicting: 35..55
+            }
+        );
+
+        tsod.reset();
+        assert!(tsod.template_switches.is_empty());
+        assert_eq!(
+            tsod.apply_modification(template_switch(50)),
+            TemplateSwitchCollision::Independent
+        );
+        assert_eq!(tsod.template_switches.as_slice(), [35..55]);
+    }
+
+    /// The sources of inter-sequence template switches stay reserved in the sibling.
+    #[test]
+    fn reset_keeps_sibling_sources() {
+        let template_switch = |position, inter_sequence| SequenceModification::TemplateSwitch {
+            position,
+            length: 10,
+            offset: -5,
+            length_difference: 5,
+            inter_sequence,
+        };
+        let mut tsod = TemplateSwitchOverlapDetector::from_template_switch_margin(2);
+        for (position, inter_sequence) in [(50, true), (100, false), (150, true), (200, true)] {
+            assert_eq!(
+                tsod.apply_modification(template_switch(position, inter_sequence)),
+                TemplateSwitchCollision::Independent
+            );
+            // Moves the later template switches, but not their sources in the sibling.
+            assert_eq!(
+                tsod.apply_modification(SequenceModification::Insertion {
+                    position: 0,
+                    source: 0,
+                    length: 3,
+                }),
+                TemplateSwitchCollision::Independent
+            );
+        }
+
+        tsod.reset();
+        // The sources are 35..45, 135..145 and 185..195, and they were not moved by the insertions.
+        assert_eq!(
+            tsod.template_switches.as_slice(),
+            [33..47, 133..147, 183..197]
+        );
+        assert_eq!(
+            tsod.apply_modification(template_switch(140, false)),
+            TemplateSwitchCollision::Overlap {
+                conflicting: 133..147
+            }
+        );
+        assert_eq!(
+            tsod.apply_modification(template_switch(100, false)),
+            TemplateSwitchCollision::Independent
+        );
+
+        // The sources of the second sequence are not reserved for a third sequence.
+        tsod.reset();
+        assert!(tsod.template_switches.is_empty());
+    }
+
+    #[test]
+    fn extreme_values() {
+        let mut tsod = TemplateSwitchOverlapDetector::from_template_switch_margin(10);
+        for (position, length, offset, length_difference) in [
+            (usize::MAX, 10, -5, 5),
+            (50, usize::MAX, -5, 5),
+            (50, 10, isize::MIN, 5),
+            (50, 10, isize::MAX, 5),
+            (50, 10, -5, isize::MIN),
+            (50, 10, -5, isize::MAX),
+            (isize::MAX as usize, 10, 0, 0),
+            (5, 0, 0, 0),
+        ] {
+            assert_eq!(
+                tsod.apply_modification(SequenceModification::TemplateSwitch {
+                    position,
+                    length,
+                    offset,
+                    length_difference,
+                    inter_sequence: false,
+                }),
+                TemplateSwitchCollision::OutOfBounds,
+                "position: {position}, length: {length}, offset: {offset}, length_difference: {length_difference}",
+            );
+        }
+        assert!(tsod.template_switches.is_empty());
+
+        assert_eq!(
+            tsod.apply_modification(SequenceModification::Deletion {
+                position: 0,
+                length: usize::MAX,
+            }),
+            TemplateSwitchCollision::Independent
+        );
+        assert_eq!(
+            tsod.apply_modification(SequenceModification::Insertion {
+                position: 0,
+                source: 0,
+                length: usize::MAX,
+            }),
+            TemplateSwitchCollision::Independent
+        );
+        assert_eq!(
+            tsod.apply_modification(SequenceModification::TemplateSwitch {
+                position: 50,
+                length: 10,
+                offset: -5,
+                length_difference: isize::MIN + 1,
+                inter_sequence: false,
+            }),
+            TemplateSwitchCollision::OutOfBounds
+        );
+        assert_eq!(
+            tsod.apply_modification(SequenceModification::TemplateSwitch {
+                position: 50,
+                length: 10,
+                offset: -5,
+                length_difference: 5,
+                inter_sequence: false,
+            }),
+            TemplateSwitchCollision::Independent
+        );
+    }
 }
@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{error::Result, sequence_modifier::SequenceModification};
+
+/// The realized mutation spectrum of a generated pair, as stored by `--spectrum-output`.
+///
+/// All counts are summed over reference and query.
+#[derive(Serialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Spectrum {
+    /// The amount of substitutions by original and substituted character, with keys like `A>C`.
+    pub substitutions: BTreeMap<String, usize>,
+    /// The amount of insertions by length.
+    pub insertion_lengths: BTreeMap<usize, usize>,
+    /// The amount of deletions by length.
+    pub deletion_lengths: BTreeMap<usize, usize>,
+    /// The amount of template switches by offset.
+    pub template_switch_offsets: BTreeMap<isize, usize>,
+    /// The amount of template switches by length.
+    pub template_switch_lengths: BTreeMap<usize, usize>,
+}
+
+impl Spectrum {
+    /// Counts the given modifications and the original and substituted characters of the substitutions among them.
+    pub fn new<'modification>(
+        modifications: impl IntoIterator<Item = &'modification SequenceModification>,
+        substitutions: impl IntoIterator<Item = (u8, u8)>,
+    ) -> Self {
+        let mut spectrum = Self::default();
+        for modification in modifications {
+            match *modification {
+                SequenceModification::TemplateSwitch { length, offset, .. } => {
+                    *spectrum.template_switch_offsets.entry(offset).or_default() += 1;
+                    *spectrum.template_switch_lengths.entry(length).or_default() += 1;
+                }
+                SequenceModification::Insertion { length, .. } => {
+                    *spectrum.insertion_lengths.entry(length).or_default() += 1
+                }
+                SequenceModification::Deletion { length, .. } => {
+                    *spectrum.deletion_lengths.entry(length).or_default() += 1
+                }
+                SequenceModification::Substitution { .. } => {}
+            }
+        }
+        for (original, substituted) in substitutions {
+            let key = format!("{}>{}", char::from(original), char::from(substituted));
+            *spectrum.substitutions.entry(key).or_default() += 1;
+        }
+
+        spectrum
+    }
+
+    /// Writes the spectrum as TSV if the path ends with `.tsv`, and as JSON otherwise.
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|extension| extension == "tsv") {
+            self.write_tsv(&mut writer)?;
+        } else {
+            serde_json::to_writer_pretty(&mut writer, self)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes the spectrum as tab-separated lines, grouped into sections that start with `#`.
+    ///
+    /// Empty sections are omitted.
+    fn write_tsv(&self, mut writer: impl Write) -> Result<()> {
+        let mut is_first_section = true;
+        let mut write_section = |title: &str, counts: Vec<(String, usize)>| -> Result<()> {
+            if counts.is_empty() {
+                return Ok(());
+            }
+            if !is_first_section {
+                writeln!(writer)?;
+            }
+            is_first_section = false;
+            writeln!(writer, "# {title}\tcount")?;
+            for (key, count) in counts {
+                writeln!(writer, "{key}\t{count}")?;
+            }
+            Ok(())
+        };
+
+        write_section(
+            "Substitution",
+            self.substitutions
+                .iter()
+                .map(|(key, count)| (key.clone(), *count))
+                .collect(),
+        )?;
+        for (title, counts) in [
+            ("Insertion length", &self.insertion_lengths),
+            ("Deletion length", &self.deletion_lengths),
+            ("Template switch length", &self.template_switch_lengths),
+        ] {
+            write_section(
+                title,
+                counts
+                    .iter()
+                    .map(|(key, count)| (key.to_string(), *count))
+                    .collect(),
+            )?;
+        }
+        write_section(
+            "Template switch offset",
+            self.template_switch_offsets
+                .iter()
+                .map(|(key, count)| (key.to_string(), *count))
+                .collect(),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::Spectrum;
+
+    #[test]
+    fn count_modifications() {
+        let modifications = [
+            SequenceModification::TemplateSwitch {
+                position: 20,
+                length: 10,
+                offset: -5,
+                length_difference: 0,
+                inter_sequence: false,
+            },
+            SequenceModification::Insertion {
+                position: 3,
+                source: 10,
+                length: 2,
+            },
+            SequenceModification::Deletion {
+                position: 7,
+                length: 2,
+            },
+            SequenceModification::Deletion {
+                position: 8,
+                length: 2,
+            },
+            SequenceModification::Substitution {
+                position: 5,
+                character_increment: 1,
+            },
+        ];
+        let spectrum = Spectrum::new(&modifications, [(b'A', b'C'), (b'G', b'T'), (b'A', b'C')]);
+
+        let mut output = Vec::new();
+        spectrum.write_tsv(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Substitution\tcount\n\
+             A>C\t2\n\
+             G>T\t1\n\
+             \n\
+             # Insertion length\tcount\n\
+             2\t1\n\
+             \n\
+             # Deletion length\tcount\n\
+             2\t2\n\
+             \n\
+             # Template switch length\tcount\n\
+             10\t1\n\
+             \n\
+             # Template switch offset\tcount\n\
+             -5\t1\n"
+        );
+        assert_eq!(
+            serde_json::to_value(&spectrum).unwrap()["deletion-lengths"]["2"],
+            2
+        );
+    }
+}
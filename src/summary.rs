@@ -0,0 +1,383 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use log::warn;
+
+use crate::{
+    cli::SequenceModificationAmount,
+    divergence::Divergence,
+    error::{Error, Result},
+    sequence_modifier::SequenceModification,
+    template_switches::TemplateSwitchRecord,
+};
+
+/// The statistics of a generated pair, as stored by `--stats-output`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PairStats {
+    pub divergence: Divergence,
+    /// The modifications applied to reference and query together.
+    pub modification_amount: SequenceModificationAmount,
+}
+
+impl PairStats {
+    pub fn new<'modification>(
+        divergence: Divergence,
+        modifications: impl IntoIterator<Item = &'modification SequenceModification>,
+    ) -> Self {
+        let mut modification_amount = SequenceModificationAmount {
+            template_switch_amount: 0,
+            gap_amount: 0,
+            substitution_amount: 0,
+        };
+        for modification in modifications {
+            match modification {
+                SequenceModification::TemplateSwitch { .. } => {
+                    modification_amount.template_switch_amount += 1
+                }
+                SequenceModification::Insertion { .. } | SequenceModification::Deletion { .. } => {
+                    modification_amount.gap_amount += 1
+                }
+                SequenceModification::Substitution { .. } => {
+                    modification_amount.substitution_amount += 1
+                }
+            }
+        }
+
+        Self {
+            divergence,
+            modification_amount,
+        }
+    }
+
+    /// Writes the statistics as tab-separated key-value pairs.
+    pub fn write_tsv_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "reference_length\t{}",
+            self.divergence.reference_length
+        )?;
+        writeln!(writer, "query_length\t{}", self.divergence.query_length)?;
+        writeln!(writer, "edit_distance\t{}", self.divergence.edit_distance)?;
+        writeln!(writer, "identity\t{}", self.divergence.identity())?;
+        writeln!(
+            writer,
+            "template_switch_amount\t{}",
+            self.modification_amount.template_switch_amount
+        )?;
+        writeln!(
+            writer,
+            "gap_amount\t{}",
+            self.modification_amount.gap_amount
+        )?;
+        writeln!(
+            writer,
+            "substitution_amount\t{}",
+            self.modification_amount.substitution_amount
+        )?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads statistics written by [`Self::write_tsv_file`].
+    ///
+    /// The identity is not read, since it is derived from the other values.
+    pub fn read_tsv_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let invalid = || Error::InvalidStatsFile(path.to_path_buf());
+
+        let mut values = BTreeMap::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let (key, value) = line.split_once('\t').ok_or_else(invalid)?;
+            values.insert(key.to_string(), value.to_string());
+        }
+        let value = |key: &str| -> Result<usize> {
+            values
+                .get(key)
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(invalid)
+        };
+
+        Ok(Self {
+            divergence: Divergence {
+                reference_length: value("reference_length")?,
+                query_length: value("query_length")?,
+                edit_distance: value("edit_distance")?,
+            },
+            modification_amount: SequenceModificationAmount {
+                template_switch_amount: value("template_switch_amount")?,
+                gap_amount: value("gap_amount")?,
+                substitution_amount: value("substitution_amount")?,
+            },
+        })
+    }
+}
+
+/// The aggregate statistics of a set of generated pairs.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub pair_stats: Vec<PairStats>,
+    pub template_switches: Vec<TemplateSwitchRecord>,
+}
+
+impl Summary {
+    /// Reads the outputs of the pairs stored in the directory.
+    ///
+    /// Files whose names start with `stats_prefix` and end with `.tsv` are read as `--stats-output`,
+    /// and files whose names start with `template_switch_prefix` and end with `.json` as `--template-switch-json`.
+    /// Other TSV and JSON files are skipped with a warning, since they are likely other outputs of the pairs,
+    /// and all other files are ignored.
+    pub fn read_directory(
+        path: impl AsRef<Path>,
+        stats_prefix: &str,
+        template_switch_prefix: &str,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file_paths = fs::read_dir(path)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        // Reading in a fixed order makes the summary independent of the file system.
+        file_paths.sort();
+
+        let mut summary = Self::default();
+        for file_path in file_paths {
+            let file_name = file_path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .unwrap_or_default();
+            match file_path
+                .extension()
+                .and_then(|extension| extension.to_str())
+            {
+                Some("tsv") if file_name.starts_with(stats_prefix) => summary
+                    .pair_stats
+                    .push(PairStats::read_tsv_file(&file_path)?),
+                Some("json") if file_name.starts_with(template_switch_prefix) => {
+                    let records: Vec<TemplateSwitchRecord> =
+                        serde_json::from_reader(BufReader::new(File::open(&file_path)?))?;
+                    summary.template_switches.extend(records);
+                }
+                Some("tsv" | "json") => {
+                    warn!("Skipping {file_path:?}, since its name matches neither prefix")
+                }
+                _ => {}
+            }
+        }
+
+        if summary.pair_stats.is_empty() && summary.template_switches.is_empty() {
+            return Err(Error::EmptySummaryInput(path.to_path_buf()));
+        }
+        Ok(summary)
+    }
+
+    /// Writes the summary as tab-separated lines, grouped into sections that start with `#`.
+    ///
+    /// The divergences are summarised by their minimum, mean and maximum,
+    /// the modifications by their total amount and their mean amount per pair,
+    /// and the template switches by histograms of their length, offset and length difference.
+    pub fn write(&self, mut writer: impl Write) -> Result<()> {
+        writeln!(writer, "# Pairs")?;
+        writeln!(writer, "stats_files\t{}", self.pair_stats.len())?;
+        writeln!(
+            writer,
+            "template_switches\t{}",
+            self.template_switches.len()
+        )?;
+
+        if !self.pair_stats.is_empty() {
+            writeln!(writer, "\n# Divergence\tmin\tmean\tmax")?;
+            for (key, values) in [
+                (
+                    "edit_distance",
+                    self.pair_stats
+                        .iter()
+                        .map(|stats| stats.divergence.edit_distance as f64)
+                        .collect::<Vec<_>>(),
+                ),
+                (
+                    "identity",
+                    self.pair_stats
+                        .iter()
+                        .map(|stats| stats.divergence.identity())
+                        .collect(),
+                ),
+            ] {
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                writeln!(writer, "{key}\t{min}\t{mean}\t{max}")?;
+            }
+
+            writeln!(writer, "\n# Modifications\ttotal\tmean")?;
+            let mut total = SequenceModificationAmount {
+                template_switch_amount: 0,
+                gap_amount: 0,
+                substitution_amount: 0,
+            };
+            for stats in &self.pair_stats {
+                total.template_switch_amount += stats.modification_amount.template_switch_amount;
+                total.gap_amount += stats.modification_amount.gap_amount;
+                total.substitution_amount += stats.modification_amount.substitution_amount;
+            }
+            for (key, total) in [
+                ("template_switch_amount", total.template_switch_amount),
+                ("gap_amount", total.gap_amount),
+                ("substitution_amount", total.substitution_amount),
+            ] {
+                let mean = total as f64 / self.pair_stats.len() as f64;
+                writeln!(writer, "{key}\t{total}\t{mean}")?;
+            }
+        }
+
+        for (title, histogram) in [
+            (
+                "Template switch length",
+                histogram(self.template_switches.iter().map(|record| {
+                    isize::try_from(record.length).expect("template switch length fits into isize")
+                })),
+            ),
+            (
+                "Template switch offset",
+                histogram(self.template_switches.iter().map(|record| record.offset)),
+            ),
+            (
+                "Template switch length difference",
+                histogram(
+                    self.template_switches
+                        .iter()
+                        .map(|record| record.length_difference),
+                ),
+            ),
+        ] {
+            if !histogram.is_empty() {
+                writeln!(writer, "\n# {title}\tcount")?;
+                for (value, count) in histogram {
+                    writeln!(writer, "{value}\t{count}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts how often each value occurs.
+fn histogram(values: impl IntoIterator<Item = isize>) -> BTreeMap<isize, usize> {
+    let mut histogram = BTreeMap::new();
+    for value in values {
+        *histogram.entry(value).or_default() += 1;
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        divergence::Divergence,
+        sequence_modifier::SequenceModification,
+        template_switches::{template_switch_records, write_template_switch_json, DerivedSequence},
+    };
+
+    use super::{PairStats, Summary};
+
+    #[test]
+    fn summarize_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+
+        let template_switch = |length, offset| SequenceModification::TemplateSwitch {
+            position: 20,
+            length,
+            offset,
+            length_difference: 0,
+            inter_sequence: false,
+        };
+        let substitution = SequenceModification::Substitution {
+            position: 5,
+            character_increment: 1,
+        };
+        for (pair_index, edit_distance, reference_modifications, query_modifications) in [
+            (
+                0,
+                25,
+                vec![template_switch(10, -5), substitution],
+                vec![template_switch(10, 0)],
+            ),
+            (1, 50, vec![template_switch(12, -5)], vec![substitution]),
+        ] {
+            let stats = PairStats::new(
+                Divergence {
+                    reference_length: 100,
+                    query_length: 100,
+                    edit_distance,
+                },
+                reference_modifications.iter().chain(&query_modifications),
+            );
+            let path = directory.join(format!("stats.{pair_index}.tsv"));
+            stats.write_tsv_file(&path).unwrap();
+            assert_eq!(PairStats::read_tsv_file(&path).unwrap(), stats);
+
+            let records = template_switch_records(
+                100,
+                [
+                    (
+                        DerivedSequence::Reference,
+                        "reference",
+                        &reference_modifications,
+                    ),
+                    (DerivedSequence::Query, "query", &query_modifications),
+                ],
+            );
+            write_template_switch_json(
+                directory.join(format!("template-switches.{pair_index}.json")),
+                &records,
+            )
+            .unwrap();
+        }
+        fs::write(directory.join("pair.0.fa"), ">reference\nACGT\n").unwrap();
+        // Other outputs of the pairs are skipped.
+        fs::write(directory.join("spectrum.0.tsv"), "substitution\tA\tC\t1\n").unwrap();
+        fs::write(directory.join("manifest.0.json"), "{}").unwrap();
+
+        let mut output = Vec::new();
+        Summary::read_directory(directory, "stats", "template-switches")
+            .unwrap()
+            .write(&mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Pairs\n\
+             stats_files\t2\n\
+             template_switches\t3\n\
+             \n\
+             # Divergence\tmin\tmean\tmax\n\
+             edit_distance\t25\t37.5\t50\n\
+             identity\t0.5\t0.625\t0.75\n\
+             \n\
+             # Modifications\ttotal\tmean\n\
+             template_switch_amount\t3\t1.5\n\
+             gap_amount\t0\t0\n\
+             substitution_amount\t2\t1\n\
+             \n\
+             # Template switch length\tcount\n\
+             10\t2\n\
+             12\t1\n\
+             \n\
+             # Template switch offset\tcount\n\
+             -5\t2\n\
+             0\t1\n\
+             \n\
+             # Template switch length difference\tcount\n\
+             0\t3\n"
+        );
+
+        fs::write(directory.join("stats.2.tsv"), "edit_distance\t3\n").unwrap();
+        assert!(Summary::read_directory(directory, "stats", "template-switches").is_err());
+    }
+}
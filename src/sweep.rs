@@ -0,0 +1,378 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    cli::GeneratePairCommand,
+    error::{Error, Result},
+    manifest::FileHash,
+};
+
+/// A parameter of the generate-pair subcommand and the values it takes in a sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepParameter {
+    /// The long name of the argument without the leading dashes, e.g. `substitution-amount`.
+    pub key: String,
+    pub values: Vec<SweepValue>,
+}
+
+/// A value of a generate-pair parameter that can be swept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SweepValue {
+    TemplateSwitchAmount(usize),
+    GapAmount(usize),
+    SubstitutionAmount(usize),
+    ReferenceAncestryFraction(f64),
+    TemplateSwitchMinLength(usize),
+    TemplateSwitchMaxLength(usize),
+    TemplateSwitchMinOffset(isize),
+    TemplateSwitchMaxOffset(isize),
+    TemplateSwitchMinLengthDifference(isize),
+    TemplateSwitchMaxLengthDifference(isize),
+    TemplateSwitchMargin(usize),
+    InterSequenceTemplateSwitchProbability(f64),
+    GapLengthMean(f64),
+}
+
+impl FromStr for SweepParameter {
+    type Err = Error;
+
+    /// Parses `KEY=VALUE,VALUE,...`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, values) = s
+            .split_once('=')
+            .ok_or_else(|| Error::InvalidSweepParameter(s.to_string()))?;
+        let key = key.trim().trim_start_matches("--");
+        let values = values
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| SweepValue::parse(key, value, s))
+            .collect::<Result<Vec<_>>>()?;
+        if values.is_empty() {
+            return Err(Error::InvalidSweepParameter(s.to_string()));
+        }
+
+        Ok(Self {
+            key: key.to_string(),
+            values,
+        })
+    }
+}
+
+impl SweepValue {
+    /// Parses the value of the parameter with the given key, where `parameter` is the whole parameter for error messages.
+    fn parse(key: &str, value: &str, parameter: &str) -> Result<Self> {
+        fn parse<T: FromStr>(value: &str, parameter: &str) -> Result<T> {
+            value
+                .parse()
+                .map_err(|_| Error::InvalidSweepParameter(parameter.to_string()))
+        }
+
+        Ok(match key {
+            "template-switch-amount" => Self::TemplateSwitchAmount(parse(value, parameter)?),
+            "gap-amount" => Self::GapAmount(parse(value, parameter)?),
+            "substitution-amount" => Self::SubstitutionAmount(parse(value, parameter)?),
+            "reference-ancestry-fraction" => {
+                Self::ReferenceAncestryFraction(parse(value, parameter)?)
+            }
+            "template-switch-min-length" => Self::TemplateSwitchMinLength(parse(value, parameter)?),
+            "template-switch-max-length" => Self::TemplateSwitchMaxLength(parse(value, parameter)?),
+            "template-switch-min-offset" => Self::TemplateSwitchMinOffset(parse(value, parameter)?),
+            "template-switch-max-offset" => Self::TemplateSwitchMaxOffset(parse(value, parameter)?),
+            "template-switch-min-length-difference" => {
+                Self::TemplateSwitchMinLengthDifference(parse(value, parameter)?)
+            }
+            "template-switch-max-length-difference" => {
+                Self::TemplateSwitchMaxLengthDifference(parse(value, parameter)?)
+            }
+            "template-switch-margin" => Self::TemplateSwitchMargin(parse(value, parameter)?),
+            "inter-sequence-template-switch-probability" => {
+                Self::InterSequenceTemplateSwitchProbability(parse(value, parameter)?)
+            }
+            "gap-length-mean" => Self::GapLengthMean(parse(value, parameter)?),
+            _ => return Err(Error::UnsupportedSweepParameter(key.to_string())),
+        })
+    }
+
+    /// The long name of the argument without the leading dashes.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::TemplateSwitchAmount(_) => "template-switch-amount",
+            Self::GapAmount(_) => "gap-amount",
+            Self::SubstitutionAmount(_) => "substitution-amount",
+            Self::ReferenceAncestryFraction(_) => "reference-ancestry-fraction",
+            Self::TemplateSwitchMinLength(_) => "template-switch-min-length",
+            Self::TemplateSwitchMaxLength(_) => "template-switch-max-length",
+            Self::TemplateSwitchMinOffset(_) => "template-switch-min-offset",
+            Self::TemplateSwitchMaxOffset(_) => "template-switch-max-offset",
+            Self::TemplateSwitchMinLengthDifference(_) => "template-switch-min-length-difference",
+            Self::TemplateSwitchMaxLengthDifference(_) => "template-switch-max-length-difference",
+            Self::TemplateSwitchMargin(_) => "template-switch-margin",
+            Self::InterSequenceTemplateSwitchProbability(_) => {
+                "inter-sequence-template-switch-probability"
+            }
+            Self::GapLengthMean(_) => "gap-length-mean",
+        }
+    }
+
+    /// Sets this value in the given command.
+    ///
+    /// Amounts cannot be swept if the command replaces them by a rate or by `--total-modifications`.
+    pub fn apply(self, command: &mut GeneratePairCommand) -> Result<()> {
+        let amount = &mut command.sequence_modification_amount;
+        let rate = &command.sequence_modification_rate;
+        let total_modifications = command
+            .sequence_modification_probabilities
+            .total_modifications;
+        let parameters = &mut command.sequence_modification_parameters;
+        let conflict = |argument: &str| Error::SweepParameterConflict {
+            key: self.key().to_string(),
+            argument: argument.to_string(),
+        };
+        if total_modifications.is_some()
+            && matches!(
+                self,
+                Self::TemplateSwitchAmount(_) | Self::GapAmount(_) | Self::SubstitutionAmount(_)
+            )
+        {
+            return Err(conflict("total-modifications"));
+        }
+
+        match self {
+            Self::TemplateSwitchAmount(value) => {
+                if rate.template_switch_rate.is_some() {
+                    return Err(conflict("template-switch-rate"));
+                }
+                amount.template_switch_amount = value;
+            }
+            Self::GapAmount(value) => {
+                if rate.gap_rate.is_some() {
+                    return Err(conflict("gap-rate"));
+                }
+                amount.gap_amount = value;
+            }
+            Self::SubstitutionAmount(value) => {
+                if rate.substitution_rate.is_some() {
+                    return Err(conflict("substitution-rate"));
+                }
+                amount.substitution_amount = value;
+            }
+            Self::ReferenceAncestryFraction(value) => command.reference_ancestry_fraction = value,
+            Self::TemplateSwitchMinLength(value) => {
+                parameters.template_switch_min_length = Some(value)
+            }
+            Self::TemplateSwitchMaxLength(value) => {
+                parameters.template_switch_max_length = Some(value)
+            }
+            Self::TemplateSwitchMinOffset(value) => parameters.template_switch_min_offset = value,
+            Self::TemplateSwitchMaxOffset(value) => parameters.template_switch_max_offset = value,
+            Self::TemplateSwitchMinLengthDifference(value) => {
+                parameters.template_switch_min_length_difference = value
+            }
+            Self::TemplateSwitchMaxLengthDifference(value) => {
+                parameters.template_switch_max_length_difference = value
+            }
+            Self::TemplateSwitchMargin(value) => parameters.template_switch_margin = value,
+            Self::InterSequenceTemplateSwitchProbability(value) => {
+                parameters.inter_sequence_template_switch_probability = value
+            }
+            Self::GapLengthMean(value) => parameters.gap_length_mean = Some(value),
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for SweepValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::TemplateSwitchAmount(value)
+            | Self::GapAmount(value)
+            | Self::SubstitutionAmount(value)
+            | Self::TemplateSwitchMinLength(value)
+            | Self::TemplateSwitchMaxLength(value)
+            | Self::TemplateSwitchMargin(value) => write!(f, "{value}"),
+            Self::TemplateSwitchMinOffset(value)
+            | Self::TemplateSwitchMaxOffset(value)
+            | Self::TemplateSwitchMinLengthDifference(value)
+            | Self::TemplateSwitchMaxLengthDifference(value) => write!(f, "{value}"),
+            Self::ReferenceAncestryFraction(value)
+            | Self::InterSequenceTemplateSwitchProbability(value)
+            | Self::GapLengthMean(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Returns the cartesian product of the values of the given parameters.
+///
+/// Each combination assigns one value to each parameter, in the order of the parameters.
+/// The combinations are ordered such that the value of the last parameter changes fastest.
+pub fn combinations(parameters: &[SweepParameter]) -> Vec<Vec<(&str, SweepValue)>> {
+    parameters
+        .iter()
+        .fold(vec![Vec::new()], |combinations, parameter| {
+            combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    parameter.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((parameter.key.as_str(), *value));
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+/// A record of a run of the sweep subcommand that ties each combination of parameters to its outputs.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SweepManifest {
+    pub version: &'static str,
+    pub random_seed: u64,
+    pub combinations: Vec<SweepCombination>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SweepCombination {
+    pub index: usize,
+    pub directory: PathBuf,
+    /// The seed of the combination, derived from the seed of the sweep and the index.
+    pub random_seed: u64,
+    pub parameters: BTreeMap<String, String>,
+    /// The written output files, in the order of [`crate::cli::GeneratePairCommand::output_paths`].
+    pub outputs: Vec<FileHash>,
+}
+
+impl SweepManifest {
+    pub fn write_json_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli::{Cli, CliCommands},
+        error::Error,
+    };
+
+    use super::{combinations, SweepParameter, SweepValue};
+    use clap::Parser;
+
+    #[test]
+    fn parameter_combinations() {
+        let parameters: Vec<SweepParameter> = [
+            "substitution-amount=10,50,100",
+            "--reference-ancestry-fraction=0.3, 0.5",
+        ]
+        .into_iter()
+        .map(|parameter| parameter.parse().unwrap())
+        .collect();
+        assert_eq!(parameters[1].key, "reference-ancestry-fraction");
+        assert_eq!(
+            parameters[1].values,
+            [
+                SweepValue::ReferenceAncestryFraction(0.3),
+                SweepValue::ReferenceAncestryFraction(0.5)
+            ]
+        );
+
+        let combinations = combinations(&parameters);
+        assert_eq!(combinations.len(), 6);
+        assert_eq!(
+            combinations[0],
+            [
+                ("substitution-amount", SweepValue::SubstitutionAmount(10)),
+                (
+                    "reference-ancestry-fraction",
+                    SweepValue::ReferenceAncestryFraction(0.3)
+                )
+            ]
+        );
+        assert_eq!(
+            combinations[1],
+            [
+                ("substitution-amount", SweepValue::SubstitutionAmount(10)),
+                (
+                    "reference-ancestry-fraction",
+                    SweepValue::ReferenceAncestryFraction(0.5)
+                )
+            ]
+        );
+        assert_eq!(
+            combinations[5],
+            [
+                ("substitution-amount", SweepValue::SubstitutionAmount(100)),
+                (
+                    "reference-ancestry-fraction",
+                    SweepValue::ReferenceAncestryFraction(0.5)
+                )
+            ]
+        );
+        assert_eq!(combinations[5][1].1.to_string(), "0.5");
+
+        assert!("substitution-amount".parse::<SweepParameter>().is_err());
+        assert!("substitution-amount=".parse::<SweepParameter>().is_err());
+        assert!("substitution-amount=-1".parse::<SweepParameter>().is_err());
+        assert!("=1,2".parse::<SweepParameter>().is_err());
+        assert!(matches!(
+            "output=a.fa".parse::<SweepParameter>(),
+            Err(Error::UnsupportedSweepParameter(key)) if key == "output"
+        ));
+    }
+
+    #[test]
+    fn apply_values() {
+        let Cli {
+            command: CliCommands::GeneratePair(mut command),
+        } = Cli::parse_from([
+            "tsgen",
+            "generate-pair",
+            "--model",
+            "model.bin",
+            "--ancestor-length",
+            "1000",
+            "--output",
+            "pair.fa",
+            "--gap-rate",
+            "0.01",
+        ])
+        else {
+            unreachable!();
+        };
+
+        SweepValue::SubstitutionAmount(20)
+            .apply(&mut command)
+            .unwrap();
+        SweepValue::TemplateSwitchMinOffset(-50)
+            .apply(&mut command)
+            .unwrap();
+        SweepValue::GapLengthMean(2.5).apply(&mut command).unwrap();
+        assert_eq!(command.sequence_modification_amount.substitution_amount, 20);
+        let parameters = &command.sequence_modification_parameters;
+        assert_eq!(parameters.template_switch_min_offset, -50);
+        assert_eq!(parameters.gap_length_mean, Some(2.5));
+
+        // The gap amount is replaced by the gap rate.
+        assert!(matches!(
+            SweepValue::GapAmount(5).apply(&mut command),
+            Err(Error::SweepParameterConflict { key, argument })
+                if key == "gap-amount" && argument == "gap-rate"
+        ));
+    }
+}
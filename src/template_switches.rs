@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    sequence_modifier::{ancestry::Ancestry, SequenceModification},
+};
+
+/// The derived sequence in which a template switch occurred.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DerivedSequence {
+    Reference,
+    Query,
+}
+
+/// The strand from which a template switch copies its replacement.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemplateSwitchStrand {
+    /// The replacement is the reverse complement of the source, which is the case for all generated template switches.
+    ReverseComplement,
+}
+
+/// The ground truth of an applied template switch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct TemplateSwitchRecord {
+    pub sequence: DerivedSequence,
+    pub record_id: String,
+    /// The start of the interval of the ancestor that the template switch replaces.
+    pub ancestor_start: usize,
+    /// The end of the interval of the ancestor that the template switch replaces.
+    ///
+    /// The interval includes ancestral characters that were deleted before the template switch was applied.
+    pub ancestor_end: usize,
+    /// The position of the template switch in the derived sequence at the time it was applied.
+    pub position: usize,
+    pub length: usize,
+    pub offset: isize,
+    pub length_difference: isize,
+    pub strand: TemplateSwitchStrand,
+    /// If set, the replacement was copied from the sibling sequence instead of from the sequence itself.
+    pub inter_sequence: bool,
+}
+
+/// Collects the template switches of the given modifications, which were applied in order to the ancestor.
+///
+/// Each derived sequence is given with its record id and its modifications.
+pub fn template_switch_records(
+    ancestor_length: usize,
+    derived_sequences: [(DerivedSequence, &str, &[SequenceModification]); 2],
+) -> Vec<TemplateSwitchRecord> {
+    let mut records = Vec::new();
+
+    for (sequence, record_id, modifications) in derived_sequences {
+        let mut ancestry = Ancestry::new(ancestor_length);
+        for modification in modifications {
+            if let SequenceModification::TemplateSwitch {
+                position,
+                length,
+                offset,
+                length_difference,
+                inter_sequence,
+            } = *modification
+            {
+                let replaced_length = (length as isize - length_difference) as usize;
+                records.push(TemplateSwitchRecord {
+                    sequence,
+                    record_id: record_id.to_string(),
+                    ancestor_start: ancestry.ancestor_position(position),
+                    ancestor_end: ancestry.ancestor_position(position + replaced_length),
+                    position,
+                    length,
+                    offset,
+                    length_difference,
+                    strand: TemplateSwitchStrand::ReverseComplement,
+                    inter_sequence,
+                });
+            }
+            ancestry.apply_modification(modification);
+        }
+    }
+
+    records
+}
+
+/// Write the template switch records as JSON array.
+pub fn write_template_switch_json(
+    path: impl AsRef<Path>,
+    records: &[TemplateSwitchRecord],
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, records)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence_modifier::SequenceModification;
+
+    use super::{
+        template_switch_records, DerivedSequence, TemplateSwitchRecord, TemplateSwitchStrand,
+    };
+
+    #[test]
+    fn ancestor_frame() {
+        let reference_modifications = [
+            SequenceModification::Deletion {
+                position: 0,
+                length: 5,
+            },
+            SequenceModification::TemplateSwitch {
+                position: 5,
+                length: 4,
+                offset: -2,
+                length_difference: 1,
+                inter_sequence: false,
+            },
+        ];
+        let query_modifications = [
+            SequenceModification::Insertion {
+                position: 0,
+                source: 10,
+                length: 3,
+            },
+            SequenceModification::Deletion {
+                position: 8,
+                length: 2,
+            },
+            SequenceModification::TemplateSwitch {
+                position: 7,
+                length: 2,
+                offset: 3,
+                length_difference: 0,
+                inter_sequence: true,
+            },
+        ];
+
+        let records = template_switch_records(
+            20,
+            [
+                (DerivedSequence::Reference, "ref", &reference_modifications),
+                (DerivedSequence::Query, "qry", &query_modifications),
+            ],
+        );
+        let record = |sequence, record_id: &str, ancestor_start, ancestor_end, modification| {
+            let SequenceModification::TemplateSwitch {
+                position,
+                length,
+                offset,
+                length_difference,
+                inter_sequence,
+            } = modification
+            else {
+                unreachable!()
+            };
+            TemplateSwitchRecord {
+                sequence,
+                record_id: record_id.to_string(),
+                ancestor_start,
+                ancestor_end,
+                position,
+                length,
+                offset,
+                length_difference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                inter_sequence,
+            }
+        };
+        assert_eq!(
+            records,
+            [
+                // Shifted by the deletion of the first five characters.
+                record(
+                    DerivedSequence::Reference,
+                    "ref",
+                    10,
+                    13,
+                    reference_modifications[1]
+                ),
+                // Shifted by the insertion of three characters, and spanning the two deleted characters.
+                record(DerivedSequence::Query, "qry", 4, 8, query_modifications[2]),
+            ]
+        );
+
+        let json = serde_json::to_string(&records[1]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"sequence":"query","record-id":"qry","ancestor-start":4,"ancestor-end":8,"position":7,"length":2,"offset":3,"length-difference":0,"strand":"reverse-complement","inter-sequence":true}"#
+        );
+    }
+}